@@ -1,25 +1,24 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::io::{ErrorKind, Write};
-use std::path::Path;
 
 use change_case::{pascal_case, snake_case};
-use std::fs::{create_dir, create_dir_all, OpenOptions};
 
-use crate::codegen::{generate_vue_view_for_table, parse_table_as_struct};
+use crate::codegen::{generate_frontend_composables, generate_vue_view_for_table, parse_table_as_struct};
 use crate::config::{
-    get_rbatis, safe_struct_field_name, CodeGenConfig, QueryConfig, RedisConfig, RelationConfig,
+    safe_struct_field_name, CodeGenConfig, QueryConfig, RedisConfig, RelationConfig,
     TableConfig,
 };
-use crate::permission::ChimesPermissionInfo;
-use crate::schema::{ColumnInfo, TableInfo};
-use crate::tmpl::{format_conf_tmpl, format_redis_conf_tmpl};
+use crate::output::{insert_keep_regions, OutputSink, WritePolicy};
+use crate::schema::{ColumnInfo, SchemaSource, TableInfo};
+use crate::tmpl::{format_conf_tmpl, format_redis_conf_tmpl, load_tera_templates};
 use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
+use std::error::Error;
 use substring::Substring;
+use tera::{Context, Tera};
 
 use super::{
-    execute_sql, generate_actix_handler_for_table, generate_js_api_for_table, generate_relation_form, is_copied_data_type, parse_data_type_as_rust_type, parse_query_as_file, parse_query_handler_as_file, parse_relation_as_file, parse_relation_handlers_as_file, parse_table_as_composite_struct, parse_table_as_request_param_struct, parse_table_as_value_object_struct, parse_yaml_as_file
+    execute_sql, generate_actix_handler_for_table, generate_js_api_for_table, generate_relation_form, is_copied_data_type, parse_data_type_as_rust_type, parse_query_as_file, parse_query_handler_as_file, parse_relation_as_file, parse_relation_handlers_as_file, parse_table_as_composite_struct, parse_table_as_request_param_struct, parse_table_as_value_object_struct, parse_yaml_as_file, render_cargo_toml, render_rust_file, render_rust_lib, render_rust_main, generate_service_for_table, generate_service_handler_for_table, generate_service_for_relation, retain_service_handler_usings, convert_handler_file_to_axum, format_axum_route, convert_file_to_rbatis4, convert_file_to_sqlx, convert_file_to_standalone, generate_grpc_for_table, generate_grpc_for_relation, generate_grpc_server_file, format_grpc_pb_source, ProtoFileImpl, generate_graphql_for_table, generate_graphql_for_query, generate_graphql_schema_file, generate_openapi_document, retain_openapi_operations, format_openapi_json, format_openapi_yaml, add_utoipa_annotations, generate_openapi_doc_file, generate_ts_api_files, generate_client_models, generate_client_api, generate_handler_test_files, generate_seed_files, generate_ddl_files, apply_redis_cache, apply_remove_batch_guard, apply_logic_deletion_filter, get_logic_deletion, apply_optimistic_lock, get_version_field
 };
 
 pub trait CodeWriter {
@@ -51,6 +50,17 @@ pub struct RelationForm {
     pub relations: Vec<RelationTable>,
 }
 
+impl RelationForm {
+    /**
+     * relation_map中的关联表，按表名排序，使生成的import等顺序固定
+     */
+    pub fn get_relation_tables(&self) -> Vec<TableConfig> {
+        let mut tables = self.relation_map.values().cloned().collect::<Vec<TableConfig>>();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        tables
+    }
+}
+
 /**
  * 代码生成的上下文
  * 它的主要功能是解释配置文件，并根据配置文件来准备代码生成所需要对应的一些参数
@@ -65,18 +75,101 @@ pub struct GenerateContext {
     pub structs: Vec<RustStruct>,
     pub queries: Vec<RustStruct>,
     pub permissions: Vec<RustPermission>,
+    #[serde(skip)]
+    pub templates: Tera,
 }
 
 impl GenerateContext {
-    pub fn create(cgconf: &CodeGenConfig, redisconf: &RedisConfig) -> Self {
-        Self {
-            codegen_conf: cgconf.clone(),
+    /**
+     * template-dir中的模板无法加载或解析时返回错误，不使用内置的模板继续生成
+     */
+    pub fn create(cgconf: &CodeGenConfig, redisconf: &RedisConfig) -> std::io::Result<Self> {
+        let templates = match load_tera_templates(&cgconf.template_dir) {
+            Ok(t) => t,
+            Err(err) => {
+                let msg = format!(
+                    "Could not load the templates from template-dir {}: {}",
+                    cgconf.template_dir.clone().unwrap_or_default(),
+                    Self::format_tera_error(&err)
+                );
+                log::error!("{}", msg);
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg));
+            }
+        };
+        let mut codegen_conf = cgconf.clone();
+        if codegen_conf.rbatis_version >= 4 || codegen_conf.orm == "sqlx" {
+            // 附件（chimes-rust）及CommonSearch（chimes-utils）依赖于rbatis 3.x，rbatis 4.x及sqlx时不生成
+            for tbc in codegen_conf.tables.iter_mut() {
+                if tbc.with_attachment || tbc.using_common_search {
+                    log::warn!(
+                        "with-attachment and common-search of {} are not supported by rbatis 4.x or sqlx, they were ignored.",
+                        tbc.name
+                    );
+                    tbc.with_attachment = false;
+                    tbc.using_common_search = false;
+                }
+            }
+        }
+        if codegen_conf.standalone {
+            // 附件依赖于chimes-rust中的表及实现，standalone时不生成
+            for tbc in codegen_conf.tables.iter_mut() {
+                if tbc.with_attachment {
+                    log::warn!(
+                        "with-attachment of {} is not supported in standalone mode, it was ignored.",
+                        tbc.name
+                    );
+                    tbc.with_attachment = false;
+                }
+            }
+        }
+        Ok(Self {
+            codegen_conf,
             redis_conf: redisconf.clone(),
             tables: vec![],
             columns: HashMap::new(),
             structs: vec![],
             queries: vec![],
             permissions: vec![],
+            templates,
+        })
+    }
+
+    fn format_tera_error(err: &tera::Error) -> String {
+        let mut msg = err.to_string();
+        let mut source = err.source();
+        while let Some(src) = source {
+            msg.push_str(": ");
+            msg.push_str(&src.to_string());
+            source = src.source();
+        }
+        msg
+    }
+
+    /**
+     * 使用模板生成文本
+     * 模板不存在或解析出错时，记录错误并返回None，调用方不应再生成对应的文件
+     */
+    pub fn render_template(&self, name: &str, target: &str, context: &Context) -> Option<String> {
+        if !self.templates.get_template_names().any(|t| t == name) {
+            log::error!(
+                "The template {} for {} was not found in the embedded templates or the template-dir {}, the file was not generated.",
+                name,
+                target,
+                self.codegen_conf.template_dir.clone().unwrap_or_default()
+            );
+            return None;
+        }
+        match self.templates.render(name, context) {
+            Ok(text) => Some(text),
+            Err(err) => {
+                log::error!(
+                    "Error for render the template {} for {}, the file was not generated. {}",
+                    name,
+                    target,
+                    Self::format_tera_error(&err)
+                );
+                None
+            }
         }
     }
 
@@ -105,6 +198,112 @@ impl GenerateContext {
         self.codegen_conf.entity_in_one_file
     }
 
+    #[allow(dead_code)]
+    pub fn is_all_handler_in_one_file(&self) -> bool {
+        self.codegen_conf.handler_in_one_file
+    }
+
+    #[allow(dead_code)]
+    pub fn is_all_query_in_one_file(&self) -> bool {
+        self.codegen_conf.query_in_one_file
+    }
+
+    #[allow(dead_code)]
+    pub fn is_generate_service(&self) -> bool {
+        self.codegen_conf.generate_service
+    }
+
+    #[allow(dead_code)]
+    pub fn is_axum(&self) -> bool {
+        self.codegen_conf.web_framework == "axum"
+    }
+
+    #[allow(dead_code)]
+    pub fn is_rbatis_v4(&self) -> bool {
+        !self.is_sqlx() && self.codegen_conf.rbatis_version >= 4
+    }
+
+    #[allow(dead_code)]
+    pub fn is_sqlx(&self) -> bool {
+        self.codegen_conf.orm == "sqlx"
+    }
+
+    #[allow(dead_code)]
+    pub fn is_standalone(&self) -> bool {
+        self.codegen_conf.standalone
+    }
+
+    #[allow(dead_code)]
+    pub fn is_generate_grpc(&self) -> bool {
+        self.codegen_conf.generate_grpc
+    }
+
+    pub fn is_generate_graphql(&self) -> bool {
+        self.codegen_conf.generate_graphql
+    }
+
+    pub fn is_generate_openapi(&self) -> bool {
+        self.codegen_conf.generate_openapi
+    }
+
+    pub fn is_typescript_api(&self) -> bool {
+        self.codegen_conf.api_language == "ts" || self.codegen_conf.api_language == "typescript"
+    }
+
+    pub fn is_vue3_element_plus(&self) -> bool {
+        self.codegen_conf.frontend == "vue3-element-plus"
+    }
+
+    pub fn is_react_antd(&self) -> bool {
+        self.codegen_conf.frontend == "react-antd"
+    }
+
+    /**
+     * 前端页面的模板名称，vue3-element-plus时使用vue3/目录下的模板
+     * react-antd时使用react/目录下的模板，其中的.vue为.tsx
+     */
+    pub fn get_frontend_template(&self, name: &str) -> String {
+        if self.is_vue3_element_plus() {
+            format!("vue3/{}", name)
+        } else if self.is_react_antd() {
+            format!("react/{}", name.replace(".vue", ".tsx"))
+        } else {
+            name.to_string()
+        }
+    }
+
+    /**
+     * 前端页面生成后的文件名
+     */
+    pub fn get_frontend_file_name(&self, name: &str) -> String {
+        let tmpl = self.get_frontend_template(name);
+        tmpl.rsplit('/').next().unwrap_or(name).to_string()
+    }
+
+    pub fn is_openapi_swagger_ui(&self) -> bool {
+        self.codegen_conf.generate_openapi && self.codegen_conf.openapi_swagger_ui
+    }
+
+    pub fn is_generate_client(&self) -> bool {
+        self.codegen_conf.generate_client
+    }
+
+    pub fn is_generate_test(&self) -> bool {
+        self.codegen_conf.generate_test
+    }
+
+    /**
+     * 允许使用Redis缓存（allow-redis-cache），并且有表配置了cache-by-fields
+     */
+    pub fn is_redis_cache(&self) -> bool {
+        self.codegen_conf.allow_redis_cache
+            && self
+                .codegen_conf
+                .tables
+                .iter()
+                .any(|t| t.cache_by_fields.is_some())
+    }
+
     #[allow(dead_code)]
     pub fn is_generate_lib(&self) -> bool {
         self.codegen_conf.generate_for_lib
@@ -146,9 +345,14 @@ impl GenerateContext {
             tbc.comment.clone()
         };
 
+        // 逻辑删除的恢复、彻底删除不对所有登录的用户开放，需要为角色授权
+        let logic_deletion = get_logic_deletion(self, &tbname).is_some();
         let mut children = vec![];
         for mk in funclist.clone() {
             if mk.api_method.is_some() {
+                let pattern = mk.api_pattern.clone().unwrap_or_default();
+                let restricted =
+                    logic_deletion && (pattern.ends_with("/purge") || pattern.ends_with("/restore"));
                 let child = RustPermission {
                     name: mk.comment.clone().unwrap_or_default(),
                     alias: mk.func_name.to_uppercase(),
@@ -156,7 +360,7 @@ impl GenerateContext {
                     module_id: alias.clone(),
                     api_pattern: mk.api_pattern.clone(),
                     api_method: mk.api_method.clone(),
-                    api_bypass: Some("user".to_string()),
+                    api_bypass: if restricted { None } else { Some("user".to_string()) },
                     children: vec![],
                 };
                 children.push(child);
@@ -325,7 +529,12 @@ impl GenerateContext {
     #[allow(dead_code)]
     pub fn get_table_conf_by_struct_name(&self, tbl: &String) -> Option<TableConfig> {
         for tc in self.codegen_conf.tables.clone() {
-            if tc.struct_name == tbl.clone() {
+            let struct_name = if tc.struct_name.is_empty() {
+                pascal_case(tc.name.clone().as_str())
+            } else {
+                tc.struct_name.clone()
+            };
+            if struct_name == tbl.clone() {
                 return Some(tc.clone());
             }
         }
@@ -909,6 +1118,23 @@ impl CodeWriter for RustStruct {
         for ln in self.annotations.clone() {
             ro.write_line(&ln);
         }
+        if !self.fields.is_empty() && self.fields.iter().all(|fd| fd.field_name.is_empty()) {
+            // 字段都没有名称时为元组结构体，如async-graphql的MergedObject
+            let types = self
+                .fields
+                .iter()
+                .map(|fd| fd.field_type.clone())
+                .collect::<Vec<String>>()
+                .join(", ");
+            if self.is_pub {
+                ro.write_line(&format!("pub struct {}({});", self.struct_name.clone(), types));
+            } else {
+                ro.write_line(&format!("struct {}({});", self.struct_name.clone(), types));
+            }
+            ro.write_line("");
+            ro.write_line("");
+            return;
+        }
         if self.is_pub {
             ro.write_line(&format!("pub struct {} {{", self.struct_name.clone()));
         } else {
@@ -971,6 +1197,10 @@ impl RustOutput {
         self.outputs.push(newline);
     }
 
+    pub fn to_text(&self) -> String {
+        self.outputs.concat()
+    }
+
     #[allow(dead_code)]
     pub fn print_out(&self) {
         for ln in self.outputs.clone() {
@@ -990,6 +1220,120 @@ pub struct RustFileImpl {
     pub usinglist: Vec<String>,
     pub structlist: Vec<RustStruct>,
     pub funclist: Vec<RustFunc>,
+    pub implist: Vec<RustTraitImpl>,
+    pub artifact: Option<RustArtifact>, // 文件的类型及其来源，用于选择对应的模板
+}
+
+/**
+ * 为类型实现trait，如From、tonic生成的服务trait
+ * 其中的方法不能为pub
+ * trait_name为空时为固有的impl块，可以带注解，如async-graphql的#[Object]、客户端的ApiClient
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RustTraitImpl {
+    pub annotations: Vec<String>,
+    pub trait_name: String,
+    pub struct_name: String,
+    pub types: Vec<(String, String)>, // 关联类型，如(Value, Vec<T>)
+    pub funclist: Vec<RustFunc>,
+}
+
+impl CodeWriter for RustTraitImpl {
+    fn write(&self, ro: &mut RustOutput) {
+        for ln in self.annotations.clone() {
+            ro.write_line(&ln);
+        }
+        if self.trait_name.is_empty() {
+            ro.write_line(&format!("impl {} {{", self.struct_name.clone()));
+        } else {
+            ro.write_line(&format!(
+                "impl {} for {} {{",
+                self.trait_name.clone(),
+                self.struct_name.clone()
+            ));
+        }
+        for (name, tp) in self.types.clone() {
+            ro.write_line(&format!("    type {} = {};", name, tp));
+        }
+        if !self.types.is_empty() {
+            ro.write_line("");
+        }
+        for func in self.funclist.clone() {
+            func.write(ro);
+        }
+        ro.write_line("}");
+        ro.write_line("");
+    }
+}
+
+/**
+ * Rust文件的类型
+ * 每种类型对应一个可覆盖的模板，其值为生成该文件的来源（表名、关系或查询的结构体名）
+ * Service的来源为表名或关系的结构体名
+ */
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum RustArtifact {
+    Entity(String),
+    Handler(String),
+    Relation(String),
+    RelationHandler(String),
+    Query(String),
+    QueryHandler(String),
+    Service(String),
+}
+
+impl RustArtifact {
+    pub fn template_name(&self) -> &'static str {
+        match self {
+            RustArtifact::Entity(_) => "rust/entity.rs",
+            RustArtifact::Handler(_) => "rust/handler.rs",
+            RustArtifact::Relation(_) => "rust/relation.rs",
+            RustArtifact::RelationHandler(_) => "rust/relation_handler.rs",
+            RustArtifact::Query(_) => "rust/query.rs",
+            RustArtifact::QueryHandler(_) => "rust/query_handler.rs",
+            RustArtifact::Service(_) => "rust/service.rs",
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RustArtifact::Entity(_) => "entity",
+            RustArtifact::Handler(_) => "handler",
+            RustArtifact::Relation(_) => "relation",
+            RustArtifact::RelationHandler(_) => "relation_handler",
+            RustArtifact::Query(_) => "query",
+            RustArtifact::QueryHandler(_) => "query_handler",
+            RustArtifact::Service(_) => "service",
+        }
+    }
+
+    pub fn source(&self) -> String {
+        match self {
+            RustArtifact::Entity(s)
+            | RustArtifact::Handler(s)
+            | RustArtifact::Relation(s)
+            | RustArtifact::RelationHandler(s)
+            | RustArtifact::Query(s)
+            | RustArtifact::QueryHandler(s)
+            | RustArtifact::Service(s) => s.clone(),
+        }
+    }
+
+    /**
+     * 同类型、不同来源的文件，如entity-in-one-file时合并后的mod.rs，其来源为空
+     */
+    pub fn with_source(&self, source: &str) -> Self {
+        let source = source.to_string();
+        match self {
+            RustArtifact::Entity(_) => RustArtifact::Entity(source),
+            RustArtifact::Handler(_) => RustArtifact::Handler(source),
+            RustArtifact::Relation(_) => RustArtifact::Relation(source),
+            RustArtifact::RelationHandler(_) => RustArtifact::RelationHandler(source),
+            RustArtifact::Query(_) => RustArtifact::Query(source),
+            RustArtifact::QueryHandler(_) => RustArtifact::QueryHandler(source),
+            RustArtifact::Service(_) => RustArtifact::Service(source),
+        }
+    }
 }
 
 impl RustFileImpl {
@@ -1013,16 +1357,10 @@ impl RustFileImpl {
         self.funclist.push(us.clone());
     }
 
-    #[allow(dead_code)]
-    pub fn write_out(&self, filename: &String) -> std::io::Result<()> {
-        let mut ro = RustOutput::default();
-        ro.write_line("/**");
-        ro.write_line(format!(" * Generate the file for {}, ", self.file_name.clone()).as_str());
-        ro.write_line(" */");
-        for crt in self.caretlist.clone() {
-            ro.write_line(format!("extern caret {};", crt).as_str());
-        }
-        ro.write_line("");
+    /**
+     * 获取排序去重后的use列表，包含了各个struct所需要的use
+     */
+    pub fn get_usings(&self) -> Vec<String> {
         let mut usings = self.usinglist.clone();
 
         for mut st in self.structlist.clone() {
@@ -1031,9 +1369,23 @@ impl RustFileImpl {
 
         usings.sort();
         usings.dedup();
-        
+        usings
+    }
+
+    /**
+     * 按内置的方式生成文件内容
+     */
+    pub fn to_source(&self) -> String {
+        let mut ro = RustOutput::default();
+        ro.write_line("/**");
+        ro.write_line(format!(" * Generate the file for {}, ", self.file_name.clone()).as_str());
+        ro.write_line(" */");
+        for crt in self.caretlist.clone() {
+            ro.write_line(format!("extern caret {};", crt).as_str());
+        }
+        ro.write_line("");
 
-        for usingline in usings {
+        for usingline in self.get_usings() {
             ro.write_line(format!("use {};", usingline).as_str());
         }
         ro.write_line("");
@@ -1044,20 +1396,15 @@ impl RustFileImpl {
         for func in self.funclist.clone() {
             func.write(&mut ro);
         }
-
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(false)
-            .create(true)
-            .truncate(true)
-            .open(filename)?;
-        for lt in ro.outputs.clone() {
-            file.write_all(lt.as_bytes())?;
+        for imp in self.implist.clone() {
+            imp.write(&mut ro);
         }
+        ro.to_text()
+    }
 
-        file.flush()?;
-
-        Ok(())
+    #[allow(dead_code)]
+    pub fn write_out(&self, sink: &mut dyn OutputSink, filename: &str) -> std::io::Result<()> {
+        sink.put(filename, &self.to_source())
     }
 }
 
@@ -1084,8 +1431,7 @@ impl VueAndJSFileImpl {
         self.funclist.push(us.clone());
     }
 
-    #[allow(dead_code)]
-    pub fn write_out(&self, filename: &String) -> std::io::Result<()> {
+    pub fn to_source(&self) -> String {
         let mut ro = RustOutput::default();
         for usingline in self.usinglist.clone() {
             ro.write_line(format!("import {};", usingline).as_str());
@@ -1093,20 +1439,33 @@ impl VueAndJSFileImpl {
         for func in self.funclist.clone() {
             ro.write_line(func.as_str());
         }
+        ro.to_text()
+    }
 
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(false)
-            .create(true)
-            .truncate(true)
-            .open(filename)?;
-        for lt in ro.outputs.clone() {
-            file.write_all(lt.as_bytes())?;
-        }
+    #[allow(dead_code)]
+    pub fn write_out(&self, sink: &mut dyn OutputSink, filename: &str) -> std::io::Result<()> {
+        sink.put(filename, &self.to_source())
+    }
+}
 
-        file.flush()?;
+/**
+ * 生成的文件
+ * path为相对于输出目录的路径，policy为该文件的写入策略
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GeneratedFile {
+    pub path: String,
+    pub content: String,
+    pub policy: WritePolicy,
+}
 
-        Ok(())
+impl GeneratedFile {
+    pub fn new(path: &str, content: &str, policy: WritePolicy) -> Self {
+        Self {
+            path: path.to_string(),
+            content: content.to_string(),
+            policy,
+        }
     }
 }
 
@@ -1127,6 +1486,10 @@ pub struct CodeGenerator {
     pub ctx: GenerateContext,
     pub files: Vec<RustFileImpl>,
     pub vuejs: Vec<VueAndJSFileImpl>,
+    pub protos: Vec<ProtoFileImpl>,
+    pub openapi: Option<serde_json::Value>,
+    pub client: Vec<RustFileImpl>,
+    pub tests: Vec<RustFileImpl>,
     //pub default_entity_using: Vec<String>,
     //pub default_handler_using: Vec<String>,
 }
@@ -1147,6 +1510,10 @@ impl CodeGenerator {
             ctx: ctx.clone(),
             files: vec![],
             vuejs: vec![],
+            protos: vec![],
+            openapi: None,
+            client: vec![],
+            tests: vec![],
             // default_entity_using: Self::get_default_entity_using(true),
             // default_handler_using: Self::get_default_handler_using(true),
         }
@@ -1222,18 +1589,17 @@ impl CodeGenerator {
      * 加载数据库表
      * 根据从Yaml文件中加载的配置来进行处理
      */
-    pub async fn load_tables(&mut self) {
-        let rb = get_rbatis();
+    pub async fn load_tables(&mut self, source: &dyn SchemaSource) {
         let ts = self.ctx.codegen_conf.schema_name.clone();
         let tables = self.ctx.codegen_conf.tables.clone();
         for f in tables {
             let tn = f.name.clone();
-            match TableInfo::load_table(rb, &ts.clone(), &tn.clone()).await {
+            match source.load_table(&ts.clone(), &tn.clone()).await {
                 Ok(tbop) => {
                     match tbop {
                         Some(tb) => {
                             // log::info!("Columns of table {} {} {} will be fetching.", tb.table_name.clone().unwrap_or_default(), tb.table_schema.clone().unwrap_or_default(), tb.table_catalog.clone().unwrap_or_default());
-                            match ColumnInfo::load_columns(rb, &ts.clone(), &tn.clone()).await {
+                            match source.load_columns(&ts.clone(), &tn.clone()).await {
                                 Ok(cols) => {
                                     // log::info!("The table {} will be added.", tb.table_name.clone().unwrap_or_default());
                                     self.ctx.add_table(&tb, &cols);
@@ -1265,7 +1631,7 @@ impl CodeGenerator {
                 fds.push(st.default_value.clone().unwrap_or_default());
             }
             log::info!("Query: {}", qry.base_sql);
-            match execute_sql(&self.ctx, qry.base_sql.as_str(), &fds).await {
+            match execute_sql(&self.ctx, source, qry.base_sql.as_str(), &fds).await {
                 Ok(rt) => {
                     let st = parse_query_as_file(&self.ctx, &qry, &rt);
                     self.files.push(st);
@@ -1332,11 +1698,17 @@ impl CodeGenerator {
                 stlist.push(mx.clone());
             }
 
-            let tbc = self.ctx.get_table_conf_by_struct_name(&sts.struct_name);
-            let attachment = if tbc.is_none() {
-                false
-            } else {
-                tbc.unwrap().with_attachment
+            // 没有配置struct-name时，结构体名为表名的PascalCase
+            let table_name = self
+                .ctx
+                .tables
+                .iter()
+                .map(|t| t.table_name.clone().unwrap_or_default())
+                .find(|tn| self.ctx.get_struct_name(tn) == Some(sts.struct_name.clone()))
+                .unwrap_or_default();
+            let attachment = match self.ctx.get_table_conf(&table_name) {
+                Some(tc) => tc.with_attachment,
+                None => false,
             };
 
             let rfi = RustFileImpl {
@@ -1346,6 +1718,8 @@ impl CodeGenerator {
                 usinglist: Self::get_default_entity_using(&self.ctx, sts.has_paging, attachment, CodeModelType::Entity),
                 structlist: stlist,
                 funclist: vec![],
+                implist: vec![],
+                artifact: Some(RustArtifact::Entity(table_name)),
             };
             self.files.push(rfi);
         }
@@ -1358,6 +1732,8 @@ impl CodeGenerator {
                 usinglist: Self::get_default_entity_using(&self.ctx, sts.has_paging, false, CodeModelType::Query),
                 structlist: vec![sts.clone()],
                 funclist: vec![],
+                implist: vec![],
+                artifact: Some(RustArtifact::Query(sts.struct_name.clone())),
             };
             self.files.push(rfi);
         }
@@ -1367,19 +1743,26 @@ impl CodeGenerator {
             let tbl_name = tbl.table_name.clone().unwrap_or_default();
             let tbc = self.ctx.get_table_conf(&tbl_name.clone()).unwrap();
             let comp = &composite_map[&tbl_name.clone()];
+            if self.ctx.is_generate_service() {
+                if let Some(rfi) = generate_service_for_table(&self.ctx, &tbl) {
+                    self.files.push(rfi);
+                }
+            }
             if tbc.generate_handler {
-                let funclist = generate_actix_handler_for_table(
-                    &mut self.ctx,
-                    &tbl.clone(),
-                    &mut usinglist,
-                    comp,
-                );
+                let funclist = if self.ctx.is_generate_service() {
+                    generate_service_handler_for_table(&mut self.ctx, &tbl.clone(), &mut usinglist, comp)
+                } else {
+                    generate_actix_handler_for_table(&mut self.ctx, &tbl.clone(), &mut usinglist, comp)
+                };
 
                 usinglist.append(&mut Self::get_default_handler_using(
                     &self.ctx,
                     tbc.page_query,
                     tbc.using_common_search
                 ));
+                if self.ctx.is_generate_service() {
+                    retain_service_handler_usings(&mut usinglist, &funclist);
+                }
                 // let tbc =  self.ctx.get_table_conf(&tbl.table_name.clone().unwrap_or_default()).unwrap();
                 let rfi = RustFileImpl {
                     file_name: format!(
@@ -1397,32 +1780,43 @@ impl CodeGenerator {
                     usinglist: usinglist,
                     structlist: vec![],
                     funclist: funclist,
+                    implist: vec![],
+                    artifact: Some(RustArtifact::Handler(tbl_name.clone())),
                 };
                 self.files.push(rfi);
 
-                let jsapi = generate_js_api_for_table(&mut self.ctx, &tbl);
-                let vjsfile = VueAndJSFileImpl {
-                    file_name: tbc.api_handler_name.clone() + ".js",
-                    mod_name: "".to_string(),
-                    js_vue: false,
-                    usinglist: vec![],
-                    funclist: vec![jsapi.clone()],
-                };
-                self.vuejs.push(vjsfile.clone());
+                if self.ctx.is_typescript_api() {
+                    // TypeScript的API在各种转换之后统一生成
+                } else if let Some(jsapi) = generate_js_api_for_table(&mut self.ctx, &tbl) {
+                    let vjsfile = VueAndJSFileImpl {
+                        file_name: tbc.api_handler_name.clone() + ".js",
+                        mod_name: "".to_string(),
+                        js_vue: false,
+                        usinglist: vec![],
+                        funclist: vec![jsapi.clone()],
+                    };
+                    self.vuejs.push(vjsfile.clone());
+                }
 
-                let jsvue = generate_vue_view_for_table(&mut self.ctx, &tbl, comp);
-                let vuefile = VueAndJSFileImpl {
-                    file_name: "index.vue".to_string(),
-                    mod_name: tbc.api_handler_name.clone(),
-                    js_vue: true,
-                    usinglist: vec![],
-                    funclist: jsvue.clone(),
-                };
-                self.vuejs.push(vuefile.clone());
+                if let Some(jsvue) = generate_vue_view_for_table(&mut self.ctx, &tbl, comp) {
+                    let vuefile = VueAndJSFileImpl {
+                        file_name: self.ctx.get_frontend_file_name("index.vue"),
+                        mod_name: tbc.api_handler_name.clone(),
+                        js_vue: true,
+                        usinglist: vec![],
+                        funclist: vec![jsvue.clone()],
+                    };
+                    self.vuejs.push(vuefile.clone());
+                }
             }
         }
 
         for rel in self.ctx.codegen_conf.relations.clone() {
+            if self.ctx.is_generate_service() {
+                if let Some(rfi) = generate_service_for_relation(&self.ctx, &rel) {
+                    self.files.push(rfi);
+                }
+            }
             match parse_relation_as_file(&self.ctx, &rel) {
                 Some(rfi) => {
                     self.files.push(rfi);
@@ -1450,16 +1844,83 @@ impl CodeGenerator {
             }
 
             if rel.generate_form {
-                let text = generate_relation_form(&mut self.ctx, &rel);
-                let jsvue = vec![text];
-                let vuefile = VueAndJSFileImpl {
-                    file_name: "form.vue".to_string(),
-                    mod_name: rel.api_handler_name.clone().unwrap_or_default(),
+                if let Some(text) = generate_relation_form(&mut self.ctx, &rel) {
+                    let jsvue = vec![text];
+                    let vuefile = VueAndJSFileImpl {
+                        file_name: self.ctx.get_frontend_file_name("form.vue"),
+                        mod_name: rel.api_handler_name.clone().unwrap_or_default(),
+                        js_vue: true,
+                        usinglist: vec![],
+                        funclist: jsvue.clone(),
+                    };
+                    self.vuejs.push(vuefile.clone());
+                }
+            }
+        }
+
+        if (self.ctx.is_vue3_element_plus() || self.ctx.is_react_antd())
+            && self.vuejs.iter().any(|f| f.js_vue)
+        {
+            if let Some(text) = generate_frontend_composables(&self.ctx) {
+                let mod_name = if self.ctx.is_react_antd() { "hooks" } else { "composables" };
+                self.vuejs.push(VueAndJSFileImpl {
+                    file_name: "useCrud.ts".to_string(),
+                    mod_name: mod_name.to_string(),
                     js_vue: true,
                     usinglist: vec![],
-                    funclist: jsvue.clone(),
-                };
-                self.vuejs.push(vuefile.clone());
+                    funclist: vec![text],
+                });
+            }
+        }
+
+        if self.ctx.is_generate_grpc() {
+            let mut services = vec![];
+            for tbl in self.ctx.tables.clone() {
+                if let Some((proto, rfi)) = generate_grpc_for_table(&self.ctx, &tbl) {
+                    if !rfi.structlist.is_empty() {
+                        let tbl_name = tbl.table_name.clone().unwrap_or_default();
+                        services.push(self.ctx.get_struct_name(&tbl_name).unwrap_or_default());
+                    }
+                    self.protos.push(proto);
+                    self.files.push(rfi);
+                }
+            }
+            for rel in self.ctx.codegen_conf.relations.clone() {
+                if let Some((proto, rfi)) = generate_grpc_for_relation(&self.ctx, &rel) {
+                    if !rfi.structlist.is_empty() {
+                        services.push(rel.struct_name.clone());
+                    }
+                    self.protos.push(proto);
+                    self.files.push(rfi);
+                }
+            }
+            self.files.push(generate_grpc_server_file(&services));
+        }
+
+        if self.ctx.is_generate_graphql() {
+            let mut tables = vec![];
+            let mut loaders = vec![];
+            for tbl in self.ctx.tables.clone() {
+                if let Some((rfi, mut lds)) = generate_graphql_for_table(&self.ctx, &tbl) {
+                    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+                    tables.push(self.ctx.get_struct_name(&tbl_name).unwrap_or_default());
+                    loaders.append(&mut lds);
+                    self.files.push(rfi);
+                }
+            }
+            let mut queries = tables.clone();
+            for qry in self.ctx.codegen_conf.queries.clone() {
+                let qfile = self.files.iter().find(|f| {
+                    f.mod_name == "query"
+                        && matches!(&f.artifact, Some(RustArtifact::Query(q)) if *q == qry.struct_name)
+                });
+                if let Some(rfi) = qfile.and_then(|f| generate_graphql_for_query(&self.ctx, &qry, f)) {
+                    queries.push(qry.struct_name.clone());
+                    self.files.push(rfi);
+                }
+            }
+            if !queries.is_empty() {
+                self.files.push(generate_graphql_schema_file(&self.ctx, &queries, &tables, &loaders));
             }
         }
 
@@ -1473,6 +1934,187 @@ impl CodeGenerator {
             }
             None => {}
         };
+
+        for tbc in self.ctx.codegen_conf.tables.iter().filter(|t| t.logic_deletion) {
+            if get_logic_deletion(&self.ctx, &tbc.name).is_none() {
+                log::warn!(
+                    "The logic-deletion-field {} was not found in table {}, logic deletion was ignored.",
+                    tbc.logic_deletion_field,
+                    tbc.name
+                );
+            }
+        }
+        for tbc in self.ctx.codegen_conf.tables.iter() {
+            if let Some(vf) = tbc.version_field.clone() {
+                if get_version_field(&self.ctx, &tbc.name).is_none() {
+                    log::warn!(
+                        "The version-field {} was not found in table {} or is not an integer, optimistic lock was ignored.",
+                        vf,
+                        tbc.name
+                    );
+                }
+            }
+        }
+        // 逻辑删除的条件按rbatis 3.x的wrapper加入，随其它条件一起转换
+        apply_logic_deletion_filter(&self.ctx, &mut self.files);
+
+        // TypeScript的API及Rust的客户端同样需要转换之前的类型
+        let canonical = if self.ctx.is_typescript_api() || self.ctx.is_generate_client() {
+            Some(self.files.clone())
+        } else {
+            None
+        };
+
+        if self.ctx.is_generate_openapi() {
+            // 在转换之前生成，此时的类型都是rbatis 3.x及actix-web的形式
+            self.openapi = Some(generate_openapi_document(&self.ctx, &self.files));
+            if self.ctx.is_openapi_swagger_ui() {
+                add_utoipa_annotations(&self.ctx, &mut self.files);
+            }
+        }
+
+        if self.ctx.is_axum() {
+            for fl in self.files.iter_mut().filter(|f| f.mod_name == "handler") {
+                convert_handler_file_to_axum(fl);
+            }
+        }
+
+        if self.ctx.is_rbatis_v4() {
+            for fl in self.files.iter_mut() {
+                convert_file_to_rbatis4(&self.ctx, fl);
+            }
+        }
+
+        if self.ctx.is_sqlx() {
+            for fl in self.files.iter_mut() {
+                convert_file_to_sqlx(&self.ctx, fl);
+            }
+        }
+
+        // 乐观锁的检查包装转换后的方法体
+        apply_optimistic_lock(&self.ctx, &mut self.files);
+
+        if self.ctx.is_standalone() {
+            for fl in self.files.iter_mut() {
+                convert_file_to_standalone(fl);
+            }
+        }
+
+        // 没有任何条件的remove_batch会删除整个表，检查加在转换后的方法体中
+        apply_remove_batch_guard(&self.ctx, &mut self.files);
+
+        if self.ctx.is_redis_cache() {
+            // 缓存的方法使用转换后的类型，在ORM转换之后加入
+            apply_redis_cache(&self.ctx, &mut self.files);
+        } else if self
+            .ctx
+            .codegen_conf
+            .tables
+            .iter()
+            .any(|t| t.cache_by_fields.is_some())
+        {
+            log::warn!("cache-by-fields requires allow-redis-cache: true, the cache methods were not generated.");
+        }
+
+        if let Some(doc) = self.openapi.as_mut() {
+            retain_openapi_operations(doc, &self.files);
+            if self.ctx.is_openapi_swagger_ui() {
+                let docfile = generate_openapi_doc_file(&self.ctx, &self.files);
+                self.files.push(docfile);
+            }
+        }
+
+        if let Some(canonical) = canonical {
+            // 转换过程中可能会去掉部分不支持的handler，只保留仍然存在的
+            let surviving = self
+                .files
+                .iter()
+                .filter(|f| f.mod_name == "handler")
+                .flat_map(|f| f.funclist.iter().map(|c| c.func_name.clone()))
+                .collect::<Vec<String>>();
+            if self.ctx.is_typescript_api() {
+                let mut tsfiles = generate_ts_api_files(&self.ctx, &canonical, &surviving);
+                self.vuejs.append(&mut tsfiles);
+            }
+            if self.ctx.is_generate_client() {
+                self.client.push(generate_client_models(&canonical));
+                self.client.push(generate_client_api(&canonical, &surviving));
+            }
+        }
+
+        if self.ctx.is_generate_test() {
+            // 测试通过actix_web::test调用handler，axum的handler暂不支持
+            if self.ctx.is_axum() {
+                log::warn!("generate-test is not supported for axum, the tests will not be generated.");
+            } else {
+                self.tests = generate_handler_test_files(&self.ctx, &self.files);
+            }
+        }
+
+        if self.ctx.is_all_entity_in_one_file() {
+            self.merge_mod_files("entity");
+        }
+        if self.ctx.is_all_handler_in_one_file() {
+            self.merge_mod_files("handler");
+        }
+        if self.ctx.is_all_query_in_one_file() {
+            self.merge_mod_files("query");
+        }
+    }
+
+    /**
+     * 将同一个mod下的所有文件合并为该mod的mod.rs
+     * 合并后同一mod内的引用（use crate::mod_name::xxx）不再需要，其余的use去重
+     */
+    fn merge_mod_files(&mut self, mod_name: &str) {
+        let pos = match self.files.iter().position(|f| f.mod_name == mod_name) {
+            Some(p) => p,
+            None => return,
+        };
+        let self_prefix = format!("crate::{}::", mod_name);
+        let mut merged = RustFileImpl {
+            file_name: "mod.rs".to_string(),
+            mod_name: mod_name.to_string(),
+            caretlist: vec![],
+            usinglist: vec![],
+            structlist: vec![],
+            funclist: vec![],
+            implist: vec![],
+            artifact: None,
+        };
+        let mut others = vec![];
+        for fl in self.files.clone() {
+            if fl.mod_name != mod_name {
+                others.push(fl);
+                continue;
+            }
+            for crt in fl.caretlist {
+                if !merged.caretlist.contains(&crt) {
+                    merged.caretlist.push(crt);
+                }
+            }
+            for us in fl.usinglist.iter() {
+                merged.usinglist.append(&mut split_using_group(us));
+            }
+            for mut st in fl.structlist {
+                for us in st.usings.iter() {
+                    merged.usinglist.append(&mut split_using_group(us));
+                }
+                st.usings = vec![];
+                merged.structlist.push(st);
+            }
+            merged.funclist.append(&mut fl.funclist.clone());
+            merged.implist.append(&mut fl.implist.clone());
+            // 合并后仍按该mod的模板（如rust/entity.rs）生成，其中包含多个表，不再对应某一个来源
+            if merged.artifact.is_none() {
+                merged.artifact = fl.artifact.as_ref().map(|a| a.with_source(""));
+            }
+        }
+        merged.usinglist.retain(|us| !us.starts_with(self_prefix.as_str()));
+        merged.usinglist.sort();
+        merged.usinglist.dedup();
+        others.insert(pos.min(others.len()), merged);
+        self.files = others;
     }
 
     /**
@@ -1480,306 +2122,427 @@ impl CodeGenerator {
      * 写到文件
      * |--entity
      * |--handler
+     * |--service（generate-service时）
      * |--utils
-     * |--main.rs
+     * |--main.rs（generate-for-lib时为lib.rs）
      * |--cargo.toml
      */
-    pub fn write_out(&self) -> std::io::Result<()> {
-        let str = self
+    /**
+     * 收集所有需要输出的文件，路径为相对于输出目录的路径
+     */
+    pub fn collect_outputs(&self) -> Vec<GeneratedFile> {
+        let overwrite = if self.ctx.codegen_conf.always_override {
+            WritePolicy::Overwrite
+        } else {
+            WritePolicy::SkipIfExists
+        };
+        let mut outputs = vec![];
+
+        let utilstext = if self.ctx.is_axum() {
+            crate::tmpl::UTILS_TMPL.to_string() + crate::tmpl::AXUM_STATE_TMPL
+        } else {
+            crate::tmpl::UTILS_TMPL.to_string()
+        };
+        let utilstext = if self.ctx.is_rbatis_v4() {
+            crate::tmpl::replace_rbatis4_utils(&utilstext)
+        } else if self.ctx.is_sqlx() {
+            crate::tmpl::replace_sqlx_utils(&utilstext)
+        } else {
+            utilstext
+        };
+        let utilstext = if self.ctx.is_standalone() {
+            // CommonSearch依赖于rbatis 3.x的wrapper
+            crate::tmpl::replace_standalone_utils(
+                &utilstext,
+                self.ctx.is_axum(),
+                !self.ctx.is_sqlx() && !self.ctx.is_rbatis_v4(),
+            )
+        } else {
+            utilstext
+        };
+        let utilstext = if self.ctx.is_generate_grpc() {
+            utilstext + crate::tmpl::GRPC_UTILS_TMPL
+        } else {
+            utilstext
+        };
+        let utilstext = if self.ctx.is_redis_cache() {
+            utilstext + crate::tmpl::REDIS_CACHE_UTILS_TMPL
+        } else {
+            utilstext
+        };
+        let utilstext = if self
             .ctx
             .codegen_conf
-            .output_path
-            .clone()
-            .as_str()
-            .to_owned();
-        let root_path = Path::new(&str);
-        if !root_path.exists() {
-            // should create the path
-            create_dir_all(root_path)?;
-        }
-        let frontpath = root_path.join("front");
-        if !frontpath.exists() {
-            // should create the path
-            create_dir(frontpath.clone())?;
-        }
-        let frontapipath = frontpath.clone().join("api");
-        if !frontapipath.exists() {
-            // should create the path
-            create_dir(frontapipath.clone())?;
-        }
-        let frontviewpath = frontpath.clone().join("views");
-        if !frontviewpath.exists() {
-            // should create the path
-            create_dir(frontviewpath.clone())?;
-        }
-        let src = root_path.join("src");
-        if !src.exists() {
-            // should create the path
-            create_dir(src.clone())?;
-        }
-        let utils = src.join("utils");
-        if !utils.exists() {
-            // should create the path
-            create_dir(utils.clone())?;
-        }
-
-        let utilsfile = utils.join("mod.rs");
-        self.write_content(
-            &utilsfile.to_str().unwrap_or_default().to_string(),
-            crate::tmpl::UTILS_TMPL,
-        )?;
-
-        let conf = root_path.join("conf");
-        if !conf.exists() {
-            // should create the path
-            create_dir(conf.clone())?;
-        }
-
-        let cargotoml = root_path.join("Cargo.toml");
-        self.write_content(
-            &cargotoml.to_str().unwrap_or_default().to_string(),
-            &crate::tmpl::replace_cargo_toml(&self.ctx.codegen_conf),
-        )?;
-
-        let scoconf = conf.join("app.yml");
+            .tables
+            .iter()
+            .any(|t| get_version_field(&self.ctx, &t.name).is_some())
+        {
+            utilstext + &crate::tmpl::replace_version_utils(self.ctx.is_rbatis_v4(), self.ctx.is_sqlx())
+        } else {
+            utilstext
+        };
+        let utilstext = if self.ctx.is_generate_service() {
+            utilstext + &crate::tmpl::replace_service_user_utils(self.ctx.is_standalone())
+        } else {
+            utilstext
+        };
+        outputs.push(GeneratedFile::new("src/utils/mod.rs", &utilstext, overwrite));
+
+        let cargotext = if self.ctx.is_generate_lib() {
+            crate::tmpl::replace_lib_cargo_toml(&self.ctx.codegen_conf)
+        } else {
+            crate::tmpl::replace_cargo_toml(&self.ctx.codegen_conf)
+        };
+        let cargotext = if self.ctx.is_axum() {
+            crate::tmpl::replace_axum_dependencies(&cargotext)
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_rbatis_v4() {
+            crate::tmpl::replace_rbatis4_dependencies(&cargotext)
+        } else if self.ctx.is_sqlx() {
+            crate::tmpl::replace_sqlx_dependencies(&cargotext)
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_standalone() {
+            crate::tmpl::replace_standalone_dependencies(&cargotext)
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_generate_grpc() {
+            crate::tmpl::replace_grpc_dependencies(&cargotext)
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_redis_cache() {
+            crate::tmpl::replace_redis_dependencies(&cargotext)
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_generate_graphql() {
+            crate::tmpl::replace_graphql_dependencies(&cargotext, self.ctx.is_axum())
+        } else {
+            cargotext
+        };
+        let cargotext = if self.ctx.is_openapi_swagger_ui() {
+            crate::tmpl::replace_openapi_dependencies(&cargotext, self.ctx.is_axum())
+        } else {
+            cargotext
+        };
+        if let Some(text) = render_cargo_toml(&self.ctx, &cargotext) {
+            outputs.push(GeneratedFile::new("Cargo.toml", &text, overwrite));
+        }
+
         let conftext = format_conf_tmpl(
             &self.ctx.codegen_conf.database_url.clone(),
             &self.ctx.codegen_conf.webserver_port.clone(),
         );
 
-        if self.ctx.redis_conf.has_redis {
+        // 生成缓存的方法时总是输出redis的配置，未配置redis时需要在app.yml中设置并启用
+        if self.ctx.redis_conf.has_redis || self.ctx.is_redis_cache() {
+            let redishost = if self.ctx.redis_conf.host.is_empty() {
+                "127.0.0.1".to_string()
+            } else {
+                self.ctx.redis_conf.host.clone()
+            };
+            let redisport = if self.ctx.redis_conf.port > 0 {
+                self.ctx.redis_conf.port
+            } else {
+                6379
+            };
             let redisconf = format_redis_conf_tmpl(
-                &self.ctx.redis_conf.host,
-                self.ctx.redis_conf.port.clone(),
+                &redishost,
+                redisport,
                 &self.ctx.redis_conf.username,
                 &self.ctx.redis_conf.password,
                 self.ctx.redis_conf.db.clone(),
+                self.ctx.redis_conf.has_redis,
             );
             let wholeconf = conftext + redisconf.as_str();
-            self.write_content(
-                &scoconf.as_path().to_str().unwrap_or_default().to_string(),
-                wholeconf.as_str(),
-            )?;
+            outputs.push(GeneratedFile::new("conf/app.yml", &wholeconf, overwrite));
         } else {
-            self.write_content(
-                &scoconf.as_path().to_str().unwrap_or_default().to_string(),
-                conftext.as_str(),
-            )?;
+            outputs.push(GeneratedFile::new("conf/app.yml", &conftext, overwrite));
         }
         let mut modmap = HashMap::<String, Vec<String>>::new();
         let mut service_func: Vec<String> = Vec::new();
 
         for fl in self.files.clone() {
-            let modpath = src.join(fl.mod_name.clone());
             if !modmap.contains_key(&fl.mod_name) {
                 modmap.insert(fl.mod_name.clone(), vec![]);
             }
-            let mut modfiles = modmap.get(&fl.mod_name).unwrap().clone();
-            modfiles.push(fl.file_name.clone());
-            modmap.insert(fl.mod_name.clone(), modfiles);
+            // 合并后的mod.rs本身就是该mod，不需要再声明
+            if fl.file_name != "mod.rs" {
+                let mut modfiles = modmap.get(&fl.mod_name).unwrap().clone();
+                modfiles.push(fl.file_name.clone());
+                modmap.insert(fl.mod_name.clone(), modfiles);
+            }
+
+            if let Some(text) = render_rust_file(&self.ctx, &fl) {
+                outputs.push(GeneratedFile::new(
+                    &format!("src/{}/{}", fl.mod_name, fl.file_name),
+                    &text,
+                    WritePolicy::Overwrite,
+                ));
+            }
 
-            if !modpath.exists() {
-                // create the path
-                create_dir(modpath.clone())?;
+            if fl.mod_name == "graphql" {
+                // GraphQL的端点挂载到web框架中
+                for func in fl.funclist.iter().filter(|f| f.api_method.is_some()) {
+                    if self.ctx.is_axum() {
+                        if let Some(route) = format_axum_route(func, &fl.mod_name) {
+                            service_func.push(route);
+                        }
+                    } else {
+                        service_func
+                            .push(format!("crate::{}::{}", fl.mod_name, func.func_name).to_string());
+                    }
+                }
             }
 
-            let filename = modpath.join(fl.file_name.clone());
-            fl.write_out(&filename.to_str().unwrap_or_default().to_string())?;
+            if fl.mod_name == "openapi" {
+                // Swagger UI挂载到web框架中，axum的SwaggerUi可以直接merge到Router中
+                for func in fl.funclist.iter() {
+                    if self.ctx.is_axum() {
+                        service_func.push(format!(".merge(crate::{}::{}())", fl.mod_name, func.func_name));
+                    } else {
+                        service_func.push(format!("crate::{}::{}()", fl.mod_name, func.func_name));
+                    }
+                }
+            }
 
             if fl.mod_name == "handler" {
                 for func in fl.funclist {
-                    service_func
-                        .push(format!("crate::{}::{}", fl.mod_name, func.func_name).to_string());
+                    if self.ctx.is_axum() {
+                        // axum没有service，按api_method和api_pattern生成Router的路由
+                        if let Some(route) = format_axum_route(&func, &fl.mod_name) {
+                            service_func.push(route);
+                        }
+                    } else {
+                        service_func
+                            .push(format!("crate::{}::{}", fl.mod_name, func.func_name).to_string());
+                    }
                 }
             }
         }
 
-        let mut mainmods: Vec<String> = Vec::new(); //生成用于main.rs的mod声明
-
-        for mkey in modmap {
+        if self.ctx.is_generate_grpc() {
+            // proto由build.rs编译，生成的代码由grpc/pb.rs引入
+            let mut protolist = vec![];
+            for proto in self.protos.iter() {
+                outputs.push(GeneratedFile::new(
+                    &format!("proto/{}", proto.file_name),
+                    &proto.to_source(),
+                    WritePolicy::Overwrite,
+                ));
+                protolist.push(proto.file_name.clone());
+            }
+            outputs.push(GeneratedFile::new(
+                "build.rs",
+                &crate::tmpl::format_grpc_build_template(protolist),
+                overwrite,
+            ));
+            outputs.push(GeneratedFile::new(
+                "src/grpc/pb.rs",
+                &format_grpc_pb_source(&self.ctx),
+                WritePolicy::Overwrite,
+            ));
+            modmap.entry("grpc".to_string()).or_default().insert(0, "pb.rs".to_string());
+        }
+
+        let mut mainmods: Vec<String> = Vec::new(); //生成用于main.rs或lib.rs的mod声明
+
+        // 按mod名称排序，使每次生成的main.rs/lib.rs保持一致
+        let mut modlist = modmap.into_iter().collect::<Vec<(String, Vec<String>)>>();
+        modlist.sort_by(|a, b| a.0.cmp(&b.0));
+        for mkey in modlist {
             let mn = mkey.0.clone();
             mainmods.push(mn.clone());
-            let tj = src.join(mkey.0.clone()).join("mod.rs"); // Generate the mod.rs for each folder
-            let tjfile = if self.ctx.codegen_conf.always_override {
-                OpenOptions::new()
-                    .write(true)
-                    .append(false)
-                    .create(true)
-                    .truncate(true)
-                    .open(tj.as_path())
+            if mkey.1.is_empty() {
+                continue;
+            }
+            // Generate the mod.rs for each folder
+            let mut modtext = String::new();
+            for ln in mkey.1.clone() {
+                let nameonly = ln.substring(0, ln.len() - 3);
+                modtext.push_str(format!("mod {};\n", nameonly).as_str());
+                modtext.push_str(format!("pub use {}::*;\n", nameonly).as_str());
+                modtext.push_str("\r\n");
+            }
+            outputs.push(GeneratedFile::new(&format!("src/{}/mod.rs", mn), &modtext, overwrite));
+        }
+
+        if self.ctx.is_generate_lib() {
+            // 生成库，由宿主应用通过configure挂载所有的服务
+            let libtext = if self.ctx.is_axum() {
+                crate::tmpl::format_axum_lib_template(mainmods.clone(), service_func.clone())
             } else {
-                OpenOptions::new()
-                    .write(true)
-                    .append(false)
-                    .create_new(true)
-                    .truncate(true)
-                    .open(tj.as_path())
+                crate::tmpl::format_lib_template(mainmods.clone(), service_func.clone())
             };
-            match tjfile {
-                Ok(mjfile) => {
-                    let mut mutfile = mjfile;
-                    for ln in mkey.1.clone() {
-                        let nameonly = ln.substring(0, ln.len() - 3);
-                        let modfmt = format!("mod {};\n", nameonly);
-                        let usingfmt = format!("pub use {}::*;\n", nameonly);
-                        mutfile.write_all(modfmt.as_bytes())?;
-                        mutfile.write_all(usingfmt.as_bytes())?;
-                        mutfile.write_all("\r\n".as_bytes())?;
-                    }
-                    mutfile.flush()?;
-                }
-                Err(err) => {
-                    if err.kind() == ErrorKind::AlreadyExists {
-                        log::info!("Skipped the existed file.");
-                    } else {
-                        log::info!("File was not create/opened. Becuase {}", err);
-                    }
-                }
+            let libtext = if self.tests.is_empty() {
+                libtext
+            } else {
+                crate::tmpl::add_test_mod(&libtext)
+            };
+            if let Some(text) = render_rust_lib(&self.ctx, &mainmods, &service_func, &libtext) {
+                outputs.push(GeneratedFile::new("src/lib.rs", &text, overwrite));
+            }
+        } else {
+            let maintext = if self.ctx.is_axum() {
+                crate::tmpl::format_axum_main_template(mainmods.clone(), service_func.clone())
+            } else {
+                crate::tmpl::format_main_template(mainmods.clone(), service_func.clone())
+            };
+            let maintext = if self.ctx.is_sqlx() {
+                crate::tmpl::replace_sqlx_main(&maintext)
+            } else {
+                maintext
+            };
+            let maintext = if self.tests.is_empty() {
+                maintext
+            } else {
+                crate::tmpl::add_test_mod(&maintext)
+            };
+            if let Some(text) = render_rust_main(&self.ctx, &mainmods, &service_func, &maintext) {
+                outputs.push(GeneratedFile::new("src/main.rs", &text, overwrite));
+            }
+        }
+
+        if let Some(doc) = self.openapi.as_ref() {
+            outputs.push(GeneratedFile::new(
+                "openapi.json",
+                &format_openapi_json(doc),
+                WritePolicy::Overwrite,
+            ));
+            outputs.push(GeneratedFile::new(
+                "openapi.yaml",
+                &format_openapi_yaml(doc),
+                WritePolicy::Overwrite,
+            ));
+        }
+
+        if !self.client.is_empty() {
+            let prefix = format!("{}-client", self.ctx.codegen_conf.app_name);
+            let codegen = &self.ctx.codegen_conf;
+            for (name, text) in [
+                ("Cargo.toml", crate::tmpl::format_client_cargo_toml(codegen)),
+                ("src/lib.rs", crate::tmpl::format_client_lib(codegen)),
+                ("src/base.rs", crate::tmpl::CLIENT_BASE_TMPL.to_string()),
+                ("src/error.rs", crate::tmpl::CLIENT_ERROR_TMPL.to_string()),
+            ] {
+                outputs.push(GeneratedFile::new(
+                    &format!("{}/{}", prefix, name),
+                    &text,
+                    WritePolicy::Overwrite,
+                ));
+            }
+            for fl in self.client.iter() {
+                outputs.push(GeneratedFile::new(
+                    &format!("{}/src/{}", prefix, fl.file_name),
+                    &fl.to_source(),
+                    WritePolicy::Overwrite,
+                ));
+            }
+        }
+
+        if !self.tests.is_empty() {
+            // src/tests只在cfg(test)时编译，由main.rs/lib.rs中的mod tests引入
+            let mut modtext = "mod common;\n".to_string();
+            for fl in self.tests.iter() {
+                modtext.push_str(&format!("mod {};\n", fl.file_name.trim_end_matches(".rs")));
+                outputs.push(GeneratedFile::new(
+                    &format!("src/tests/{}", fl.file_name),
+                    &fl.to_source(),
+                    WritePolicy::Overwrite,
+                ));
             }
+            outputs.push(GeneratedFile::new("src/tests/mod.rs", &modtext, WritePolicy::Overwrite));
+            outputs.push(GeneratedFile::new(
+                "src/tests/common.rs",
+                &crate::tmpl::format_test_common_template(self.ctx.is_standalone(), self.ctx.is_sqlx()),
+                WritePolicy::Overwrite,
+            ));
         }
 
-        let main = src.join("main.rs");
-        self.write_content(
-            &main.to_str().unwrap_or_default().to_string(),
-            crate::tmpl::format_main_template(mainmods, service_func).as_str(),
-        )?;
+        // model-first时为声明的表生成建表语句
+        outputs.append(&mut generate_ddl_files(&self.ctx));
 
         for fl in self.vuejs.clone() {
-            if fl.js_vue == true {
-                let schpath = frontviewpath
-                    .clone()
-                    .join(self.ctx.codegen_conf.schema_name.clone());
-                if !schpath.exists() {
-                    // should create the path
-                    create_dir(schpath.clone())?;
-                }
-                let modpath = schpath.clone().join(fl.mod_name.clone());
-                if !modpath.exists() {
-                    // should create the path
-                    create_dir(modpath.clone())?;
-                }
-                let filename = modpath.join(fl.file_name.clone());
-                fl.write_out(&filename.to_str().unwrap_or_default().to_string())?;
+            let filename = if fl.js_vue == true {
+                format!(
+                    "front/views/{}/{}/{}",
+                    self.ctx.codegen_conf.schema_name, fl.mod_name, fl.file_name
+                )
             } else {
-                let modpath = frontapipath
-                    .clone()
-                    .join(self.ctx.codegen_conf.schema_name.clone());
-                if !modpath.exists() {
-                    // should create the path
-                    create_dir(modpath.clone())?;
-                }
-                let filename = modpath.join(fl.file_name.clone());
-                fl.write_out(&filename.to_str().unwrap_or_default().to_string())?;
+                format!(
+                    "front/api/{}/{}",
+                    self.ctx.codegen_conf.schema_name, fl.file_name
+                )
+            };
+            outputs.push(GeneratedFile::new(&filename, &fl.to_source(), WritePolicy::Overwrite));
+        }
+
+        for gf in outputs.iter_mut() {
+            gf.policy = self.ctx.codegen_conf.get_file_policy(&gf.path, gf.policy);
+            if gf.policy == WritePolicy::Merge && gf.path.ends_with(".rs") {
+                gf.content = insert_keep_regions(&gf.content);
             }
         }
 
-        Ok(())
+        outputs
     }
 
-    fn write_content(&self, filename: &String, content: &str) -> std::io::Result<()> {
-        let file = if self.ctx.codegen_conf.always_override {
-            OpenOptions::new()
-                .write(true)
-                .append(false)
-                .create(true)
-                .truncate(true)
-                .open(filename)
-        } else {
-            OpenOptions::new()
-                .write(true)
-                .append(false)
-                .create_new(true)
-                .truncate(true)
-                .open(filename)
-        };
-        match file {
-            Ok(mjfile) => {
-                let mut mutfile = mjfile;
-                mutfile.write_all(content.as_bytes())?;
-                mutfile.flush()?;
-            }
-            Err(err) => {
-                if err.kind() == ErrorKind::AlreadyExists {
-                    log::info!("Skipped the existed file.");
-                } else {
-                    log::info!("File was not create/opened. Becuase {}", err);
-                }
-            }
-        };
-        Ok(())
+    /**
+     * 将生成的文件写入到输出目标
+     */
+    pub fn write_out(&self, sink: &mut dyn OutputSink) -> std::io::Result<Vec<GeneratedFile>> {
+        let outputs = self.collect_outputs();
+        for gf in outputs.iter() {
+            sink.write_generated(gf)?;
+        }
+        sink.finish()?;
+        Ok(outputs)
     }
 
     /**
-     * 将Permission写入到数据库
+     * 将seed子命令生成的演示数据写入到输出目标，只需要load_tables，不需要generate
      */
-    pub async fn write_permission(&self) {
-        let rb = get_rbatis();
-        for ele in self.ctx.permissions.clone() {
-            let mut perm = ChimesPermissionInfo {
-                id: None,
-                alias: Some(ele.alias.clone()),
-                create_time: None,
-                name: Some(ele.name.clone()),
-                pid: Some(0i64),
-                api_pattern: ele.api_pattern,
-                service_id: Some(ele.service_id.clone()),
-                api_method: ele.api_method,
-                api_bypass: ele.api_bypass,
-            };
-            // log::info!("Permission: {} {}", ele.name.clone(), ele.alias.clone());
-
-            let mut query = ChimesPermissionInfo::default();
-            query.alias = Some(ele.alias.clone());
-            query.service_id = Some(ele.service_id.clone());
-            let stid = match query.query_list(rb).await {
-                Ok(rs) => {
-                    if rs.len() > 0 {
-                        let mut mp = rs[0].clone();
-                        mp.name = perm.name.clone();
-                        // mp.api_bypass = perm.api_bypass.clone();
-                        mp.api_method = perm.api_method.clone();
-                        mp.api_pattern = perm.api_pattern.clone();
-                        match mp.update(rb).await {
-                            Ok(_r) => rs[0].id.unwrap_or_default(),
-                            Err(_) => rs[0].id.unwrap_or_default(),
-                        }
+    pub fn write_seed(&self, sink: &mut dyn OutputSink) -> std::io::Result<Vec<GeneratedFile>> {
+        let outputs = generate_seed_files(&self.ctx);
+        for gf in outputs.iter() {
+            sink.write_generated(gf)?;
+        }
+        sink.finish()?;
+        Ok(outputs)
+    }
+}
+
+/**
+ * 将use a::{b, c}拆分为use a::b和use a::c，以便合并文件时去重
+ * 嵌套的分组保持原样
+ */
+fn split_using_group(us: &String) -> Vec<String> {
+    let text = us.trim();
+    match (text.find("::{"), text.ends_with('}')) {
+        (Some(pos), true) => {
+            let inner = &text[pos + 3..text.len() - 1];
+            if inner.contains('{') {
+                return vec![text.to_string()];
+            }
+            inner
+                .split(',')
+                .map(|it| it.trim())
+                .filter(|it| !it.is_empty())
+                .map(|it| {
+                    if it == "self" {
+                        text[..pos].to_string()
                     } else {
-                        match perm.save(rb).await {
-                            Ok(_r) => perm.id.unwrap(),
-                            Err(err) => {
-                                log::info!("Error: {}", err.to_string());
-                                0i64
-                            }
-                        }
+                        format!("{}::{}", &text[..pos], it)
                     }
-                }
-                Err(err) => {
-                    log::info!("Error: {}", err.to_string());
-                    0i64
-                }
-            };
-            if stid != 0i64 {
-                for chl in ele.children.clone() {
-                    let mut chperm = ChimesPermissionInfo {
-                        id: None,
-                        alias: Some(chl.alias.clone()),
-                        create_time: None,
-                        name: Some(chl.name.clone()),
-                        pid: Some(stid),
-                        api_pattern: chl.api_pattern,
-                        service_id: Some(chl.service_id.clone()),
-                        api_method: chl.api_method,
-                        api_bypass: chl.api_bypass,
-                    };
-                    match chperm.save_or_update(rb).await {
-                        Ok(_) => {}
-                        Err(err) => {
-                            log::info!("Error: {}", err.to_string());
-                        }
-                    }
-                }
-            }
+                })
+                .collect()
         }
+        _ => vec![text.to_string()],
     }
 }
 
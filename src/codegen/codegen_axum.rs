@@ -0,0 +1,131 @@
+use crate::codegen::{RustFileImpl, RustFunc};
+
+/**
+ * axum的handler
+ * handler先按actix-web的方式生成，再转换为axum的形式，实体等其它代码与actix-web共用：
+ * 1、去掉#[get]/#[post]宏，路由由main.rs/lib.rs中的Router根据api_method和api_pattern注册
+ * 2、参数转换为axum的提取器，State/Extension/Path/Uri在前，Json作为请求体放在最后
+ * 3、SystemUser<ChimesUserInfo>从Extension中获取，需要由认证的中间件放入到请求的extensions中
+ * 4、get_rbatis()改为从State<AppState>中获取
+ * 5、返回值由Result<HttpResponse>改为Response
+ */
+pub fn convert_handler_file_to_axum(fl: &mut RustFileImpl) {
+    let mut extracts = vec![];
+    let mut has_uri = false;
+    let mut has_state = false;
+    for func in fl.funclist.iter_mut() {
+        *func = convert_handler_to_axum(func);
+        for pm in func.params.iter() {
+            for ex in ["State", "Extension", "Path", "Query"] {
+                if pm.1.starts_with(format!("{}<", ex).as_str()) && !extracts.contains(&ex) {
+                    extracts.push(ex);
+                }
+            }
+            has_uri = has_uri || pm.1 == "Uri";
+            has_state = has_state || pm.1.starts_with("State<");
+        }
+    }
+
+    let still_get_rbatis = fl
+        .funclist
+        .iter()
+        .any(|f| f.bodylines.iter().any(|l| l.contains("get_rbatis()")));
+    fl.usinglist.retain(|us| {
+        !us.starts_with("actix_web") && (still_get_rbatis || us != "chimes_utils::get_rbatis")
+    });
+
+    if !extracts.is_empty() {
+        fl.usinglist.push(format!("axum::extract::{{{}}}", extracts.join(", ")));
+    }
+    if has_uri {
+        fl.usinglist.push("axum::http::Uri".to_string());
+    }
+    fl.usinglist.push("axum::response::{IntoResponse, Response}".to_string());
+    fl.usinglist.push("axum::Json".to_string());
+    if has_state {
+        fl.usinglist.push("crate::utils::AppState".to_string());
+    }
+}
+
+pub fn convert_handler_to_axum(func: &RustFunc) -> RustFunc {
+    let mut params = vec![];
+    let mut body = vec![];
+    let mut renames = vec![];
+
+    if func.bodylines.iter().any(|l| l.contains("get_rbatis()")) {
+        params.push((0, "State(state)".to_string(), "State<AppState>".to_string()));
+    }
+
+    for (name, ptype) in func.params.iter() {
+        if ptype == "SystemUser<ChimesUserInfo>" {
+            params.push((1, format!("Extension({})", name), format!("Extension<{}>", ptype)));
+        } else if ptype.starts_with("web::Path<") {
+            params.push((2, format!("Path({})", name), ptype.replacen("web::", "", 1)));
+            renames.push((format!("{}.into_inner()", name), name.clone()));
+        } else if ptype.starts_with("web::Query<") {
+            params.push((2, format!("Query({})", name), ptype.replacen("web::", "", 1)));
+        } else if ptype == "HttpRequest" {
+            params.push((2, name.clone(), "Uri".to_string()));
+            renames.push((
+                format!("{}.query_string()", name),
+                format!("{}.query().unwrap_or_default()", name),
+            ));
+        } else if ptype.starts_with("web::Json<") {
+            params.push((9, format!("Json({})", name), ptype.replacen("web::", "", 1)));
+        } else {
+            params.push((5, name.clone(), ptype.clone()));
+        }
+    }
+    params.sort_by_key(|p| p.0);
+
+    for line in func.bodylines.iter() {
+        let mut ln = line
+            .replace("get_rbatis()", "state.rb")
+            .replace("Ok(HttpResponse::Ok().json(ret))", "ret.into_response()")
+            .replace("web::Json", "Json");
+        for (from, to) in renames.iter() {
+            ln = ln.replace(from.as_str(), to.as_str());
+        }
+        body.push(ln);
+    }
+
+    RustFunc {
+        is_pub: true,
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("Response".to_string()),
+        params: params.into_iter().map(|p| (p.1, p.2)).collect(),
+        bodylines: body,
+        macros: func
+            .macros
+            .iter()
+            .filter(|mc| !mc.starts_with("#[get(") && !mc.starts_with("#[post("))
+            .cloned()
+            .collect(),
+        api_pattern: func.api_pattern.clone().map(|pt| to_axum_pattern(&pt)),
+        ..func.clone()
+    }
+}
+
+/**
+ * actix-web的路径参数为{id}，axum为:id
+ */
+pub fn to_axum_pattern(pattern: &str) -> String {
+    pattern.replace('{', ":").replace('}', "")
+}
+
+/**
+ * 生成Router中的一个路由，如 .route("/api/v1/user/get/:id", get(crate::handler::user_get))
+ */
+pub fn format_axum_route(func: &RustFunc, mod_name: &str) -> Option<String> {
+    match (func.api_method.clone(), func.api_pattern.clone()) {
+        (Some(method), Some(pattern)) => Some(format!(
+            ".route(\"{}\", {}(crate::{}::{}))",
+            pattern,
+            method.to_lowercase(),
+            mod_name,
+            func.func_name
+        )),
+        _ => None,
+    }
+}
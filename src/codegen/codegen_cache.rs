@@ -0,0 +1,519 @@
+use crate::codegen::{
+    GenerateContext, RustArtifact, RustFileImpl, RustFunc, RustStruct, RustStructField,
+};
+use crate::config::TableConfig;
+use change_case::{pascal_case, snake_case};
+use regex::Regex;
+
+/**
+ * cache-by-fields中配置的列
+ */
+pub fn get_cache_by_columns(tbc: &TableConfig) -> Vec<String> {
+    tbc.cache_by_fields
+        .clone()
+        .unwrap_or_default()
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/**
+ * 多租户时，实体中的租户字段（company_id、company_code）
+ * 缓存的key及load_by_{field}的条件都需要包括租户字段，不同租户中相同的值不会相互覆盖
+ */
+fn get_tenant_fields(ctx: &GenerateContext, fields: &[RustStructField]) -> Vec<RustStructField> {
+    if !ctx.codegen_conf.multi_tenancy {
+        return vec![];
+    }
+    fields
+        .iter()
+        .filter(|f| {
+            let col = f.column_name.to_lowercase();
+            col == "company_id" || col == "company_code"
+        })
+        .cloned()
+        .collect()
+}
+
+/**
+ * 生成按缓存字段查询的load_by_{field}
+ * 按wrapper的方式生成，在转换为rbatis 4.x/sqlx时与其它的查询一起转换
+ */
+pub fn generate_func_load_by_cache_fields(
+    ctx: &GenerateContext,
+    tbconf: &TableConfig,
+    fields: &Vec<RustStructField>,
+    exists: &Vec<RustFunc>,
+) -> Vec<RustFunc> {
+    let tenants = get_tenant_fields(ctx, fields);
+    let mut funclist = vec![];
+    for colname in get_cache_by_columns(tbconf) {
+        let fd = match fields
+            .iter()
+            .find(|f| f.column_name.to_lowercase() == colname.to_lowercase())
+        {
+            Some(f) => f,
+            None => {
+                log::warn!(
+                    "The cache-by-fields column {} was not found in table {}.",
+                    colname,
+                    tbconf.name
+                );
+                continue;
+            }
+        };
+        let func_name = format!("load_by_{}", snake_case(&fd.column_name));
+        if exists.iter().any(|f| f.func_name == func_name) {
+            continue;
+        }
+        let mut params = vec![("rb".to_string(), "&Rbatis".to_string())];
+        let mut body = vec!["let wp = rb.new_wrapper()".to_string()];
+        for tf in tenants.iter().filter(|t| t.field_name != fd.field_name) {
+            params.push((tf.field_name.clone(), format!("&{}", tf.field_type)));
+            body.push(format!("    .eq(\"{}\", {})", tf.column_name, tf.field_name));
+        }
+        params.push((fd.field_name.clone(), format!("&{}", fd.field_type)));
+        body.push(format!("    .eq(\"{}\", {});", fd.column_name, fd.field_name));
+        body.push("rb.fetch_by_wrapper::<Option<Self>>(wp).await".to_string());
+        funclist.push(RustFunc {
+            is_struct_fn: true,
+            is_self_fn: false,
+            is_self_mut: false,
+            is_pub: true,
+            is_async: true,
+            func_name,
+            return_is_option: true,
+            return_is_result: true,
+            return_type: Some("Self".to_string()),
+            params,
+            bodylines: body,
+            macros: vec!["#[allow(dead_code)]".to_string()],
+            comment: Some(tbconf.comment.clone()),
+            api_method: None,
+            api_pattern: None,
+        });
+    }
+    funclist
+}
+
+/**
+ * Redis缓存
+ * 在ORM转换之后进行，为配置了cache-by-fields的实体加入：
+ * 1、cache_get_by_{field}、cache_set、cache_del，缓存的key为{table}:{column}:{value}
+ * 2、load_by_{field}_cached，先读缓存，不存在时调用load_by_{field}并写入缓存
+ * 3、cache_set同时将写入的key记录在{table}:cached:{pk}及{table}:cached中，
+ *    cache_del按主键删除记录的key，字段的值被修改或者为None时旧的key也能失效
+ * 4、save/update/update_selective/remove/restore/purge成功后调用cache_del，
+ *    remove_ids调用cache_del_ids，remove_batch/remove_not_ids调用cache_clear
+ * 5、以上的失效在事务中执行，提交前其它的请求仍可能读到旧的数据并写回缓存，
+ *    因此Handler及Service中在事务提交成功后再执行一次失效
+ * 6、多租户时缓存的key为{table}:{company_id}:{column}:{value}
+ */
+pub fn apply_redis_cache(ctx: &GenerateContext, files: &mut Vec<RustFileImpl>) {
+    let mut applied = vec![];
+    for fl in files.iter_mut() {
+        let table = match fl.artifact.clone() {
+            Some(RustArtifact::Entity(t)) => t,
+            _ => continue,
+        };
+        let tbconf = match ctx.get_table_conf(&table) {
+            Some(t) => t,
+            None => continue,
+        };
+        if get_cache_by_columns(&tbconf).is_empty() {
+            continue;
+        }
+        let struct_name = match ctx.get_struct_name(&table) {
+            Some(t) => t,
+            None => pascal_case(&table),
+        };
+        if let Some(st) = fl
+            .structlist
+            .iter_mut()
+            .find(|s| s.struct_name == struct_name)
+        {
+            let pkfields = ctx
+                .get_table_column_by_primary_key(&table)
+                .iter()
+                .filter_map(|col| {
+                    let colname = col.column_name.clone().unwrap_or_default();
+                    st.fields
+                        .iter()
+                        .find(|f| f.column_name.to_lowercase() == colname.to_lowercase())
+                        .map(|f| (f.field_name.clone(), f.is_option))
+                })
+                .collect::<Vec<(String, bool)>>();
+            let tenants = get_tenant_fields(ctx, &st.fields);
+            apply_redis_cache_to_struct(&tbconf, &pkfields, &tenants, st);
+            let has_del_ids = st.funclist.iter().any(|f| f.func_name == "cache_del_ids");
+            applied.push((table, struct_name, has_del_ids));
+        }
+    }
+
+    for fl in files.iter_mut() {
+        let table = match fl.artifact.clone() {
+            Some(RustArtifact::Handler(t)) | Some(RustArtifact::Service(t)) => t,
+            _ => continue,
+        };
+        if let Some((_, struct_name, has_del_ids)) = applied.iter().find(|(t, _, _)| t == &table) {
+            for func in fl
+                .funclist
+                .iter_mut()
+                .chain(fl.structlist.iter_mut().flat_map(|st| st.funclist.iter_mut()))
+            {
+                apply_cache_invalidation_after_commit(func, struct_name, *has_del_ids);
+            }
+        }
+    }
+
+    for tbc in ctx.codegen_conf.tables.iter() {
+        if !get_cache_by_columns(tbc).is_empty() && !applied.iter().any(|(t, _, _)| t == &tbc.name) {
+            log::warn!(
+                "The entity of table {} was not found, the cache methods were not generated.",
+                tbc.name
+            );
+        }
+    }
+}
+
+fn apply_redis_cache_to_struct(
+    tbconf: &TableConfig,
+    pkfields: &Vec<(String, bool)>,
+    tenants: &[RustStructField],
+    st: &mut RustStruct,
+) {
+    // (列名，字段名，字段类型，是否为Option)
+    let mut cachefields = vec![];
+    for colname in get_cache_by_columns(tbconf) {
+        match st
+            .fields
+            .iter()
+            .find(|f| f.column_name.to_lowercase() == colname.to_lowercase())
+        {
+            Some(fd) => cachefields.push((
+                fd.column_name.clone(),
+                fd.field_name.clone(),
+                fd.field_type.clone(),
+                fd.is_option,
+            )),
+            None => continue,
+        }
+    }
+    if cachefields.is_empty() {
+        return;
+    }
+
+    // 按主键记录写入的key，主键为None时不记录
+    let all_index = format!("\"{}:cached\"", tbconf.name);
+    let (pk_open, pk_index) = cache_index_of_pk(&tbconf.name, pkfields);
+
+    let mut setlines = vec![];
+    let mut dellines = vec![];
+    let mut funclist = vec![];
+    for (column, field, ftype, is_option) in cachefields.iter() {
+        let tenants = tenants
+            .iter()
+            .filter(|t| &t.field_name != field)
+            .collect::<Vec<&RustStructField>>();
+        let key = format!(
+            "&format!(\"{}:{}{}:{{}}\", {}val)",
+            tbconf.name,
+            "{}:".repeat(tenants.len()),
+            column,
+            tenants
+                .iter()
+                .map(|t| format!("{}, ", t.field_name))
+                .collect::<String>()
+        );
+        let suffix = snake_case(column);
+        let mut params = tenants
+            .iter()
+            .map(|t| (t.field_name.clone(), format!("&{}", t.field_type)))
+            .collect::<Vec<(String, String)>>();
+        params.push(("val".to_string(), format!("&{}", ftype)));
+        funclist.push(RustFunc {
+            is_struct_fn: true,
+            is_self_fn: false,
+            is_self_mut: false,
+            is_pub: true,
+            is_async: true,
+            func_name: format!("cache_get_by_{}", suffix),
+            return_is_option: true,
+            return_is_result: false,
+            return_type: Some("Self".to_string()),
+            params,
+            bodylines: vec![format!(
+                "crate::utils::redis_cache_get::<Self>({}).await",
+                key
+            )],
+            macros: vec!["#[allow(dead_code)]".to_string()],
+            comment: Some(tbconf.comment.clone()),
+            api_method: None,
+            api_pattern: None,
+        });
+
+        // 租户字段及缓存字段都有值时才写入或删除缓存
+        let mut opens = vec![];
+        for (name, is_option) in tenants
+            .iter()
+            .map(|t| (t.field_name.clone(), t.is_option))
+            .chain([(field.clone(), *is_option)])
+        {
+            let bind = if &name == field { "val".to_string() } else { name.clone() };
+            if is_option {
+                opens.push(format!("if let Some({}) = self.{}.as_ref() {{", bind, name));
+            } else {
+                opens.push("{".to_string());
+                opens.push(format!("let {} = &self.{};", bind, name));
+            }
+        }
+        let closes = opens.iter().filter(|l| l.ends_with('{')).count();
+        setlines.append(&mut opens.clone());
+        dellines.append(&mut opens);
+        setlines.push(format!(
+            "crate::utils::redis_cache_set({}, self, {}).await;",
+            key, tbconf.cache_ttl
+        ));
+        setlines.push(format!(
+            "crate::utils::redis_cache_track({}, {}, {}).await;",
+            all_index, key, tbconf.cache_ttl
+        ));
+        if let Some(open) = pk_open.clone() {
+            setlines.push(open);
+            setlines.push(format!(
+                "crate::utils::redis_cache_track({}, {}, {}).await;",
+                pk_index, key, tbconf.cache_ttl
+            ));
+            setlines.push("}".to_string());
+        }
+        dellines.push(format!("crate::utils::redis_cache_del({}).await;", key));
+        for _ in 0..closes {
+            setlines.push("}".to_string());
+            dellines.push("}".to_string());
+        }
+
+        // 只有load_by_{field}在转换后仍然存在时才生成读穿透的方法
+        let load_name = format!("load_by_{}", suffix);
+        if let Some(load) = st.funclist.iter().find(|f| f.func_name == load_name) {
+            let pname = load
+                .params
+                .iter()
+                .filter(|p| p.0 != "rb")
+                .map(|p| p.0.clone())
+                .collect::<Vec<String>>()
+                .join(", ");
+            let mut cached = load.clone();
+            cached.func_name = format!("{}_cached", load_name);
+            cached.bodylines = vec![
+                format!(
+                    "if let Some(cached) = Self::cache_get_by_{}({}).await {{",
+                    suffix, pname
+                ),
+                "return Ok(Some(cached));".to_string(),
+                "}".to_string(),
+                format!("let ret = Self::{}(rb, {}).await?;", load_name, pname),
+                "if let Some(t) = ret.as_ref() {".to_string(),
+                "t.cache_set().await;".to_string(),
+                "}".to_string(),
+                "Ok(ret)".to_string(),
+            ];
+            funclist.push(cached);
+        }
+    }
+
+    if let Some(open) = pk_open.clone() {
+        dellines.push(open);
+        dellines.push(format!("crate::utils::redis_cache_del_tracked({}).await;", pk_index));
+        dellines.push("}".to_string());
+    }
+
+    for (name, lines) in [("cache_set", setlines), ("cache_del", dellines)] {
+        funclist.push(RustFunc {
+            is_struct_fn: true,
+            is_self_fn: true,
+            is_self_mut: false,
+            is_pub: true,
+            is_async: true,
+            func_name: name.to_string(),
+            return_is_option: false,
+            return_is_result: false,
+            return_type: None,
+            params: vec![],
+            bodylines: lines,
+            macros: vec!["#[allow(dead_code)]".to_string()],
+            comment: Some(tbconf.comment.clone()),
+            api_method: None,
+            api_pattern: None,
+        });
+    }
+
+    // 删除主键为ids的缓存，只有单一的主键才有remove_ids
+    let ids_type = st
+        .funclist
+        .iter()
+        .find(|f| f.func_name == "remove_ids")
+        .and_then(|f| f.params.iter().find(|p| p.0 == "ids").map(|p| p.1.clone()));
+    if let Some(ids_type) = ids_type.clone() {
+        funclist.push(RustFunc {
+            is_struct_fn: true,
+            is_self_fn: false,
+            is_self_mut: false,
+            is_pub: true,
+            is_async: true,
+            func_name: "cache_del_ids".to_string(),
+            return_is_option: false,
+            return_is_result: false,
+            return_type: None,
+            params: vec![("ids".to_string(), ids_type)],
+            bodylines: vec![
+                "for id in ids.iter() {".to_string(),
+                format!(
+                    "crate::utils::redis_cache_del_tracked(&format!(\"{}:cached:{{}}\", id)).await;",
+                    tbconf.name
+                ),
+                "}".to_string(),
+            ],
+            macros: vec!["#[allow(dead_code)]".to_string()],
+            comment: Some(tbconf.comment.clone()),
+            api_method: None,
+            api_pattern: None,
+        });
+    }
+    funclist.push(RustFunc {
+        is_struct_fn: true,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: true,
+        func_name: "cache_clear".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: None,
+        params: vec![],
+        bodylines: vec![format!("crate::utils::redis_cache_del_tracked({}).await;", all_index)],
+        macros: vec!["#[allow(dead_code)]".to_string()],
+        comment: Some(tbconf.comment.clone()),
+        api_method: None,
+        api_pattern: None,
+    });
+
+    for func in st.funclist.iter_mut() {
+        let invalidation = match func.func_name.as_str() {
+            "save" | "update" | "update_selective" | "remove" | "restore" | "purge" if func.is_self_fn => {
+                "self.cache_del().await;"
+            }
+            "remove_ids" if ids_type.is_some() => "Self::cache_del_ids(ids).await;",
+            "remove_batch" | "remove_not_ids" => "Self::cache_clear().await;",
+            _ => continue,
+        };
+        wrap_cache_invalidation(func, invalidation);
+    }
+    st.funclist.append(&mut funclist);
+}
+
+/**
+ * 按主键记录缓存key的集合，返回判断主键存在的语句开头及集合的key
+ * 单一主键时为{table}:cached:{pk}，与cache_del_ids一致；联合主键时各主键的值以:隔开
+ */
+fn cache_index_of_pk(table: &str, pkfields: &Vec<(String, bool)>) -> (Option<String>, String) {
+    if pkfields.is_empty() {
+        return (None, String::new());
+    }
+    let opts = pkfields
+        .iter()
+        .filter(|(_, is_option)| *is_option)
+        .map(|(f, _)| f.clone())
+        .collect::<Vec<String>>();
+    let open = if opts.is_empty() {
+        "{".to_string()
+    } else {
+        format!(
+            "if {} {{",
+            opts.iter()
+                .map(|f| format!("self.{}.is_some()", f))
+                .collect::<Vec<String>>()
+                .join(" && ")
+        )
+    };
+    let args = pkfields
+        .iter()
+        .map(|(f, is_option)| {
+            if *is_option {
+                format!("self.{}.clone().unwrap()", f)
+            } else {
+                format!("self.{}", f)
+            }
+        })
+        .collect::<Vec<String>>();
+    let index = format!(
+        "&format!(\"{}:cached{}\", {})",
+        table,
+        ":{}".repeat(args.len()),
+        args.join(", ")
+    );
+    (Some(open), index)
+}
+
+/**
+ * 在事务提交成功后再次使缓存失效
+ * 根据方法体中在事务上调用的实体方法确定失效的方式，
+ * 失效的语句放在tx.commit().await?;之后，或match tx.commit().await的Ok(_)分支中
+ */
+fn apply_cache_invalidation_after_commit(func: &mut RustFunc, struct_name: &str, has_del_ids: bool) {
+    let selfcall = Regex::new(r"(\w+)\.(save|update|update_selective|remove|restore|purge)\(&mut tx\b").unwrap();
+    let staticcall = Regex::new(&format!(
+        r"\b{}::(remove_ids|remove_batch|remove_not_ids)\(&mut tx, (\w+)",
+        struct_name
+    ))
+    .unwrap();
+    let mut invalidation = None;
+    let mut body = vec![];
+    let mut lines = func.bodylines.iter().peekable();
+    while let Some(line) = lines.next() {
+        body.push(line.clone());
+        if let Some(cap) = selfcall.captures(line) {
+            invalidation = Some(format!("{}.cache_del().await;", &cap[1]));
+        } else if let Some(cap) = staticcall.captures(line) {
+            invalidation = Some(if &cap[1] == "remove_ids" && has_del_ids {
+                format!("{}::cache_del_ids({}).await;", struct_name, &cap[2])
+            } else {
+                format!("{}::cache_clear().await;", struct_name)
+            });
+        }
+        if !line.contains("tx.commit().await") || invalidation.is_none() {
+            continue;
+        }
+        if line.trim().starts_with("match ") {
+            match lines.peek() {
+                Some(next) if next.trim() == "Ok(_) => {" => body.push(lines.next().unwrap().clone()),
+                _ => continue,
+            }
+        } else if !line.trim().ends_with("?;") {
+            continue;
+        }
+        body.push(invalidation.take().unwrap());
+    }
+    func.bodylines = body;
+}
+
+/**
+ * 执行成功后使缓存失效
+ * 原来的方法体放在块中，其中的?仍然直接返回
+ */
+fn wrap_cache_invalidation(func: &mut RustFunc, invalidation: &str) {
+    let rettype = func.return_type.clone().unwrap_or("()".to_string());
+    let rettype = if func.return_is_option {
+        format!("Option<{}>", rettype)
+    } else {
+        rettype
+    };
+    let mut body = vec![format!("let ret: Result<{}, Error> = {{", rettype)];
+    body.append(&mut func.bodylines.clone());
+    body.push("};".to_string());
+    body.push("if ret.is_ok() {".to_string());
+    body.push(invalidation.to_string());
+    body.push("}".to_string());
+    body.push("ret".to_string());
+    func.bodylines = body;
+}
@@ -0,0 +1,308 @@
+use regex::Regex;
+
+use crate::codegen::{
+    strip_generic, RustFileImpl, RustFunc, RustStruct, RustStructField, RustTraitImpl,
+};
+
+/**
+ * Rust的客户端SDK（generate-client: true）
+ * 在输出目录中生成独立的{app-name}-client crate，基于reqwest：
+ * 1、models.rs：entity、query中的结构体（实体、XxxQuery、XxxValue、关系、自定义查询），保留serde的注解
+ * 2、api.rs：ApiClient的方法，每个handler一个同名的async方法，返回Result<T, Error>，T为ApiResult<T>中的T
+ * 3、base.rs、error.rs：ApiClient及Error、ApiResult、Page，来自client_tmpl
+ * 与TypeScript的API相同，在ORM及web框架的转换之前生成，转换时被去掉的handler不生成
+ */
+fn client_type(tp: &str, structs: &Vec<String>) -> String {
+    let tp = tp.trim().trim_start_matches('&').trim();
+    for wrapper in ["Option", "Vec"] {
+        if let Some(inner) = strip_generic(tp, wrapper) {
+            return format!("{}<{}>", wrapper, client_type(inner, structs));
+        }
+    }
+    if let Some(inner) = strip_generic(tp, "Page").or(strip_generic(tp, "rbatis::Page")) {
+        return format!("Page<{}>", client_type(inner, structs));
+    }
+    match tp.trim_start_matches("rbatis::") {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128" | "isize"
+        | "usize" | "f32" | "f64" | "bool" | "String" => tp.to_string(),
+        "str" => "String".to_string(),
+        // 日期、时间按服务端输出的字符串处理，避免格式不一致时无法解析
+        "DateTimeNative" | "DateTimeUtc" | "Timestamp" | "TimestampZ" | "DateNative"
+        | "DateUtc" | "TimeNative" | "TimeUtc" => "String".to_string(),
+        "Decimal" => "rust_decimal::Decimal".to_string(),
+        "Bytes" => "Vec<u8>".to_string(),
+        other => {
+            let name = other.rsplit("::").next().unwrap_or(other);
+            if structs.contains(&name.to_string()) {
+                name.to_string()
+            } else {
+                // CommonSearch、ChimesAttachmentInfo等客户端中没有的类型
+                "serde_json::Value".to_string()
+            }
+        }
+    }
+}
+
+fn client_field(fd: &RustStructField, structs: &Vec<String>) -> RustStructField {
+    let mut annotations = fd
+        .annotations
+        .iter()
+        .filter(|an| an.trim().starts_with("#[serde("))
+        .cloned()
+        .collect::<Vec<String>>();
+    // 服务端按列名反序列化，按字段名序列化
+    if fd.orignal_field_name.is_none()
+        && !fd.column_name.is_empty()
+        && fd.column_name != fd.field_name
+    {
+        annotations.push(format!(
+            "#[serde(rename(serialize = \"{}\"))]",
+            fd.column_name
+        ));
+    }
+    RustStructField {
+        is_pub: true,
+        column_name: String::new(),
+        field_type: client_type(&fd.field_type, structs),
+        annotations,
+        ..fd.clone()
+    }
+}
+
+fn collect_client_structs(files: &[RustFileImpl]) -> Vec<RustStruct> {
+    let mut structs: Vec<RustStruct> = vec![];
+    for st in files
+        .iter()
+        .filter(|f| f.mod_name == "entity" || f.mod_name == "query")
+        .flat_map(|f| f.structlist.iter())
+        .filter(|st| st.fields.iter().any(|fd| !fd.field_name.is_empty()))
+    {
+        if !structs.iter().any(|s| s.struct_name == st.struct_name) {
+            structs.push(st.clone());
+        }
+    }
+    structs
+}
+
+/**
+ * 生成models.rs
+ */
+pub fn generate_client_models(files: &[RustFileImpl]) -> RustFileImpl {
+    let structs = collect_client_structs(files);
+    let names = structs
+        .iter()
+        .map(|st| st.struct_name.clone())
+        .collect::<Vec<String>>();
+    let mut structlist = vec![];
+    for st in structs.iter() {
+        let mut annotations =
+            vec!["#[derive(Debug, Clone, Default, Deserialize, Serialize)]".to_string()];
+        annotations.extend(
+            st.annotations
+                .iter()
+                .filter(|an| an.trim().starts_with("#[serde("))
+                .cloned(),
+        );
+        structlist.push(RustStruct {
+            is_pub: true,
+            has_paging: false,
+            struct_name: st.struct_name.clone(),
+            annotations,
+            fields: st
+                .fields
+                .iter()
+                .filter(|fd| !fd.field_name.is_empty())
+                .map(|fd| client_field(fd, &names))
+                .collect(),
+            funclist: vec![],
+            usings: vec![],
+        });
+    }
+    let mut usinglist = vec!["serde_derive::{Deserialize, Serialize}".to_string()];
+    if structlist
+        .iter()
+        .any(|st| st.fields.iter().any(|fd| fd.field_type.contains("Page<")))
+    {
+        usinglist.push("crate::error::Page".to_string());
+    }
+    RustFileImpl {
+        file_name: "models.rs".to_string(),
+        mod_name: "models".to_string(),
+        caretlist: vec![],
+        usinglist,
+        structlist,
+        funclist: vec![],
+        implist: vec![],
+        artifact: None,
+    }
+}
+
+fn client_method(func: &RustFunc, structs: &Vec<String>) -> Option<RustFunc> {
+    let (method, pattern) = match (func.api_method.clone(), func.api_pattern.clone()) {
+        (Some(m), Some(p)) => (m.to_uppercase(), p),
+        _ => return None,
+    };
+    let mut params = vec![];
+    let mut body = "None::<&()>".to_string();
+    for (_, ptype) in func.params.iter() {
+        if let Some(inner) = strip_generic(ptype, "web::Json") {
+            let tp = client_type(inner, structs);
+            let tp = match strip_generic(&tp, "Vec") {
+                Some(item) => format!("&[{}]", item),
+                None => format!("&{}", tp),
+            };
+            params.push(("data".to_string(), tp));
+            body = "Some(data)".to_string();
+        }
+    }
+
+    // web::Path<(A, B)>按顺序对应路径中的各个{name}
+    let mut path_types = vec![];
+    for (_, ptype) in func.params.iter() {
+        if let Some(inner) = strip_generic(ptype, "web::Path") {
+            match inner.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                Some(tuple) => path_types.extend(tuple.split(',').map(|t| t.trim().to_string())),
+                None => path_types.push(inner.to_string()),
+            }
+        }
+    }
+    let reg = Regex::new(r"\{(\w+)\}").unwrap();
+    let mut path_args = vec![];
+    for (i, cap) in reg.captures_iter(&pattern).enumerate() {
+        let tp = client_type(
+            &path_types.get(i).cloned().unwrap_or("String".to_string()),
+            structs,
+        );
+        let tp = if tp == "String" {
+            "&str".to_string()
+        } else {
+            tp
+        };
+        params.push((cap[1].to_string(), tp));
+        path_args.push(cap[1].to_string());
+    }
+
+    let mut query = vec![];
+    let qreg = Regex::new(r#"get_hash_value\(&dic, "(\w+)"\)"#).unwrap();
+    for ln in func.bodylines.iter() {
+        for cap in qreg.captures_iter(ln) {
+            let name = cap[1].to_string();
+            if !params.iter().any(|(p, _)| *p == name) {
+                query.push(format!("(\"{}\", {})", name, name));
+                params.push((name, "Option<&str>".to_string()));
+            }
+        }
+    }
+
+    let rreg = Regex::new(r"web::Json<ApiResult<(.+)>> = web::Json\(ApiResult::ok\(").unwrap();
+    let return_type = func
+        .bodylines
+        .iter()
+        .find_map(|ln| rreg.captures(ln).map(|cap| client_type(&cap[1], structs)))
+        .unwrap_or("serde_json::Value".to_string());
+
+    let mut bodylines = vec![];
+    let path = if path_args.is_empty() {
+        format!("\"{}\"", pattern)
+    } else {
+        bodylines.push(format!(
+            "let path = format!(\"{}\", {});",
+            reg.replace_all(&pattern, "{}"),
+            path_args.join(", ")
+        ));
+        "&path".to_string()
+    };
+    bodylines.push(format!(
+        "self.send(reqwest::Method::{}, {}, {}, &[{}]).await",
+        method,
+        path,
+        body,
+        query.join(", ")
+    ));
+
+    let macros = match func.comment.clone().filter(|c| !c.trim().is_empty()) {
+        Some(cmt) => vec![format!("/// {}", cmt.replace('\n', " "))],
+        None => vec![],
+    };
+    Some(RustFunc {
+        is_struct_fn: true,
+        is_self_fn: true,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: true,
+        func_name: func.func_name.clone(),
+        return_is_option: false,
+        return_is_result: true,
+        return_type: Some(return_type),
+        params,
+        bodylines,
+        macros,
+        comment: func.comment.clone(),
+        api_method: None,
+        api_pattern: None,
+    })
+}
+
+/**
+ * 生成api.rs，surviving为ORM及web框架转换之后仍然存在的handler
+ */
+pub fn generate_client_api(files: &[RustFileImpl], surviving: &[String]) -> RustFileImpl {
+    let structs = collect_client_structs(files)
+        .iter()
+        .map(|st| st.struct_name.clone())
+        .collect::<Vec<String>>();
+    let funclist = files
+        .iter()
+        .filter(|f| f.mod_name == "handler")
+        .flat_map(|f| f.funclist.iter())
+        .filter(|f| surviving.contains(&f.func_name))
+        .filter_map(|f| client_method(f, &structs))
+        .collect::<Vec<RustFunc>>();
+
+    // 只引入用到的类型
+    let mut errors = vec!["Error".to_string()];
+    let mut models = vec![];
+    let reg = Regex::new(r"[A-Za-z_]\w*").unwrap();
+    for func in funclist.iter() {
+        let mut types = func
+            .params
+            .iter()
+            .map(|(_, tp)| tp.clone())
+            .collect::<Vec<String>>();
+        types.push(func.return_type.clone().unwrap_or_default());
+        for tp in types.iter() {
+            for m in reg.find_iter(tp) {
+                let name = m.as_str().to_string();
+                if name == "Page" && !errors.contains(&name) {
+                    errors.push(name);
+                } else if structs.contains(&name) && !models.contains(&name) {
+                    models.push(name);
+                }
+            }
+        }
+    }
+    errors.sort();
+    models.sort();
+    let mut usinglist = vec![format!("crate::error::{{{}}}", errors.join(", "))];
+    if !models.is_empty() {
+        usinglist.push(format!("crate::models::{{{}}}", models.join(", ")));
+    }
+    usinglist.push("crate::ApiClient".to_string());
+
+    RustFileImpl {
+        file_name: "api.rs".to_string(),
+        mod_name: "api".to_string(),
+        caretlist: vec![],
+        usinglist,
+        structlist: vec![],
+        funclist: vec![],
+        implist: vec![RustTraitImpl {
+            annotations: vec![],
+            trait_name: String::new(),
+            struct_name: "ApiClient".to_string(),
+            types: vec![],
+            funclist,
+        }],
+        artifact: None,
+    }
+}
@@ -0,0 +1,321 @@
+use crate::codegen::{GenerateContext, GeneratedFile};
+use crate::config::{ColumnConfig, TableConfig};
+use crate::output::WritePolicy;
+
+/**
+ * model-first的DDL
+ * 为配置中声明了列的表生成ddl/{dialect}.sql，dialect为ddl-dialects中的mysql、postgres、sqlite
+ * 列的类型按MySQL书写，其它数据库按以下的方式对应：
+ * 1、postgres：tinyint => BOOLEAN（与实体中的bool一致），自增 => GENERATED BY DEFAULT AS IDENTITY，
+ *    datetime => TIMESTAMP，blob => BYTEA，json => JSONB，注释使用COMMENT ON
+ * 2、sqlite：整数 => INTEGER，自增的主键 => INTEGER PRIMARY KEY AUTOINCREMENT，decimal => NUMERIC，
+ *    日期、时间及字符串 => TEXT，注释写在列的后面
+ * 索引在MySQL中写在CREATE TABLE中，其它数据库使用CREATE INDEX
+ */
+pub fn generate_ddl_files(ctx: &GenerateContext) -> Vec<GeneratedFile> {
+    let tables = ctx.codegen_conf.get_declared_tables();
+    if tables.is_empty() {
+        return vec![];
+    }
+    let mut files = vec![];
+    for dialect in ctx.codegen_conf.ddl_dialects.iter() {
+        let (title, text) = match dialect.as_str() {
+            "mysql" => ("MySQL", format_mysql_ddl(&tables)),
+            "postgres" | "postgresql" => ("PostgreSQL", format_postgres_ddl(&tables)),
+            "sqlite" => ("SQLite", format_sqlite_ddl(&tables)),
+            _ => {
+                log::warn!(
+                    "Unknown ddl dialect {}, the dialect should be mysql, postgres or sqlite.",
+                    dialect
+                );
+                continue;
+            }
+        };
+        let header = format!(
+            "-- 由代码生成器根据rbatis.yml中声明的表生成的{}建表语句\n",
+            title
+        );
+        files.push(GeneratedFile::new(
+            &format!("ddl/{}.sql", dialect),
+            &(header + &text),
+            WritePolicy::Overwrite,
+        ));
+    }
+    files
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+const NUMERIC_TYPES: [&str; 13] = [
+    "tinyint",
+    "smallint",
+    "mediumint",
+    "int",
+    "integer",
+    "bigint",
+    "bit",
+    "decimal",
+    "numeric",
+    "float",
+    "double",
+    "real",
+    "year",
+];
+
+/**
+ * 默认值：数字按原样，当前时间统一为CURRENT_TIMESTAMP，其它的按字符串
+ */
+fn default_literal(cc: &ColumnConfig, dialect: &str) -> Option<String> {
+    let value = cc.default_value.clone()?;
+    let upper = value.trim().to_uppercase();
+    if upper == "NULL" {
+        return Some("NULL".to_string());
+    }
+    if upper == "CURRENT_TIMESTAMP" || upper == "CURRENT_TIMESTAMP()" || upper == "NOW()" {
+        return Some("CURRENT_TIMESTAMP".to_string());
+    }
+    if NUMERIC_TYPES.contains(&cc.data_type.as_str()) && value.trim().parse::<f64>().is_ok() {
+        if dialect == "postgres" && cc.data_type == "tinyint" {
+            let on = value.trim() != "0";
+            return Some(if on { "TRUE" } else { "FALSE" }.to_string());
+        }
+        return Some(value.trim().to_string());
+    }
+    Some(sql_string(&value))
+}
+
+fn primary_keys(tbc: &TableConfig) -> Vec<String> {
+    tbc.primary_key
+        .split(',')
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+fn is_primary_key(tbc: &TableConfig, cc: &ColumnConfig) -> bool {
+    primary_keys(tbc)
+        .iter()
+        .any(|k| k.to_lowercase() == cc.name.to_lowercase())
+}
+
+fn format_mysql_ddl(tables: &Vec<TableConfig>) -> String {
+    let mut text = String::new();
+    for tbc in tables.iter() {
+        let mut lines = vec![];
+        for cc in tbc.columns.iter() {
+            let mut line = format!("  `{}` {}", cc.name, cc.column_type);
+            if !cc.nullable || is_primary_key(tbc, cc) {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(dv) = default_literal(cc, "mysql") {
+                line.push_str(&format!(" DEFAULT {}", dv));
+            }
+            if cc.auto_increment {
+                line.push_str(" AUTO_INCREMENT");
+            }
+            if !cc.comment.is_empty() {
+                line.push_str(&format!(" COMMENT {}", sql_string(&cc.comment)));
+            }
+            lines.push(line);
+        }
+        let pkeys = primary_keys(tbc);
+        if !pkeys.is_empty() {
+            lines.push(format!(
+                "  PRIMARY KEY ({})",
+                pkeys
+                    .iter()
+                    .map(|k| format!("`{}`", k))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        for idx in tbc.indexes.iter() {
+            lines.push(format!(
+                "  {}KEY `{}` ({})",
+                if idx.unique { "UNIQUE " } else { "" },
+                idx.name,
+                idx.columns
+                    .iter()
+                    .map(|c| format!("`{}`", c))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        text.push_str(&format!(
+            "\nCREATE TABLE IF NOT EXISTS `{}` (\n{}\n) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COMMENT={};\n",
+            tbc.name,
+            lines.join(",\n"),
+            sql_string(&tbc.comment)
+        ));
+    }
+    text
+}
+
+fn postgres_type(cc: &ColumnConfig) -> String {
+    match cc.data_type.as_str() {
+        "tinyint" => "BOOLEAN".to_string(),
+        "bit" | "smallint" | "year" => "SMALLINT".to_string(),
+        "mediumint" | "int" | "integer" => "INTEGER".to_string(),
+        "bigint" => "BIGINT".to_string(),
+        "decimal" | "numeric" => match cc.precision {
+            Some(p) => format!("NUMERIC({},{})", p, cc.scale.unwrap_or_default()),
+            None => "NUMERIC".to_string(),
+        },
+        "float" | "real" => "REAL".to_string(),
+        "double" => "DOUBLE PRECISION".to_string(),
+        "char" => format!("CHAR({})", cc.length.unwrap_or(1)),
+        "varchar" => format!("VARCHAR({})", cc.length.unwrap_or(255)),
+        "enum" | "set" => "VARCHAR(255)".to_string(),
+        "datetime" | "timestamp" => "TIMESTAMP".to_string(),
+        "date" => "DATE".to_string(),
+        "time" => "TIME".to_string(),
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => {
+            "BYTEA".to_string()
+        }
+        "json" => "JSONB".to_string(),
+        _ => "TEXT".to_string(),
+    }
+}
+
+fn format_postgres_ddl(tables: &Vec<TableConfig>) -> String {
+    let mut text = String::new();
+    for tbc in tables.iter() {
+        let mut lines = vec![];
+        for cc in tbc.columns.iter() {
+            let mut line = format!("  \"{}\" {}", cc.name, postgres_type(cc));
+            if cc.auto_increment {
+                line.push_str(" GENERATED BY DEFAULT AS IDENTITY");
+            }
+            if !cc.nullable || is_primary_key(tbc, cc) {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(dv) = default_literal(cc, "postgres") {
+                line.push_str(&format!(" DEFAULT {}", dv));
+            }
+            lines.push(line);
+        }
+        let pkeys = primary_keys(tbc);
+        if !pkeys.is_empty() {
+            lines.push(format!(
+                "  PRIMARY KEY ({})",
+                pkeys
+                    .iter()
+                    .map(|k| format!("\"{}\"", k))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        text.push_str(&format!(
+            "\nCREATE TABLE IF NOT EXISTS \"{}\" (\n{}\n);\n",
+            tbc.name,
+            lines.join(",\n")
+        ));
+        text.push_str(&format_create_indexes(tbc));
+        if !tbc.comment.is_empty() {
+            text.push_str(&format!(
+                "COMMENT ON TABLE \"{}\" IS {};\n",
+                tbc.name,
+                sql_string(&tbc.comment)
+            ));
+        }
+        for cc in tbc.columns.iter().filter(|c| !c.comment.is_empty()) {
+            text.push_str(&format!(
+                "COMMENT ON COLUMN \"{}\".\"{}\" IS {};\n",
+                tbc.name,
+                cc.name,
+                sql_string(&cc.comment)
+            ));
+        }
+    }
+    text
+}
+
+fn sqlite_type(cc: &ColumnConfig) -> &'static str {
+    match cc.data_type.as_str() {
+        "tinyint" | "bit" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "year" => {
+            "INTEGER"
+        }
+        "decimal" | "numeric" => "NUMERIC",
+        "float" | "real" | "double" => "REAL",
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+fn format_sqlite_ddl(tables: &Vec<TableConfig>) -> String {
+    let mut text = String::new();
+    for tbc in tables.iter() {
+        let pkeys = primary_keys(tbc);
+        // SQLite只有INTEGER PRIMARY KEY才能自增，此时主键写在列中
+        let inline_pk = pkeys.len() == 1
+            && tbc
+                .columns
+                .iter()
+                .any(|c| c.auto_increment && is_primary_key(tbc, c));
+        let mut lines = vec![];
+        for cc in tbc.columns.iter() {
+            let mut line = format!("  \"{}\" {}", cc.name, sqlite_type(cc));
+            if inline_pk && is_primary_key(tbc, cc) {
+                line.push_str(" PRIMARY KEY AUTOINCREMENT");
+            } else if !cc.nullable || is_primary_key(tbc, cc) {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(dv) = default_literal(cc, "sqlite") {
+                line.push_str(&format!(" DEFAULT {}", dv));
+            }
+            lines.push((line, cc.comment.clone()));
+        }
+        if !pkeys.is_empty() && !inline_pk {
+            let line = format!(
+                "  PRIMARY KEY ({})",
+                pkeys
+                    .iter()
+                    .map(|k| format!("\"{}\"", k))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            lines.push((line, String::new()));
+        }
+        let count = lines.len();
+        let body = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, (line, comment))| {
+                let sep = if i + 1 < count { "," } else { "" };
+                if comment.is_empty() {
+                    format!("{}{}", line, sep)
+                } else {
+                    format!("{}{} -- {}", line, sep, comment.replace('\n', " "))
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        text.push_str(&format!("\n-- {}\n", tbc.comment));
+        text.push_str(&format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (\n{}\n);\n",
+            tbc.name, body
+        ));
+        text.push_str(&format_create_indexes(tbc));
+    }
+    text
+}
+
+fn format_create_indexes(tbc: &TableConfig) -> String {
+    let mut text = String::new();
+    for idx in tbc.indexes.iter() {
+        text.push_str(&format!(
+            "CREATE {}INDEX IF NOT EXISTS \"{}\" ON \"{}\" ({});\n",
+            if idx.unique { "UNIQUE " } else { "" },
+            idx.name,
+            tbc.name,
+            idx.columns
+                .iter()
+                .map(|c| format!("\"{}\"", c))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    text
+}
@@ -1,6 +1,6 @@
 use crate::codegen::{
-    make_skip_columns, parse_column_list, parse_data_type_as_rust_type, GenerateContext, RustFunc,
-    RustStruct, RustStructField,
+    make_skip_columns, parse_column_list, parse_data_type_as_rust_type, GenerateContext,
+    RustArtifact, RustFileImpl, RustFunc, RustStruct, RustStructField,
 };
 use crate::config::{
     safe_struct_field_name, RelationConfig, Relationship, SimpleFuncation, TableConfig,
@@ -9,7 +9,11 @@ use crate::schema::{ColumnInfo, TableInfo};
 use change_case::pascal_case;
 use substring::Substring;
 
-use super::{is_copied_data_type, parse_column_as_field, RustStructFieldExtend};
+use super::{
+    generate_func_load_by_cache_fields, generate_include_deleted_field, get_logic_deletion,
+    get_version_field, is_copied_data_type, parse_column_as_field, LogicDeletion,
+    RustStructFieldExtend,
+};
 
 pub fn parse_table_as_struct(
     ctx: &GenerateContext,
@@ -52,6 +56,12 @@ pub fn parse_table_as_struct(
         })
     }
 
+    let logic_deletion = get_logic_deletion(ctx, &tbl_name);
+    if logic_deletion.is_some() {
+        fields.push(generate_include_deleted_field());
+        usings.push("rbatis::executor::ExecutorMut".to_string());
+    }
+
     let crudtbl = format!(
         "#[crud_table(table_name:\"{}\"|table_columns:\"{}\")]",
         tbl_name.clone(),
@@ -78,6 +88,12 @@ pub fn parse_table_as_struct(
     funclist.push(delete_batch_func);
     let delete_func = generate_func_delete_for_struct(ctx, tbl);
     funclist.push(delete_func);
+    if let Some(ld) = logic_deletion.as_ref() {
+        let restore_func = generate_func_restore_for_struct(ctx, tbl, ld);
+        funclist.push(restore_func);
+        let purge_func = generate_func_purge_for_struct(ctx, tbl);
+        funclist.push(purge_func);
+    }
 
     if pkcols.len() == 1 {
         let delete_ids = generate_func_delete_ids_for_struct(ctx, tbl);
@@ -133,6 +149,11 @@ pub fn parse_table_as_struct(
         funclist.push(remove_attachsfun);
     }
 
+    if ctx.is_redis_cache() {
+        let mut loadfuncs = generate_func_load_by_cache_fields(ctx, &tbconf, &fields, &funclist);
+        funclist.append(&mut loadfuncs);
+    }
+
     RustStruct {
         is_pub: true,
         has_paging: tbconf.page_query,
@@ -403,8 +424,14 @@ pub fn generate_func_update_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -
             ));
         }
     }
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        body.append(&mut vf.update_condition());
+    }
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        body.push(vf.increment_line());
+    }
 
     let mut savestr = String::new();
 
@@ -534,8 +561,14 @@ pub fn generate_func_update_selective_for_struct(
         }
     }
 
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        body.append(&mut vf.update_condition());
+    }
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        body.push(vf.increment_line());
+    }
 
     if tbconf.with_attachment {
         body.push(format!("match self.save_attachment(rb).await {{"));
@@ -576,6 +609,40 @@ pub fn generate_func_update_selective_for_struct(
  * 执行Update Seletive操作
  */
 pub fn generate_func_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -> RustFunc {
+    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+    // 逻辑删除时只更新删除标志
+    let set = get_logic_deletion(ctx, &tbl_name).map(|ld| ld.deleted_sql);
+    generate_func_remove_by_pkey_for_struct(ctx, tbl, "remove", set)
+}
+
+/**
+ * 逻辑删除时，彻底删除（物理删除）
+ */
+pub fn generate_func_purge_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -> RustFunc {
+    generate_func_remove_by_pkey_for_struct(ctx, tbl, "purge", None)
+}
+
+/**
+ * 逻辑删除时，恢复已删除的数据
+ */
+pub fn generate_func_restore_for_struct(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    ld: &LogicDeletion,
+) -> RustFunc {
+    generate_func_remove_by_pkey_for_struct(ctx, tbl, "restore", Some(ld.undeleted_sql.clone()))
+}
+
+/**
+ * 按主键删除
+ * set为None时执行删除，否则将逻辑删除的列更新为set
+ */
+fn generate_func_remove_by_pkey_for_struct(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    func_name: &str,
+    set: Option<String>,
+) -> RustFunc {
     let tbl_name = tbl.table_name.clone().unwrap_or_default();
     let tblinfo = ctx.get_table_conf(&tbl_name.clone());
     let tbconf = tblinfo.unwrap();
@@ -635,23 +702,31 @@ pub fn generate_func_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
 
-    if tbconf.with_attachment {
-        body.push(format!("match self.remove_attachments(rb).await {{"));
-        body.push(format!("Ok(_) => {{}},"));
-        body.push(format!("Err(_) => {{}}"));
-        body.push(format!("}}"));
-    }
+    let ld = get_logic_deletion(ctx, &tbl_name);
+    match (set, ld) {
+        (Some(set), Some(ld)) => {
+            body.append(&mut ld.update_lines(&tbl_name, &set));
+        }
+        _ => {
+            if tbconf.with_attachment {
+                body.push(format!("match self.remove_attachments(rb).await {{"));
+                body.push(format!("Ok(_) => {{}},"));
+                body.push(format!("Err(_) => {{}}"));
+                body.push(format!("}}"));
+            }
 
-    let mut savestr = String::new();
-    savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
-    body.push(savestr);
+            let mut savestr = String::new();
+            savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
+            body.push(savestr);
+        }
+    }
     RustFunc {
         is_struct_fn: true,
         is_self_fn: true,
         is_self_mut: true,
         is_pub: true,
         is_async: true,
-        func_name: "remove".to_string(),
+        func_name: func_name.to_string(),
         return_is_option: false,
         return_is_result: true,
         return_type: Some("u64".to_string()),
@@ -862,7 +937,9 @@ pub fn generate_func_delete_not_ids_for_struct(ctx: &GenerateContext, tbl: &Tabl
 
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
-    if tbconf.with_attachment {
+    if let Some(ld) = get_logic_deletion(ctx, &tbl_name) {
+        body.append(&mut ld.update_lines(&tbl_name, &ld.deleted_sql));
+    } else if tbconf.with_attachment {
         let mut savestr = String::new();
         savestr.push_str("match rb.fetch_list_by_wrapper::<Self>(wp.clone()).await {");
         body.push(savestr);
@@ -981,7 +1058,9 @@ pub fn generate_func_delete_ids_for_struct(ctx: &GenerateContext, tbl: &TableInf
 
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
-    if tbconf.with_attachment {
+    if let Some(ld) = get_logic_deletion(ctx, &tbl_name) {
+        body.append(&mut ld.update_lines(&tbl_name, &ld.deleted_sql));
+    } else if tbconf.with_attachment {
         let mut savestr = String::new();
         savestr.push_str("match rb.fetch_list_by_wrapper::<Self>(wp.clone()).await {");
         body.push(savestr);
@@ -1139,10 +1218,14 @@ pub fn generate_func_delete_rel_ids_for_struct(
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
 
-    let mut savestr = String::new();
-    savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
+    if let Some(ld) = get_logic_deletion(ctx, &tbl_name) {
+        body.append(&mut ld.update_lines(&tbl_name, &ld.deleted_sql));
+    } else {
+        let mut savestr = String::new();
+        savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
 
-    body.push(savestr);
+        body.push(savestr);
+    }
 
     for col in pkcols.clone() {
         params.push((
@@ -1205,24 +1288,28 @@ pub fn generate_func_delete_batch_for_struct(ctx: &GenerateContext, tbl: &TableI
     let last = body.remove(body.len() - 1);
     body.push(last + ";");
 
-    if tbconf.with_attachment {
-        body.push(format!(
-            "match rb.fetch_list_by_wrapper::<Self>(wp.clone()).await {{"
-        ));
-        body.push(format!("Ok(ls) => {{"));
-        body.push(format!("for mut it in ls.clone() {{"));
-        body.push(format!("it.remove_attachments(rb).await?;"));
-        body.push(format!("}}"));
-        body.push(format!("}}"));
-        body.push(format!("Err(err) => {{"));
-        body.push(format!("return Err(err);"));
-        body.push(format!("}}"));
-        body.push(format!("}}"));
-    }
+    if let Some(ld) = get_logic_deletion(ctx, &tbl_name) {
+        body.append(&mut ld.update_lines(&tbl_name, &ld.deleted_sql));
+    } else {
+        if tbconf.with_attachment {
+            body.push(format!(
+                "match rb.fetch_list_by_wrapper::<Self>(wp.clone()).await {{"
+            ));
+            body.push(format!("Ok(ls) => {{"));
+            body.push(format!("for mut it in ls.clone() {{"));
+            body.push(format!("it.remove_attachments(rb).await?;"));
+            body.push(format!("}}"));
+            body.push(format!("}}"));
+            body.push(format!("Err(err) => {{"));
+            body.push(format!("return Err(err);"));
+            body.push(format!("}}"));
+            body.push(format!("}}"));
+        }
 
-    let mut savestr = String::new();
-    savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
-    body.push(savestr);
+        let mut savestr = String::new();
+        savestr.push_str("rb.remove_by_wrapper::<Self>(wp).await");
+        body.push(savestr);
+    }
     RustFunc {
         is_struct_fn: true,
         is_self_fn: true,
@@ -1242,6 +1329,52 @@ pub fn generate_func_delete_batch_for_struct(ctx: &GenerateContext, tbl: &TableI
     }
 }
 
+/**
+ * remove_batch按实体中有值的字段作为条件，所有的字段都为None时没有任何条件，会删除整个表
+ * 在ORM转换之后进行（转换会替换方法体），在方法的开头加入检查，没有条件时返回错误
+ * 有非Option的列时总会有条件，不需要检查；日期范围的结束字段（与开始字段为同一列）单独不构成条件
+ */
+pub fn apply_remove_batch_guard(ctx: &GenerateContext, files: &mut [RustFileImpl]) {
+    let error = if ctx.is_sqlx() {
+        "return Err(Error::Protocol(\"remove_batch requires at least one condition\".to_string()));"
+    } else {
+        "return Err(Error::E(\"remove_batch requires at least one condition\".to_string()));"
+    };
+    for fl in files.iter_mut() {
+        if !matches!(fl.artifact, Some(RustArtifact::Entity(ref t)) if !t.is_empty()) {
+            continue;
+        }
+        for st in fl.structlist.iter_mut() {
+            let mut columns: Vec<String> = vec![];
+            let mut conds = vec![];
+            let mut always = false;
+            for fd in st.fields.iter().filter(|f| !f.column_name.is_empty()) {
+                if columns.contains(&fd.column_name) {
+                    continue;
+                }
+                columns.push(fd.column_name.clone());
+                if fd.is_option {
+                    conds.push(format!("self.{}.is_none()", fd.field_name));
+                } else {
+                    always = true;
+                }
+            }
+            if always || conds.is_empty() {
+                continue;
+            }
+            for func in st
+                .funclist
+                .iter_mut()
+                .filter(|f| f.func_name == "remove_batch" && f.is_self_fn)
+            {
+                func.bodylines.insert(0, format!("if {} {{", conds.join(" && ")));
+                func.bodylines.insert(1, error.to_string());
+                func.bodylines.insert(2, "}".to_string());
+            }
+        }
+    }
+}
+
 /**
  * 根据列来生成查询条件
  * 主要是处理基本的列的条件，以及：
@@ -2069,6 +2202,10 @@ fn generate_func_value_object_to_entity(ctx: &GenerateContext, tbl: &TableInfo)
         }
     }
 
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        body.push(format!("include_deleted: false,"));
+    }
+
     body.push(format!("}}"));
 
     let params = Vec::new();
@@ -2281,6 +2418,10 @@ pub fn parse_composite_column_list(
         let fd = parse_column_as_field(ctx, tbl, &col, rename_id,  usings);
         let mut rsfd = RustStructFieldExtend::parse(&fd);
         let colname = col.column_name.clone().unwrap_or_default();
+        if tbl.version_field.clone().unwrap_or_default().to_lowercase() == colname.to_lowercase() {
+            // 乐观锁的版本不需要输入，编辑时随表单原样提交
+            rsfd.hidden = true;
+        }
         if !union_fields.contains(&colname) {
             union_fields.push(colname.clone());
             columns.push_str(format!("t0.{}", colname).as_str());
@@ -2406,6 +2547,14 @@ pub fn parse_table_as_composite_struct(
         generate_query_func_for_extend_struct(ctx, tbl, &fields, true, &columns, &joinstr);
     funclist.push(page_query_func);
 
+    let mut fields = fields
+        .into_iter()
+        .map(|f| f.field)
+        .collect::<Vec<RustStructField>>();
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        fields.push(generate_include_deleted_field());
+    }
+
     let rs = RustStruct {
         is_pub: true,
         has_paging: tbconf.page_query,
@@ -2414,10 +2563,7 @@ pub fn parse_table_as_composite_struct(
             None => pascal_case(tbl_name.clone().as_str()) + "Present",
         },
         annotations: anno,
-        fields: fields
-            .into_iter()
-            .map(|f| f.field)
-            .collect::<Vec<RustStructField>>(),
+        fields,
         funclist: funclist,
         usings
     };
@@ -2449,7 +2595,7 @@ pub fn generate_query_func_for_extend_struct(
     params.push(("rb".to_string(), "&Rbatis".to_string()));
     params.push((
         "param".to_string(),
-        format!(" &{}Present", tbconf.struct_name.clone()),
+        format!(" &{}Present", ctx.get_struct_name(&tbl_name).unwrap_or_default()),
     ));
     if paged {
         params.push(("curr".to_string(), "u64".to_string()));
@@ -2468,6 +2614,15 @@ pub fn generate_query_func_for_extend_struct(
 
     body.push(format!("let mut sql = r#\"{}\"#.to_string();", sql));
     body.push(format!("let mut rb_args = vec![];"));
+    // 逻辑删除时与实体的查询一样，通过include_deleted包含已删除的数据
+    if let Some(ld) = get_logic_deletion(ctx, &tbl_name) {
+        body.push("if !param.include_deleted {".to_string());
+        body.push(format!(
+            "sql.push_str(\" AND t0.{} = {} \");",
+            ld.column, ld.undeleted_sql
+        ));
+        body.push("}".to_string());
+    }
     for fd in fields.clone() {
         if fd.flag == 0i64 {
             if fd.field.field_type == "rbatis::DateTimeNative".to_string() {
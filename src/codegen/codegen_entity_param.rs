@@ -6,7 +6,10 @@ use crate::config::{safe_struct_field_name, TableConfig};
 use crate::schema::{ColumnInfo, TableInfo};
 use change_case::pascal_case;
 
-use super::{is_copied_type, is_date_time_type, is_multi_item_field};
+use super::{
+    generate_include_deleted_field, get_logic_deletion, is_copied_type, is_date_time_type,
+    is_multi_item_field,
+};
 
 pub fn parse_column_as_param_field(
     ctx: &GenerateContext,
@@ -108,7 +111,10 @@ pub fn parse_table_as_request_param_struct(
 
     let mut usings = vec![];
 
-    let fields = parse_param_column_list(ctx, &tbconf, cols, &mut usings);
+    let mut fields = parse_param_column_list(ctx, &tbconf, cols, &mut usings);
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        fields.push(generate_include_deleted_field());
+    }
     // if columns.ends_with(",") {
     //    columns = columns.substring(0, columns.len() - 1).to_string();
     // }
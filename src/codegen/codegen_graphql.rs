@@ -0,0 +1,1286 @@
+use change_case::{pascal_case, snake_case};
+
+use crate::codegen::{
+    get_logic_deletion, get_primary_key_columns, parse_data_type_as_rust_type, GenerateContext,
+    RustFileImpl, RustFunc, RustStruct, RustStructField, RustTraitImpl,
+};
+use crate::config::{safe_struct_field_name, QueryConfig, Relationship};
+use crate::schema::TableInfo;
+
+/**
+ * GraphQL的生成（generate-graphql）
+ * 每个表及自定义查询生成graphql/{name}.rs，并生成graphql/schema.rs：
+ * 1、实体生成{S}Object（SimpleObject）及{S}Input（InputObject），查询生成{Q}Object及{Q}ParamsInput，字段的类型转换同gRPC
+ * 2、表的{S}GraphqlQuery包含按主键获取、列表、分页（page-query）及树（tree-parent-field），{S}GraphqlMutation包含create、update、delete
+ * 3、以该表为major-table的关系，其one-to-one及one-to-many成为{S}Object中的字段，通过DataLoader按批加载，有middle-table时先查中间表
+ * 4、schema.rs中将所有的Query及Mutation合并为QueryRoot及MutationRoot，并生成挂载到web框架中的graphql端点
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum GqlKind {
+    Same, // GraphQL直接支持的类型，不需要转换
+    Text, // Decimal、日期、时间，使用to_string/parse转换
+    Json,
+}
+
+fn get_graphql_object_name(struct_name: &String) -> String {
+    format!("{}Object", struct_name)
+}
+
+fn get_graphql_query_name(struct_name: &String) -> String {
+    format!("{}GraphqlQuery", struct_name)
+}
+
+fn get_graphql_mutation_name(struct_name: &String) -> String {
+    format!("{}GraphqlMutation", struct_name)
+}
+
+fn gql_kind(field_type: &str) -> Option<GqlKind> {
+    match field_type {
+        "i64" | "i32" | "i16" | "i8" | "u64" | "u32" | "u16" | "u8" | "bool" | "f32" | "f64"
+        | "String" => Some(GqlKind::Same),
+        "rbatis::DateTimeNative"
+        | "rbatis::DateTimeUtc"
+        | "rbatis::Timestamp"
+        | "rbatis::TimestampZ"
+        | "rbatis::DateNative"
+        | "rbatis::DateUtc"
+        | "rbatis::TimeNative"
+        | "rbatis::TimeUtc"
+        | "rbatis::Decimal" => Some(GqlKind::Text),
+        "rbatis::Json" => Some(GqlKind::Json),
+        _ => None,
+    }
+}
+
+fn gql_type(kind: &GqlKind, field_type: &str) -> String {
+    match kind {
+        GqlKind::Same => field_type.to_string(),
+        GqlKind::Text | GqlKind::Json => "String".to_string(),
+    }
+}
+
+/**
+ * 可以作为DataLoader的key的类型
+ */
+fn is_loader_key_type(field_type: &str) -> bool {
+    matches!(
+        field_type,
+        "i64" | "i32" | "i16" | "i8" | "u64" | "u32" | "u16" | "u8" | "String"
+    )
+}
+
+/**
+ * 实体的字段转换为Object字段的表达式，src为实体的字段，如e.name
+ */
+fn to_object_expr(kind: &GqlKind, is_option: bool, src: &str) -> String {
+    match (kind, is_option) {
+        (GqlKind::Same, _) => src.to_string(),
+        (GqlKind::Text, true) => format!("{}.as_ref().map(|v| v.to_string())", src),
+        (GqlKind::Text, false) => format!("{}.to_string()", src),
+        (GqlKind::Json, true) => format!(
+            "{}.as_ref().and_then(|v| serde_json::to_string(v).ok())",
+            src
+        ),
+        (GqlKind::Json, false) => format!("serde_json::to_string(&{}).unwrap_or_default()", src),
+    }
+}
+
+/**
+ * Input的字段（都为Option）转换为实体字段的表达式，src为Input的字段，如m.name
+ */
+fn from_input_expr(kind: &GqlKind, is_option: bool, src: &str) -> String {
+    let expr = match kind {
+        GqlKind::Same => src.to_string(),
+        GqlKind::Text => format!("{}.and_then(|v| v.parse().ok())", src),
+        GqlKind::Json => format!("{}.and_then(|v| serde_json::from_str(&v).ok())", src),
+    };
+    if is_option {
+        expr
+    } else {
+        format!("{}.unwrap_or_default()", expr)
+    }
+}
+
+fn doc_comment(comment: &Option<String>) -> Vec<String> {
+    match comment
+        .clone()
+        .unwrap_or_default()
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+    {
+        Some(c) if !c.is_empty() => vec![format!("/// {}", c)],
+        _ => vec![],
+    }
+}
+
+/**
+ * 结构体中可以在GraphQL中表示的字段
+ */
+fn gql_fields(st: &RustStruct) -> Vec<(RustStructField, GqlKind)> {
+    st.fields
+        .iter()
+        .filter_map(|fd| match gql_kind(&fd.field_type) {
+            Some(kind) => Some((fd.clone(), kind)),
+            None => {
+                log::info!(
+                    "{}.{} ({}) could not be represented in GraphQL, it was skipped.",
+                    st.struct_name,
+                    fd.field_name,
+                    fd.field_type
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+fn gql_struct(
+    struct_name: String,
+    annotations: Vec<String>,
+    fields: &[(RustStructField, GqlKind)],
+    input: bool,
+) -> RustStruct {
+    RustStruct {
+        is_pub: true,
+        has_paging: false,
+        struct_name,
+        annotations,
+        fields: fields
+            .iter()
+            .map(|(fd, kind)| RustStructField {
+                is_pub: true,
+                schema_name: None,
+                column_name: String::new(),
+                field_name: fd.field_name.clone(),
+                orignal_field_name: None,
+                comment: fd.comment.clone(),
+                field_type: gql_type(kind, &fd.field_type),
+                is_option: input || fd.is_option,
+                length: 0i64,
+                annotations: doc_comment(&fd.comment),
+            })
+            .collect(),
+        funclist: vec![],
+        usings: vec![],
+    }
+}
+
+fn from_impl(target: &str, source: &String, param: &str, body: Vec<String>) -> RustTraitImpl {
+    RustTraitImpl {
+        annotations: vec![],
+        trait_name: format!("From<{}>", source),
+        struct_name: target.to_string(),
+        types: vec![],
+        funclist: vec![RustFunc {
+            is_struct_fn: true,
+            is_self_fn: false,
+            is_self_mut: false,
+            is_pub: false,
+            is_async: false,
+            func_name: "from".to_string(),
+            return_is_option: false,
+            return_is_result: false,
+            return_type: Some("Self".to_string()),
+            params: vec![(param.to_string(), source.clone())],
+            bodylines: body,
+            macros: vec![],
+            comment: None,
+            api_method: None,
+            api_pattern: None,
+        }],
+    }
+}
+
+/**
+ * 实体转换为Object
+ */
+fn object_from_impl(
+    struct_name: &String,
+    fields: &[(RustStructField, GqlKind)],
+) -> RustTraitImpl {
+    let mut body = vec!["Self {".to_string()];
+    for (fd, kind) in fields.iter() {
+        body.push(format!(
+            "{}: {},",
+            fd.field_name,
+            to_object_expr(kind, fd.is_option, &format!("e.{}", fd.field_name))
+        ));
+    }
+    body.push("}".to_string());
+    from_impl(
+        &get_graphql_object_name(struct_name),
+        struct_name,
+        "e",
+        body,
+    )
+}
+
+/**
+ * Input转换为实体，Input中没有的字段使用Default
+ */
+fn input_into_impl(
+    input_name: &String,
+    struct_name: &str,
+    fields: &[(RustStructField, GqlKind)],
+    all_fields: usize,
+) -> RustTraitImpl {
+    let mut body = vec!["Self {".to_string()];
+    for (fd, kind) in fields.iter() {
+        body.push(format!(
+            "{}: {},",
+            fd.field_name,
+            from_input_expr(kind, fd.is_option, &format!("m.{}", fd.field_name))
+        ));
+    }
+    if fields.len() < all_fields {
+        body.push("..Default::default()".to_string());
+    }
+    body.push("}".to_string());
+    from_impl(struct_name, input_name, "m", body)
+}
+
+fn gql_func(
+    func_name: &str,
+    params: Vec<(String, String)>,
+    ret: String,
+    body: Vec<String>,
+) -> RustFunc {
+    RustFunc {
+        is_struct_fn: true,
+        is_self_fn: true,
+        is_self_mut: false,
+        is_pub: false,
+        is_async: true,
+        func_name: func_name.to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some(ret),
+        params,
+        bodylines: body,
+        macros: vec![],
+        comment: None,
+        api_method: None,
+        api_pattern: None,
+    }
+}
+
+fn object_impl(struct_name: String, funclist: Vec<RustFunc>) -> RustTraitImpl {
+    RustTraitImpl {
+        annotations: vec!["#[Object]".to_string()],
+        trait_name: String::new(),
+        struct_name,
+        types: vec![],
+        funclist,
+    }
+}
+
+fn resolver_struct(struct_name: String) -> RustStruct {
+    RustStruct {
+        is_pub: true,
+        has_paging: false,
+        struct_name,
+        annotations: vec!["#[derive(Debug, Clone, Default)]".to_string()],
+        fields: vec![],
+        funclist: vec![],
+        usings: vec![],
+    }
+}
+
+/**
+ * 在事务中执行实体的方法，成功后返回Object
+ */
+fn transaction_lines(call: &str) -> Vec<String> {
+    let mut body = vec![];
+    body.push("match rb.acquire_begin().await {".to_string());
+    body.push("Ok(mut tx) => {".to_string());
+    body.push(format!("match {} {{", call));
+    body.push("Ok(_) => {".to_string());
+    body.push("tx.commit().await?;".to_string());
+    body.push("Ok(val.into())".to_string());
+    body.push("}".to_string());
+    body.push("Err(err) => {".to_string());
+    body.push("let _ = tx.rollback().await.is_ok();".to_string());
+    body.push("Err(err.into())".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    body.push("Err(err) => Err(err.into()),".to_string());
+    body.push("}".to_string());
+    body
+}
+
+fn find_field(st: &RustStruct, column: &str) -> Option<RustStructField> {
+    let name = safe_struct_field_name(&column.to_lowercase());
+    st.fields.iter().find(|fd| fd.field_name == name).cloned()
+}
+
+fn find_entity(ctx: &GenerateContext, tbl_name: &String) -> Option<RustStruct> {
+    let struct_name = ctx.get_struct_name(tbl_name)?;
+    ctx.structs
+        .iter()
+        .find(|s| s.struct_name == struct_name)
+        .cloned()
+}
+
+/**
+ * 取出字段的值作为key，Option的字段为None时跳过
+ */
+fn key_lines(fd: &RustStructField, src: &str, inner: Vec<String>) -> Vec<String> {
+    let mut body = vec![];
+    if fd.is_option {
+        body.push(format!(
+            "if let Some(key) = {}.{}.clone() {{",
+            src, fd.field_name
+        ));
+    } else {
+        body.push("{".to_string());
+        body.push(format!("let key = {}.{}.clone();", src, fd.field_name));
+    }
+    body.extend(inner);
+    body.push("}".to_string());
+    body
+}
+
+fn in_query_lines(
+    ctx: &GenerateContext,
+    table: &String,
+    column: &String,
+    keys: &str,
+    target: &String,
+    var: &str,
+) -> Vec<String> {
+    let mut body = vec![];
+    body.push("let mut rb_args = vec![];".to_string());
+    body.push(format!("for key in {}.iter() {{", keys));
+    body.push("rb_args.push(rbson::to_bson(key).unwrap_or_default());".to_string());
+    body.push("}".to_string());
+    body.push(format!(
+        "let sql = format!(\"SELECT * FROM {} WHERE {} IN ({{}}){}\", vec![\"?\"; rb_args.len()].join(\", \"));",
+        table,
+        column,
+        undeleted_condition(ctx, table)
+    ));
+    body.push(format!(
+        "let {}: Vec<{}> = rb.fetch(&sql, rb_args).await?;",
+        var, target
+    ));
+    body
+}
+
+/**
+ * 逻辑删除的表只加载未删除的数据
+ */
+fn undeleted_condition(ctx: &GenerateContext, table: &String) -> String {
+    match get_logic_deletion(ctx, table) {
+        Some(ld) => format!(" AND {} = {}", ld.column, ld.undeleted_sql),
+        None => String::new(),
+    }
+}
+
+/**
+ * 关系中的一个关联表，生成major-table的Object中的字段及对应的DataLoader
+ * 返回(字段的resolver, DataLoader的结构体, Loader的实现)
+ */
+fn relation_loader(
+    ctx: &GenerateContext,
+    st: &RustStruct,
+    rl: &Relationship,
+    many: bool,
+) -> Option<(RustFunc, RustStruct, RustTraitImpl, Vec<String>)> {
+    let tbl_name = rl.table_name.clone().unwrap_or_default();
+    let tpc = ctx.get_table_conf(&tbl_name)?;
+    let target = find_entity(ctx, &tbl_name)?;
+    let major_field = find_field(st, &rl.major_field.clone().unwrap_or_default())?;
+    let join_column = rl.join_field.clone().unwrap_or_default();
+    let field_name = if many {
+        format!("{}s", tpc.api_handler_name)
+    } else {
+        tpc.api_handler_name.clone()
+    };
+    if !is_loader_key_type(&major_field.field_type) {
+        log::info!(
+            "{}.{} could not be used as the key of DataLoader, {} was skipped.",
+            st.struct_name,
+            major_field.field_name,
+            field_name
+        );
+        return None;
+    }
+    let key_type = major_field.field_type.clone();
+    let object_name = get_graphql_object_name(&tpc.struct_name);
+    let value_type = if many {
+        format!("Vec<{}>", object_name)
+    } else {
+        object_name.clone()
+    };
+    let loader_name = format!(
+        "{}{}Loader",
+        st.struct_name,
+        pascal_case(field_name.as_str())
+    );
+    let mut entities = vec![tpc.struct_name.clone()];
+
+    let mut body = vec!["let rb = get_rbatis();".to_string()];
+    match rl.middle_table.clone() {
+        Some(middle) => {
+            // 先按major-field查询中间表，再按join-field查询关联表
+            let mid = find_entity(ctx, &middle)?;
+            let mid_major = find_field(&mid, &rl.major_field.clone().unwrap_or_default())?;
+            let mid_join = find_field(&mid, &join_column)?;
+            let tp_join = find_field(&target, &join_column)?;
+            if mid_major.field_type != key_type {
+                log::info!(
+                    "The type of {}.{} is not same as {}.{}, {} was skipped.",
+                    mid.struct_name,
+                    mid_major.field_name,
+                    st.struct_name,
+                    major_field.field_name,
+                    field_name
+                );
+                return None;
+            }
+            entities.push(mid.struct_name.clone());
+            body.append(&mut in_query_lines(
+                ctx,
+                &middle,
+                &rl.major_field.clone().unwrap_or_default(),
+                "keys",
+                &mid.struct_name,
+                "mids",
+            ));
+            body.push("if mids.is_empty() {".to_string());
+            body.push("return Ok(HashMap::new());".to_string());
+            body.push("}".to_string());
+            body.push("let mut rb_args = vec![];".to_string());
+            body.push("for mid in mids.iter() {".to_string());
+            body.push(format!(
+                "rb_args.push(rbson::to_bson(&mid.{}).unwrap_or_default());",
+                mid_join.field_name
+            ));
+            body.push("}".to_string());
+            body.push(format!(
+                "let sql = format!(\"SELECT * FROM {} WHERE {} IN ({{}}){}\", vec![\"?\"; rb_args.len()].join(\", \"));",
+                tbl_name,
+                join_column,
+                undeleted_condition(ctx, &tbl_name)
+            ));
+            body.push(format!(
+                "let list: Vec<{}> = rb.fetch(&sql, rb_args).await?;",
+                tpc.struct_name
+            ));
+            body.push(format!(
+                "let mut map: HashMap<{}, Self::Value> = HashMap::new();",
+                key_type
+            ));
+            body.push("for mid in mids.iter() {".to_string());
+            let cond = match (tp_join.is_option, mid_join.is_option) {
+                (true, false) => format!(
+                    "v.{} == Some(mid.{}.clone())",
+                    tp_join.field_name, mid_join.field_name
+                ),
+                (false, true) => format!(
+                    "Some(v.{}.clone()) == mid.{}",
+                    tp_join.field_name, mid_join.field_name
+                ),
+                _ => format!("v.{} == mid.{}", tp_join.field_name, mid_join.field_name),
+            };
+            let mut inner = vec![format!("for val in list.iter().filter(|v| {}) {{", cond)];
+            if many {
+                inner.push(
+                    "map.entry(key.clone()).or_default().push(val.clone().into());".to_string(),
+                );
+            } else {
+                inner.push(
+                    "map.entry(key.clone()).or_insert_with(|| val.clone().into());".to_string(),
+                );
+            }
+            inner.push("}".to_string());
+            body.append(&mut key_lines(&mid_major, "mid", inner));
+            body.push("}".to_string());
+            body.push("Ok(map)".to_string());
+        }
+        None => {
+            let tp_join = find_field(&target, &join_column)?;
+            if tp_join.field_type != key_type {
+                log::info!(
+                    "The type of {}.{} is not same as {}.{}, {} was skipped.",
+                    target.struct_name,
+                    tp_join.field_name,
+                    st.struct_name,
+                    major_field.field_name,
+                    field_name
+                );
+                return None;
+            }
+            body.append(&mut in_query_lines(
+                ctx,
+                &tbl_name,
+                &join_column,
+                "keys",
+                &tpc.struct_name,
+                "list",
+            ));
+            body.push(format!(
+                "let mut map: HashMap<{}, Self::Value> = HashMap::new();",
+                key_type
+            ));
+            body.push("for val in list {".to_string());
+            let inner = if many {
+                vec!["map.entry(key).or_default().push(val.into());".to_string()]
+            } else {
+                vec!["map.entry(key).or_insert_with(|| val.into());".to_string()]
+            };
+            body.append(&mut key_lines(&tp_join, "val", inner));
+            body.push("}".to_string());
+            body.push("Ok(map)".to_string());
+        }
+    }
+
+    let load = RustFunc {
+        is_struct_fn: true,
+        is_self_fn: true,
+        is_self_mut: false,
+        is_pub: false,
+        is_async: true,
+        func_name: "load".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some(format!("Result<HashMap<{}, Self::Value>>", key_type)),
+        params: vec![("keys".to_string(), format!("&[{}]", key_type))],
+        bodylines: body,
+        macros: vec![],
+        comment: None,
+        api_method: None,
+        api_pattern: None,
+    };
+    let loader_impl = RustTraitImpl {
+        annotations: vec![],
+        trait_name: format!("Loader<{}>", key_type),
+        struct_name: loader_name.clone(),
+        types: vec![
+            ("Value".to_string(), value_type.clone()),
+            ("Error".to_string(), "async_graphql::Error".to_string()),
+        ],
+        funclist: vec![load],
+    };
+
+    // Object中的字段，通过DataLoader加载
+    let mut body = vec![format!(
+        "let loader = ctx.data_unchecked::<DataLoader<{}>>();",
+        loader_name
+    )];
+    let (ret, found, missing) = if many {
+        (
+            format!("Result<{}>", value_type),
+            "Ok(loader.load_one(key).await?.unwrap_or_default())",
+            "Ok(vec![])",
+        )
+    } else {
+        (
+            format!("Result<Option<{}>>", value_type),
+            "Ok(loader.load_one(key).await?)",
+            "Ok(None)",
+        )
+    };
+    if major_field.is_option {
+        body.push(format!("match self.{}.clone() {{", major_field.field_name));
+        body.push(format!("Some(key) => {},", found));
+        body.push(format!("None => {},", missing));
+        body.push("}".to_string());
+    } else {
+        body.push(format!(
+            "let key = self.{}.clone();",
+            major_field.field_name
+        ));
+        body.push(found.to_string());
+    }
+    let resolver = gql_func(
+        &field_name,
+        vec![("ctx".to_string(), "&Context<'_>".to_string())],
+        ret,
+        body,
+    );
+
+    Some((
+        resolver,
+        resolver_struct(loader_name),
+        loader_impl,
+        entities,
+    ))
+}
+
+fn graphql_usings(
+    rfi: &RustFileImpl,
+    entities: &[String],
+    objects: &[String],
+) -> Vec<String> {
+    let text = rfi.to_source();
+    let mut names = vec![];
+    for (code, name) in [
+        ("#[ComplexObject]", "ComplexObject"),
+        ("&Context<'_>", "Context"),
+        ("InputObject", "InputObject"),
+        ("#[Object]", "Object"),
+        ("Result<", "Result"),
+        ("SimpleObject", "SimpleObject"),
+    ] {
+        if text.contains(code) {
+            names.push(name);
+        }
+    }
+    let mut usings = vec![format!("async_graphql::{{{}}}", names.join(", "))];
+    match (text.contains("DataLoader<"), text.contains("Loader<")) {
+        (true, true) => usings.push("async_graphql::dataloader::{DataLoader, Loader}".to_string()),
+        (false, true) => usings.push("async_graphql::dataloader::Loader".to_string()),
+        _ => {}
+    }
+    if text.contains("HashMap") {
+        usings.push("std::collections::HashMap".to_string());
+    }
+    if text.contains("get_rbatis()") {
+        usings.push("chimes_utils::get_rbatis".to_string());
+    }
+    for et in entities.iter() {
+        usings.push(format!("crate::entity::{}", et));
+    }
+    for ob in objects.iter() {
+        usings.push(format!("crate::graphql::{}", ob));
+    }
+    usings
+}
+
+/**
+ * 为表生成GraphQL的类型及resolver
+ * 返回生成的文件及其中的DataLoader
+ */
+pub fn generate_graphql_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+) -> Option<(RustFileImpl, Vec<String>)> {
+    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+    let tbc = ctx.get_table_conf(&tbl_name)?;
+    let st = find_entity(ctx, &tbl_name)?;
+    let struct_name = st.struct_name.clone();
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+    if pkcols.is_empty() {
+        log::info!(
+            "{} has no primary key, the GraphQL resolvers were not generated.",
+            tbl_name
+        );
+        return None;
+    }
+    let fields = gql_fields(&st);
+    let object_name = get_graphql_object_name(&struct_name);
+    let input_name = format!("{}Input", struct_name);
+    let api = snake_case(tbc.api_handler_name.as_str());
+
+    let mut structlist = vec![];
+    let mut implist = vec![];
+    let mut entities = vec![struct_name.clone()];
+    let mut objects = vec![];
+    let mut loaders = vec![];
+
+    // 以该表为major-table的关系，成为Object中的字段
+    let mut rel_funcs: Vec<RustFunc> = vec![];
+    for rel in ctx
+        .codegen_conf
+        .relations
+        .iter()
+        .filter(|r| r.major_table == tbl_name)
+    {
+        let rls = rel
+            .one_to_one
+            .iter()
+            .map(|rl| (rl, false))
+            .chain(rel.one_to_many.iter().map(|rl| (rl, true)));
+        for (rl, many) in rls {
+            if let Some((resolver, loader, loader_impl, mut ets)) =
+                relation_loader(ctx, &st, rl, many)
+            {
+                if st.fields.iter().any(|f| f.field_name == resolver.func_name)
+                    || rel_funcs.iter().any(|f| f.func_name == resolver.func_name)
+                {
+                    continue;
+                }
+                let target = ets[0].clone();
+                if target != struct_name {
+                    objects.push(get_graphql_object_name(&target));
+                }
+                entities.append(&mut ets);
+                loaders.push(loader.struct_name.clone());
+                rel_funcs.push(resolver);
+                structlist.push(loader);
+                implist.push(loader_impl);
+            }
+        }
+    }
+
+    let mut anno = vec!["#[derive(Debug, Clone, Default, SimpleObject)]".to_string()];
+    if rel_funcs.is_empty() {
+        anno.push(format!("#[graphql(name = \"{}\")]", struct_name));
+    } else {
+        anno.push(format!("#[graphql(name = \"{}\", complex)]", struct_name));
+    }
+    let mut object = gql_struct(object_name.clone(), anno, &fields, false);
+    object
+        .annotations
+        .splice(0..0, doc_comment(&Some(tbc.comment.clone())));
+    structlist.insert(0, object);
+    structlist.insert(
+        1,
+        gql_struct(
+            input_name.clone(),
+            vec!["#[derive(Debug, Clone, Default, InputObject)]".to_string()],
+            &fields,
+            true,
+        ),
+    );
+
+    // 主键作为参数
+    let mut key_params = vec![];
+    let mut key_args = vec![];
+    for col in pkcols.iter() {
+        let colname =
+            safe_struct_field_name(&col.column_name.clone().unwrap_or_default().to_lowercase());
+        let dt = parse_data_type_as_rust_type(&col.data_type.clone().unwrap_or_default());
+        match gql_kind(&dt) {
+            Some(GqlKind::Same) => {
+                key_params.push((colname.clone(), dt.clone()));
+                key_args.push(format!("&{}", colname));
+            }
+            _ => {
+                key_params.push((colname.clone(), "String".to_string()));
+                key_args.push(format!("&({}.parse().unwrap_or_default())", colname));
+            }
+        }
+    }
+
+    let mut queries = vec![];
+    let mut body = vec!["let rb = get_rbatis();".to_string()];
+    body.push(format!(
+        "let val = {}::from_id(rb, {}).await?;",
+        struct_name,
+        key_args.join(", ")
+    ));
+    body.push("Ok(val.map(|v| v.into()))".to_string());
+    queries.push(gql_func(
+        &api,
+        key_params.clone(),
+        format!("Result<Option<{}>>", object_name),
+        body,
+    ));
+
+    let body = vec![
+        format!(
+            "let val = query.map({}::from).unwrap_or_default();",
+            struct_name
+        ),
+        "let rb = get_rbatis();".to_string(),
+        "let list = val.query_list(rb).await?;".to_string(),
+        "Ok(list.into_iter().map(|v| v.into()).collect())".to_string(),
+    ];
+    queries.push(gql_func(
+        &format!("{}_list", api),
+        vec![("query".to_string(), format!("Option<{}>", input_name))],
+        format!("Result<Vec<{}>>", object_name),
+        body,
+    ));
+
+    if tbc.page_query {
+        let page_name = format!("{}Page", struct_name);
+        structlist.push(RustStruct {
+            is_pub: true,
+            has_paging: false,
+            struct_name: page_name.clone(),
+            annotations: vec!["#[derive(Debug, Clone, Default, SimpleObject)]".to_string()],
+            fields: [
+                ("records", format!("Vec<{}>", object_name)),
+                ("total", "u64".to_string()),
+                ("page_no", "u64".to_string()),
+                ("page_size", "u64".to_string()),
+            ]
+            .into_iter()
+            .map(|(name, tp)| RustStructField {
+                is_pub: true,
+                field_name: name.to_string(),
+                field_type: tp,
+                ..Default::default()
+            })
+            .collect(),
+            funclist: vec![],
+            usings: vec![],
+        });
+        let body = vec![
+            format!(
+                "let val = query.map({}::from).unwrap_or_default();",
+                struct_name
+            ),
+            "let rb = get_rbatis();".to_string(),
+            "let page = val.query_paged(rb, current, size).await?;".to_string(),
+            format!("Ok({} {{", page_name),
+            "records: page.records.into_iter().map(|v| v.into()).collect(),".to_string(),
+            "total: page.total,".to_string(),
+            "page_no: page.page_no,".to_string(),
+            "page_size: page.page_size,".to_string(),
+            "})".to_string(),
+        ];
+        queries.push(gql_func(
+            &format!("{}_page", api),
+            vec![
+                ("query".to_string(), format!("Option<{}>", input_name)),
+                ("current".to_string(), "u64".to_string()),
+                ("size".to_string(), "u64".to_string()),
+            ],
+            format!("Result<{}>", page_name),
+            body,
+        ));
+    }
+
+    if let Some(treecol) = tbc
+        .tree_parent_field
+        .clone()
+        .and_then(|f| ctx.find_table_column(&tbl_name, &f))
+    {
+        let dt = parse_data_type_as_rust_type(&treecol.data_type.clone().unwrap_or_default());
+        let (pid_type, pid_arg) = match gql_kind(&dt) {
+            Some(GqlKind::Same) => (dt.clone(), "&pid".to_string()),
+            _ => (
+                "String".to_string(),
+                "&pid.and_then(|v| v.parse().ok())".to_string(),
+            ),
+        };
+        let body = vec![
+            "let rb = get_rbatis();".to_string(),
+            format!(
+                "let list = {}::query_tree(rb, {}).await?;",
+                struct_name, pid_arg
+            ),
+            "Ok(list.into_iter().map(|v| v.into()).collect())".to_string(),
+        ];
+        queries.push(gql_func(
+            &format!("{}_tree", api),
+            vec![("pid".to_string(), format!("Option<{}>", pid_type))],
+            format!("Result<Vec<{}>>", object_name),
+            body,
+        ));
+    }
+
+    let mut mutations = vec![];
+    for (action, method) in [("create", "save"), ("update", "update")] {
+        let mut body = vec![
+            format!("let mut val = {}::from(input);", struct_name),
+            "let rb = get_rbatis();".to_string(),
+        ];
+        body.append(&mut transaction_lines(&format!(
+            "val.{}(&mut tx).await",
+            method
+        )));
+        mutations.push(gql_func(
+            &format!("{}_{}", action, api),
+            vec![("input".to_string(), input_name.clone())],
+            format!("Result<{}>", object_name),
+            body,
+        ));
+    }
+    let mut body = vec!["let rb = get_rbatis();".to_string()];
+    body.push(format!(
+        "let mut val = match {}::from_id(rb, {}).await? {{",
+        struct_name,
+        key_args.join(", ")
+    ));
+    body.push("Some(val) => val,".to_string());
+    body.push(format!(
+        "None => return Err(\"{} not found\".into()),",
+        struct_name
+    ));
+    body.push("};".to_string());
+    body.append(&mut transaction_lines("val.remove(&mut tx).await"));
+    mutations.push(gql_func(
+        &format!("delete_{}", api),
+        key_params.clone(),
+        format!("Result<{}>", object_name),
+        body,
+    ));
+
+    structlist.push(resolver_struct(get_graphql_query_name(&struct_name)));
+    structlist.push(resolver_struct(get_graphql_mutation_name(&struct_name)));
+
+    let mut trait_impls = vec![];
+    if !rel_funcs.is_empty() {
+        trait_impls.push(RustTraitImpl {
+            annotations: vec!["#[ComplexObject]".to_string()],
+            trait_name: String::new(),
+            struct_name: object_name.clone(),
+            types: vec![],
+            funclist: rel_funcs,
+        });
+    }
+    trait_impls.push(object_impl(get_graphql_query_name(&struct_name), queries));
+    trait_impls.push(object_impl(
+        get_graphql_mutation_name(&struct_name),
+        mutations,
+    ));
+    trait_impls.push(object_from_impl(&struct_name, &fields));
+    trait_impls.push(input_into_impl(
+        &input_name,
+        &struct_name,
+        &fields,
+        st.fields.len(),
+    ));
+    trait_impls.append(&mut implist);
+
+    let mut rfi = make_graphql_file(&struct_name, structlist, trait_impls);
+    entities.sort();
+    entities.dedup();
+    objects.sort();
+    objects.dedup();
+    rfi.usinglist = graphql_usings(&rfi, &entities, &objects);
+    Some((rfi, loaders))
+}
+
+/**
+ * 为自定义查询生成GraphQL的类型及resolver
+ * file为parse_query_as_file生成的文件，其中包含{Q}Params及{Q}
+ */
+pub fn generate_graphql_for_query(
+    _ctx: &GenerateContext,
+    qry: &QueryConfig,
+    file: &RustFileImpl,
+) -> Option<RustFileImpl> {
+    let struct_name = qry.struct_name.clone();
+    let params_name = format!("{}Params", struct_name);
+    let st = file
+        .structlist
+        .iter()
+        .find(|s| s.struct_name == struct_name)?;
+    let pst = file
+        .structlist
+        .iter()
+        .find(|s| s.struct_name == params_name)?;
+    let fields = gql_fields(st);
+    let params = gql_fields(pst);
+    let object_name = get_graphql_object_name(&struct_name);
+    let input_name = format!("{}Input", params_name);
+    let api = snake_case(qry.api_handler_name.as_str());
+
+    let mut object = gql_struct(
+        object_name.clone(),
+        vec![
+            "#[derive(Debug, Clone, Default, SimpleObject)]".to_string(),
+            format!("#[graphql(name = \"{}\")]", struct_name),
+        ],
+        &fields,
+        false,
+    );
+    object
+        .annotations
+        .splice(0..0, doc_comment(&Some(qry.comment.clone())));
+    let mut structlist = vec![
+        object,
+        gql_struct(
+            input_name.clone(),
+            vec!["#[derive(Debug, Clone, Default, InputObject)]".to_string()],
+            &params,
+            true,
+        ),
+    ];
+
+    let mut queries = vec![];
+    let mut head = vec![
+        format!(
+            "let param = params.map({}::from).unwrap_or_default();",
+            params_name
+        ),
+        "let rb = get_rbatis();".to_string(),
+    ];
+    if qry.single_result {
+        let mut body = head.clone();
+        body.push(format!(
+            "let val = {}::query(rb, &param).await?;",
+            struct_name
+        ));
+        body.push("Ok(val.map(|v| v.into()))".to_string());
+        queries.push(gql_func(
+            &api,
+            vec![("params".to_string(), format!("Option<{}>", input_name))],
+            format!("Result<Option<{}>>", object_name),
+            body,
+        ));
+    } else {
+        let mut body = head.clone();
+        body.push(format!(
+            "let list = {}::query(rb, &param).await?;",
+            struct_name
+        ));
+        body.push("Ok(list.into_iter().map(|v| v.into()).collect())".to_string());
+        queries.push(gql_func(
+            &api,
+            vec![("params".to_string(), format!("Option<{}>", input_name))],
+            format!("Result<Vec<{}>>", object_name),
+            body,
+        ));
+
+        let page_name = format!("{}Page", struct_name);
+        structlist.push(RustStruct {
+            is_pub: true,
+            has_paging: false,
+            struct_name: page_name.clone(),
+            annotations: vec!["#[derive(Debug, Clone, Default, SimpleObject)]".to_string()],
+            fields: [
+                ("records", format!("Vec<{}>", object_name)),
+                ("total", "u64".to_string()),
+                ("page_no", "u64".to_string()),
+                ("page_size", "u64".to_string()),
+            ]
+            .into_iter()
+            .map(|(name, tp)| RustStructField {
+                is_pub: true,
+                field_name: name.to_string(),
+                field_type: tp,
+                ..Default::default()
+            })
+            .collect(),
+            funclist: vec![],
+            usings: vec![],
+        });
+        head.push(format!(
+            "let page = {}::query_paged(rb, &param, current, size).await?;",
+            struct_name
+        ));
+        head.push(format!("Ok({} {{", page_name));
+        head.push("records: page.records.into_iter().map(|v| v.into()).collect(),".to_string());
+        head.push("total: page.total,".to_string());
+        head.push("page_no: page.page_no,".to_string());
+        head.push("page_size: page.page_size,".to_string());
+        head.push("})".to_string());
+        queries.push(gql_func(
+            &format!("{}_page", api),
+            vec![
+                ("params".to_string(), format!("Option<{}>", input_name)),
+                ("current".to_string(), "u64".to_string()),
+                ("size".to_string(), "u64".to_string()),
+            ],
+            format!("Result<{}>", page_name),
+            head,
+        ));
+    }
+
+    structlist.push(resolver_struct(get_graphql_query_name(&struct_name)));
+    let trait_impls = vec![
+        object_impl(get_graphql_query_name(&struct_name), queries),
+        object_from_impl(&struct_name, &fields),
+        input_into_impl(&input_name, &params_name, &params, pst.fields.len()),
+    ];
+    let mut rfi = make_graphql_file(&struct_name, structlist, trait_impls);
+    rfi.usinglist = graphql_usings(&rfi, &[], &[]);
+    rfi.usinglist.push(format!(
+        "crate::query::{{{}, {}}}",
+        params_name, struct_name
+    ));
+    Some(rfi)
+}
+
+fn make_graphql_file(
+    struct_name: &str,
+    structlist: Vec<RustStruct>,
+    implist: Vec<RustTraitImpl>,
+) -> RustFileImpl {
+    RustFileImpl {
+        file_name: format!("{}.rs", snake_case(struct_name)),
+        mod_name: "graphql".to_string(),
+        caretlist: vec![],
+        usinglist: vec![],
+        structlist,
+        funclist: vec![],
+        implist,
+        artifact: None,
+    }
+}
+
+/**
+ * 合并所有的Query及Mutation，并生成GraphQL的端点
+ * queries为有{S}GraphqlQuery的结构体名，mutations为有{S}GraphqlMutation的结构体名
+ */
+pub fn generate_graphql_schema_file(
+    ctx: &GenerateContext,
+    queries: &[String],
+    mutations: &[String],
+    loaders: &[String],
+) -> RustFileImpl {
+    let root = |name: &str, fields: Vec<String>| RustStruct {
+        is_pub: true,
+        has_paging: false,
+        struct_name: name.to_string(),
+        annotations: vec!["#[derive(MergedObject, Default)]".to_string()],
+        fields: fields
+            .into_iter()
+            .map(|tp| RustStructField {
+                field_type: tp,
+                ..Default::default()
+            })
+            .collect(),
+        funclist: vec![],
+        usings: vec![],
+    };
+
+    let mut usinglist = vec![];
+    let mut structlist = vec![root(
+        "QueryRoot",
+        queries.iter().map(get_graphql_query_name).collect(),
+    )];
+    let mutation = if mutations.is_empty() {
+        "EmptyMutation".to_string()
+    } else {
+        structlist.push(root(
+            "MutationRoot",
+            mutations.iter().map(get_graphql_mutation_name).collect(),
+        ));
+        "MutationRoot".to_string()
+    };
+    let schema_type = format!("Schema<QueryRoot, {}, EmptySubscription>", mutation);
+    usinglist.push("async_graphql::{EmptySubscription, MergedObject, Schema}".to_string());
+    usinglist.push("async_graphql::http::GraphiQLSource".to_string());
+    if mutations.is_empty() {
+        usinglist.push("async_graphql::EmptyMutation".to_string());
+    }
+    for name in queries.iter().map(get_graphql_query_name) {
+        usinglist.push(format!("crate::graphql::{}", name));
+    }
+    for name in mutations.iter().map(get_graphql_mutation_name) {
+        usinglist.push(format!("crate::graphql::{}", name));
+    }
+    for name in loaders.iter() {
+        usinglist.push(format!("crate::graphql::{}", name));
+    }
+    if !loaders.is_empty() {
+        usinglist.push("async_graphql::dataloader::DataLoader".to_string());
+    }
+    usinglist.push("std::sync::OnceLock".to_string());
+
+    let mut funclist = vec![];
+    funclist.push(RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: false,
+        func_name: "get_graphql_schema".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some(format!("&'static {}", schema_type)),
+        params: vec![],
+        bodylines: vec![
+            format!(
+                "static SCHEMA: OnceLock<{}> = OnceLock::new();",
+                schema_type
+            ),
+            "SCHEMA.get_or_init(|| {".to_string(),
+            format!(
+                "Schema::build(QueryRoot::default(), {}, EmptySubscription).finish()",
+                if mutations.is_empty() {
+                    "EmptyMutation"
+                } else {
+                    "MutationRoot::default()"
+                }
+            ),
+            "})".to_string(),
+        ],
+        macros: vec![],
+        comment: Some("GraphQL的Schema，只创建一次".to_string()),
+        api_method: None,
+        api_pattern: None,
+    });
+
+    // DataLoader按请求创建，同一个请求中的关联字段合并为一次查询
+    let url_pattern = format!("{}/graphql", ctx.codegen_conf.api_handler_prefix.clone());
+    let mut body = vec![];
+    if loaders.is_empty() {
+        body.push("let request = req.into_inner();".to_string());
+    } else {
+        body.push("let mut request = req.into_inner();".to_string());
+        for ld in loaders.iter() {
+            body.push(format!(
+                "request = request.data(DataLoader::new({}::default(), tokio::spawn));",
+                ld
+            ));
+        }
+    }
+    body.push("get_graphql_schema().execute(request).await.into()".to_string());
+
+    let playground_pattern = format!("{}/playground", url_pattern);
+    let (playground_ret, playground_body) = if ctx.is_axum() {
+        usinglist.push("async_graphql_axum::{GraphQLRequest, GraphQLResponse}".to_string());
+        usinglist.push("axum::response::Html".to_string());
+        (
+            "Html<String>".to_string(),
+            vec![format!(
+                "Html(GraphiQLSource::build().endpoint(\"{}\").finish())",
+                url_pattern
+            )],
+        )
+    } else {
+        usinglist.push("async_graphql_actix_web::{GraphQLRequest, GraphQLResponse}".to_string());
+        usinglist.push("actix_web::HttpResponse".to_string());
+        (
+            "HttpResponse".to_string(),
+            vec![
+                "HttpResponse::Ok()".to_string(),
+                "    .content_type(\"text/html; charset=utf-8\")".to_string(),
+                format!(
+                    "    .body(GraphiQLSource::build().endpoint(\"{}\").finish())",
+                    url_pattern
+                ),
+            ],
+        )
+    };
+    let handler =
+        |name: &str, method: &str, pattern: &String, params, ret: String, body| RustFunc {
+            is_struct_fn: false,
+            is_self_fn: false,
+            is_self_mut: false,
+            is_pub: ctx.is_axum(),
+            is_async: true,
+            func_name: name.to_string(),
+            return_is_option: false,
+            return_is_result: false,
+            return_type: Some(ret),
+            params,
+            bodylines: body,
+            macros: if ctx.is_axum() {
+                vec![]
+            } else {
+                vec![format!("#[{}(\"{}\")]", method.to_lowercase(), pattern)]
+            },
+            comment: None,
+            api_method: Some(method.to_string()),
+            api_pattern: Some(pattern.clone()),
+        };
+    funclist.push(handler(
+        "graphql_handler",
+        "POST",
+        &url_pattern,
+        vec![("req".to_string(), "GraphQLRequest".to_string())],
+        "GraphQLResponse".to_string(),
+        body,
+    ));
+    funclist.push(handler(
+        "graphql_playground",
+        "GET",
+        &playground_pattern,
+        vec![],
+        playground_ret,
+        playground_body,
+    ));
+
+    RustFileImpl {
+        file_name: "schema.rs".to_string(),
+        mod_name: "graphql".to_string(),
+        caretlist: vec![],
+        usinglist,
+        structlist,
+        funclist,
+        implist: vec![],
+        artifact: None,
+    }
+}
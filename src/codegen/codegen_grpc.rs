@@ -0,0 +1,927 @@
+use change_case::snake_case;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::codegen::{
+    parse_data_type_as_rust_type, parse_relation_as_struct, GenerateContext, RustFileImpl,
+    RustFunc, RustOutput, RustStruct, RustStructField, RustTraitImpl,
+};
+use crate::config::RelationConfig;
+use crate::schema::{ColumnInfo, TableInfo};
+
+/**
+ * gRPC的生成（generate-grpc）
+ * 每个表及关系生成proto/{name}.proto及grpc/{name}.rs：
+ * 1、message的字段由实体的RustStructField转换，日期时间使用google.protobuf.Timestamp，Decimal、日期、时间及Json使用string
+ * 2、表的服务包括Get/Create/Update/Delete/List/Page/Tree（Page在page-query时，Tree在tree-parent-field时），关系的服务包括Get/Save/Delete
+ * 3、tonic的服务实现调用实体的from_id、save、update、remove、query_list、query_paged、query_tree，关系调用load、save、remove
+ * 4、proto的message与实体之间互相实现From，proto中没有的字段（如附件）使用Default
+ * proto由build.rs中的tonic-build编译，生成的代码由grpc::pb引入
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProtoFileImpl {
+    pub file_name: String,
+    pub package: String,
+    pub imports: Vec<String>,
+    pub blocks: Vec<String>, // message及service的定义
+}
+
+impl ProtoFileImpl {
+    pub fn to_source(&self) -> String {
+        let mut ro = RustOutput::default();
+        ro.write_line("syntax = \"proto3\";");
+        ro.write_line("");
+        ro.write_line(&format!("package {};", self.package));
+        ro.write_line("");
+        let mut imports = self.imports.clone();
+        imports.sort();
+        imports.dedup();
+        for im in imports.iter() {
+            ro.write_line(&format!("import \"{}\";", im));
+        }
+        if !imports.is_empty() {
+            ro.write_line("");
+        }
+        for blk in self.blocks.iter() {
+            ro.write_line(blk);
+            ro.write_line("");
+        }
+        ro.to_text()
+    }
+}
+
+/**
+ * 字段在proto中的表示
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum ProtoKind {
+    Scalar(&'static str, Option<(&'static str, &'static str)>), // proto的类型，需要转换时为(proto的Rust类型, 实体的Rust类型)
+    Timestamp,
+    Text, // Decimal、日期、时间，使用to_string/parse转换
+    Json,
+    Bytes,
+    Message(String),
+    Repeated(String),
+}
+
+/**
+ * proto的package，所有的proto使用同一个package
+ */
+pub fn get_grpc_package(ctx: &GenerateContext) -> String {
+    snake_case(ctx.codegen_conf.app_name.as_str())
+}
+
+fn get_grpc_service_name(struct_name: &String) -> String {
+    format!("{}GrpcService", struct_name)
+}
+
+fn proto_kind(ctx: &GenerateContext, field_type: &str) -> Option<ProtoKind> {
+    let kind = match field_type {
+        "i64" => ProtoKind::Scalar("int64", None),
+        "i32" => ProtoKind::Scalar("int32", None),
+        "i16" => ProtoKind::Scalar("int32", Some(("i32", "i16"))),
+        "u64" => ProtoKind::Scalar("uint64", None),
+        "u32" => ProtoKind::Scalar("uint32", None),
+        "u16" => ProtoKind::Scalar("uint32", Some(("u32", "u16"))),
+        "bool" => ProtoKind::Scalar("bool", None),
+        "f32" => ProtoKind::Scalar("float", None),
+        "f64" => ProtoKind::Scalar("double", None),
+        "String" => ProtoKind::Scalar("string", None),
+        "rbatis::DateTimeNative"
+        | "rbatis::DateTimeUtc"
+        | "rbatis::Timestamp"
+        | "rbatis::TimestampZ" => ProtoKind::Timestamp,
+        "rbatis::DateNative" | "rbatis::DateUtc" | "rbatis::TimeNative" | "rbatis::TimeUtc"
+        | "rbatis::Decimal" => ProtoKind::Text,
+        "rbatis::Json" => ProtoKind::Json,
+        "rbatis::Bytes" => ProtoKind::Bytes,
+        _ => {
+            let is_message = |name: &str| {
+                ctx.get_table_conf_by_struct_name(&name.to_string())
+                    .is_some()
+            };
+            match field_type
+                .strip_prefix("Vec<")
+                .and_then(|t| t.strip_suffix('>'))
+            {
+                Some(inner) if is_message(inner) => ProtoKind::Repeated(inner.to_string()),
+                Some(_) => return None,
+                None if is_message(field_type) => ProtoKind::Message(field_type.to_string()),
+                None => return None,
+            }
+        }
+    };
+    Some(kind)
+}
+
+fn proto_type(kind: &ProtoKind, is_option: bool) -> String {
+    let optional = if is_option { "optional " } else { "" };
+    match kind {
+        ProtoKind::Scalar(t, _) => format!("{}{}", optional, t),
+        ProtoKind::Timestamp => "google.protobuf.Timestamp".to_string(),
+        ProtoKind::Text | ProtoKind::Json => format!("{}string", optional),
+        ProtoKind::Bytes => format!("{}bytes", optional),
+        ProtoKind::Message(name) => name.clone(),
+        ProtoKind::Repeated(name) => format!("repeated {}", name),
+    }
+}
+
+/**
+ * 实体的字段转换为proto字段的表达式，src为实体的字段，如e.name
+ */
+fn to_proto_expr(kind: &ProtoKind, is_option: bool, src: &str) -> String {
+    match (kind, is_option) {
+        (ProtoKind::Scalar(_, None), _) => src.to_string(),
+        (ProtoKind::Scalar(_, Some((pt, _))), true) => format!("{}.map(|v| v as {})", src, pt),
+        (ProtoKind::Scalar(_, Some((pt, _))), false) => format!("{} as {}", src, pt),
+        (ProtoKind::Timestamp, true) => format!("{}.as_ref().and_then(to_timestamp)", src),
+        (ProtoKind::Timestamp, false) => format!("to_timestamp(&{})", src),
+        (ProtoKind::Text, true) => format!("{}.as_ref().map(|v| v.to_string())", src),
+        (ProtoKind::Text, false) => format!("{}.to_string()", src),
+        (ProtoKind::Json, true) => format!(
+            "{}.as_ref().and_then(|v| serde_json::to_string(v).ok())",
+            src
+        ),
+        (ProtoKind::Json, false) => format!("serde_json::to_string(&{}).unwrap_or_default()", src),
+        (ProtoKind::Bytes, true) => format!("{}.as_ref().map(|v| v.to_vec())", src),
+        (ProtoKind::Bytes, false) => format!("{}.to_vec()", src),
+        (ProtoKind::Message(_), _) => format!("{}.map(|v| v.into())", src),
+        (ProtoKind::Repeated(_), _) => format!("{}.into_iter().map(|v| v.into()).collect()", src),
+    }
+}
+
+/**
+ * proto的字段转换为实体字段的表达式，src为proto的字段，如m.name
+ */
+fn from_proto_expr(kind: &ProtoKind, is_option: bool, src: &str) -> String {
+    match (kind, is_option) {
+        (ProtoKind::Scalar(_, None), _) => src.to_string(),
+        (ProtoKind::Scalar(_, Some((_, et))), true) => format!("{}.map(|v| v as {})", src, et),
+        (ProtoKind::Scalar(_, Some((_, et))), false) => format!("{} as {}", src, et),
+        (ProtoKind::Timestamp, true) => format!("{}.as_ref().and_then(from_timestamp)", src),
+        (ProtoKind::Timestamp, false) => format!(
+            "{}.as_ref().and_then(from_timestamp).unwrap_or_default()",
+            src
+        ),
+        (ProtoKind::Text, true) => format!("{}.and_then(|v| v.parse().ok())", src),
+        (ProtoKind::Text, false) => format!("{}.parse().unwrap_or_default()", src),
+        (ProtoKind::Json, true) => format!("{}.and_then(|v| serde_json::from_str(&v).ok())", src),
+        (ProtoKind::Json, false) => format!("serde_json::from_str(&{}).unwrap_or_default()", src),
+        (ProtoKind::Bytes, true) => format!("{}.map(|v| v.into())", src),
+        (ProtoKind::Bytes, false) => format!("{}.into()", src),
+        (ProtoKind::Message(_), _) => format!("{}.map(|v| v.into())", src),
+        (ProtoKind::Repeated(_), _) => format!("{}.into_iter().map(|v| v.into()).collect()", src),
+    }
+}
+
+fn proto_field_name(field_name: &str) -> String {
+    field_name.trim_start_matches("r#").to_string()
+}
+
+fn proto_comment(comment: &Option<String>) -> String {
+    match comment
+        .clone()
+        .unwrap_or_default()
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+    {
+        Some(c) if !c.is_empty() => format!(" // {}", c),
+        _ => String::new(),
+    }
+}
+
+/**
+ * 结构体中可以在proto中表示的字段
+ */
+fn proto_fields(ctx: &GenerateContext, st: &RustStruct) -> Vec<(RustStructField, ProtoKind)> {
+    st.fields
+        .iter()
+        .filter_map(|fd| match proto_kind(ctx, &fd.field_type) {
+            Some(kind) => Some((fd.clone(), kind)),
+            None => {
+                log::info!(
+                    "{}.{} ({}) could not be represented in proto, it was skipped.",
+                    st.struct_name,
+                    fd.field_name,
+                    fd.field_type
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+fn format_message(
+    name: &String,
+    comment: &Option<String>,
+    fields: &[(String, String, Option<String>)],
+) -> String {
+    let mut lines = vec![];
+    let cmt = proto_comment(comment);
+    if !cmt.is_empty() {
+        lines.push(format!("//{}", &cmt[3..]));
+    }
+    lines.push(format!("message {} {{", name));
+    for (i, (fdname, fdtype, fdcmt)) in fields.iter().enumerate() {
+        lines.push(format!(
+            "  {} {} = {};{}",
+            fdtype,
+            fdname,
+            i + 1,
+            proto_comment(fdcmt)
+        ));
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn format_service(name: &String, rpcs: &Vec<(&str, String, String)>) -> String {
+    let mut lines = vec![format!("service {} {{", name)];
+    for (rpc, req, resp) in rpcs.iter() {
+        lines.push(format!("  rpc {}({}) returns ({});", rpc, req, resp));
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+pub fn get_primary_key_columns(ctx: &GenerateContext, tbl_name: &str) -> Vec<ColumnInfo> {
+    let mut pkcols = ctx.get_table_column_by_primary_key(&tbl_name.to_string());
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(&tbl_name.to_string()));
+    }
+    pkcols
+}
+
+/**
+ * 主键的message及from_id/load的参数
+ */
+fn parse_key_message(
+    ctx: &GenerateContext,
+    struct_name: &String,
+    pkcols: &[ColumnInfo],
+) -> (String, Vec<String>) {
+    let mut fields = vec![];
+    let mut args = vec![];
+    for col in pkcols.iter() {
+        let colname = col.column_name.clone().unwrap_or_default().to_lowercase();
+        let dt = parse_data_type_as_rust_type(&col.data_type.clone().unwrap_or_default());
+        let kind = proto_kind(ctx, &dt).unwrap_or(ProtoKind::Scalar("string", None));
+        fields.push((
+            colname.clone(),
+            proto_type(&kind, false),
+            col.column_comment.clone(),
+        ));
+        let expr = from_proto_expr(&kind, false, &format!("key.{}", colname));
+        if expr.contains(' ') || expr.contains('(') {
+            args.push(format!("&({})", expr));
+        } else {
+            args.push(format!("&{}", expr));
+        }
+    }
+    (
+        format_message(&format!("{}Key", struct_name), &None, &fields),
+        args,
+    )
+}
+
+fn grpc_func(func_name: &str, req: String, resp: String, body: Vec<String>) -> RustFunc {
+    RustFunc {
+        is_struct_fn: true,
+        is_self_fn: true,
+        is_self_mut: false,
+        is_pub: false,
+        is_async: true,
+        func_name: func_name.to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some(format!("Result<Response<pb::{}>, Status>", resp)),
+        params: vec![("request".to_string(), format!("Request<pb::{}>", req))],
+        bodylines: body,
+        macros: vec![],
+        comment: None,
+        api_method: None,
+        api_pattern: None,
+    }
+}
+
+/**
+ * 在事务中执行实体的方法，成功后返回resp
+ */
+fn transaction_lines(call: &str, resp: &str) -> Vec<String> {
+    let mut body = vec![];
+    body.push("match rb.acquire_begin().await {".to_string());
+    body.push("Ok(mut tx) => {".to_string());
+    body.push(format!("match {} {{", call));
+    body.push("Ok(_) => {".to_string());
+    body.push("match tx.commit().await {".to_string());
+    body.push(format!("Ok(_) => Ok(Response::new({})),", resp));
+    body.push("Err(err) => Err(Status::internal(err.to_string())),".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    body.push("Err(err) => {".to_string());
+    body.push("let _ = tx.rollback().await.is_ok();".to_string());
+    body.push("Err(Status::internal(err.to_string()))".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    body.push("Err(err) => Err(Status::internal(err.to_string())),".to_string());
+    body.push("}".to_string());
+    body
+}
+
+/**
+ * 按主键加载，不存在时返回NotFound
+ */
+fn load_lines(struct_name: &String, load_fn: &str, args: &[String], var: &str) -> Vec<String> {
+    let mut body = vec![];
+    body.push("let key = request.into_inner();".to_string());
+    body.push("let rb = get_rbatis();".to_string());
+    body.push(format!(
+        "let {} = match {}::{}(rb, {}).await {{",
+        var,
+        struct_name,
+        load_fn,
+        args.join(", ")
+    ));
+    body.push("Ok(Some(val)) => val,".to_string());
+    body.push(format!(
+        "Ok(None) => return Err(Status::not_found(\"{} not found\")),",
+        struct_name
+    ));
+    body.push("Err(err) => return Err(Status::internal(err.to_string())),".to_string());
+    body.push("};".to_string());
+    body
+}
+
+/**
+ * proto的message与实体之间的From
+ */
+fn generate_from_impls(
+    struct_name: &String,
+    fields: &[(RustStructField, ProtoKind)],
+    all_fields: usize,
+) -> Vec<RustTraitImpl> {
+    let mut to_entity = vec!["Self {".to_string()];
+    let mut to_proto = vec!["Self {".to_string()];
+    for (fd, kind) in fields.iter() {
+        to_entity.push(format!(
+            "{}: {},",
+            fd.field_name,
+            from_proto_expr(kind, fd.is_option, &format!("m.{}", fd.field_name))
+        ));
+        to_proto.push(format!(
+            "{}: {},",
+            fd.field_name,
+            to_proto_expr(kind, fd.is_option, &format!("e.{}", fd.field_name))
+        ));
+    }
+    if fields.len() < all_fields {
+        to_entity.push("..Default::default()".to_string());
+    }
+    to_entity.push("}".to_string());
+    to_proto.push("}".to_string());
+
+    let from_func = |param: &str, ptype: String, body: Vec<String>| RustFunc {
+        is_struct_fn: true,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: false,
+        is_async: false,
+        func_name: "from".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("Self".to_string()),
+        params: vec![(param.to_string(), ptype)],
+        bodylines: body,
+        macros: vec![],
+        comment: None,
+        api_method: None,
+        api_pattern: None,
+    };
+
+    vec![
+        RustTraitImpl {
+            annotations: vec![],
+            trait_name: format!("From<pb::{}>", struct_name),
+            struct_name: struct_name.clone(),
+            types: vec![],
+            funclist: vec![from_func("m", format!("pb::{}", struct_name), to_entity)],
+        },
+        RustTraitImpl {
+            annotations: vec![],
+            trait_name: format!("From<{}>", struct_name),
+            struct_name: format!("pb::{}", struct_name),
+            types: vec![],
+            funclist: vec![from_func("e", struct_name.clone(), to_proto)],
+        },
+    ]
+}
+
+/**
+ * 生成的Rust文件中需要的use
+ */
+fn grpc_usings(rfi: &RustFileImpl) -> Vec<String> {
+    let text = rfi.to_source();
+    let mut usings = vec![
+        "tonic::{Request, Response, Status}".to_string(),
+        "crate::grpc::pb".to_string(),
+        "chimes_utils::get_rbatis".to_string(),
+    ];
+    match (
+        text.contains("to_timestamp"),
+        text.contains("from_timestamp"),
+    ) {
+        (true, true) => usings.push("crate::utils::{from_timestamp, to_timestamp}".to_string()),
+        (true, false) => usings.push("crate::utils::to_timestamp".to_string()),
+        (false, true) => usings.push("crate::utils::from_timestamp".to_string()),
+        (false, false) => {}
+    }
+    usings
+}
+
+fn message_imports(fields: &[(RustStructField, ProtoKind)], self_name: &String) -> Vec<String> {
+    let mut imports = vec![];
+    for (_, kind) in fields.iter() {
+        match kind {
+            ProtoKind::Timestamp => imports.push("google/protobuf/timestamp.proto".to_string()),
+            ProtoKind::Message(name) | ProtoKind::Repeated(name) if name != self_name => {
+                imports.push(format!("{}.proto", snake_case(name.as_str())))
+            }
+            _ => {}
+        }
+    }
+    imports
+}
+
+/**
+ * 为表生成proto及tonic的服务实现
+ */
+pub fn generate_grpc_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+) -> Option<(ProtoFileImpl, RustFileImpl)> {
+    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+    let tbc = ctx.get_table_conf(&tbl_name)?;
+    let tbl_struct_name = ctx.get_struct_name(&tbl_name)?;
+    let st = ctx
+        .structs
+        .iter()
+        .find(|s| s.struct_name == tbl_struct_name)?
+        .clone();
+    let struct_name = st.struct_name.clone();
+    let fields = proto_fields(ctx, &st);
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+    if pkcols.is_empty() {
+        log::info!(
+            "{} has no primary key, the gRPC service was not generated.",
+            tbl_name
+        );
+        return None;
+    }
+
+    let mut blocks = vec![];
+    blocks.push(format_message(
+        &struct_name,
+        &Some(tbc.comment.clone()),
+        &fields
+            .iter()
+            .map(|(fd, kind)| {
+                (
+                    proto_field_name(&fd.field_name),
+                    proto_type(kind, fd.is_option),
+                    fd.comment.clone(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    ));
+    let (keymsg, keyargs) = parse_key_message(ctx, &struct_name, &pkcols);
+    blocks.push(keymsg);
+    blocks.push(format_message(
+        &format!("{}List", struct_name),
+        &None,
+        &[(
+            "records".to_string(),
+            format!("repeated {}", struct_name),
+            None,
+        )],
+    ));
+
+    let mut rpcs = vec![
+        ("Get", format!("{}Key", struct_name), struct_name.clone()),
+        ("Create", struct_name.clone(), struct_name.clone()),
+        ("Update", struct_name.clone(), struct_name.clone()),
+        ("Delete", format!("{}Key", struct_name), struct_name.clone()),
+        ("List", struct_name.clone(), format!("{}List", struct_name)),
+    ];
+    let mut funclist = vec![];
+    let mut body = load_lines(&struct_name, "from_id", &keyargs, "val");
+    body.push("Ok(Response::new(val.into()))".to_string());
+    funclist.push(grpc_func(
+        "get",
+        format!("{}Key", struct_name),
+        struct_name.clone(),
+        body,
+    ));
+
+    let mut body = vec![
+        format!("let mut val = {}::from(request.into_inner());", struct_name),
+        "let rb = get_rbatis();".to_string(),
+    ];
+    body.append(&mut transaction_lines(
+        "val.save(&mut tx).await",
+        "val.into()",
+    ));
+    funclist.push(grpc_func(
+        "create",
+        struct_name.clone(),
+        struct_name.clone(),
+        body,
+    ));
+
+    let mut body = vec![
+        format!("let mut val = {}::from(request.into_inner());", struct_name),
+        "let rb = get_rbatis();".to_string(),
+    ];
+    body.append(&mut transaction_lines(
+        "val.update(&mut tx).await",
+        "val.into()",
+    ));
+    funclist.push(grpc_func(
+        "update",
+        struct_name.clone(),
+        struct_name.clone(),
+        body,
+    ));
+
+    let mut body = load_lines(&struct_name, "from_id", &keyargs, "mut val");
+    body.append(&mut transaction_lines(
+        "val.remove(&mut tx).await",
+        "val.into()",
+    ));
+    funclist.push(grpc_func(
+        "delete",
+        format!("{}Key", struct_name),
+        struct_name.clone(),
+        body,
+    ));
+
+    let mut body = vec![
+        format!("let val = {}::from(request.into_inner());", struct_name),
+        "let rb = get_rbatis();".to_string(),
+    ];
+    body.push("match val.query_list(rb).await {".to_string());
+    body.push(format!(
+        "Ok(list) => Ok(Response::new(pb::{}List {{ records: list.into_iter().map(|v| v.into()).collect() }})),",
+        struct_name
+    ));
+    body.push("Err(err) => Err(Status::internal(err.to_string())),".to_string());
+    body.push("}".to_string());
+    funclist.push(grpc_func(
+        "list",
+        struct_name.clone(),
+        format!("{}List", struct_name),
+        body,
+    ));
+
+    if tbc.page_query {
+        blocks.push(format_message(
+            &format!("{}PageRequest", struct_name),
+            &None,
+            &[
+                ("query".to_string(), struct_name.clone(), None),
+                ("current".to_string(), "uint64".to_string(), None),
+                ("size".to_string(), "uint64".to_string(), None),
+            ],
+        ));
+        blocks.push(format_message(
+            &format!("{}Page", struct_name),
+            &None,
+            &[
+                (
+                    "records".to_string(),
+                    format!("repeated {}", struct_name),
+                    None,
+                ),
+                ("total".to_string(), "uint64".to_string(), None),
+                ("page_no".to_string(), "uint64".to_string(), None),
+                ("page_size".to_string(), "uint64".to_string(), None),
+            ],
+        ));
+        rpcs.push((
+            "Page",
+            format!("{}PageRequest", struct_name),
+            format!("{}Page", struct_name),
+        ));
+
+        let mut body = vec![
+            "let req = request.into_inner();".to_string(),
+            format!(
+                "let val = req.query.map({}::from).unwrap_or_default();",
+                struct_name
+            ),
+            "let rb = get_rbatis();".to_string(),
+        ];
+        body.push("match val.query_paged(rb, req.current, req.size).await {".to_string());
+        body.push(format!(
+            "Ok(page) => Ok(Response::new(pb::{}Page {{",
+            struct_name
+        ));
+        body.push("records: page.records.into_iter().map(|v| v.into()).collect(),".to_string());
+        body.push("total: page.total,".to_string());
+        body.push("page_no: page.page_no,".to_string());
+        body.push("page_size: page.page_size,".to_string());
+        body.push("})),".to_string());
+        body.push("Err(err) => Err(Status::internal(err.to_string())),".to_string());
+        body.push("}".to_string());
+        funclist.push(grpc_func(
+            "page",
+            format!("{}PageRequest", struct_name),
+            format!("{}Page", struct_name),
+            body,
+        ));
+    }
+
+    if let Some(treecol) = tbc
+        .tree_parent_field
+        .clone()
+        .and_then(|f| ctx.find_table_column(&tbl_name, &f))
+    {
+        let dt = parse_data_type_as_rust_type(&treecol.data_type.clone().unwrap_or_default());
+        let kind = proto_kind(ctx, &dt).unwrap_or(ProtoKind::Scalar("string", None));
+        blocks.push(format_message(
+            &format!("{}TreeRequest", struct_name),
+            &None,
+            &[(
+                "pid".to_string(),
+                proto_type(&kind, true),
+                treecol.column_comment.clone(),
+            )],
+        ));
+        rpcs.push((
+            "Tree",
+            format!("{}TreeRequest", struct_name),
+            format!("{}List", struct_name),
+        ));
+
+        let mut body = vec![
+            "let req = request.into_inner();".to_string(),
+            "let rb = get_rbatis();".to_string(),
+        ];
+        body.push(format!(
+            "match {}::query_tree(rb, &{}).await {{",
+            struct_name,
+            from_proto_expr(&kind, true, "req.pid")
+        ));
+        body.push(format!(
+            "Ok(list) => Ok(Response::new(pb::{}List {{ records: list.into_iter().map(|v| v.into()).collect() }})),",
+            struct_name
+        ));
+        body.push("Err(err) => Err(Status::internal(err.to_string())),".to_string());
+        body.push("}".to_string());
+        funclist.push(grpc_func(
+            "tree",
+            format!("{}TreeRequest", struct_name),
+            format!("{}List", struct_name),
+            body,
+        ));
+    }
+
+    blocks.push(format_service(&format!("{}Service", struct_name), &rpcs));
+
+    let proto = ProtoFileImpl {
+        file_name: format!("{}.proto", snake_case(struct_name.as_str())),
+        package: get_grpc_package(ctx),
+        imports: message_imports(&fields, &struct_name),
+        blocks,
+    };
+
+    Some((
+        proto,
+        make_grpc_file(
+            &struct_name,
+            funclist,
+            generate_from_impls(&struct_name, &fields, st.fields.len()),
+        ),
+    ))
+}
+
+/**
+ * 为关系生成proto及tonic的服务实现
+ * 服务按generate-select、generate-save及generate-delete生成Get、Save及Delete
+ */
+pub fn generate_grpc_for_relation(
+    ctx: &GenerateContext,
+    rel: &RelationConfig,
+) -> Option<(ProtoFileImpl, RustFileImpl)> {
+    let st = parse_relation_as_struct(ctx, rel);
+    if st.struct_name.is_empty() {
+        return None;
+    }
+    let struct_name = st.struct_name.clone();
+    let fields = proto_fields(ctx, &st);
+    let pkcols = get_primary_key_columns(ctx, &rel.major_table);
+
+    let mut blocks = vec![];
+    blocks.push(format_message(
+        &struct_name,
+        &Some(rel.comment.clone()),
+        &fields
+            .iter()
+            .map(|(fd, kind)| {
+                (
+                    proto_field_name(&fd.field_name),
+                    proto_type(kind, fd.is_option),
+                    fd.comment.clone(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    ));
+
+    let mut rpcs = vec![];
+    let mut funclist = vec![];
+    if !pkcols.is_empty() && (rel.generate_select || rel.generate_delete) {
+        let (keymsg, keyargs) = parse_key_message(ctx, &struct_name, &pkcols);
+        blocks.push(keymsg);
+        if rel.generate_select {
+            rpcs.push(("Get", format!("{}Key", struct_name), struct_name.clone()));
+            let mut body = load_lines(&struct_name, "load", &keyargs, "val");
+            body.push("Ok(Response::new(val.into()))".to_string());
+            funclist.push(grpc_func(
+                "get",
+                format!("{}Key", struct_name),
+                struct_name.clone(),
+                body,
+            ));
+        }
+        if rel.generate_delete {
+            rpcs.push(("Delete", format!("{}Key", struct_name), struct_name.clone()));
+            let mut body = load_lines(&struct_name, "load", &keyargs, "val");
+            body.append(&mut transaction_lines(
+                "val.remove(&mut tx).await",
+                "val.into()",
+            ));
+            funclist.push(grpc_func(
+                "delete",
+                format!("{}Key", struct_name),
+                struct_name.clone(),
+                body,
+            ));
+        }
+    }
+    if rel.generate_save {
+        rpcs.push(("Save", struct_name.clone(), struct_name.clone()));
+        let mut body = vec![
+            format!("let mut val = {}::from(request.into_inner());", struct_name),
+            "let rb = get_rbatis();".to_string(),
+        ];
+        body.append(&mut transaction_lines(
+            "val.save(&mut tx).await",
+            "val.into()",
+        ));
+        funclist.push(grpc_func(
+            "save",
+            struct_name.clone(),
+            struct_name.clone(),
+            body,
+        ));
+    }
+
+    if !rpcs.is_empty() {
+        blocks.push(format_service(&format!("{}Service", struct_name), &rpcs));
+    }
+
+    let proto = ProtoFileImpl {
+        file_name: format!("{}.proto", snake_case(struct_name.as_str())),
+        package: get_grpc_package(ctx),
+        imports: message_imports(&fields, &struct_name),
+        blocks,
+    };
+
+    Some((
+        proto,
+        make_grpc_file(
+            &struct_name,
+            funclist,
+            generate_from_impls(&struct_name, &fields, st.fields.len()),
+        ),
+    ))
+}
+
+fn make_grpc_file(
+    struct_name: &String,
+    funclist: Vec<RustFunc>,
+    mut implist: Vec<RustTraitImpl>,
+) -> RustFileImpl {
+    let mut rfi = RustFileImpl {
+        file_name: format!("{}.rs", snake_case(struct_name.as_str())),
+        mod_name: "grpc".to_string(),
+        caretlist: vec![],
+        usinglist: vec![],
+        structlist: vec![],
+        funclist: vec![],
+        implist: vec![],
+        artifact: None,
+    };
+    if !funclist.is_empty() {
+        let service_name = get_grpc_service_name(struct_name);
+        rfi.structlist.push(RustStruct {
+            is_pub: true,
+            has_paging: false,
+            struct_name: service_name.clone(),
+            annotations: vec!["#[derive(Debug, Clone, Default)]".to_string()],
+            fields: vec![],
+            funclist: vec![],
+            usings: vec![],
+        });
+        rfi.implist.push(RustTraitImpl {
+            annotations: vec!["#[tonic::async_trait]".to_string()],
+            trait_name: format!(
+                "pb::{}_service_server::{}Service",
+                snake_case(struct_name.as_str()),
+                struct_name
+            ),
+            struct_name: service_name,
+            types: vec![],
+            funclist,
+        });
+    }
+    rfi.implist.append(&mut implist);
+    rfi.usinglist = grpc_usings(&rfi);
+    if rfi.structlist.is_empty() {
+        rfi.usinglist
+            .retain(|us| us.starts_with("crate::grpc") || us.starts_with("crate::utils"));
+    }
+    rfi.usinglist
+        .push(format!("crate::entity::{}", struct_name));
+    rfi
+}
+
+/**
+ * 注册所有的tonic服务并启动gRPC服务
+ */
+pub fn generate_grpc_server_file(services: &[String]) -> RustFileImpl {
+    let mut usinglist = vec![
+        "std::net::SocketAddr".to_string(),
+        "tonic::transport::Server".to_string(),
+    ];
+    let mut body = vec!["Server::builder()".to_string()];
+    for sn in services.iter() {
+        usinglist.push(format!(
+            "crate::grpc::pb::{}_service_server::{}ServiceServer",
+            snake_case(sn.as_str()),
+            sn
+        ));
+        usinglist.push(format!("crate::grpc::{}", get_grpc_service_name(sn)));
+        body.push(format!(
+            "    .add_service({}ServiceServer::new({}::default()))",
+            sn,
+            get_grpc_service_name(sn)
+        ));
+    }
+    body.push("    .serve(addr)".to_string());
+    body.push("    .await".to_string());
+
+    let func = RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: true,
+        func_name: "serve_grpc".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("Result<(), tonic::transport::Error>".to_string()),
+        params: vec![("addr".to_string(), "SocketAddr".to_string())],
+        bodylines: body,
+        macros: vec!["#[allow(dead_code)]".to_string()],
+        comment: Some("启动gRPC服务".to_string()),
+        api_method: None,
+        api_pattern: None,
+    };
+
+    RustFileImpl {
+        file_name: "grpc_server.rs".to_string(),
+        mod_name: "grpc".to_string(),
+        caretlist: vec![],
+        usinglist,
+        structlist: vec![],
+        funclist: vec![func],
+        implist: vec![],
+        artifact: None,
+    }
+}
+
+/**
+ * tonic-build生成的代码
+ */
+pub fn format_grpc_pb_source(ctx: &GenerateContext) -> String {
+    let mut ro = RustOutput::default();
+    ro.write_line("// Generated by tonic-build from the proto directory");
+    ro.write_line("#![allow(clippy::all)]");
+    ro.write_line("");
+    ro.write_line(&format!(
+        "tonic::include_proto!(\"{}\");",
+        get_grpc_package(ctx)
+    ));
+    ro.to_text()
+}
@@ -1,4 +1,6 @@
-use super::{parse_column_list, RustStruct};
+use super::{
+    get_logic_deletion, get_version_field, parse_column_list, update_error_line, RustStruct,
+};
 use crate::codegen::{parse_data_type_as_rust_type, GenerateContext, RustFunc};
 use crate::schema::TableInfo;
 use change_case::pascal_case;
@@ -33,6 +35,12 @@ pub fn generate_actix_handler_for_table(
     funclist.push(update_handler);
     let delete_handler = generate_handler_delete_for_struct(ctx, tbl);
     funclist.push(delete_handler);
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        let restore_handler = generate_handler_restore_for_struct(ctx, tbl);
+        funclist.push(restore_handler);
+        let purge_handler = generate_handler_purge_for_struct(ctx, tbl);
+        funclist.push(purge_handler);
+    }
 
     if pkcols.len() == 1 {
         let delete_ids_handler = generate_handler_delete_ids_for_struct(ctx, tbl);
@@ -586,9 +594,10 @@ pub fn generate_handler_update_for_struct(ctx: &GenerateContext, tbl: &TableInfo
     body.push("}".to_string());
     body.push("Err(err) => {".to_string());
     body.push(format!("let _ = tx.rollback().await.is_ok();"));
-    body.push(format!(
-        "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::error(5010, &err.to_string()));",
-        tbl_struct_name.clone()
+    body.push(update_error_line(
+        &tbl_struct_name,
+        5010,
+        get_version_field(ctx, &tbl_name).is_some(),
     ));
     body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
     body.push("}".to_string());
@@ -746,6 +755,34 @@ pub fn generate_handler_save_for_struct(ctx: &GenerateContext, tbl: &TableInfo)
  *
  */
 pub fn generate_handler_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -> RustFunc {
+    generate_handler_remove_by_pkey_for_struct(ctx, tbl, "remove", "delete", "删除")
+}
+
+/**
+ * 生成handler：逻辑删除时，恢复已删除的数据
+ */
+pub fn generate_handler_restore_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -> RustFunc {
+    generate_handler_remove_by_pkey_for_struct(ctx, tbl, "restore", "restore", "恢复")
+}
+
+/**
+ * 生成handler：逻辑删除时，彻底删除
+ */
+pub fn generate_handler_purge_for_struct(ctx: &GenerateContext, tbl: &TableInfo) -> RustFunc {
+    generate_handler_remove_by_pkey_for_struct(ctx, tbl, "purge", "purge", "彻底删除")
+}
+
+/**
+ * 按主键执行实体的方法（remove、restore、purge）
+ * action为handler的名称及URL的后缀
+ */
+fn generate_handler_remove_by_pkey_for_struct(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    method: &str,
+    action: &str,
+    comment: &str,
+) -> RustFunc {
     let tbl_name = tbl.table_name.clone().unwrap_or_default();
     let tbc = ctx.get_table_conf(&tbl_name.clone()).unwrap();
 
@@ -788,7 +825,7 @@ pub fn generate_handler_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo
 
     body.push(format!("match rb.acquire_begin().await {{"));
     body.push(format!("Ok(mut tx) => {{"));
-    body.push(format!("match val.remove(&mut tx).await {{"));
+    body.push(format!("match val.{}(&mut tx).await {{", method));
 
     body.push(format!("Ok(_st) => {{"));
     body.push(format!("match tx.commit().await {{"));
@@ -826,12 +863,13 @@ pub fn generate_handler_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo
     body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
     body.push("}".to_string());
     body.push("}".to_string());
-    let func_name = tbc.api_handler_name.clone() + "_delete";
+    let func_name = format!("{}_{}", tbc.api_handler_name.clone(), action);
 
     let url_pattern = format!(
-        "{}/{}/delete",
+        "{}/{}/{}",
         ctx.codegen_conf.api_handler_prefix.clone(),
-        tbc.api_handler_name.clone()
+        tbc.api_handler_name.clone(),
+        action
     );
     let postmacro = format!("#[post(\"{}\")]", url_pattern.clone());
 
@@ -848,7 +886,7 @@ pub fn generate_handler_delete_for_struct(ctx: &GenerateContext, tbl: &TableInfo
         params: params,
         bodylines: body,
         macros: vec![postmacro],
-        comment: Some(format!("{}删除", tbc.comment.clone())),
+        comment: Some(format!("{}{}", tbc.comment.clone(), comment)),
         api_method: Some("POST".to_string()),
         api_pattern: Some(url_pattern.clone()),
     }
@@ -0,0 +1,233 @@
+//! copyright © 了知信息科技 2021 - present
+//! 部分重新生成：只为发生了变化的表（及依赖于它们的表和relation）重新生成代码，
+//! 再与上一次生成的结果合并，mod.rs、main.rs等汇总的文件仍由合并后的结果统一输出
+use std::collections::HashMap;
+
+use crate::codegen::{CodeGenerator, GenerateContext, RustArtifact, RustFileImpl, VueAndJSFileImpl};
+use crate::config::{CodeGenConfig, RelationConfig};
+use change_case::snake_case;
+
+/**
+ * 是否可以部分重新生成
+ * gRPC、GraphQL、OpenAPI、TypeScript API、客户端、测试以及合并为一个文件时，
+ * 生成的文件汇总了所有的表，只能全部重新生成
+ */
+pub fn is_incremental_supported(ctx: &GenerateContext) -> bool {
+    !(ctx.is_generate_grpc()
+        || ctx.is_generate_graphql()
+        || ctx.is_generate_openapi()
+        || ctx.is_typescript_api()
+        || ctx.is_generate_client()
+        || ctx.is_generate_test()
+        || ctx.is_all_entity_in_one_file()
+        || ctx.is_all_handler_in_one_file()
+        || ctx.is_all_query_in_one_file())
+}
+
+/**
+ * 获取表的列注释中通过关联表{name}引用的表
+ */
+fn get_referenced_tables(ctx: &GenerateContext, tn: &str) -> Vec<String> {
+    ctx.get_table_columns(&tn.to_string())
+        .iter()
+        .filter_map(|c| {
+            let cmt = c.column_comment.clone().unwrap_or_default();
+            ctx.codegen_conf
+                .tables
+                .iter()
+                .find(|t| cmt.contains(&format!("关联表{}", t.name)))
+                .map(|t| t.name.clone())
+        })
+        .collect()
+}
+
+/**
+ * relation中涉及的所有表，包括主表、一对一、一对多以及中间表
+ */
+fn get_relation_tables(rel: &RelationConfig) -> Vec<String> {
+    let mut tables = vec![rel.major_table.clone()];
+    for rs in rel.one_to_one.iter().chain(rel.one_to_many.iter()) {
+        tables.extend(rs.table_name.clone());
+        tables.extend(rs.middle_table.clone());
+    }
+    tables
+}
+
+/**
+ * 受影响的表：发生了变化的表，以及在注释中通过关联表引用了它们的表（其Present结构包含被引用表的字段）
+ */
+pub fn expand_affected_tables(ctx: &GenerateContext, tables: &[String]) -> Vec<String> {
+    let mut affected = tables.to_vec();
+    for tbc in ctx.codegen_conf.tables.iter() {
+        if !affected.contains(&tbc.name)
+            && get_referenced_tables(ctx, &tbc.name).iter().any(|t| tables.contains(t))
+        {
+            affected.push(tbc.name.clone());
+        }
+    }
+    affected.sort();
+    affected.dedup();
+    affected
+}
+
+/**
+ * 涉及到受影响的表的relation
+ */
+pub fn get_affected_relations(conf: &CodeGenConfig, tables: &[String]) -> Vec<RelationConfig> {
+    conf.relations
+        .iter()
+        .filter(|rel| get_relation_tables(rel).iter().any(|t| tables.contains(t)))
+        .cloned()
+        .collect()
+}
+
+/**
+ * 只包含受影响的表及relation的配置
+ * 生成这些表及relation所依赖的表（关联表、relation中的其它表）也包括在内，但它们生成的文件不会被使用
+ */
+pub fn restrict_config(ctx: &GenerateContext, tables: &[String]) -> CodeGenConfig {
+    let relations = get_affected_relations(&ctx.codegen_conf, tables);
+    let mut needed = tables.to_vec();
+    for rel in relations.iter() {
+        needed.extend(get_relation_tables(rel));
+    }
+    let mut pos = 0;
+    while pos < needed.len() {
+        for tn in get_referenced_tables(ctx, &needed[pos]) {
+            if !needed.contains(&tn) {
+                needed.push(tn);
+            }
+        }
+        pos += 1;
+    }
+
+    let mut conf = ctx.codegen_conf.clone();
+    conf.tables.retain(|t| needed.contains(&t.name));
+    conf.relations = relations;
+    conf.queries.clear();
+    conf.config_template_generate = None;
+    conf
+}
+
+/**
+ * 文件所属的表或relation，不属于任何表或relation（如查询、app_config.rs）时返回None
+ * 表的Present结构的文件其来源为空，通过文件名来确定所属的表
+ */
+fn get_rust_file_owner(ctx: &GenerateContext, fl: &RustFileImpl) -> Option<String> {
+    match fl.artifact.as_ref()? {
+        RustArtifact::Query(_) | RustArtifact::QueryHandler(_) => None,
+        RustArtifact::Entity(src) if src.is_empty() => ctx.codegen_conf.tables.iter().find_map(|t| {
+            let present = ctx.get_struct_name(&t.name).unwrap_or_default() + "Present";
+            if fl.file_name == format!("{}.rs", snake_case(&present)) {
+                Some(t.name.clone())
+            } else {
+                None
+            }
+        }),
+        art => Some(art.source()),
+    }
+}
+
+/**
+ * 前端文件所属的表或relation，通过api-handler-name来确定
+ */
+fn get_vue_file_owner(ctx: &GenerateContext, fl: &VueAndJSFileImpl) -> Option<String> {
+    let api = if fl.js_vue {
+        fl.mod_name.clone()
+    } else {
+        fl.file_name.trim_end_matches(".js").to_string()
+    };
+    let mut owners = HashMap::new();
+    for tbc in ctx.codegen_conf.tables.iter() {
+        owners.insert(tbc.api_handler_name.clone(), tbc.name.clone());
+    }
+    for rel in ctx.codegen_conf.relations.iter() {
+        if let Some(name) = rel.api_handler_name.clone() {
+            owners.insert(name, rel.struct_name.clone());
+        }
+    }
+    owners.get(&api).cloned()
+}
+
+/**
+ * 文件在完整生成时的顺序：查询（读取表结构时生成）、表（按配置的顺序，Present在实体之后）、relation，
+ * 最后是不属于任何表的文件，同一mod中的文件按此顺序排列，与完整生成时的mod.rs相同
+ */
+fn get_generate_order(ctx: &GenerateContext, owner: &Option<String>, present: bool) -> (usize, usize, usize) {
+    let owner = match owner {
+        Some(o) => o,
+        None => return (3, 0, 0),
+    };
+    if let Some(p) = ctx.codegen_conf.tables.iter().position(|t| &t.name == owner) {
+        return (1, p, present as usize);
+    }
+    match ctx.codegen_conf.relations.iter().position(|r| &r.struct_name == owner) {
+        Some(p) => (2, p, 0),
+        None => (3, 0, 0),
+    }
+}
+
+/**
+ * 用part中受影响的表及relation的文件替换prev中对应的文件，放入base中
+ * base为按完整的配置读取了表结构（未生成）的结果，用于输出汇总的文件
+ * 合并后的文件按完整生成时的顺序排列，已经不再生成的文件被去掉
+ */
+pub fn splice_generated(
+    mut base: CodeGenerator,
+    prev: &CodeGenerator,
+    part: &CodeGenerator,
+    tables: &[String],
+) -> CodeGenerator {
+    let mut owned = tables.to_vec();
+    for rel in get_affected_relations(&prev.ctx.codegen_conf, tables)
+        .into_iter()
+        .chain(get_affected_relations(&part.ctx.codegen_conf, tables))
+    {
+        owned.push(rel.struct_name);
+    }
+    let is_owned = |owner: &Option<String>| owner.as_ref().map(|o| owned.contains(o)).unwrap_or(false);
+
+    let rust_order = |ctx: &GenerateContext, f: &RustFileImpl| {
+        let owner = get_rust_file_owner(ctx, f);
+        match f.artifact {
+            Some(RustArtifact::Query(_)) | Some(RustArtifact::QueryHandler(_)) => (0, 0, 0),
+            Some(RustArtifact::Entity(ref src)) => get_generate_order(&base.ctx, &owner, src.is_empty()),
+            _ => get_generate_order(&base.ctx, &owner, false),
+        }
+    };
+    let mut files = vec![];
+    for f in prev.files.iter() {
+        if !is_owned(&get_rust_file_owner(&prev.ctx, f)) {
+            files.push((rust_order(&prev.ctx, f), f.clone()));
+        }
+    }
+    for f in part.files.iter() {
+        if is_owned(&get_rust_file_owner(&part.ctx, f)) {
+            files.push((rust_order(&part.ctx, f), f.clone()));
+        }
+    }
+    files.sort_by_key(|(order, _)| *order);
+
+    let mut vuejs = vec![];
+    for f in prev.vuejs.iter() {
+        let owner = get_vue_file_owner(&prev.ctx, f);
+        if !is_owned(&owner) {
+            vuejs.push((get_generate_order(&base.ctx, &owner, false), f.clone()));
+        }
+    }
+    for f in part.vuejs.iter() {
+        let owner = get_vue_file_owner(&part.ctx, f);
+        // 不属于任何表的文件（如useCrud.ts）只在上一次没有生成时加入
+        let shared = owner.is_none()
+            && !prev.vuejs.iter().any(|v| v.mod_name == f.mod_name && v.file_name == f.file_name);
+        if is_owned(&owner) || shared {
+            vuejs.push((get_generate_order(&base.ctx, &owner, false), f.clone()));
+        }
+    }
+    vuejs.sort_by_key(|(order, _)| *order);
+
+    base.files = files.into_iter().map(|(_, f)| f).collect();
+    base.vuejs = vuejs.into_iter().map(|(_, f)| f).collect();
+    base.ctx.permissions = prev.ctx.permissions.clone();
+    base
+}
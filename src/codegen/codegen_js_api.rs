@@ -1,17 +1,13 @@
 use crate::codegen::GenerateContext;
-use crate::config::TEMPLATES;
 use crate::schema::TableInfo;
 use std::collections::HashSet;
 use tera::Context;
 
-use super::{parse_composite_column_list, tera_pascal, RelationForm};
+use super::{parse_composite_column_list, RelationForm};
 
-pub fn generate_js_api_for_table(ctx: &mut GenerateContext, tbl: &TableInfo) -> String {
-    let mut tera = TEMPLATES.clone();
+pub fn generate_js_api_for_table(ctx: &mut GenerateContext, tbl: &TableInfo) -> Option<String> {
     let tbl_name = tbl.table_name.clone().unwrap_or_default();
 
-    tera.register_function("pascal", tera_pascal);
-
     let tbl = ctx.get_table_info(&tbl_name);
     let tbc = ctx.get_table_conf(&tbl_name);
 
@@ -69,15 +65,5 @@ pub fn generate_js_api_for_table(ctx: &mut GenerateContext, tbl: &TableInfo) ->
         Err(_) => Context::new(),
     };
 
-    match tera.render("api-js.js", &context) {
-        Ok(text) => text,
-        Err(err) => {
-            log::info!(
-                "Error for parse the api-js template for {} error: {}",
-                tbl_name.clone(),
-                err.to_string()
-            );
-            String::new()
-        }
-    }
+    ctx.render_template("api-js.js", &tbl_name, &context)
 }
@@ -0,0 +1,170 @@
+use crate::codegen::{
+    find_wrapper_call, parse_data_type_as_rust_type, GenerateContext, RustFileImpl, RustFunc,
+    RustStructField, WrapperKind,
+};
+
+/**
+ * 逻辑删除
+ * 配置了logic-deletion的表，删除时只将logic-deletion-field的列更新为logic-deleted-value，
+ * 查询时只查询该列为logic-undeleted-value的数据
+ */
+#[derive(Debug, Clone)]
+pub struct LogicDeletion {
+    pub column: String,
+    pub deleted: String,       // Rust中的值，如1、true、"Y"
+    pub undeleted: String,     // Rust中的值，如0、false、"N"
+    pub deleted_sql: String,   // SQL中的值，如1、true、'Y'
+    pub undeleted_sql: String, // SQL中的值，如0、false、'N'
+}
+
+/**
+ * 表的逻辑删除，没有配置logic-deletion或者表中没有该列时返回None
+ */
+pub fn get_logic_deletion(ctx: &GenerateContext, tbl_name: &String) -> Option<LogicDeletion> {
+    let tbc = ctx.get_table_conf(tbl_name)?;
+    if !tbc.logic_deletion {
+        return None;
+    }
+    let col = ctx.find_table_column(tbl_name, &tbc.logic_deletion_field)?;
+    let field_type =
+        parse_data_type_as_rust_type(&col.data_type.clone().unwrap_or_default().to_lowercase());
+    let (deleted, deleted_sql) = logic_deletion_value(&field_type, &tbc.logic_deleted_value);
+    let (undeleted, undeleted_sql) = logic_deletion_value(&field_type, &tbc.logic_undeleted_value);
+    Some(LogicDeletion {
+        column: col.column_name.clone().unwrap_or_default(),
+        deleted,
+        undeleted,
+        deleted_sql,
+        undeleted_sql,
+    })
+}
+
+/**
+ * 按列的类型转换配置的值，返回（Rust中的值，SQL中的值）
+ */
+fn logic_deletion_value(field_type: &str, value: &str) -> (String, String) {
+    match field_type {
+        "bool" => {
+            let b = if value == "1" || value.to_lowercase() == "true" {
+                "true"
+            } else {
+                "false"
+            };
+            (b.to_string(), b.to_string())
+        }
+        "String" => (format!("\"{}\"", value), format!("'{}'", value)),
+        _ => (value.to_string(), value.to_string()),
+    }
+}
+
+impl LogicDeletion {
+    /**
+     * 将wrapper所匹配的数据更新为指定的值（已删除或未删除）
+     * wp.sql中为wrapper生成的条件
+     */
+    pub fn update_lines(&self, table: &str, sqlval: &str) -> Vec<String> {
+        vec![
+            format!(
+                "let sql = format!(\"UPDATE {} SET {} = {} WHERE {{}}\", wp.sql);",
+                table, self.column, sqlval
+            ),
+            "rb.exec(&sql, wp.args).await.map(|r| r.rows_affected)".to_string(),
+        ]
+    }
+
+    /**
+     * 查询的条件，self的方法中可以通过include_deleted来包含已删除的数据
+     */
+    pub fn query_condition(&self, include_flag: bool) -> String {
+        if include_flag {
+            format!(
+                "    .r#if(!self.include_deleted, |w| w.and().eq(\"{}\", {}))",
+                self.column, self.undeleted
+            )
+        } else {
+            format!("    .eq(\"{}\", {})", self.column, self.undeleted)
+        }
+    }
+}
+
+/**
+ * 查询时是否包含已删除的数据，不是表中的列
+ * 为false时不输出，即实体的JSON及保存时都不包含该字段
+ */
+pub fn generate_include_deleted_field() -> RustStructField {
+    RustStructField {
+        is_pub: true,
+        schema_name: None,
+        column_name: "".to_string(),
+        field_name: "include_deleted".to_string(),
+        orignal_field_name: None,
+        comment: Some("查询时包含已删除的数据".to_string()),
+        field_type: "bool".to_string(),
+        is_option: false,
+        length: 0,
+        annotations: vec![
+            "#[serde(default, skip_serializing_if = \"std::ops::Not::not\")]".to_string(),
+        ],
+    }
+}
+
+/**
+ * 逻辑删除的查询条件
+ * 在ORM转换之前进行，为使用wrapper的查询（单个、列表、分页）加入未删除的条件，
+ * 带有include_deleted字段的结构体，其self的方法中可以通过include_deleted包含已删除的数据
+ */
+pub fn apply_logic_deletion_filter(ctx: &GenerateContext, files: &mut Vec<RustFileImpl>) {
+    for fl in files.iter_mut() {
+        for st in fl.structlist.iter_mut() {
+            let has_flag = st.fields.iter().any(|f| f.field_name == "include_deleted");
+            for func in st.funclist.iter_mut() {
+                let call = match find_wrapper_call(func) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                if !matches!(
+                    call.kind,
+                    WrapperKind::SelectOne | WrapperKind::SelectList | WrapperKind::Page
+                ) {
+                    continue;
+                }
+                let entity = if call.entity == "Self" {
+                    st.struct_name.clone()
+                } else {
+                    call.entity.clone()
+                };
+                let ld = match ctx
+                    .get_table_conf_by_struct_name(&entity)
+                    .and_then(|t| get_logic_deletion(ctx, &t.name))
+                {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let cond = ld.query_condition(has_flag && func.is_self_fn);
+                insert_wrapper_condition(func, cond);
+            }
+        }
+    }
+}
+
+/**
+ * 在new_wrapper()之后加入一个条件
+ */
+fn insert_wrapper_condition(func: &mut RustFunc, cond: String) {
+    let wpos = match func
+        .bodylines
+        .iter()
+        .position(|l| l.contains("new_wrapper()"))
+    {
+        Some(p) => p,
+        None => return,
+    };
+    let line = func.bodylines[wpos].trim_end().to_string();
+    match line.strip_suffix(";") {
+        Some(t) => {
+            func.bodylines[wpos] = t.to_string();
+            func.bodylines.insert(wpos + 1, cond + ";");
+        }
+        None => func.bodylines.insert(wpos + 1, cond),
+    }
+}
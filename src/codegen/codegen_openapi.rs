@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use yaml_rust::{yaml::Hash, Yaml, YamlEmitter};
+
+use crate::codegen::{
+    GenerateContext, RustFileImpl, RustFunc, RustStruct, RustStructField, RustStructFieldExtend,
+};
+
+/**
+ * OpenAPI的生成（generate-openapi）
+ * 根据生成的handler（表、自定义查询及关系）生成openapi.json及openapi.yaml：
+ * 1、路径及方法来自handler的api_pattern及api_method，summary为handler的注释，tag为api-handler-name
+ * 2、web::Json<T>为请求体，web::Path<T>按顺序对应路径中的{name}，从HttpRequest中读取的pid等为query参数
+ * 3、响应为ApiResult<T>，T从handler中构造的web::Json<ApiResult<T>>得到
+ * 4、entity及query中的结构体生成components中的schema，字段的注释、可空、字符串长度以及注释中的必填、验证
+ *    分别对应description、nullable、maxLength、required及pattern（验证模式）/x-validate
+ * 文档在ORM及web框架的转换之前生成，此时的类型都是rbatis 3.x及actix-web的形式
+ */
+const OPENAPI_SCHEMA_MODS: [&str; 2] = ["entity", "query"];
+
+const OPENAPI_SECURITY_SCHEME: &str = "bearerAuth";
+
+fn collect_openapi_structs(files: &[RustFileImpl]) -> HashMap<String, RustStruct> {
+    let mut structs = HashMap::new();
+    for fl in files
+        .iter()
+        .filter(|f| OPENAPI_SCHEMA_MODS.contains(&f.mod_name.as_str()))
+    {
+        for st in fl.structlist.iter() {
+            structs.insert(st.struct_name.clone(), st.clone());
+        }
+    }
+    structs
+}
+
+fn strip_generic<'a>(tp: &'a str, name: &str) -> Option<&'a str> {
+    tp.strip_prefix(format!("{}<", name).as_str())
+        .and_then(|t| t.strip_suffix('>'))
+        .map(|t| t.trim())
+}
+
+/**
+ * 基本类型对应的schema，不是基本类型时返回None
+ */
+fn primitive_schema(tp: &str) -> Option<Value> {
+    let tp = tp.trim_start_matches("rbatis::");
+    match tp {
+        "i8" | "i16" | "i32" | "u8" | "u16" | "u32" => {
+            Some(json!({"type": "integer", "format": "int32"}))
+        }
+        "i64" | "u64" | "i128" | "u128" | "isize" | "usize" => {
+            Some(json!({"type": "integer", "format": "int64"}))
+        }
+        "f32" => Some(json!({"type": "number", "format": "float"})),
+        "f64" => Some(json!({"type": "number", "format": "double"})),
+        "bool" => Some(json!({"type": "boolean"})),
+        "String" | "str" => Some(json!({"type": "string"})),
+        "DateTimeNative" | "DateTimeUtc" | "Timestamp" | "TimestampZ" => {
+            Some(json!({"type": "string", "format": "date-time"}))
+        }
+        "DateNative" | "DateUtc" => Some(json!({"type": "string", "format": "date"})),
+        "TimeNative" | "TimeUtc" => Some(json!({"type": "string", "format": "time"})),
+        "Decimal" => Some(json!({"type": "string", "format": "decimal"})),
+        "Bytes" => Some(json!({"type": "string", "format": "byte"})),
+        "Json" | "serde_json::Value" => Some(json!({})),
+        _ => None,
+    }
+}
+
+fn nullable_schema(schema: Value) -> Value {
+    match schema {
+        Value::Object(mut obj) => {
+            if obj.contains_key("$ref") {
+                // OpenAPI 3.0中$ref的同级属性会被忽略
+                json!({"allOf": [Value::Object(obj)], "nullable": true})
+            } else {
+                obj.insert("nullable".to_string(), Value::Bool(true));
+                Value::Object(obj)
+            }
+        }
+        other => other,
+    }
+}
+
+fn page_schema(records: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "records": {"type": "array", "items": records},
+            "total": {"type": "integer", "format": "int64"},
+            "page_no": {"type": "integer", "format": "int64"},
+            "page_size": {"type": "integer", "format": "int64"}
+        }
+    })
+}
+
+/**
+ * Rust类型对应的schema，引用到的结构体放入refs中，以便生成到components中
+ */
+fn type_schema(tp: &str, structs: &HashMap<String, RustStruct>, refs: &mut Vec<String>) -> Value {
+    let tp = tp.trim().trim_start_matches('&').trim();
+    if let Some(inner) = strip_generic(tp, "Option") {
+        return nullable_schema(type_schema(inner, structs, refs));
+    }
+    if let Some(inner) = strip_generic(tp, "Vec") {
+        return json!({"type": "array", "items": type_schema(inner, structs, refs)});
+    }
+    if let Some(inner) = strip_generic(tp, "Page").or(strip_generic(tp, "rbatis::Page")) {
+        return page_schema(type_schema(inner, structs, refs));
+    }
+    if let Some(schema) = primitive_schema(tp) {
+        return schema;
+    }
+    let name = tp.rsplit("::").next().unwrap_or(tp);
+    if structs.contains_key(name) {
+        if !refs.contains(&name.to_string()) {
+            refs.push(name.to_string());
+        }
+        return json!({ "$ref": format!("#/components/schemas/{}", name) });
+    }
+    json!({"type": "object"})
+}
+
+fn is_skipped_field(fd: &RustStructField) -> bool {
+    fd.field_name.is_empty() || fd.annotations.iter().any(|an| an.contains("serde(skip)"))
+}
+
+fn field_schema(
+    fd: &RustStructField,
+    structs: &HashMap<String, RustStruct>,
+    refs: &mut Vec<String>,
+) -> Value {
+    let ext = RustStructFieldExtend::parse(fd);
+    let mut schema = type_schema(&fd.field_type, structs, refs);
+    if let Value::Object(obj) = &mut schema {
+        if let Some(cmt) = fd.comment.clone().filter(|c| !c.trim().is_empty()) {
+            obj.insert("description".to_string(), Value::String(cmt));
+        }
+        if fd.length > 0 && obj.get("type") == Some(&Value::String("string".to_string())) {
+            obj.insert("maxLength".to_string(), json!(fd.length));
+        }
+        if let Some(validate) = ext.validate.clone().filter(|v| !v.is_empty()) {
+            if ext.regex_check {
+                obj.insert("pattern".to_string(), Value::String(validate));
+            } else {
+                obj.insert("x-validate".to_string(), Value::String(validate));
+            }
+        }
+    }
+    if fd.is_option {
+        nullable_schema(schema)
+    } else {
+        schema
+    }
+}
+
+fn struct_schema(
+    st: &RustStruct,
+    structs: &HashMap<String, RustStruct>,
+    refs: &mut Vec<String>,
+) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+    for fd in st.fields.iter().filter(|f| !is_skipped_field(f)) {
+        properties.insert(fd.field_name.clone(), field_schema(fd, structs, refs));
+        if !fd.is_option || RustStructFieldExtend::parse(fd).required {
+            required.push(Value::String(fd.field_name.clone()));
+        }
+    }
+    let mut schema = json!({"type": "object", "properties": properties});
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+    schema
+}
+
+fn api_result_schema(data: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "status": {"type": "integer", "format": "int32"},
+            "message": {"type": "string"},
+            "data": nullable_schema(data),
+            "timestamp": {"type": "integer", "format": "int64", "nullable": true}
+        }
+    })
+}
+
+/**
+ * handler的tag，为api-handler-prefix之后的第一段，即api-handler-name
+ */
+fn operation_tag(ctx: &GenerateContext, pattern: &str) -> String {
+    let prefix = ctx.codegen_conf.api_handler_prefix.clone();
+    pattern
+        .strip_prefix(prefix.as_str())
+        .unwrap_or(pattern)
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/**
+ * 路径参数的名称及类型，web::Path<(A, B)>按顺序对应路径中的各个{name}
+ */
+fn path_params(func: &RustFunc, pattern: &str) -> Vec<(String, String)> {
+    let mut types = vec![];
+    for (_, ptype) in func.params.iter() {
+        if let Some(inner) = strip_generic(ptype, "web::Path") {
+            match inner.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                Some(tuple) => types.extend(tuple.split(',').map(|t| t.trim().to_string())),
+                None => types.push(inner.to_string()),
+            }
+        }
+    }
+    let reg = Regex::new(r"\{(\w+)\}").unwrap();
+    reg.captures_iter(pattern)
+        .enumerate()
+        .map(|(i, cap)| {
+            (
+                cap[1].to_string(),
+                types.get(i).cloned().unwrap_or("String".to_string()),
+            )
+        })
+        .collect()
+}
+
+/**
+ * handler中通过get_hash_value从query string中读取的参数
+ */
+fn query_params(func: &RustFunc) -> Vec<String> {
+    let reg = Regex::new(r#"get_hash_value\(&dic, "(\w+)"\)"#).unwrap();
+    let mut names = vec![];
+    for ln in func.bodylines.iter() {
+        for cap in reg.captures_iter(ln) {
+            if !names.contains(&cap[1].to_string()) {
+                names.push(cap[1].to_string());
+            }
+        }
+    }
+    names
+}
+
+fn request_body_type(func: &RustFunc) -> Option<String> {
+    func.params
+        .iter()
+        .find_map(|(_, ptype)| strip_generic(ptype, "web::Json").map(|t| t.to_string()))
+}
+
+fn response_data_type(func: &RustFunc) -> Option<String> {
+    let reg = Regex::new(r"web::Json<ApiResult<(.+)>> = web::Json\(ApiResult::ok\(").unwrap();
+    func.bodylines
+        .iter()
+        .find_map(|ln| reg.captures(ln).map(|cap| cap[1].to_string()))
+}
+
+fn has_system_user(func: &RustFunc) -> bool {
+    func.params
+        .iter()
+        .any(|(_, ptype)| ptype.trim_start_matches('&').starts_with("SystemUser<"))
+}
+
+fn generate_openapi_operation(
+    ctx: &GenerateContext,
+    func: &RustFunc,
+    pattern: &str,
+    structs: &HashMap<String, RustStruct>,
+    refs: &mut Vec<String>,
+) -> Value {
+    let mut op = Map::new();
+    op.insert("tags".to_string(), json!([operation_tag(ctx, pattern)]));
+    if let Some(cmt) = func.comment.clone() {
+        op.insert("summary".to_string(), Value::String(cmt));
+    }
+    op.insert(
+        "operationId".to_string(),
+        Value::String(func.func_name.clone()),
+    );
+
+    let mut params = vec![];
+    for (name, tp) in path_params(func, pattern) {
+        params.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": type_schema(&tp, structs, refs)
+        }));
+    }
+    for name in query_params(func) {
+        params.push(json!({
+            "name": name,
+            "in": "query",
+            "required": false,
+            "schema": {"type": "string"}
+        }));
+    }
+    if !params.is_empty() {
+        op.insert("parameters".to_string(), Value::Array(params));
+    }
+
+    if let Some(body) = request_body_type(func) {
+        op.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": {"schema": type_schema(&body, structs, refs)}
+                }
+            }),
+        );
+    }
+
+    let data = match response_data_type(func) {
+        Some(tp) => type_schema(&tp, structs, refs),
+        None => json!({}),
+    };
+    op.insert(
+        "responses".to_string(),
+        json!({
+            "200": {
+                "description": "ApiResult",
+                "content": {
+                    "application/json": {"schema": api_result_schema(data)}
+                }
+            }
+        }),
+    );
+
+    if has_system_user(func) {
+        op.insert(
+            "security".to_string(),
+            json!([{ OPENAPI_SECURITY_SCHEME: [] }]),
+        );
+    }
+    Value::Object(op)
+}
+
+/**
+ * 生成OpenAPI 3的文档
+ */
+pub fn generate_openapi_document(ctx: &GenerateContext, files: &[RustFileImpl]) -> Value {
+    let structs = collect_openapi_structs(files);
+    let mut refs = vec![];
+    let mut paths = Map::new();
+
+    for fl in files.iter().filter(|f| f.mod_name == "handler") {
+        for func in fl.funclist.iter() {
+            let (method, pattern) = match (func.api_method.clone(), func.api_pattern.clone()) {
+                (Some(m), Some(p)) => (m.to_lowercase(), p),
+                _ => continue,
+            };
+            let op = generate_openapi_operation(ctx, func, &pattern, &structs, &mut refs);
+            let item = paths
+                .entry(pattern.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            item[method.as_str()] = op;
+        }
+    }
+
+    // 结构体中引用到的其它结构体也需要生成
+    let mut schemas = Map::new();
+    let mut pos = 0;
+    while pos < refs.len() {
+        let name = refs[pos].clone();
+        if let Some(st) = structs.get(&name) {
+            let schema = struct_schema(st, &structs, &mut refs);
+            schemas.insert(name, schema);
+        }
+        pos += 1;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": ctx.codegen_conf.app_name.clone(),
+            "version": ctx.codegen_conf.app_version.clone()
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                OPENAPI_SECURITY_SCHEME: {"type": "http", "scheme": "bearer"}
+            }
+        }
+    })
+}
+
+/**
+ * rbatis 4.x、sqlx等转换时可能会去掉不支持的handler（如CommonSearch），文档中相应地去掉这些操作
+ */
+pub fn retain_openapi_operations(doc: &mut Value, files: &[RustFileImpl]) {
+    let funcs = files
+        .iter()
+        .filter(|f| f.mod_name == "handler")
+        .flat_map(|f| f.funclist.iter().map(|func| func.func_name.clone()))
+        .collect::<Vec<String>>();
+    if let Some(paths) = doc["paths"].as_object_mut() {
+        for item in paths.values_mut() {
+            if let Some(ops) = item.as_object_mut() {
+                ops.retain(|_, op| match op["operationId"].as_str() {
+                    Some(id) => funcs.contains(&id.to_string()),
+                    None => true,
+                });
+            }
+        }
+        paths.retain(|_, item| item.as_object().map(|m| !m.is_empty()).unwrap_or(true));
+    }
+}
+
+fn json_to_yaml(val: &Value) -> Yaml {
+    match val {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(items) => Yaml::Array(items.iter().map(json_to_yaml).collect()),
+        Value::Object(obj) => {
+            let mut hash = Hash::new();
+            for (k, v) in obj.iter() {
+                hash.insert(Yaml::String(k.clone()), json_to_yaml(v));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+pub fn format_openapi_json(doc: &Value) -> String {
+    serde_json::to_string_pretty(doc).unwrap_or_default() + "\n"
+}
+
+pub fn format_openapi_yaml(doc: &Value) -> String {
+    let mut text = String::new();
+    let mut emitter = YamlEmitter::new(&mut text);
+    if let Err(err) = emitter.dump(&json_to_yaml(doc)) {
+        log::warn!("Could not write the openapi.yaml. {:?}", err);
+    }
+    text + "\n"
+}
+
+/**
+ * utoipa中字段的value_type，不能直接使用的类型（rbatis的日期、时间、Decimal等）需要指定
+ */
+fn utoipa_value_type(
+    fd: &RustStructField,
+    structs: &HashMap<String, RustStruct>,
+) -> Option<String> {
+    let tp = fd.field_type.trim();
+    let is_plain = |t: &str| {
+        let t = t.trim();
+        match t {
+            "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128"
+            | "isize" | "usize" | "f32" | "f64" | "bool" | "String" => true,
+            _ => structs.contains_key(t),
+        }
+    };
+    if is_plain(tp) || strip_generic(tp, "Vec").map(is_plain).unwrap_or(false) {
+        return None;
+    }
+    let vt = match tp.trim_start_matches("rbatis::") {
+        "DateTimeNative" | "DateTimeUtc" | "Timestamp" | "TimestampZ" | "DateNative"
+        | "DateUtc" | "TimeNative" | "TimeUtc" | "Decimal" => "String",
+        "Bytes" => "Vec<u8>",
+        _ if tp.starts_with("Vec<") => "Vec<Object>",
+        _ => "Object",
+    };
+    if fd.is_option {
+        Some(format!("Option<{}>", vt))
+    } else {
+        Some(vt.to_string())
+    }
+}
+
+fn utoipa_path_macro(
+    ctx: &GenerateContext,
+    func: &RustFunc,
+    structs: &HashMap<String, RustStruct>,
+) -> Option<String> {
+    let (method, pattern) = match (func.api_method.clone(), func.api_pattern.clone()) {
+        (Some(m), Some(p)) => (m.to_lowercase(), p),
+        _ => return None,
+    };
+    let mut items = vec![
+        method,
+        format!("path = \"{}\"", pattern),
+        format!("tag = \"{}\"", operation_tag(ctx, &pattern)),
+    ];
+    // 只有实现了ToSchema的类型可以作为request_body，CommonSearch等只在openapi.json中描述
+    if let Some(body) = request_body_type(func) {
+        let inner = strip_generic(&body, "Vec").unwrap_or(&body);
+        if primitive_schema(inner).is_some() || structs.contains_key(inner) {
+            items.push(format!("request_body = {}", body));
+        }
+    }
+    let mut params = vec![];
+    for (name, tp) in path_params(func, &pattern) {
+        params.push(format!("(\"{}\" = {}, Path)", name, tp));
+    }
+    for name in query_params(func) {
+        params.push(format!("(\"{}\" = Option<String>, Query)", name));
+    }
+    if !params.is_empty() {
+        items.push(format!("params({})", params.join(", ")));
+    }
+    items.push(format!(
+        "responses((status = 200, description = \"ApiResult<{}>\"))",
+        response_data_type(func).unwrap_or("()".to_string())
+    ));
+    Some(format!("#[utoipa::path({})]", items.join(", ")))
+}
+
+/**
+ * openapi-swagger-ui：entity及query中的结构体派生utoipa::ToSchema，handler加上#[utoipa::path]
+ * 在ORM及web框架的转换之前进行，axum转换时保留#[utoipa::path]
+ */
+pub fn add_utoipa_annotations(ctx: &GenerateContext, files: &mut [RustFileImpl]) {
+    let structs = collect_openapi_structs(files);
+    for fl in files.iter_mut() {
+        if OPENAPI_SCHEMA_MODS.contains(&fl.mod_name.as_str()) {
+            for st in fl.structlist.iter_mut() {
+                let pos = st
+                    .annotations
+                    .iter()
+                    .position(|an| an.starts_with("#[derive(") && an.contains("Serialize"));
+                if let Some(p) = pos {
+                    st.annotations[p] = st.annotations[p].replacen(")]", ", utoipa::ToSchema)]", 1);
+                    for fd in st.fields.iter_mut().filter(|f| !is_skipped_field(f)) {
+                        if let Some(vt) = utoipa_value_type(fd, &structs) {
+                            fd.annotations
+                                .push(format!("#[schema(value_type = {})]", vt));
+                        }
+                    }
+                }
+            }
+        } else if fl.mod_name == "handler" {
+            for func in fl.funclist.iter_mut() {
+                if let Some(mc) = utoipa_path_macro(ctx, func, &structs) {
+                    func.macros.push(mc);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * 生成openapi/api_doc.rs，ApiDoc汇总所有的#[utoipa::path]及ToSchema，swagger_ui()由main.rs/lib.rs挂载
+ * 在ORM及web框架的转换之后生成，不包括转换时被去掉的handler
+ */
+pub fn generate_openapi_doc_file(ctx: &GenerateContext, files: &[RustFileImpl]) -> RustFileImpl {
+    let mut paths = vec![];
+    let mut schemas = vec![];
+    for fl in files.iter() {
+        if fl.mod_name == "handler" {
+            for func in fl.funclist.iter() {
+                if func
+                    .macros
+                    .iter()
+                    .any(|mc| mc.starts_with("#[utoipa::path("))
+                {
+                    paths.push(format!("        crate::handler::{},", func.func_name));
+                }
+            }
+        } else if OPENAPI_SCHEMA_MODS.contains(&fl.mod_name.as_str()) {
+            for st in fl.structlist.iter() {
+                if st
+                    .annotations
+                    .iter()
+                    .any(|an| an.contains("utoipa::ToSchema"))
+                {
+                    schemas.push(format!(
+                        "        crate::{}::{},",
+                        fl.mod_name, st.struct_name
+                    ));
+                }
+            }
+        }
+    }
+
+    let openapi = format!(
+        "#[openapi(\n    info(title = \"{}\", version = \"{}\"),\n    paths(\n{}\n    ),\n    components(schemas(\n{}\n    ))\n)]",
+        ctx.codegen_conf.app_name,
+        ctx.codegen_conf.app_version,
+        paths.join("\n"),
+        schemas.join("\n")
+    );
+    let apidoc = RustStruct {
+        is_pub: true,
+        has_paging: false,
+        struct_name: "ApiDoc".to_string(),
+        annotations: vec!["#[derive(OpenApi)]".to_string(), openapi],
+        fields: vec![],
+        funclist: vec![],
+        usings: vec![],
+    };
+
+    // actix-web的SwaggerUi需要匹配其下的所有路径
+    let swagger_path = if ctx.is_axum() {
+        "/swagger-ui"
+    } else {
+        "/swagger-ui/{_:.*}"
+    };
+    let swagger = RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: false,
+        func_name: "swagger_ui".to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("SwaggerUi".to_string()),
+        params: vec![],
+        bodylines: vec![format!(
+            "SwaggerUi::new(\"{}\").url(\"{}/openapi.json\", ApiDoc::openapi())",
+            swagger_path, ctx.codegen_conf.api_handler_prefix
+        )],
+        macros: vec![],
+        comment: Some("Swagger UI".to_string()),
+        api_method: None,
+        api_pattern: None,
+    };
+
+    RustFileImpl {
+        file_name: "api_doc.rs".to_string(),
+        mod_name: "openapi".to_string(),
+        caretlist: vec![],
+        usinglist: vec![
+            "utoipa::OpenApi".to_string(),
+            "utoipa_swagger_ui::SwaggerUi".to_string(),
+        ],
+        structlist: vec![apidoc],
+        funclist: vec![swagger],
+        implist: vec![],
+        artifact: None,
+    }
+}
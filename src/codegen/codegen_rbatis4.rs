@@ -0,0 +1,583 @@
+use change_case::snake_case;
+use regex::Regex;
+
+use crate::codegen::{
+    find_wrapper_call, get_version_field, is_common_search_func, parse_wrapper_conditions,
+    GenerateContext, RustFileImpl, RustFunc, RustStruct, WrapperCondition, WrapperKind,
+};
+
+/**
+ * rbatis 3.x的类型与rbatis 4.x（rbdc）中的类型的对应
+ */
+const RBATIS4_TYPES: [(&str, &str); 9] = [
+    ("rbatis::DateTimeNative", "rbatis::rbdc::datetime::DateTime"),
+    ("rbatis::DateTimeUtc", "rbatis::rbdc::datetime::DateTime"),
+    ("rbatis::DateNative", "rbatis::rbdc::date::Date"),
+    ("rbatis::DateUtc", "rbatis::rbdc::date::Date"),
+    ("rbatis::TimeNative", "rbatis::rbdc::time::Time"),
+    ("rbatis::TimeUtc", "rbatis::rbdc::time::Time"),
+    ("rbatis::Decimal", "rbatis::rbdc::decimal::Decimal"),
+    ("rbatis::Bytes", "rbatis::rbdc::bytes::Bytes"),
+    ("rbatis::Json", "rbatis::rbdc::json::Json"),
+];
+
+/**
+ * 将代码中rbatis 3.x的类型替换为rbatis 4.x的类型
+ * Timestamp、TimestampZ在rbdc中均为rbatis::rbdc::timestamp::Timestamp
+ */
+pub fn to_rbatis4_type(text: &str) -> String {
+    let mut ret = text
+        .replace("rbatis::TimestampZ", "rbatis::rbdc::timestamp::Timestamp")
+        .replace("rbatis::Timestamp", "rbatis::rbdc::timestamp::Timestamp");
+    for (from, to) in RBATIS4_TYPES.iter() {
+        ret = ret.replace(from, to);
+    }
+    ret
+}
+
+/**
+ * rbatis 4.x的生成
+ * 代码先按rbatis 3.1的方式生成，再转换为rbatis 4.x的形式：
+ * 1、#[crud_table]替换为crud!，from_id使用impl_select!，query_all使用select_all，save使用insert
+ * 2、使用wrapper的查询、更新及删除转换为htmlsql!/htmlsql_select_page!，r#if的条件转换为<if test="...">的动态SQL
+ * 3、&Rbatis与&mut RBatisTxExecutor<'_>的参数统一为&dyn Executor，调用方的rb和&mut tx无须改动
+ * 4、rb.fetch/rb.fetch_page的原生SQL转换为rb.query_decode，rbson转换为rbs
+ * 5、CommonSearch（chimes-utils）依赖于rbatis 3.x的wrapper，相关的方法及handler不再生成
+ */
+pub fn convert_file_to_rbatis4(ctx: &GenerateContext, fl: &mut RustFileImpl) {
+    for st in fl.structlist.iter_mut() {
+        convert_struct_to_rbatis4(ctx, st);
+    }
+
+    fl.funclist.retain(|func| {
+        let unsupported = is_common_search_func(func);
+        if unsupported {
+            log::warn!(
+                "{}::{} uses CommonSearch which is not supported by rbatis 4.x, it was not generated.",
+                fl.mod_name,
+                func.func_name
+            );
+        }
+        !unsupported
+    });
+    for func in fl.funclist.iter_mut() {
+        convert_func_lines_to_rbatis4(func);
+    }
+    for imp in fl.implist.iter_mut() {
+        for func in imp.funclist.iter_mut() {
+            convert_func_lines_to_rbatis4(func);
+        }
+    }
+
+    let mut usings = vec![];
+    for us in fl.usinglist.iter() {
+        if let Some(t) = to_rbatis4_using(us) {
+            usings.push(t);
+        }
+    }
+    let text = fl.to_source();
+    for (mac, us) in [
+        ("crud!(", "rbatis::crud"),
+        ("impl_select!(", "rbatis::impl_select"),
+        ("htmlsql!(", "rbatis::htmlsql"),
+        ("htmlsql_select_page!(", "rbatis::htmlsql_select_page"),
+        ("-> ExecResult", "rbatis::rbdc::db::ExecResult"),
+        ("PageRequest::new(", "rbatis::plugin::page::PageRequest"),
+    ] {
+        if text.contains(mac) {
+            usings.push(us.to_string());
+        }
+    }
+    if text.contains("&dyn Executor") {
+        usings.push("rbatis::executor::Executor".to_string());
+    }
+    if text.contains("Page<") || text.contains("Page::new_total(") {
+        usings.push("rbatis::plugin::page::Page".to_string());
+    }
+    usings.sort();
+    usings.dedup();
+    fl.usinglist = usings;
+}
+
+fn to_rbatis4_using(us: &String) -> Option<String> {
+    match us.as_str() {
+        "rbatis::crud_table"
+        | "rbatis::crud::{CRUD, CRUDMut, Skip}"
+        | "rbatis::crud::CRUD"
+        | "rbson::Bson"
+        | "rbatis::rbatis::Rbatis"
+        | "rbatis::executor::{RbatisRef, RBatisTxExecutor}"
+        | "rbatis::executor::RBatisTxExecutor"
+        | "rbatis::executor::ExecutorMut"
+        | "rbatis::Page"
+        | "rbatis::PageRequest"
+        | "chimes_utils::CommonSearch" => None,
+        "rbatis::error::Error" => Some("rbatis::Error".to_string()),
+        "chimes_utils::get_rbatis" => Some("crate::utils::get_rbatis".to_string()),
+        _ => Some(to_rbatis4_type(us)),
+    }
+}
+
+/**
+ * 转换结构体
+ * 带有#[crud_table]的实体替换为crud!，并将其中使用wrapper的方法转换为htmlsql!
+ */
+fn convert_struct_to_rbatis4(ctx: &GenerateContext, st: &mut RustStruct) {
+    for fd in st.fields.iter_mut() {
+        fd.field_type = to_rbatis4_type(&fd.field_type);
+    }
+    st.usings = st.usings.iter().filter_map(to_rbatis4_using).collect();
+
+    let crud_table = st
+        .annotations
+        .iter()
+        .find(|an| an.starts_with("#[crud_table("))
+        .cloned();
+    let mut items = vec![];
+    if let Some(anno) = crud_table {
+        let reg = Regex::new("table_name:\"([^\"]+)\"").unwrap();
+        // 自定义查询的结构只用于query_decode，不需要crud!
+        let is_entity = ctx.get_table_conf_by_struct_name(&st.struct_name).is_some();
+        if let Some(cap) = reg.captures(&anno) {
+            if is_entity {
+                items.push(format!("crud!({}{{}}, \"{}\");", st.struct_name, &cap[1]));
+            }
+        }
+    }
+
+    let mut funclist = vec![];
+    for func in st.funclist.clone() {
+        if is_common_search_func(&func) {
+            log::warn!(
+                "{}::{} uses CommonSearch which is not supported by rbatis 4.x, it was not generated.",
+                st.struct_name,
+                func.func_name
+            );
+            continue;
+        }
+        match convert_struct_func_to_rbatis4(ctx, st, &func) {
+            Ok((nfunc, item)) => {
+                if let Some(it) = item {
+                    items.push(it);
+                }
+                funclist.push(nfunc);
+            }
+            Err(line) => {
+                log::warn!(
+                    "{}::{} could not be converted to rbatis 4.x ({}), it was not generated.",
+                    st.struct_name,
+                    func.func_name,
+                    line
+                );
+            }
+        }
+    }
+    st.funclist = funclist;
+
+    // crud!、impl_select!、htmlsql!等放在结构体定义之前
+    let mut annotations = items;
+    annotations.append(
+        &mut st
+            .annotations
+            .iter()
+            .filter(|an| !an.starts_with("#[crud_table("))
+            .cloned()
+            .collect(),
+    );
+    st.annotations = annotations;
+}
+
+/**
+ * 转换结构体的方法，返回转换后的方法以及其需要的crud!/htmlsql!等宏
+ * 不能转换时返回无法识别的代码行
+ */
+fn convert_struct_func_to_rbatis4(
+    ctx: &GenerateContext,
+    st: &RustStruct,
+    func: &RustFunc,
+) -> Result<(RustFunc, Option<String>), String> {
+    let mut nfunc = func.clone();
+    convert_func_lines_to_rbatis4(&mut nfunc);
+
+    if func.bodylines.iter().any(|l| l.contains("rb.save(self,")) {
+        nfunc.bodylines = convert_save_lines(func);
+        return Ok((nfunc, None));
+    }
+
+    let conds = match parse_wrapper_conditions(func) {
+        Some(c) => c?,
+        None => return Ok((nfunc, None)),
+    };
+
+    let call = find_wrapper_call(func)
+        .ok_or_else(|| func.bodylines.last().cloned().unwrap_or_default())?;
+    let entity = if call.entity == "Self" {
+        st.struct_name.clone()
+    } else {
+        call.entity.clone()
+    };
+    let table = ctx
+        .get_table_conf_by_struct_name(&entity)
+        .map(|t| t.name)
+        .ok_or_else(|| format!("no table for {}", entity))?;
+    let fnname = format!("{}_{}", snake_case(&st.struct_name), func.func_name);
+
+    let args = nfunc
+        .params
+        .iter()
+        .filter(|p| p.0 != "rb" && !call.page.contains(&p.0))
+        .cloned()
+        .collect::<Vec<(String, String)>>();
+    let mut declargs = vec![];
+    let mut callargs = vec![];
+    if func.is_self_fn {
+        declargs.push(format!("this: &{}", st.struct_name));
+        callargs.push("self".to_string());
+    }
+    for (name, ptype) in args.iter() {
+        declargs.push(format!("{}: {}", name, ptype));
+        callargs.push(name.clone());
+    }
+
+    if func.func_name == "from_id" && call.kind == WrapperKind::SelectOne {
+        // 按主键查询使用impl_select!，条件中可能还有逻辑删除等固定的条件
+        let mut wheres = vec![];
+        for cond in conds.iter() {
+            wheres.push(translate_operation(cond).ok_or_else(|| format!("{:?}", cond))?);
+        }
+        let conds = wheres.join(" and ");
+        let item = format!(
+            "impl_select!({}{{select_by_pk({}) -> Option => \"`where {} limit 1`\"}});",
+            st.struct_name,
+            declargs.join(", "),
+            conds
+        );
+        nfunc.bodylines = vec![format!(
+            "Self::select_by_pk(rb, {}).await",
+            callargs.join(", ")
+        )];
+        return Ok((nfunc, Some(item)));
+    }
+
+    if conds.is_empty()
+        && call.kind == WrapperKind::SelectList
+        && args.is_empty()
+        && entity == st.struct_name
+    {
+        // 没有任何条件的查询，使用crud!生成的select_all
+        nfunc.bodylines = vec!["Self::select_all(rb).await".to_string()];
+        return Ok((nfunc, None));
+    }
+
+    let mut wheresql = String::new();
+    for cond in conds.iter() {
+        wheresql.push_str(&translate_wrapper_condition(cond).ok_or_else(|| format!("{:?}", cond))?);
+    }
+    let wheresql = if wheresql.is_empty() {
+        wheresql
+    } else {
+        format!("<where>{}</where>", wheresql)
+    };
+
+    let mut rbargs = vec!["rb: &dyn Executor".to_string()];
+    rbargs.append(&mut declargs.clone());
+    let item = match call.kind.clone() {
+        WrapperKind::SelectOne => {
+            nfunc.bodylines = vec![format!("{}(rb, {}).await", fnname, callargs.join(", "))];
+            format!(
+                "htmlsql!({}({}) -> Option<{}> => r#\"<select id=\"{}\">`select * from {}`{}` limit 1`</select>\"#);",
+                fnname, rbargs.join(", "), entity, fnname, table, wheresql
+            )
+        }
+        WrapperKind::SelectList => {
+            nfunc.bodylines = vec![format!("{}(rb, {}).await", fnname, callargs.join(", "))];
+            format!(
+                "htmlsql!({}({}) -> Vec<{}> => r#\"<select id=\"{}\">`select * from {}`{}</select>\"#);",
+                fnname, rbargs.join(", "), entity, fnname, table, wheresql
+            )
+        }
+        WrapperKind::Page => {
+            let mut pageargs = vec![
+                "rb".to_string(),
+                format!("&PageRequest::new({})", call.page.join(", ")),
+            ];
+            pageargs.append(&mut callargs.clone());
+            nfunc.bodylines = vec![format!("{}({}).await", fnname, pageargs.join(", "))];
+            format!(
+                "htmlsql_select_page!({}({}) -> {} => r#\"<select id=\"{}\">`select `<if test=\"do_count == true\">`count(1) from {}`</if><if test=\"do_count == false\">`* from {}`</if>{}<if test=\"do_count == false\">` limit ${{page_no}},${{page_size}}`</if></select>\"#);",
+                fnname, declargs.join(", "), entity, fnname, table, table, wheresql
+            )
+        }
+        WrapperKind::Delete => {
+            nfunc.bodylines = vec![format!(
+                "{}(rb, {}).await.map(|r| r.rows_affected)",
+                fnname,
+                callargs.join(", ")
+            )];
+            format!(
+                "htmlsql!({}({}) -> ExecResult => r#\"<delete id=\"{}\">`delete from {}`{}</delete>\"#);",
+                fnname, rbargs.join(", "), fnname, table, wheresql
+            )
+        }
+        WrapperKind::Update(skips, selective) => {
+            let version = get_version_field(ctx, &table);
+            let mut sets = String::new();
+            for fd in st.fields.iter().filter(|f| !f.column_name.is_empty()) {
+                if skips.contains(&fd.column_name) || skips.contains(&fd.field_name) {
+                    continue;
+                }
+                let fdname = fd.field_name.replace("r#", "");
+                if let Some(vf) = version.as_ref().filter(|v| v.column == fd.column_name) {
+                    // 乐观锁的版本在SQL中加1，条件中仍为原来的值
+                    sets.push_str(&format!("`{},`", vf.increment_sql()));
+                } else if selective {
+                    sets.push_str(&format!(
+                        "<if test=\"this.{} != null\">`{} = #{{this.{}}},`</if>",
+                        fdname, fd.column_name, fdname
+                    ));
+                } else {
+                    sets.push_str(&format!("`{} = #{{this.{}}},`", fd.column_name, fdname));
+                }
+            }
+            nfunc.bodylines = vec![format!(
+                "{}(rb, {}).await.map(|r| r.rows_affected)",
+                fnname,
+                callargs.join(", ")
+            )];
+            format!(
+                "htmlsql!({}({}) -> ExecResult => r#\"<update id=\"{}\">`update {}`<set>{}</set>{}</update>\"#);",
+                fnname, rbargs.join(", "), fnname, table, sets, wheresql
+            )
+        }
+        WrapperKind::UpdateSet(sets) => {
+            nfunc.bodylines = vec![format!(
+                "{}(rb, {}).await.map(|r| r.rows_affected)",
+                fnname,
+                callargs.join(", ")
+            )];
+            format!(
+                "htmlsql!({}({}) -> ExecResult => r#\"<update id=\"{}\">`update {} set {}`{}</update>\"#);",
+                fnname, rbargs.join(", "), fnname, table, sets, wheresql
+            )
+        }
+    };
+    Ok((nfunc, Some(item)))
+}
+
+/**
+ * save使用crud!生成的insert，自增的主键从last_insert_id中获取
+ */
+fn convert_save_lines(func: &RustFunc) -> Vec<String> {
+    let mut body = vec!["let ds = Self::insert(rb, self).await?;".to_string()];
+    for ln in func.bodylines.iter() {
+        let ln = ln.trim();
+        if ln.starts_with("self.") && ln.contains("= ds.last_insert_id") {
+            body.push(
+                ln.replace(
+                    "ds.last_insert_id.unwrap_or_default()",
+                    "ds.last_insert_id.as_i64().unwrap_or_default()",
+                )
+                .replace("ds.last_insert_id;", "ds.last_insert_id.as_i64();"),
+            );
+        }
+    }
+    body.push("Ok(ds.rows_affected)".to_string());
+    body
+}
+
+/**
+ * 将wrapper的一个条件转换为html_sql的片段
+ * .eq("col", x) => ` and col = #{x}`
+ * .r#if(x.is_some(), |w| w.and().eq("col", x.unwrap())) => <if test="x != null">` and col = #{x}`</if>
+ */
+fn translate_wrapper_condition(cond: &WrapperCondition) -> Option<String> {
+    let sql = translate_operation(cond)?;
+    match cond.test.clone() {
+        Some(t) => Some(format!(
+            "<if test=\"{}\">` and {}`</if>",
+            translate_test(&t)?,
+            sql
+        )),
+        None => Some(format!("` and {}`", sql)),
+    }
+}
+
+fn translate_operation(cond: &WrapperCondition) -> Option<String> {
+    let col = cond.column.clone();
+    let args = &cond.args;
+    match (cond.op.as_str(), args.len()) {
+        ("push_sql", 1) => Some(col.trim().replace("?", &translate_value(&args[0])?)),
+        ("eq", 1) => Some(format!("{} = {}", col, translate_value(&args[0])?)),
+        ("ne", 1) => Some(format!("{} <> {}", col, translate_value(&args[0])?)),
+        ("gt", 1) => Some(format!("{} > {}", col, translate_value(&args[0])?)),
+        ("ge", 1) => Some(format!("{} >= {}", col, translate_value(&args[0])?)),
+        ("lt", 1) => Some(format!("{} < {}", col, translate_value(&args[0])?)),
+        ("le", 1) => Some(format!("{} <= {}", col, translate_value(&args[0])?)),
+        ("like", 1) => Some(format!(
+            "{} like concat('%', {}, '%')",
+            col,
+            translate_value(&args[0])?
+        )),
+        ("is_null", 0) => Some(format!("{} is null", col)),
+        ("between", 2) => Some(format!(
+            "{} between {} and {}",
+            col,
+            translate_value(&args[0])?,
+            translate_value(&args[1])?
+        )),
+        ("in", 1) | ("not_in", 1) => {
+            let not = if cond.op == "not_in" { "not " } else { "" };
+            let arg = args[0].trim();
+            if let Some(sp) = arg.find(".split(\"") {
+                // in_columns：按分隔符拆分的字符串
+                let spliter = &arg[sp + ".split(\"".len()..];
+                let spliter = &spliter[..spliter.find('"')?];
+                let path = translate_path(&arg[..sp])?;
+                Some(format!(
+                    "{}FIND_IN_SET({}, REPLACE(#{{{}}}, '{}', ','))",
+                    not, col, path, spliter
+                ))
+            } else {
+                Some(format!(
+                    "{} {}in (`<foreach collection=\"{}\" item=\"item\" separator=\",\">`#{{item}}`</foreach>`)",
+                    col,
+                    not,
+                    translate_path(arg)?
+                ))
+            }
+        }
+        _ => None,
+    }
+}
+
+/**
+ * r#if的条件转换为test表达式
+ */
+fn translate_test(cond: &str) -> Option<String> {
+    let mut tests = vec![];
+    for part in cond.split("&&") {
+        let part = part.trim();
+        if part.contains(".contains(") {
+            // in_columns的分隔符检查，由FIND_IN_SET处理
+            continue;
+        }
+        if let Some(p) = part.strip_prefix('!') {
+            tests.push(format!("{} != true", translate_path(p)?));
+        } else if let Some(p) = part.strip_suffix(".is_some()") {
+            tests.push(format!("{} != null", translate_path(p)?));
+        } else if let Some(p) = part.strip_suffix(".is_none()") {
+            tests.push(format!("{} == null", translate_path(p)?));
+        } else if let Some(p) = part.strip_suffix(".is_empty() == false") {
+            tests.push(format!("{}.len() > 0", translate_path(p)?));
+        } else if let Some(pos) = part.find(".len()") {
+            tests.push(format!("{}{}", translate_path(&part[..pos])?, &part[pos..]));
+        } else {
+            return None;
+        }
+    }
+    Some(tests.join(" && "))
+}
+
+/**
+ * 参数的值，变量转换为#{x}，常量（如树形的根节点值、逻辑删除的值）直接使用
+ */
+fn translate_value(expr: &str) -> Option<String> {
+    let mut e = expr.trim();
+    if e.starts_with("Some(") && e.ends_with(")") {
+        e = &e["Some(".len()..e.len() - 1];
+    }
+    if e == "true" || e == "false" {
+        return Some(e.to_string());
+    }
+    if let Some(t) = e.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        return Some(format!("'{}'", t));
+    }
+    match translate_path(e) {
+        Some(p) => Some(format!("#{{{}}}", p)),
+        None => {
+            if Regex::new(r"^-?[\d.]+$|^'[^']*'$").unwrap().is_match(e) {
+                Some(e.to_string())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/**
+ * 变量的路径，如self.name.clone().unwrap() => this.name
+ */
+fn translate_path(expr: &str) -> Option<String> {
+    let mut e = expr.trim().trim_start_matches('&').to_string();
+    loop {
+        let before = e.clone();
+        for suffix in [
+            ".clone()",
+            ".unwrap()",
+            ".unwrap_or_default()",
+            ".as_slice()",
+        ] {
+            if let Some(s) = e.strip_suffix(suffix) {
+                e = s.to_string();
+            }
+        }
+        if before == e {
+            break;
+        }
+    }
+    if !Regex::new(r"^[A-Za-z_][\w#.]*(\[\d+\])?$")
+        .unwrap()
+        .is_match(&e)
+    {
+        return None;
+    }
+    let e = e.replace("r#", "");
+    if e == "self" {
+        Some("this".to_string())
+    } else if let Some(s) = e.strip_prefix("self.") {
+        Some(format!("this.{}", s))
+    } else {
+        Some(e)
+    }
+}
+
+/**
+ * 不依赖于wrapper的代码：参数、返回值及代码行中的类型和API
+ */
+fn convert_func_lines_to_rbatis4(func: &mut RustFunc) {
+    for pm in func.params.iter_mut() {
+        pm.1 = match pm.1.as_str() {
+            "&mut RBatisTxExecutor<'_>" | "&Rbatis" => "&dyn Executor".to_string(),
+            _ => to_rbatis4_type(&pm.1),
+        };
+    }
+    func.return_type = func.return_type.clone().map(|t| to_rbatis4_type(&t));
+
+    let page =
+        Regex::new(r"^rb\.fetch_page\(&sql, rb_args, &PageRequest::new\((\w+), (\w+)\)\)\.await$")
+            .unwrap();
+    let mut body = vec![];
+    for ln in func.bodylines.iter() {
+        if let Some(cap) = page.captures(ln.trim()) {
+            // 原生SQL的分页：先查询总数，再使用limit查询当前页
+            body.push("let total: u64 = rb.query_decode(&format!(\"SELECT COUNT(1) FROM ({}) t\", sql), rb_args.clone()).await?;".to_string());
+            body.push(format!(
+                "let mut page = Page::new_total({}, {}, total);",
+                &cap[1], &cap[2]
+            ));
+            body.push(format!(
+                "page.records = rb.query_decode(&format!(\"{{}} LIMIT {{}}, {{}}\", sql, ({}.max(1) - 1) * {}, {}), rb_args).await?;",
+                &cap[1], &cap[2], &cap[2]
+            ));
+            body.push("Ok(page)".to_string());
+            continue;
+        }
+        body.push(
+            to_rbatis4_type(ln)
+                .replace("rbson::to_bson(", "rbs::to_value(")
+                .replace("rb.fetch(", "rb.query_decode(")
+                .replace("rb.get_rbatis()", "rb"),
+        );
+    }
+    func.bodylines = body;
+}
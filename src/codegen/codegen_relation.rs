@@ -2,13 +2,14 @@ use change_case::{pascal_case, snake_case};
 use std::collections::{HashMap, HashSet};
 
 use super::{
-    is_copied_type, parse_composite_column_list, CodeGenerator, RelationForm, RelationTable, RustFileImpl
+    generate_service_handler_for_relation, get_logic_deletion, retain_service_handler_usings, get_version_field, is_copied_type, parse_composite_column_list, update_error_line, CodeGenerator, RelationForm, RelationTable, RustArtifact,
+    RustFileImpl,
 };
 use crate::codegen::{
     parse_column_list, parse_data_type_as_rust_type, GenerateContext, RustFunc, RustStruct,
     RustStructField,
 };
-use crate::config::{safe_struct_field_name, RelationConfig, TEMPLATES};
+use crate::config::{safe_struct_field_name, RelationConfig};
 use crate::schema::TableInfo;
 use serde_json::{json, Value};
 use substring::Substring;
@@ -437,7 +438,11 @@ pub fn parse_relation_as_file(ctx: &GenerateContext, rel: &RelationConfig) -> Op
                 tbconf.with_attachment,
                 super::CodeModelType::Relation
             );
-            usinglist.push(format!("chimes_rust::{{SystemUser, ChimesUserInfo}}"));
+            if ctx.is_generate_service() {
+                usinglist.push(format!("crate::utils::ServiceUser"));
+            } else {
+                usinglist.push(format!("chimes_rust::{{SystemUser, ChimesUserInfo}}"));
+            }
             usinglist.push(format!("crate::entity::{}", tbconf.struct_name));
 
             for rl in rel.one_to_one.clone() {
@@ -473,6 +478,8 @@ pub fn parse_relation_as_file(ctx: &GenerateContext, rel: &RelationConfig) -> Op
                 usinglist: usinglist,
                 structlist: vec![st],
                 funclist: vec![],
+                implist: vec![],
+                artifact: Some(RustArtifact::Relation(rel.struct_name.clone())),
             };
             Some(rfi)
         }
@@ -540,24 +547,32 @@ pub fn parse_relation_handlers_as_file(
             }
 
             let mut funclist = vec![];
-            if rel.generate_select {
-                let funcsel = generate_handler_load_for_relation(ctx, rel);
-                funclist.push(funcsel);
-            }
+            if ctx.is_generate_service() {
+                usinglist.push(format!("crate::service::{}Service", st.struct_name));
+                funclist.append(&mut generate_service_handler_for_relation(ctx, rel));
+            } else {
+                if rel.generate_select {
+                    let funcsel = generate_handler_load_for_relation(ctx, rel);
+                    funclist.push(funcsel);
+                }
 
-            if rel.generate_delete {
-                let funcdel = generate_handler_remove_for_relation(ctx, rel);
-                funclist.push(funcdel);
+                if rel.generate_delete {
+                    let funcdel = generate_handler_remove_for_relation(ctx, rel);
+                    funclist.push(funcdel);
 
-                let funcdel_multi = generate_handler_remove_multi_for_relation(ctx, rel);
-                funclist.push(funcdel_multi);
-            }
+                    let funcdel_multi = generate_handler_remove_multi_for_relation(ctx, rel);
+                    funclist.push(funcdel_multi);
+                }
 
-            if rel.generate_save {
-                let funcsave = generate_handler_save_for_relation(ctx, rel);
-                funclist.push(funcsave);
+                if rel.generate_save {
+                    let funcsave = generate_handler_save_for_relation(ctx, rel);
+                    funclist.push(funcsave);
+                }
             }
 
+            if ctx.is_generate_service() {
+                retain_service_handler_usings(&mut usinglist, &funclist);
+            }
             ctx.add_permission_for_relation(rel, &funclist);
 
             let rfi = RustFileImpl {
@@ -567,6 +582,8 @@ pub fn parse_relation_handlers_as_file(
                 usinglist: usinglist,
                 structlist: vec![],
                 funclist: funclist,
+                implist: vec![],
+                artifact: Some(RustArtifact::RelationHandler(rel.struct_name.clone())),
             };
             Some(rfi)
         }
@@ -888,6 +905,9 @@ fn generate_func_to_major_table(ctx: &GenerateContext, rel: &RelationConfig) ->
         if tbconf.with_attachment {
             body.push(format!("attachments: self.attachments.clone(),"));
         }
+        if get_logic_deletion(ctx, &tbl_name).is_some() {
+            body.push(format!("include_deleted: false,"));
+        }
         body.push(format!("}}"));
     } else {
         let fname = tbconf.api_handler_name.clone();
@@ -996,11 +1016,18 @@ fn generate_func_refine(ctx: &GenerateContext, rel: &RelationConfig) -> RustFunc
         ));
     }
 
+    // generate-service时由Service调用，只接收当前用户及租户的值
+    let su_type = if ctx.is_generate_service() {
+        body = body.into_iter().map(|ln| ln.replace("su.user.", "su.")).collect();
+        "&ServiceUser"
+    } else {
+        "&SystemUser<ChimesUserInfo>"
+    };
     let mut params = Vec::new();
     if has_su {
-        params.push(("su".to_string(), "&SystemUser<ChimesUserInfo>".to_string()));
+        params.push(("su".to_string(), su_type.to_string()));
     } else {
-        params.push(("_su".to_string(), "&SystemUser<ChimesUserInfo>".to_string()));
+        params.push(("_su".to_string(), su_type.to_string()));
     }
     RustFunc {
         is_struct_fn: true,
@@ -1180,7 +1207,7 @@ fn generate_func_from_pkey_for_relation(ctx: &GenerateContext, tbl: &RelationCon
                 let joinfd = otp.join_field.clone().unwrap_or_default();
                 let majorfd = otp.major_field.clone().unwrap_or_default();
 
-                let sql = format!(
+                let mut sql = format!(
                     "SELECT tp.* FROM {} tp INNER JOIN {} mt ON tp.{} = mt.{} WHERE mt.{} = ?",
                     otp.table_name.clone().unwrap_or_default(),
                     otp.middle_table.clone().unwrap_or_default(),
@@ -1188,6 +1215,12 @@ fn generate_func_from_pkey_for_relation(ctx: &GenerateContext, tbl: &RelationCon
                     joinfd.clone(),
                     majorfd.clone()
                 );
+                // 关联的表为逻辑删除时，不加载已删除的数据
+                if let Some(ld) =
+                    get_logic_deletion(ctx, &otp.table_name.clone().unwrap_or_default())
+                {
+                    sql.push_str(&format!(" AND tp.{} = {}", ld.column, ld.undeleted_sql));
+                }
                 body.push(format!("let mut rb_args = vec![];"));
                 body.push(format!(
                     "let sql_{} = \"{}\";",
@@ -1335,6 +1368,7 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
     body.push(format!("}}")); // end of if
     body.push(format!("}}")); // end of if
     body.push(format!("else {{"));
+    // 主表配置了version-field时update_selective检查版本，冲突时返回错误，不再保存其它的表
     body.push(format!(
         "ret = match self_{}.update_selective(rb).await {{",
         tbconf.api_handler_name.clone()
@@ -1343,10 +1377,19 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
     body.push(format!("None"));
     body.push(format!("}}")); //  end of Ok
     body.push(format!("Err(err) => {{")); //  begin of none
-    body.push(format!(
-        "log::info!(\"Update {} occurred an error {{}}\", err);",
-        tbconf.api_handler_name.clone()
-    ));
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        body.push(format!(
+            "log::info!(\"Update {} occurred an error {{}}, version: {{:?}}\", err, self_{}.{});",
+            tbconf.api_handler_name.clone(),
+            tbconf.api_handler_name.clone(),
+            vf.field_name
+        ));
+    } else {
+        body.push(format!(
+            "log::info!(\"Update {} occurred an error {{}}\", err);",
+            tbconf.api_handler_name.clone()
+        ));
+    }
     body.push(format!("Some(err)"));
     body.push(format!("}}")); //  end of error
     body.push(format!("}}")); // end of if
@@ -1540,12 +1583,13 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
                 body.push(format!("}};")); // end of rm_{}
                 body.push(format!("}}")); // end of if
 
+                body.push("if ret.is_none() {".to_string());
                 body.push(format!(
                     "for row in self.{}s.clone() {{",
                     targtblconf.api_handler_name
                 ));
                 body.push(format!(
-                    "let svrow_{} = {} {{",
+                    "let mut svrow_{} = {} {{",
                     tpc.api_handler_name.clone(),
                     tpc.struct_name.clone()
                 ));
@@ -1614,7 +1658,12 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
                 body.push(format!("Some(err)"));
                 body.push(format!("}}")); //  end of error
                 body.push(format!("}};")); // end of rm_{}
+                // 第一个出错的行即结束，后面的行不能覆盖ret中的错误
+                body.push("if ret.is_some() {".to_string());
+                body.push("break;".to_string());
+                body.push("}".to_string());
                 body.push(format!("}}")); // end of for
+                body.push("}".to_string()); // end of if
             } else {
                 // if otp.middle_table.clone().is_some() {
                 if !tbl.deleted_by_relation {
@@ -1785,6 +1834,9 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
                 body.push(format!("}}")); //  end of error
                 body.push(format!("}};")); // end of rm_{}
                 body.push(format!("}}")); // end of if
+                body.push("if ret.is_some() {".to_string());
+                body.push("break;".to_string());
+                body.push("}".to_string());
                 body.push(format!("}}")); // end of for
                 body.push(format!("}}"));
             }
@@ -1796,6 +1848,29 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
     body.push(format!("Err(err)"));
     body.push(format!("}}"));
     body.push(format!("None => {{"));
+    // 全部保存成功后才将主表的版本写回，出错时事务回滚，版本保持不变
+    if let Some(vf) = get_version_field(ctx, &tbl_name) {
+        if tbl.extend_major {
+            body.push(format!(
+                "self.{} = self_{}.{};",
+                vf.field_name,
+                tbconf.api_handler_name.clone(),
+                vf.field_name
+            ));
+        } else {
+            body.push(format!(
+                "if let Some(mp) = self.{}.as_mut() {{",
+                tbconf.api_handler_name.clone()
+            ));
+            body.push(format!(
+                "mp.{} = self_{}.{};",
+                vf.field_name,
+                tbconf.api_handler_name.clone(),
+                vf.field_name
+            ));
+            body.push("}".to_string());
+        }
+    }
     body.push(format!("Ok(true)"));
     body.push(format!("}}"));
     body.push(format!("}}"));
@@ -1803,7 +1878,7 @@ fn generate_func_update_for_relation(ctx: &GenerateContext, tbl: &RelationConfig
     RustFunc {
         is_struct_fn: true,
         is_self_fn: true,
-        is_self_mut: false,
+        is_self_mut: true,
         is_pub: true,
         is_async: true,
         func_name: "save".to_string(),
@@ -2735,7 +2810,11 @@ pub fn generate_handler_save_for_relation(ctx: &GenerateContext, tbl: &RelationC
     body.push(format!("}}"));
     body.push(format!("Err(err) => {{"));
     body.push(format!("let _ = tx.rollback().await.is_ok();"));
-    body.push(format!("let ret: web::Json<ApiResult<String>> = web::Json(ApiResult::error(5010, &err.to_string()));"));
+    body.push(update_error_line(
+        "String",
+        5010,
+        get_version_field(ctx, &tbl_name).is_some(),
+    ));
     body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
     body.push(format!("}}"));
     body.push(format!("}}"));
@@ -2789,11 +2868,7 @@ pub fn tera_pascal(param: &HashMap<String, serde_json::Value>) -> Result<Value,
     }
 }
 
-pub fn generate_relation_form(ctx: &mut GenerateContext, rel: &RelationConfig) -> String {
-    let mut tera = TEMPLATES.clone();
-
-    tera.register_function("pascal", tera_pascal);
-
+pub fn generate_relation_form(ctx: &mut GenerateContext, rel: &RelationConfig) -> Option<String> {
     let tbl = ctx.get_table_info(&rel.major_table);
     let tbc = ctx.get_table_conf(&rel.major_table);
 
@@ -2945,20 +3020,13 @@ pub fn generate_relation_form(ctx: &mut GenerateContext, rel: &RelationConfig) -
 
     relform.relation_count = relform.relations.len() as u64;
 
-    let context = match Context::from_serialize(&relform) {
+    let mut context = match Context::from_serialize(&relform) {
         Ok(c) => c,
         Err(_) => Context::new(),
     };
+    context.insert("typescript_api", &ctx.is_typescript_api());
+    context.insert("relation_tables", &relform.get_relation_tables());
 
-    match tera.render("form.vue", &context) {
-        Ok(text) => text,
-        Err(err) => {
-            log::info!(
-                "Error for parse form.vue for {} the template: {}",
-                rel.major_table.clone(),
-                err.to_string()
-            );
-            String::new()
-        }
-    }
+    let tmpl = ctx.get_frontend_template("form.vue");
+    ctx.render_template(&tmpl, &rel.major_table, &context)
 }
@@ -0,0 +1,1099 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+
+use crate::codegen::{
+    convert_file_to_rbatis4, convert_file_to_sqlx, convert_file_to_standalone,
+    parse_column_as_field, GenerateContext, GeneratedFile, RustFileImpl, RustFunc,
+    RustStructFieldExtend,
+};
+use crate::config::{safe_struct_field_name, SeedConfig, TableConfig};
+use crate::output::WritePolicy;
+
+/**
+ * 演示及压测数据（seed子命令）
+ * 为每个配置的表生成seed.rows行数据，值根据列的类型、长度及名称推断：
+ * 1、名称：email、phone/mobile、username、name、_code、_no、address、url等生成相应格式的值
+ * 2、注释中的字典{name}从seed.dicts中取值，关联表{table}及relations中的关联字段取被引用表中已生成的值
+ * 3、tree-parent-field引用同一个表中前面的行，第一行为根节点（tree-root-value）
+ * 表按引用关系排序，被引用的表先生成；相同的random-seed及表结构生成相同的数据
+ * 输出为seed/seed.sql中的INSERT语句，或src/seed中通过生成的实体保存的模块（seed.format: rust）
+ */
+const SURNAMES: [&str; 20] = [
+    "王", "李", "张", "刘", "陈", "杨", "黄", "赵", "吴", "周", "徐", "孙", "马", "朱", "胡", "郭",
+    "何", "林", "罗", "高",
+];
+
+const GIVEN_NAMES: [&str; 24] = [
+    "伟", "芳", "娜", "敏", "静", "丽", "强", "磊", "军", "洋", "勇", "艳", "杰", "涛", "明", "超",
+    "秀英", "建国", "志强", "晓东", "雨婷", "子涵", "浩然", "欣怡",
+];
+
+const CITIES: [(&str, &str); 8] = [
+    ("北京市", "朝阳区"),
+    ("上海市", "浦东新区"),
+    ("广州市", "天河区"),
+    ("深圳市", "南山区"),
+    ("杭州市", "西湖区"),
+    ("成都市", "武侯区"),
+    ("武汉市", "洪山区"),
+    ("南京市", "鼓楼区"),
+];
+
+const ROADS: [&str; 8] = [
+    "人民路",
+    "中山路",
+    "解放路",
+    "建设路",
+    "和平路",
+    "长江路",
+    "科技路",
+    "文化路",
+];
+
+const COMPANY_WORDS: [&str; 8] = [
+    "华信", "创新", "远景", "卓越", "恒通", "博达", "智联", "新源",
+];
+
+const COMPANY_SUFFIXES: [&str; 4] = [
+    "科技有限公司",
+    "信息技术有限公司",
+    "贸易有限公司",
+    "网络科技有限公司",
+];
+
+const REMARKS: [&str; 5] = [
+    "示例数据，仅用于演示",
+    "用于测试的记录",
+    "自动生成的演示数据",
+    "请根据实际情况修改",
+    "压测数据",
+];
+
+const PERSON_TABLES: [&str; 9] = [
+    "user", "member", "person", "employee", "customer", "staff", "student", "teacher", "contact",
+];
+
+/**
+ * 生成的值，输出SQL时按MySQL的字面量，输出Rust时按实体反序列化的JSON
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeedValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Decimal(String),
+    Bool(bool),
+    Text(String),
+    DateTime(NaiveDateTime),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Bytes,
+    Json,
+}
+
+impl SeedValue {
+    pub fn to_sql(&self) -> String {
+        match self {
+            SeedValue::Null => "NULL".to_string(),
+            SeedValue::Int(v) => v.to_string(),
+            SeedValue::Float(v) => format!("{:.2}", v),
+            SeedValue::Decimal(v) => v.clone(),
+            SeedValue::Bool(v) => if *v { "1" } else { "0" }.to_string(),
+            SeedValue::Text(v) => format!("'{}'", v.replace('\\', "\\\\").replace('\'', "''")),
+            SeedValue::DateTime(v) => format!("'{}'", v.format("%Y-%m-%d %H:%M:%S")),
+            SeedValue::Date(v) => format!("'{}'", v.format("%Y-%m-%d")),
+            SeedValue::Time(v) => format!("'{}'", v.format("%H:%M:%S")),
+            SeedValue::Bytes => "''".to_string(),
+            SeedValue::Json => "'{}'".to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            SeedValue::Null => Value::Null,
+            SeedValue::Int(v) => json!(v),
+            SeedValue::Float(v) => json!((v * 100.0).round() / 100.0),
+            SeedValue::Decimal(v) => json!(v),
+            SeedValue::Bool(v) => json!(v),
+            SeedValue::Text(v) => json!(v),
+            SeedValue::DateTime(v) => json!(v.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            SeedValue::Date(v) => json!(v.format("%Y-%m-%d").to_string()),
+            SeedValue::Time(v) => json!(v.format("%H:%M:%S").to_string()),
+            SeedValue::Bytes => json!([]),
+            SeedValue::Json => json!({}),
+        }
+    }
+}
+
+/**
+ * 列引用的其它表（或同一个表）中的列
+ */
+#[derive(Debug, Clone)]
+pub struct SeedRef {
+    pub column: String,
+    pub table: String,
+    pub ref_column: String,
+}
+
+/**
+ * 一个表生成的数据，rows中值的顺序与columns相同
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SeedTable {
+    pub table_name: String,
+    pub struct_name: String,
+    pub comment: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SeedValue>>,
+    pub refs: Vec<SeedRef>,
+    pub auto_key: Option<String>, // 自增的主键，输出Rust时保存后由数据库生成
+}
+
+impl SeedTable {
+    fn column_values(&self, column: &str) -> Option<Vec<SeedValue>> {
+        let pos = self
+            .columns
+            .iter()
+            .position(|c| c.to_lowercase() == column.to_lowercase())?;
+        Some(
+            self.rows
+                .iter()
+                .map(|r| r[pos].clone())
+                .filter(|v| *v != SeedValue::Null)
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SeedKind {
+    Int,
+    Float,
+    Decimal,
+    Bool,
+    Text,
+    DateTime,
+    Date,
+    Time,
+    Bytes,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+struct SeedColumn {
+    name: String,
+    lname: String,
+    kind: SeedKind,
+    data_type: String,
+    nullable: bool,
+    length: usize,
+    precision: i64,
+    scale: i64,
+    caption: String,
+    dict: Option<String>,
+    is_pk: bool,
+    tree: bool,
+    reference: Option<Vec<SeedValue>>, // 被引用的列中已生成的值，None表示不是外键
+}
+
+fn seed_kind(field_type: &str) -> SeedKind {
+    match field_type.trim_start_matches("rbatis::") {
+        "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" => SeedKind::Int,
+        "f32" | "f64" => SeedKind::Float,
+        "Decimal" => SeedKind::Decimal,
+        "bool" => SeedKind::Bool,
+        "DateTimeNative" | "DateTimeUtc" | "Timestamp" | "TimestampZ" => SeedKind::DateTime,
+        "DateNative" | "DateUtc" => SeedKind::Date,
+        "TimeNative" | "TimeUtc" => SeedKind::Time,
+        "Bytes" => SeedKind::Bytes,
+        "Json" => SeedKind::Json,
+        _ => SeedKind::Text,
+    }
+}
+
+/**
+ * 表名的哈希，与random-seed一起作为每个表的随机数种子，增减其它的表不影响该表的数据
+ */
+fn table_hash(name: &str) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for b in name.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn table_key_column(ctx: &GenerateContext, tbl: &String) -> Option<String> {
+    let mut pkcols = ctx.get_table_column_by_primary_key(tbl);
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(tbl));
+    }
+    if pkcols.len() == 1 {
+        pkcols[0].column_name.clone()
+    } else {
+        None
+    }
+}
+
+fn push_seed_ref(
+    ctx: &GenerateContext,
+    refs: &mut HashMap<String, Vec<SeedRef>>,
+    table: &str,
+    column: &str,
+    ref_table: &str,
+    ref_column: Option<String>,
+) {
+    let has_column = |tbl: &str, col: &str| {
+        ctx.get_table_columns(&tbl.to_string())
+            .iter()
+            .any(|c| c.column_name.clone().unwrap_or_default().to_lowercase() == col.to_lowercase())
+    };
+    let ref_column = match ref_column
+        .filter(|c| !c.is_empty())
+        .or(table_key_column(ctx, &ref_table.to_string()))
+    {
+        Some(c) => c,
+        None => return,
+    };
+    if column.is_empty() || !has_column(table, column) || !has_column(ref_table, &ref_column) {
+        return;
+    }
+    let list = refs.entry(table.to_string()).or_default();
+    if !list
+        .iter()
+        .any(|r| r.column.to_lowercase() == column.to_lowercase())
+    {
+        list.push(SeedRef {
+            column: column.to_string(),
+            table: ref_table.to_string(),
+            ref_column,
+        });
+    }
+}
+
+/**
+ * 表之间的引用：注释中的关联表{table}，以及relations中一对一、一对多（含中间表）的关联字段
+ */
+fn collect_seed_refs(ctx: &GenerateContext) -> HashMap<String, Vec<SeedRef>> {
+    let mut refs = HashMap::new();
+    for tbc in ctx.codegen_conf.tables.iter() {
+        let mut usings = vec![];
+        for col in ctx.get_table_columns(&tbc.name).iter() {
+            let fd = parse_column_as_field(ctx, tbc, col, false, &mut usings);
+            if let Some(rel) = RustStructFieldExtend::parse(&fd).relation {
+                push_seed_ref(ctx, &mut refs, &tbc.name, &fd.column_name, rel.trim(), None);
+            }
+        }
+    }
+    for rel in ctx.codegen_conf.relations.iter() {
+        for rl in rel.one_to_one.iter().chain(rel.one_to_many.iter()) {
+            let table = rl.table_name.clone().unwrap_or_default();
+            let join_field = rl.join_field.clone().unwrap_or_default();
+            match rl.middle_table.clone() {
+                Some(mt) => {
+                    let major_field = rl.major_field.clone().unwrap_or_default();
+                    push_seed_ref(
+                        ctx,
+                        &mut refs,
+                        &mt,
+                        &major_field,
+                        &rel.major_table,
+                        rl.major_field.clone(),
+                    );
+                    push_seed_ref(
+                        ctx,
+                        &mut refs,
+                        &mt,
+                        &join_field,
+                        &table,
+                        rl.join_field.clone(),
+                    );
+                }
+                None => {
+                    push_seed_ref(
+                        ctx,
+                        &mut refs,
+                        &table,
+                        &join_field,
+                        &rel.major_table,
+                        rl.major_field.clone(),
+                    );
+                }
+            }
+        }
+    }
+    refs
+}
+
+/**
+ * 按引用关系排序，被引用的表在前；循环引用时按配置的顺序，未生成的被引用表的列为NULL
+ */
+fn sort_seed_tables(
+    tables: Vec<TableConfig>,
+    refs: &HashMap<String, Vec<SeedRef>>,
+) -> Vec<TableConfig> {
+    let mut sorted: Vec<TableConfig> = vec![];
+    let mut pending = tables;
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|t| {
+            refs.get(&t.name)
+                .map(|rs| {
+                    rs.iter().all(|r| {
+                        r.table == t.name
+                            || sorted.iter().any(|s| s.name == r.table)
+                            || !pending.iter().any(|p| p.name == r.table)
+                    })
+                })
+                .unwrap_or(true)
+        });
+        match ready {
+            Some(pos) => sorted.push(pending.remove(pos)),
+            None => {
+                log::warn!(
+                    "The table {} has cyclic references, some of the referenced columns will be NULL.",
+                    pending[0].name
+                );
+                sorted.push(pending.remove(0));
+            }
+        }
+    }
+    sorted
+}
+
+fn fit(text: String, length: usize) -> String {
+    if length > 0 && text.chars().count() > length {
+        text.chars().take(length).collect()
+    } else {
+        text
+    }
+}
+
+/**
+ * 编码、编号等需要唯一的值，超长时保留后面的序号部分
+ */
+fn fit_tail(text: String, length: usize) -> String {
+    let count = text.chars().count();
+    if length > 0 && count > length {
+        text.chars().skip(count - length).collect()
+    } else {
+        text
+    }
+}
+
+fn pick<'a>(rng: &mut StdRng, list: &[&'a str]) -> &'a str {
+    list[rng.gen_range(0..list.len())]
+}
+
+fn contains_any(name: &str, words: &[&str]) -> bool {
+    words.iter().any(|w| name.contains(w))
+}
+
+fn person_name(rng: &mut StdRng) -> String {
+    format!("{}{}", pick(rng, &SURNAMES), pick(rng, &GIVEN_NAMES))
+}
+
+fn random_datetime(rng: &mut StdRng) -> NaiveDateTime {
+    let base = NaiveDate::from_ymd_opt(2024, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap_or_default();
+    base + Duration::seconds(rng.gen_range(0..365 * 24 * 3600))
+}
+
+/**
+ * 字典的值或树形的根节点等配置的值转换为列的类型
+ */
+fn typed_value(col: &SeedColumn, text: &str, index: usize) -> SeedValue {
+    match col.kind {
+        SeedKind::Int => SeedValue::Int(text.parse().unwrap_or(index as i64 + 1)),
+        SeedKind::Float => SeedValue::Float(text.parse().unwrap_or(1.0)),
+        SeedKind::Decimal => SeedValue::Decimal(text.to_string()),
+        SeedKind::Bool => SeedValue::Bool(text == "1" || text.to_lowercase() == "true"),
+        _ => SeedValue::Text(fit(text.to_string(), col.length)),
+    }
+}
+
+fn seed_number(rng: &mut StdRng, col: &SeedColumn, min: f64, max: f64) -> SeedValue {
+    // decimal(p, s)的整数部分不能超过p - s位
+    let scale = if col.scale > 0 { col.scale } else { 2 };
+    let max = if col.precision > col.scale && col.precision > 0 {
+        max.min(10f64.powi((col.precision - col.scale) as i32) - 1.0)
+    } else {
+        max
+    };
+    let value = rng.gen_range(min.min(max)..=max);
+    match col.kind {
+        SeedKind::Decimal => SeedValue::Decimal(format!("{:.*}", scale as usize, value)),
+        SeedKind::Int => SeedValue::Int(value as i64),
+        _ => SeedValue::Float((value * 100.0).round() / 100.0),
+    }
+}
+
+fn seed_int(rng: &mut StdRng, col: &SeedColumn, tbc: &TableConfig, index: usize) -> SeedValue {
+    let n = col.lname.as_str();
+    let value = if col.data_type == "bit" || contains_any(n, &["gender", "sex"]) {
+        rng.gen_range(0..=1)
+    } else if n == "age" || n.ends_with("_age") {
+        rng.gen_range(18..=60)
+    } else if contains_any(n, &["status", "state", "enable"]) {
+        if rng.gen_bool(0.8) {
+            1
+        } else {
+            0
+        }
+    } else if contains_any(n, &["sort", "order", "seq", "rank"]) {
+        index as i64 + 1
+    } else if n == "year" || n.ends_with("_year") {
+        rng.gen_range(2020..=2024)
+    } else if contains_any(n, &["quantity", "qty", "count", "num", "stock", "amount"]) {
+        rng.gen_range(1..=100)
+    } else if n == "company_id"
+        || (n
+            != tbc
+                .tree_parent_field
+                .clone()
+                .unwrap_or_default()
+                .to_lowercase()
+            && contains_any(
+                n,
+                &[
+                    "create_by",
+                    "modify_by",
+                    "update_by",
+                    "create_user",
+                    "modify_user",
+                    "update_user",
+                ],
+            ))
+    {
+        1
+    } else {
+        rng.gen_range(1..=1000)
+    };
+    SeedValue::Int(value)
+}
+
+fn seed_text(rng: &mut StdRng, col: &SeedColumn, tbc: &TableConfig, index: usize) -> SeedValue {
+    let n = col.lname.as_str();
+    let short = tbc
+        .name
+        .rsplit('_')
+        .next()
+        .unwrap_or(&tbc.name)
+        .to_lowercase();
+    let seq = index + 1;
+    let text = if n.contains("email") || n.contains("mail") {
+        format!("{}{:03}@example.com", short, seq)
+    } else if contains_any(n, &["phone", "mobile", "tel"]) {
+        format!(
+            "1{}{:09}",
+            pick(rng, &["3", "5", "7", "8", "9"]),
+            rng.gen_range(0..1_000_000_000u64)
+        )
+    } else if contains_any(n, &["id_card", "idcard", "id_no", "identity"]) {
+        let birth = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default()
+            + Duration::days(rng.gen_range(0..30 * 365));
+        format!(
+            "110101{}{:04}",
+            birth.format("%Y%m%d"),
+            rng.gen_range(0..10000)
+        )
+    } else if n == "ip" || n.ends_with("_ip") || n.contains("ip_addr") {
+        format!(
+            "192.168.{}.{}",
+            rng.gen_range(0..=255),
+            rng.gen_range(1..=254)
+        )
+    } else if contains_any(n, &["url", "link", "website", "homepage", "href"]) {
+        format!("https://www.example.com/{}/{}", short, seq)
+    } else if contains_any(
+        n,
+        &["avatar", "image", "img", "picture", "photo", "logo", "icon"],
+    ) {
+        format!("https://www.example.com/images/{}_{}.png", short, seq)
+    } else if contains_any(n, &["address", "addr"]) {
+        let (city, district) = CITIES[rng.gen_range(0..CITIES.len())];
+        format!(
+            "{}{}{}{}号",
+            city,
+            district,
+            pick(rng, &ROADS),
+            rng.gen_range(1..=999)
+        )
+    } else if n.ends_with("_code") || n == "code" {
+        let prefix = short.chars().take(4).collect::<String>().to_uppercase();
+        return SeedValue::Text(fit_tail(format!("{}{:04}", prefix, seq), col.length));
+    } else if n.ends_with("_no") || n == "no" || n.ends_with("_number") {
+        return SeedValue::Text(fit_tail(
+            format!("{}{:06}", random_datetime(rng).format("%Y%m%d"), seq),
+            col.length,
+        ));
+    } else if contains_any(n, &["username", "login_name", "account"]) {
+        return SeedValue::Text(fit_tail(format!("{}{:03}", short, seq), col.length));
+    } else if contains_any(
+        n,
+        &[
+            "nick",
+            "real_name",
+            "full_name",
+            "person",
+            "contact",
+            "linkman",
+            "user_name",
+            "leader",
+            "manager",
+        ],
+    ) || (n == "name" && contains_any(&tbc.name.to_lowercase(), &PERSON_TABLES))
+    {
+        person_name(rng)
+    } else if contains_any(n, &["company", "corp", "enterprise", "org_name"]) {
+        let (city, _) = CITIES[rng.gen_range(0..CITIES.len())];
+        format!(
+            "{}{}{}",
+            city.trim_end_matches('市'),
+            pick(rng, &COMPANY_WORDS),
+            pick(rng, &COMPANY_SUFFIXES)
+        )
+    } else if contains_any(n, &["gender", "sex"]) {
+        pick(rng, &["男", "女"]).to_string()
+    } else if contains_any(n, &["status", "state"]) {
+        if rng.gen_bool(0.8) { "1" } else { "0" }.to_string()
+    } else if contains_any(n, &["password", "passwd", "pwd"]) {
+        // 123456的md5
+        "e10adc3949ba59abbe56e057f20f883e".to_string()
+    } else if contains_any(
+        n,
+        &[
+            "remark", "desc", "memo", "note", "content", "summary", "intro", "comment",
+        ],
+    ) {
+        pick(rng, &REMARKS).to_string()
+    } else if n == "company_code" {
+        "default".to_string()
+    } else if contains_any(
+        n,
+        &[
+            "create_by",
+            "modify_by",
+            "update_by",
+            "create_user",
+            "modify_user",
+            "update_user",
+        ],
+    ) {
+        "admin".to_string()
+    } else {
+        // 其余的按注释中的名称加序号，如部门名称 => 部门1
+        let caption = col
+            .caption
+            .trim_end_matches("名称")
+            .trim_end_matches("名字");
+        return SeedValue::Text(fit_tail(format!("{}{}", caption, seq), col.length));
+    };
+    SeedValue::Text(fit(text, col.length))
+}
+
+fn seed_column_value(
+    rng: &mut StdRng,
+    conf: &SeedConfig,
+    tbc: &TableConfig,
+    col: &SeedColumn,
+    index: usize,
+) -> SeedValue {
+    if let Some(name) = col.dict.as_ref() {
+        let values = conf.get_dict_values(name).unwrap_or(vec![
+            "1".to_string(),
+            "2".to_string(),
+            "3".to_string(),
+        ]);
+        let value = values[rng.gen_range(0..values.len())].clone();
+        return typed_value(col, &value, index);
+    }
+    if tbc.logic_deletion && col.lname == tbc.logic_deletion_field.to_lowercase() {
+        // 逻辑删除的列，演示数据均为未删除
+        return typed_value(col, &tbc.logic_undeleted_value, index);
+    }
+    if tbc.version_field.clone().unwrap_or_default().to_lowercase() == col.lname {
+        // 乐观锁的版本，演示数据从0开始
+        return typed_value(col, "0", index);
+    }
+    let n = col.lname.as_str();
+    match col.kind {
+        SeedKind::Int if col.is_pk => SeedValue::Int(conf.id_start + index as i64),
+        SeedKind::Text if col.is_pk && (col.length == 0 || col.length >= 32) => SeedValue::Text(
+            (0..32)
+                .map(|_| format!("{:x}", rng.gen_range(0..16)))
+                .collect(),
+        ),
+        SeedKind::Int => seed_int(rng, col, tbc, index),
+        SeedKind::Float | SeedKind::Decimal => {
+            if contains_any(n, &["rate", "ratio", "percent", "discount"]) {
+                seed_number(rng, col, 0.01, 0.99)
+            } else if contains_any(
+                n,
+                &[
+                    "price", "amount", "money", "fee", "cost", "salary", "balance", "total",
+                ],
+            ) {
+                seed_number(rng, col, 10.0, 9999.99)
+            } else {
+                seed_number(rng, col, 1.0, 1000.0)
+            }
+        }
+        SeedKind::Bool => {
+            if contains_any(n, &["enable", "active", "valid"]) || n.starts_with("is_") {
+                SeedValue::Bool(rng.gen_bool(0.8))
+            } else {
+                SeedValue::Bool(rng.gen_bool(0.5))
+            }
+        }
+        SeedKind::DateTime => SeedValue::DateTime(random_datetime(rng)),
+        SeedKind::Date => SeedValue::Date(random_datetime(rng).date()),
+        SeedKind::Time => SeedValue::Time(random_datetime(rng).time()),
+        SeedKind::Bytes if !col.nullable => SeedValue::Bytes,
+        SeedKind::Json if !col.nullable => SeedValue::Json,
+        SeedKind::Bytes | SeedKind::Json => SeedValue::Null,
+        SeedKind::Text => seed_text(rng, col, tbc, index),
+    }
+}
+
+fn generate_seed_table(
+    ctx: &GenerateContext,
+    conf: &SeedConfig,
+    tbc: &TableConfig,
+    rows: usize,
+    refs: &Vec<SeedRef>,
+    generated: &Vec<SeedTable>,
+) -> SeedTable {
+    let mut rng = StdRng::seed_from_u64(conf.random_seed ^ table_hash(&tbc.name));
+    let mut pkcols = ctx.get_table_column_by_primary_key(&tbc.name);
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(&tbc.name));
+    }
+    let pknames = pkcols
+        .iter()
+        .map(|c| c.column_name.clone().unwrap_or_default())
+        .collect::<Vec<String>>();
+    let tree_field = tbc
+        .tree_parent_field
+        .clone()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let mut usings = vec![];
+    let mut columns = vec![];
+    for col in ctx.get_table_columns(&tbc.name).iter() {
+        let fd = parse_column_as_field(ctx, tbc, col, false, &mut usings);
+        let ext = RustStructFieldExtend::parse(&fd);
+        let name = fd.column_name.clone();
+        let reference = refs
+            .iter()
+            .find(|r| r.column.to_lowercase() == name.to_lowercase() && r.table != tbc.name)
+            .map(|r| {
+                generated
+                    .iter()
+                    .find(|t| t.table_name == r.table)
+                    .and_then(|t| t.column_values(&r.ref_column))
+                    .unwrap_or_default()
+            });
+        columns.push(SeedColumn {
+            lname: name.to_lowercase(),
+            kind: seed_kind(&fd.field_type),
+            data_type: col.data_type.clone().unwrap_or_default().to_lowercase(),
+            nullable: col.is_nullable.clone().unwrap_or_default().to_lowercase() == "yes",
+            length: col.character_maximum_length.unwrap_or_default().max(0) as usize,
+            precision: col.numeric_precision.unwrap_or_default(),
+            scale: col.numeric_scale.unwrap_or_default(),
+            caption: if ext.caption.is_empty() {
+                name.clone()
+            } else {
+                ext.caption.clone()
+            },
+            dict: ext.dict.clone().filter(|d| !d.is_empty()),
+            is_pk: pknames.contains(&name),
+            tree: !tree_field.is_empty() && name.to_lowercase() == tree_field,
+            reference,
+            name,
+        });
+    }
+    let pk_pos = columns.iter().position(|c| c.is_pk);
+
+    let mut keys = HashSet::new();
+    let mut table_rows: Vec<Vec<SeedValue>> = vec![];
+    let mut attempts = 0;
+    while table_rows.len() < rows && attempts < rows * 10 {
+        attempts += 1;
+        let index = table_rows.len();
+        let mut row = vec![];
+        for col in columns.iter() {
+            let value = if col.tree {
+                // 第一行及部分行为根节点，其余的父节点为前面的行
+                match pk_pos {
+                    Some(pos) if index > 0 && rng.gen_bool(0.7) => {
+                        table_rows[rng.gen_range(0..index)][pos].clone()
+                    }
+                    _ => match tbc
+                        .tree_root_value
+                        .clone()
+                        .unwrap_or_default()
+                        .to_lowercase()
+                        .as_str()
+                    {
+                        "null" => SeedValue::Null,
+                        "" => typed_value(col, "0", index),
+                        root => typed_value(col, root, index),
+                    },
+                }
+            } else if let Some(values) = col.reference.as_ref() {
+                if values.is_empty() {
+                    if col.nullable {
+                        SeedValue::Null
+                    } else {
+                        seed_column_value(&mut rng, conf, tbc, col, index)
+                    }
+                } else {
+                    values[rng.gen_range(0..values.len())].clone()
+                }
+            } else {
+                seed_column_value(&mut rng, conf, tbc, col, index)
+            };
+            row.push(value);
+        }
+        // 复合主键（如中间表）的组合不能重复
+        let key = columns
+            .iter()
+            .zip(row.iter())
+            .filter(|(c, _)| c.is_pk)
+            .map(|(_, v)| v.to_sql())
+            .collect::<Vec<String>>()
+            .join(",");
+        if !key.is_empty() && !keys.insert(key) {
+            continue;
+        }
+        table_rows.push(row);
+    }
+    if table_rows.len() < rows {
+        log::warn!(
+            "Only {} rows were generated for {} as the primary key could not be unique.",
+            table_rows.len(),
+            tbc.name
+        );
+    }
+
+    let mut table_refs = refs.clone();
+    if !tree_field.is_empty() {
+        if let (Some(col), Some(pk)) = (columns.iter().find(|c| c.tree), pknames.first()) {
+            table_refs.push(SeedRef {
+                column: col.name.clone(),
+                table: tbc.name.clone(),
+                ref_column: pk.clone(),
+            });
+        }
+    }
+    SeedTable {
+        table_name: tbc.name.clone(),
+        struct_name: ctx
+            .get_struct_name(&tbc.name)
+            .unwrap_or(tbc.struct_name.clone()),
+        comment: tbc.comment.clone(),
+        columns: columns.iter().map(|c| c.name.clone()).collect(),
+        rows: table_rows,
+        refs: table_refs,
+        auto_key: ctx
+            .get_table_auto_incremnt_column(&tbc.name)
+            .and_then(|c| c.column_name)
+            .filter(|c| pknames.contains(c)),
+    }
+}
+
+/**
+ * 按依赖顺序为每个配置的表生成数据
+ */
+pub fn generate_seed_tables(ctx: &GenerateContext) -> Vec<SeedTable> {
+    let conf = &ctx.codegen_conf.seed;
+    let rows = if conf.rows > 0 {
+        conf.rows as usize
+    } else {
+        10
+    };
+    let refs = collect_seed_refs(ctx);
+    let tables = ctx
+        .codegen_conf
+        .tables
+        .iter()
+        .filter(|t| !ctx.get_table_columns(&t.name).is_empty())
+        .cloned()
+        .collect::<Vec<TableConfig>>();
+    let mut generated = vec![];
+    for tbc in sort_seed_tables(tables, &refs).iter() {
+        let table_refs = refs.get(&tbc.name).cloned().unwrap_or_default();
+        let table = generate_seed_table(ctx, conf, tbc, rows, &table_refs, &generated);
+        generated.push(table);
+    }
+    generated
+}
+
+/**
+ * seed.sql：每个表一条（每500行一条）INSERT语句
+ */
+pub fn format_seed_sql(tables: &Vec<SeedTable>, conf: &SeedConfig) -> String {
+    let mut text = String::new();
+    text.push_str("-- 由代码生成器生成的演示数据（seed），请在测试或演示的数据库中执行\n");
+    text.push_str(&format!(
+        "-- random-seed: {}，id-start: {}，表按引用关系排序，被引用的表在前\n",
+        conf.random_seed, conf.id_start
+    ));
+    for tbl in tables.iter().filter(|t| !t.rows.is_empty()) {
+        text.push_str(&format!("\n-- {}（{}）\n", tbl.comment, tbl.table_name));
+        let columns = tbl
+            .columns
+            .iter()
+            .map(|c| format!("`{}`", c))
+            .collect::<Vec<String>>()
+            .join(", ");
+        for chunk in tbl.rows.chunks(500) {
+            text.push_str(&format!(
+                "INSERT INTO `{}` ({}) VALUES\n",
+                tbl.table_name, columns
+            ));
+            let values = chunk
+                .iter()
+                .map(|row| {
+                    format!(
+                        "({})",
+                        row.iter()
+                            .map(|v| v.to_sql())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )
+                })
+                .collect::<Vec<String>>();
+            text.push_str(&values.join(",\n"));
+            text.push_str(";\n");
+        }
+    }
+    text
+}
+
+fn seed_func(
+    name: &str,
+    comment: String,
+    params: Vec<(String, String)>,
+    bodylines: Vec<String>,
+    is_pub: bool,
+) -> RustFunc {
+    RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub,
+        is_async: true,
+        func_name: name.to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("Result<(), String>".to_string()),
+        params,
+        bodylines,
+        macros: vec![format!("/// {}", comment)],
+        comment: Some(comment),
+        api_method: None,
+        api_pattern: None,
+    }
+}
+
+/**
+ * seed_data.rs：每个表一个函数，通过生成的实体的save保存，seed_all在同一个事务中按依赖顺序调用
+ * 自增主键由数据库生成，引用它的列在保存前替换为实际的值
+ */
+pub fn generate_seed_rust_file(tables: &Vec<SeedTable>) -> RustFileImpl {
+    let auto_tables = tables
+        .iter()
+        .filter(|t| t.auto_key.is_some())
+        .map(|t| (t.table_name.clone(), t.auto_key.clone().unwrap_or_default()))
+        .collect::<HashMap<String, String>>();
+    let mut funclist = vec![];
+    let mut has_ref = false;
+    let mut entities = vec![];
+    for tbl in tables.iter().filter(|t| !t.rows.is_empty()) {
+        // 只有引用自增主键的列需要替换
+        let remaps = tbl
+            .refs
+            .iter()
+            .filter(|r| {
+                auto_tables
+                    .get(&r.table)
+                    .map(|k| k.to_lowercase() == r.ref_column.to_lowercase())
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<&SeedRef>>();
+        has_ref = has_ref || !remaps.is_empty();
+        let ids = if remaps.is_empty() && tbl.auto_key.is_none() {
+            "_ids"
+        } else {
+            "ids"
+        };
+
+        let mut body = vec!["let rows: Vec<Value> = serde_json::from_str(r##\"[".to_string()];
+        let count = tbl.rows.len();
+        for (i, row) in tbl.rows.iter().enumerate() {
+            let mut obj = serde_json::Map::new();
+            for (col, val) in tbl.columns.iter().zip(row.iter()) {
+                obj.insert(col.clone(), val.to_json());
+            }
+            let sep = if i + 1 < count { "," } else { "" };
+            body.push(format!("{}{}", Value::Object(obj), sep));
+        }
+        body.push("]\"##)".to_string());
+        body.push(".map_err(|err| err.to_string())?;".to_string());
+        body.push(format!(
+            "for {}row in rows {{",
+            if remaps.is_empty() { "" } else { "mut " }
+        ));
+        for rf in remaps.iter() {
+            body.push(format!(
+                "seed_ref(&mut row, \"{}\", \"{}\", ids);",
+                rf.column, rf.table
+            ));
+        }
+        if let Some(key) = tbl.auto_key.as_ref() {
+            body.push(format!(
+                "let key = format!(\"{}:{{}}\", row[\"{}\"]);",
+                tbl.table_name, key
+            ));
+        }
+        body.push(format!(
+            "let mut val: {} = serde_json::from_value(row).map_err(|err| err.to_string())?;",
+            tbl.struct_name
+        ));
+        body.push("val.save(rb).await.map_err(|err| err.to_string())?;".to_string());
+        if let Some(key) = tbl.auto_key.as_ref() {
+            body.push(format!(
+                "ids.insert(key, json!(val.{}));",
+                safe_struct_field_name(&key.to_lowercase())
+            ));
+        }
+        body.push("}".to_string());
+        body.push("Ok(())".to_string());
+
+        funclist.push(seed_func(
+            &format!("seed_{}", tbl.table_name.to_lowercase()),
+            format!("{}（{}）的{}行演示数据", tbl.comment, tbl.table_name, count),
+            vec![
+                ("rb".to_string(), "&mut RBatisTxExecutor<'_>".to_string()),
+                (ids.to_string(), "&mut HashMap<String, Value>".to_string()),
+            ],
+            body,
+            false,
+        ));
+        entities.push(tbl.struct_name.clone());
+    }
+
+    let mut body = vec![
+        "let rb = get_rbatis();".to_string(),
+        "let mut tx = rb.acquire_begin().await.map_err(|err| err.to_string())?;".to_string(),
+        "let mut ids = HashMap::new();".to_string(),
+    ];
+    for func in funclist.iter() {
+        body.push(format!("{}(&mut tx, &mut ids).await?;", func.func_name));
+    }
+    body.push("tx.commit().await.map_err(|err| err.to_string())?;".to_string());
+    body.push("Ok(())".to_string());
+    funclist.insert(
+        0,
+        seed_func(
+            "seed_all",
+            "按依赖顺序保存所有表的演示数据，在同一个事务中执行".to_string(),
+            vec![],
+            body,
+            true,
+        ),
+    );
+
+    if has_ref {
+        let mut func = seed_func(
+            "seed_ref",
+            "将引用自增主键的列替换为保存后数据库生成的值".to_string(),
+            vec![
+                ("row".to_string(), "&mut Value".to_string()),
+                ("column".to_string(), "&str".to_string()),
+                ("table".to_string(), "&str".to_string()),
+                ("ids".to_string(), "&HashMap<String, Value>".to_string()),
+            ],
+            vec![
+                "if let Some(id) = ids.get(&format!(\"{}:{}\", table, row[column])) {".to_string(),
+                "row[column] = id.clone();".to_string(),
+                "}".to_string(),
+            ],
+            false,
+        );
+        func.is_async = false;
+        func.return_type = None;
+        funclist.push(func);
+    }
+
+    entities.sort();
+    entities.dedup();
+    let mut usinglist = vec![
+        "std::collections::HashMap".to_string(),
+        "rbatis::executor::RBatisTxExecutor".to_string(),
+        "chimes_utils::get_rbatis".to_string(),
+    ];
+    if auto_tables.is_empty() {
+        usinglist.push("serde_json::Value".to_string());
+    } else {
+        usinglist.push("serde_json::{json, Value}".to_string());
+    }
+    if !entities.is_empty() {
+        usinglist.push(format!("crate::entity::{{{}}}", entities.join(", ")));
+    }
+    RustFileImpl {
+        file_name: "seed_data.rs".to_string(),
+        mod_name: "seed".to_string(),
+        caretlist: vec![],
+        usinglist,
+        structlist: vec![],
+        funclist,
+        implist: vec![],
+        artifact: None,
+    }
+}
+
+/**
+ * seed子命令输出的文件，Rust的模块与其它代码一样按ORM及standalone转换
+ */
+pub fn generate_seed_files(ctx: &GenerateContext) -> Vec<GeneratedFile> {
+    let tables = generate_seed_tables(ctx);
+    let conf = &ctx.codegen_conf.seed;
+    if conf.format != "rust" {
+        return vec![GeneratedFile::new(
+            "seed/seed.sql",
+            &format_seed_sql(&tables, conf),
+            WritePolicy::Overwrite,
+        )];
+    }
+    let mut fl = generate_seed_rust_file(&tables);
+    if ctx.is_rbatis_v4() {
+        convert_file_to_rbatis4(ctx, &mut fl);
+    }
+    if ctx.is_sqlx() {
+        convert_file_to_sqlx(ctx, &mut fl);
+    }
+    if ctx.is_standalone() {
+        convert_file_to_standalone(&mut fl);
+    }
+    vec![
+        GeneratedFile::new(
+            &format!("src/{}/{}", fl.mod_name, fl.file_name),
+            &fl.to_source(),
+            WritePolicy::Overwrite,
+        ),
+        GeneratedFile::new(
+            &format!("src/{}/mod.rs", fl.mod_name),
+            "mod seed_data;\npub use seed_data::*;\n",
+            WritePolicy::Overwrite,
+        ),
+    ]
+}
@@ -0,0 +1,1097 @@
+use change_case::{pascal_case, snake_case};
+
+use super::{
+    generate_handler_query_list_for_composite, generate_handler_query_page_for_composite,
+    get_logic_deletion, get_version_field, parse_relation_as_struct, process_common_fields, process_detail_common_fields_v2,
+    process_enity_common_fields_v2, update_error_line, RustArtifact, RustFileImpl,
+};
+use crate::codegen::{
+    parse_column_list, parse_data_type_as_rust_type, GenerateContext, RustFunc, RustStruct,
+};
+use crate::config::{RelationConfig, TableConfig};
+use crate::schema::{ColumnInfo, TableInfo};
+
+/**
+ * Service层
+ * 每个表和关系生成一个{Struct}Service，包含业务操作（create, update, delete, query, tree等）
+ * 事务、审计字段的填充以及多租户的检查都在Service中完成，handler只负责参数的转换和结果的输出
+ * 生成的Service可以在job或其它的传输层中复用
+ */
+fn get_service_name(struct_name: &String) -> String {
+    format!("{}Service", struct_name)
+}
+
+fn get_primary_key_columns(ctx: &GenerateContext, tbl_name: &String) -> Vec<ColumnInfo> {
+    let mut pkcols = ctx.get_table_column_by_primary_key(&tbl_name.clone());
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(&tbl_name.clone()));
+    }
+    pkcols
+}
+
+fn get_query_param_type(tbc: &TableConfig, tbl_struct_name: &String) -> String {
+    if tbc.using_common_search {
+        "CommonSearch".to_string()
+    } else if tbc.generate_param_struct {
+        format!("{}Query", tbl_struct_name.clone())
+    } else {
+        tbl_struct_name.clone()
+    }
+}
+
+/**
+ * Service只接收当前用户及租户的值（crate::utils::ServiceUser），不依赖于web框架的提取器
+ * handler中通过ServiceUser::from(&su.user)转换
+ */
+fn su_param(has_su: bool) -> (String, String) {
+    if has_su {
+        ("su".to_string(), "&ServiceUser".to_string())
+    } else {
+        ("_su".to_string(), "&ServiceUser".to_string())
+    }
+}
+
+fn new_service_func(
+    func_name: &str,
+    params: Vec<(String, String)>,
+    body: Vec<String>,
+    return_type: String,
+    return_is_option: bool,
+    comment: String,
+) -> RustFunc {
+    RustFunc {
+        is_struct_fn: true,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: true,
+        func_name: func_name.to_string(),
+        return_is_option: return_is_option,
+        return_is_result: true,
+        return_type: Some(return_type),
+        params: params,
+        // 填充审计字段及多租户检查的代码来自handler，其中的su.user.xxx在Service中为su.xxx
+        bodylines: body.into_iter().map(|ln| ln.replace("su.user.", "su.")).collect(),
+        macros: vec![],
+        comment: Some(comment),
+        api_method: None,
+        api_pattern: None,
+    }
+}
+
+/**
+ * 在事务中执行操作，成功后提交事务，失败则回滚
+ */
+fn push_transaction_lines(body: &mut Vec<String>, call: &str, ok_value: &str) {
+    body.push(format!("let mut tx = rb.acquire_begin().await?;"));
+    body.push(format!("match {}.await {{", call));
+    body.push(format!("Ok(_st) => {{"));
+    body.push(format!("tx.commit().await?;"));
+    body.push(format!("Ok({})", ok_value));
+    body.push(format!("}}"));
+    body.push(format!("Err(err) => {{"));
+    body.push(format!("let _ = tx.rollback().await.is_ok();"));
+    body.push(format!("Err(err)"));
+    body.push(format!("}}"));
+    body.push(format!("}}"));
+}
+
+/**
+ * 多租户时，表中用于区分租户的字段（company_id或company_code）
+ */
+fn get_tenancy_field(ctx: &GenerateContext, tbl_name: &String) -> Option<String> {
+    if !ctx.codegen_conf.multi_tenancy {
+        return None;
+    }
+    let tbconf = ctx.get_table_conf(tbl_name)?;
+    let mut columns = String::new();
+    let mut usings = vec![];
+    let cols = ctx.get_table_columns(tbl_name);
+    let fields = parse_column_list(ctx, &tbconf, &cols, &mut columns, false, &mut usings);
+    fields
+        .into_iter()
+        .map(|fl| fl.field_name)
+        .find(|name| name == "company_id" || name == "company_code")
+}
+
+/**
+ * 多租户时，检查记录是否属于当前用户所在的公司
+ * 不属于时执行fail_line（如返回Ok(None)）
+ */
+fn push_tenancy_check(
+    ctx: &GenerateContext,
+    body: &mut Vec<String>,
+    tbl_name: &String,
+    var_name: &str,
+    fail_line: &str,
+) -> bool {
+    match get_tenancy_field(ctx, tbl_name) {
+        Some(field) => {
+            body.push(format!("if {}.{} != su.user.{}.clone() {{", var_name, field, field));
+            body.push(fail_line.to_string());
+            body.push("}".to_string());
+            true
+        }
+        None => false,
+    }
+}
+
+/**
+ * 生成表对应的Service
+ */
+pub fn generate_service_for_table(ctx: &GenerateContext, tbl: &TableInfo) -> Option<RustFileImpl> {
+    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+    let tbc = match ctx.get_table_conf(&tbl_name.clone()) {
+        Some(t) => t,
+        None => return None,
+    };
+    let tbl_struct_name = match ctx.get_struct_name(&tbl_name.clone()) {
+        Some(t) => t,
+        None => pascal_case(tbl_name.clone().as_str()),
+    };
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+
+    let mut funclist = vec![];
+    funclist.push(generate_service_save_for_table(ctx, tbl, &tbc, &tbl_struct_name, 1));
+    funclist.push(generate_service_save_for_table(ctx, tbl, &tbc, &tbl_struct_name, 2));
+    funclist.push(generate_service_delete_for_table(
+        ctx,
+        tbl,
+        &tbc,
+        &tbl_struct_name,
+        "delete",
+        "remove",
+        "删除",
+    ));
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        funclist.push(generate_service_delete_for_table(
+            ctx,
+            tbl,
+            &tbc,
+            &tbl_struct_name,
+            "restore",
+            "restore",
+            "恢复",
+        ));
+        funclist.push(generate_service_delete_for_table(
+            ctx,
+            tbl,
+            &tbc,
+            &tbl_struct_name,
+            "purge",
+            "purge",
+            "彻底删除",
+        ));
+    }
+    if pkcols.len() == 1 {
+        funclist.push(generate_service_delete_ids_for_table(
+            ctx,
+            tbl,
+            &tbc,
+            &tbl_struct_name,
+            &pkcols[0],
+        ));
+    }
+    funclist.push(generate_service_query_for_table(ctx, tbl, &tbc, &tbl_struct_name, false));
+    if tbc.page_query {
+        funclist.push(generate_service_query_for_table(ctx, tbl, &tbc, &tbl_struct_name, true));
+    }
+    funclist.push(generate_service_get_for_table(ctx, &tbc, &tbl_struct_name, &pkcols));
+    if tbc.tree_parent_field.is_some() {
+        if let Some(func) = generate_service_tree_for_table(ctx, &tbc, &tbl_struct_name) {
+            funclist.push(func);
+        }
+    }
+
+    let mut usinglist = vec![];
+    usinglist.push("crate::utils::ServiceUser".to_string());
+    usinglist.push("chimes_utils::get_rbatis".to_string());
+    usinglist.push("rbatis::error::Error".to_string());
+    if tbc.page_query {
+        usinglist.push("rbatis::Page".to_string());
+    }
+    if tbc.using_common_search {
+        usinglist.push("chimes_utils::CommonSearch".to_string());
+    }
+    if tbc.generate_param_struct && !tbc.using_common_search {
+        usinglist.push(format!(
+            "crate::entity::{{{}, {}Query}}",
+            tbl_struct_name.clone(),
+            tbl_struct_name.clone()
+        ));
+    } else {
+        usinglist.push(format!("crate::entity::{}", tbl_struct_name.clone()));
+    }
+
+    let st = RustStruct {
+        is_pub: true,
+        has_paging: tbc.page_query,
+        struct_name: get_service_name(&tbl_struct_name),
+        annotations: vec!["#[derive(Debug, Clone, Default)]".to_string()],
+        fields: vec![],
+        funclist: funclist,
+        usings: vec![],
+    };
+
+    Some(RustFileImpl {
+        file_name: format!("{}.rs", snake_case(tbl_struct_name.as_str())),
+        mod_name: "service".to_string(),
+        caretlist: vec![],
+        usinglist: usinglist,
+        structlist: vec![st],
+        funclist: vec![],
+        implist: vec![],
+        artifact: Some(RustArtifact::Service(tbl_name.clone())),
+    })
+}
+
+/**
+ * operation为1时为create(save)，为2时为update
+ */
+fn generate_service_save_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+    operation: i64,
+) -> RustFunc {
+    let mut body = vec![];
+    let mut somebody = vec![];
+    let (has_su, _) = process_common_fields(ctx, &mut somebody, tbl, operation);
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!("let mut val = val.clone();"));
+    body.append(&mut somebody);
+
+    let (func_name, call, comment) = if operation == 1 {
+        ("create", "val.save(&mut tx)", format!("{}保存", tbc.comment.clone()))
+    } else if tbc.update_seletive {
+        (
+            "update",
+            "val.update_selective(&mut tx)",
+            format!("{}更新", tbc.comment.clone()),
+        )
+    } else {
+        ("update", "val.update(&mut tx)", format!("{}更新", tbc.comment.clone()))
+    };
+    push_transaction_lines(&mut body, call, "val");
+
+    new_service_func(
+        func_name,
+        vec![su_param(has_su), ("val".to_string(), format!("&{}", tbl_struct_name.clone()))],
+        body,
+        tbl_struct_name.clone(),
+        false,
+        comment,
+    )
+}
+
+/**
+ * 按主键执行实体的方法，func_name为delete（remove）、restore、purge
+ */
+fn generate_service_delete_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+    func_name: &str,
+    method: &str,
+    comment: &str,
+) -> RustFunc {
+    let mut body = vec![];
+    let mut somebody = vec![];
+    let (has_su, _) = process_common_fields(ctx, &mut somebody, tbl, 2);
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!("let mut val = val.clone();"));
+    body.append(&mut somebody);
+    push_transaction_lines(&mut body, &format!("val.{}(&mut tx)", method), "val");
+
+    new_service_func(
+        func_name,
+        vec![su_param(has_su), ("val".to_string(), format!("&{}", tbl_struct_name.clone()))],
+        body,
+        tbl_struct_name.clone(),
+        false,
+        format!("{}{}", tbc.comment.clone(), comment),
+    )
+}
+
+fn generate_service_delete_ids_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+    pkcol: &ColumnInfo,
+) -> RustFunc {
+    let pktype = parse_data_type_as_rust_type(
+        &pkcol.data_type.clone().unwrap_or_default().to_lowercase(),
+    );
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    let mut has_su = false;
+    let call = if ctx.codegen_conf.multi_tenancy {
+        body.push(format!("let cond = {} {{", tbl_struct_name.clone()));
+        has_su = process_enity_common_fields_v2(ctx, &mut body, tbl, 2);
+        format!("{}::remove_ids(&mut tx, ids, &cond)", tbl_struct_name.clone())
+    } else {
+        format!("{}::remove_ids(&mut tx, ids)", tbl_struct_name.clone())
+    };
+    body.push(format!("let mut tx = rb.acquire_begin().await?;"));
+    body.push(format!("match {}.await {{", call));
+    body.push(format!("Ok(st) => {{"));
+    body.push(format!("tx.commit().await?;"));
+    body.push(format!("Ok(st)"));
+    body.push(format!("}}"));
+    body.push(format!("Err(err) => {{"));
+    body.push(format!("let _ = tx.rollback().await.is_ok();"));
+    body.push(format!("Err(err)"));
+    body.push(format!("}}"));
+    body.push(format!("}}"));
+
+    new_service_func(
+        "delete_ids",
+        vec![su_param(has_su), ("ids".to_string(), format!("&[{}]", pktype))],
+        body,
+        "u64".to_string(),
+        false,
+        format!("{}批量删除", tbc.comment.clone()),
+    )
+}
+
+fn generate_service_query_for_table(
+    ctx: &GenerateContext,
+    tbl: &TableInfo,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+    paged: bool,
+) -> RustFunc {
+    let mut body = vec![];
+    let mut has_su = false;
+    body.push(format!("let rb = get_rbatis();"));
+    let pager = if paged { ", current, size" } else { "" };
+    if tbc.using_common_search {
+        body.push(format!(
+            "{}::common_query_{}(rb, val.clone(){}).await",
+            tbl_struct_name.clone(),
+            if paged { "paged" } else { "list" },
+            pager
+        ));
+    } else {
+        let mut somebody = vec![];
+        let (su, has_cond) = process_common_fields(ctx, &mut somebody, tbl, 4);
+        has_su = su;
+        if has_cond {
+            body.push(format!("let mut val = val.clone();"));
+            body.append(&mut somebody);
+        }
+        body.push(format!(
+            "val.query_{}(rb{}).await",
+            if paged { "paged" } else { "list" },
+            pager
+        ));
+    }
+
+    let mut params = vec![
+        su_param(has_su),
+        ("val".to_string(), format!("&{}", get_query_param_type(tbc, tbl_struct_name))),
+    ];
+    if paged {
+        params.push(("current".to_string(), "u64".to_string()));
+        params.push(("size".to_string(), "u64".to_string()));
+    }
+
+    new_service_func(
+        if paged { "query_paged" } else { "query_list" },
+        params,
+        body,
+        if paged {
+            format!("Page<{}>", tbl_struct_name.clone())
+        } else {
+            format!("Vec<{}>", tbl_struct_name.clone())
+        },
+        false,
+        if paged {
+            format!("{}分页查询", tbc.comment.clone())
+        } else {
+            format!("{}查询", tbc.comment.clone())
+        },
+    )
+}
+
+fn generate_service_get_for_table(
+    ctx: &GenerateContext,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+    pkcols: &Vec<ColumnInfo>,
+) -> RustFunc {
+    let mut params = vec![];
+    let mut param_text = String::new();
+    for col in pkcols.clone() {
+        let dt = parse_data_type_as_rust_type(&col.data_type.unwrap_or_default());
+        let colname = col.column_name.unwrap_or_default().to_lowercase();
+        params.push((colname.clone(), format!("&{}", dt)));
+        param_text.push_str(format!(", {}", colname.as_str()).as_str());
+    }
+
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!(
+        "match {}::from_id(rb{}).await? {{",
+        tbl_struct_name.clone(),
+        param_text
+    ));
+    body.push(format!("Some(tv) => {{"));
+    let has_su = push_tenancy_check(ctx, &mut body, &tbc.name, "tv", "return Ok(None);");
+    body.push(format!("Ok(Some(tv))"));
+    body.push(format!("}}"));
+    body.push(format!("None => Ok(None),"));
+    body.push(format!("}}"));
+
+    params.insert(0, su_param(has_su));
+    new_service_func(
+        "get",
+        params,
+        body,
+        tbl_struct_name.clone(),
+        true,
+        format!("{}获取", tbc.comment.clone()),
+    )
+}
+
+fn generate_service_tree_for_table(
+    ctx: &GenerateContext,
+    tbc: &TableConfig,
+    tbl_struct_name: &String,
+) -> Option<RustFunc> {
+    let treecol = ctx.find_table_column(&tbc.name, &tbc.tree_parent_field.clone().unwrap_or_default())?;
+    let treecol_type = parse_data_type_as_rust_type(&treecol.data_type.unwrap_or_default());
+
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    let tenancy = get_tenancy_field(ctx, &tbc.name);
+    match tenancy.clone() {
+        Some(field) => {
+            // 只返回当前用户所在公司的节点
+            body.push(format!("let rows = {}::query_tree(rb, pid).await?;", tbl_struct_name.clone()));
+            body.push(format!(
+                "Ok(rows.into_iter().filter(|tv| tv.{} == su.user.{}).collect())",
+                field, field
+            ));
+        }
+        None => body.push(format!("{}::query_tree(rb, pid).await", tbl_struct_name.clone())),
+    }
+
+    Some(new_service_func(
+        "tree",
+        vec![su_param(tenancy.is_some()), ("pid".to_string(), format!("&Option<{}>", treecol_type))],
+        body,
+        format!("Vec<{}>", tbl_struct_name.clone()),
+        false,
+        format!("{}树形查询", tbc.comment.clone()),
+    ))
+}
+
+/**
+ * route为(函数名的后缀, URL的后缀, HTTP方法)
+ */
+fn new_service_handler(
+    ctx: &GenerateContext,
+    api_handler_name: &str,
+    route: (&str, &str, &str),
+    params: Vec<(String, String)>,
+    body: Vec<String>,
+    comment: String,
+) -> RustFunc {
+    let (func_suffix, url_suffix, method) = route;
+    let url_pattern = format!(
+        "{}/{}/{}",
+        ctx.codegen_conf.api_handler_prefix.clone(),
+        api_handler_name,
+        url_suffix
+    );
+    let postmacro = format!("#[{}(\"{}\")]", method.to_lowercase(), url_pattern.clone());
+    RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: true,
+        is_async: true,
+        func_name: format!("{}_{}", api_handler_name, func_suffix),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: Some("Result<HttpResponse>".to_string()),
+        params: params,
+        bodylines: body,
+        macros: vec![postmacro],
+        comment: Some(comment),
+        api_method: Some(method.to_string()),
+        api_pattern: Some(url_pattern.clone()),
+    }
+}
+
+/**
+ * 调用Service并输出其结果
+ * map_lines用于将Service的结果st转换为输出的值（变量名为st）
+ */
+fn push_service_call_lines(body: &mut Vec<String>, call: &str, ret_type: &str, map_lines: Vec<String>) {
+    body.push(format!("match {}.await {{", call));
+    body.push(format!("Ok(st) => {{"));
+    for ln in map_lines {
+        body.push(ln);
+    }
+    body.push(format!(
+        "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::ok(st));",
+        ret_type
+    ));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("Err(err) => {{"));
+    body.push(format!(
+        "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::error(5010, &err.to_string()));",
+        ret_type
+    ));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("}}"));
+}
+
+fn push_service_option_lines(body: &mut Vec<String>, call: &str, ret_type: &str) {
+    body.push(format!("match {}.await {{", call));
+    body.push(format!("Ok(Some(st)) => {{"));
+    body.push(format!(
+        "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::ok(st));",
+        ret_type
+    ));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("Ok(None) => {{"));
+    body.push(format!("let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::error(5040, &\"Not-Found\".to_string()));", ret_type));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("Err(err) => {{"));
+    body.push(format!(
+        "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::error(5010, &err.to_string()));",
+        ret_type
+    ));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("}}"));
+}
+
+fn push_service_success_lines(body: &mut Vec<String>, call: &str) {
+    body.push(format!("match {}.await {{", call));
+    body.push(format!("Ok(_) => {{"));
+    body.push(format!("let ret: web::Json<ApiResult<String>> = web::Json(ApiResult::ok(\"SUCCESS\".to_string()));"));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("Err(err) => {{"));
+    body.push(format!("let ret: web::Json<ApiResult<String>> = web::Json(ApiResult::error(5010, &err.to_string()));"));
+    body.push(format!("Ok(HttpResponse::Ok().json(ret))"));
+    body.push(format!("}}"));
+    body.push(format!("}}"));
+}
+
+fn value_map_lines(tbl_struct_name: &String, records: bool) -> Vec<String> {
+    let mut lines = vec![];
+    if records {
+        lines.push(format!("let mtts:Vec<{}Value> = st.records.into_iter().map(|f| {}Value::from_entity_with(&f, true, &vec![])).collect();", tbl_struct_name.clone(), tbl_struct_name.clone()));
+        lines.push(format!(
+            "let mut st = Page::new_total(st.page_no, st.page_size, st.total);"
+        ));
+        lines.push(format!("st.records = mtts;"));
+    } else {
+        lines.push(format!("let st:Vec<{}Value> = st.into_iter().map(|f| {}Value::from_entity_with(&f, true, &vec![])).collect();", tbl_struct_name.clone(), tbl_struct_name.clone()));
+    }
+    lines
+}
+
+/**
+ * 调用Service的handler只负责参数的转换，数据库的连接和分页由Service完成
+ * 加入ServiceUser，去掉没有用到的get_rbatis和Page
+ */
+pub fn retain_service_handler_usings(usinglist: &mut Vec<String>, funclist: &[RustFunc]) {
+    let uses = |pat: &str| {
+        funclist.iter().any(|f| {
+            f.bodylines.iter().any(|ln| ln.contains(pat)) || f.params.iter().any(|p| p.1.contains(pat))
+        })
+    };
+    let uses_rbatis = uses("get_rbatis()");
+    let uses_page = uses("Page<") || uses("Page::");
+    usinglist.retain(|us| {
+        (us != "chimes_utils::get_rbatis" || uses_rbatis) && (us != "rbatis::Page" || uses_page)
+    });
+    usinglist.push("crate::utils::ServiceUser".to_string());
+}
+
+/**
+ * 生成调用Service的handler
+ * 与generate_actix_handler_for_table生成的handler具有相同的名称、URL和参数
+ */
+pub fn generate_service_handler_for_table(
+    ctx: &mut GenerateContext,
+    tbl: &TableInfo,
+    usinglist: &mut Vec<String>,
+    comps: &Option<RustStruct>,
+) -> Vec<RustFunc> {
+    let mut funclist = vec![];
+    let tbl_name = tbl.table_name.clone().unwrap_or_default();
+    let tbl_struct_name = match ctx.get_struct_name(&tbl_name.clone()) {
+        Some(t) => t,
+        None => pascal_case(tbl_name.clone().as_str()),
+    };
+    let service_name = get_service_name(&tbl_struct_name);
+    let tbc = ctx.get_table_conf(&tbl_name.clone()).unwrap();
+    let hn = tbc.api_handler_name.clone();
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+    let su = ("su".to_string(), "SystemUser<ChimesUserInfo>".to_string());
+    let json_req = ("req".to_string(), format!("web::Json<{}>", tbl_struct_name.clone()));
+
+    let mut actions = vec![
+        ("save", "create", "create", "保存"),
+        ("update", "update", "update", "更新"),
+        ("delete", "delete", "delete", "删除"),
+    ];
+    if get_logic_deletion(ctx, &tbl_name).is_some() {
+        actions.push(("restore", "restore", "restore", "恢复"));
+        actions.push(("purge", "purge", "purge", "彻底删除"));
+    }
+    for (func_suffix, url_suffix, method, comment) in actions {
+        let mut body = vec![];
+        body.push(format!("let val = req.to_owned();"));
+        push_service_call_lines(
+            &mut body,
+            &format!("{}::{}(&ServiceUser::from(&su.user), &val)", service_name.clone(), method),
+            &tbl_struct_name,
+            vec![],
+        );
+        if method == "update" && get_version_field(ctx, &tbl_name).is_some() {
+            // 版本冲突时返回5409
+            let errline = update_error_line(&tbl_struct_name, 5010, false);
+            for ln in body.iter_mut().filter(|l| **l == errline) {
+                *ln = update_error_line(&tbl_struct_name, 5010, true);
+            }
+        }
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            (func_suffix, url_suffix, "POST"),
+            vec![su.clone(), json_req.clone()],
+            body,
+            format!("{}{}", tbc.comment.clone(), comment),
+        ));
+    }
+
+    if pkcols.len() == 1 {
+        let pktype = parse_data_type_as_rust_type(
+            &pkcols[0].data_type.clone().unwrap_or_default().to_lowercase(),
+        );
+        let mut body = vec![];
+        push_service_call_lines(
+            &mut body,
+            &format!("{}::delete_ids(&ServiceUser::from(&su.user), req.as_slice())", service_name.clone()),
+            "u64",
+            vec![],
+        );
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            ("delete_ids", "delete_ids", "POST"),
+            vec![su.clone(), ("req".to_string(), format!("web::Json<Vec<{}>>", pktype))],
+            body,
+            format!("{}批量删除", tbc.comment.clone()),
+        ));
+    }
+
+    let query_req = (
+        "req".to_string(),
+        format!("web::Json<{}>", get_query_param_type(&tbc, &tbl_struct_name)),
+    );
+    let is_tree = tbc.tree_parent_field.is_some();
+    let list_type = if is_tree {
+        format!("Vec<{}Value>", tbl_struct_name.clone())
+    } else {
+        format!("Vec<{}>", tbl_struct_name.clone())
+    };
+    let mut body = vec![];
+    body.push(format!("let val = req.to_owned();"));
+    push_service_call_lines(
+        &mut body,
+        &format!("{}::query_list(&ServiceUser::from(&su.user), &val)", service_name.clone()),
+        &list_type,
+        if is_tree {
+            value_map_lines(&tbl_struct_name, false)
+        } else {
+            vec![]
+        },
+    );
+    funclist.push(new_service_handler(
+        ctx,
+        &hn,
+        ("search", "search", "POST"),
+        vec![su.clone(), query_req.clone()],
+        body,
+        format!("{}查询", tbc.comment.clone()),
+    ));
+
+    if tbc.page_query {
+        let page_type = if is_tree {
+            format!("Page<{}Value>", tbl_struct_name.clone())
+        } else {
+            format!("Page<{}>", tbl_struct_name.clone())
+        };
+        let mut body = vec![];
+        body.push(format!("let val = req.to_owned();"));
+        body.push(format!("let (current, size) = path_param.into_inner();"));
+        push_service_call_lines(
+            &mut body,
+            &format!("{}::query_paged(&ServiceUser::from(&su.user), &val, current, size)", service_name.clone()),
+            &page_type,
+            if is_tree {
+                value_map_lines(&tbl_struct_name, true)
+            } else {
+                vec![]
+            },
+        );
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            ("paged", "paged/{current}/{size}", "POST"),
+            vec![
+                su.clone(),
+                query_req.clone(),
+                ("path_param".to_string(), "web::Path<(u64, u64)>".to_string()),
+            ],
+            body,
+            format!("{}分页查询", tbc.comment.clone()),
+        ));
+        usinglist.push(format!("rbatis::Page"));
+    }
+
+    let mut params = vec![su.clone()];
+    let mut param_text = String::new();
+    let mut body = vec![];
+    for col in pkcols.clone() {
+        let dt = parse_data_type_as_rust_type(&col.data_type.unwrap_or_default());
+        let colname = col.column_name.unwrap_or_default().to_lowercase();
+        params.push((format!("{}_req", colname.clone()), format!("web::Path<{}>", dt)));
+        body.push(format!("let {} = {}_req.to_owned();", colname.clone(), colname.clone()));
+        param_text.push_str(format!(", &{}", colname.as_str()).as_str());
+    }
+    push_service_option_lines(
+        &mut body,
+        &format!("{}::get(&ServiceUser::from(&su.user){})", service_name.clone(), param_text),
+        &tbl_struct_name,
+    );
+    funclist.push(new_service_handler(
+        ctx,
+        &hn,
+        ("get", "get/{id}", "GET"),
+        params,
+        body,
+        format!("{}获取", tbc.comment.clone()),
+    ));
+
+    if let Some(comp) = comps.clone() {
+        funclist.push(generate_handler_query_list_for_composite(ctx, tbl, &Some(comp.clone())));
+        if tbc.page_query {
+            funclist.push(generate_handler_query_page_for_composite(ctx, tbl, &Some(comp)));
+        }
+        usinglist.push(format!("crate::entity::{}Present", tbl_struct_name.clone()));
+    }
+
+    if is_tree {
+        if let Some(treecol) = ctx.find_table_column(&tbl_name, &tbc.tree_parent_field.clone().unwrap_or_default()) {
+            let treecol_type = parse_data_type_as_rust_type(&treecol.data_type.unwrap_or_default());
+            let mut body = vec![];
+            body.push(format!("let query = req.query_string();"));
+            body.push(format!("let dic = crate::utils::parse_query(query);"));
+            body.push(format!("let val = crate::utils::get_hash_value(&dic, \"pid\");"));
+            if treecol_type == "String" {
+                body.push(format!("let valopt = if val.is_empty() {{"));
+                body.push(format!("None"));
+                body.push(format!("}} else {{"));
+                body.push(format!("Some(val)"));
+                body.push(format!("}};"));
+            } else {
+                body.push(format!("let valopt = match val.parse::<{}>() {{", treecol_type));
+                body.push(format!("Ok(tv) => Some(tv),"));
+                body.push(format!("Err(_) => None"));
+                body.push(format!("}};"));
+            }
+            push_service_call_lines(
+                &mut body,
+                &format!("{}::tree(&ServiceUser::from(&su.user), &valopt)", service_name.clone()),
+                &format!("Vec<{}Value>", tbl_struct_name.clone()),
+                value_map_lines(&tbl_struct_name, false),
+            );
+            funclist.push(new_service_handler(
+                ctx,
+                &hn,
+                ("tree", "tree", "GET"),
+                vec![su.clone(), ("req".to_string(), "HttpRequest".to_string())],
+                body,
+                format!("{}树形查询", tbc.comment.clone()),
+            ));
+            usinglist.push(format!("actix_web::HttpRequest"));
+        }
+        usinglist.push(format!(
+            "crate::entity::{{{}, {}Value}}",
+            tbl_struct_name.clone(),
+            tbl_struct_name.clone()
+        ));
+    } else {
+        usinglist.push(format!("crate::entity::{}", tbl_struct_name.clone()));
+    }
+    if tbc.generate_param_struct && !tbc.using_common_search {
+        usinglist.push(format!("crate::entity::{}Query", tbl_struct_name.clone()));
+    }
+    usinglist.push(format!("crate::service::{}", service_name.clone()));
+
+    ctx.add_permission(tbl, &funclist);
+
+    funclist
+}
+
+/**
+ * 生成关系对应的Service
+ */
+pub fn generate_service_for_relation(ctx: &GenerateContext, rel: &RelationConfig) -> Option<RustFileImpl> {
+    let tbl_name = rel.major_table.clone();
+    let tbc = ctx.get_table_conf(&tbl_name)?;
+    let table_info = ctx.get_table_info(&tbl_name)?;
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+    let rel_name = rel.struct_name.clone();
+
+    let mut pk_params = vec![];
+    let mut pk_text = String::new();
+    for col in pkcols.clone() {
+        let dt = parse_data_type_as_rust_type(&col.data_type.unwrap_or_default());
+        let colname = col.column_name.unwrap_or_default().to_lowercase();
+        pk_params.push((colname.clone(), format!("&{}", dt)));
+        pk_text.push_str(format!(", {}", colname).as_str());
+    }
+
+    let mut funclist = vec![];
+
+    // load
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!("match {}::load(rb{}).await? {{", rel_name.clone(), pk_text.clone()));
+    body.push(format!("Some(tv) => {{"));
+    let has_su = push_tenancy_check(ctx, &mut body, &tbl_name, "tv", "return Ok(None);");
+    body.push(format!("Ok(Some(tv))"));
+    body.push(format!("}}"));
+    body.push(format!("None => Ok(None),"));
+    body.push(format!("}}"));
+    let mut params = pk_params.clone();
+    if ctx.codegen_conf.multi_tenancy {
+        params.insert(0, su_param(has_su));
+    }
+    funclist.push(new_service_func(
+        "load",
+        params,
+        body,
+        rel_name.clone(),
+        true,
+        format!("{}加载", rel.comment.clone()),
+    ));
+
+    // remove
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!("match {}::load(rb{}).await? {{", rel_name.clone(), pk_text.clone()));
+    body.push(format!("Some(cst) => {{"));
+    let has_su = push_tenancy_check(ctx, &mut body, &tbl_name, "cst", "return Ok(None);");
+    push_transaction_lines(&mut body, "cst.remove(&mut tx)", "Some(cst)");
+    body.push(format!("}}"));
+    body.push(format!("None => Ok(None),"));
+    body.push(format!("}}"));
+    let mut params = pk_params.clone();
+    params.insert(0, su_param(has_su));
+    funclist.push(new_service_func(
+        "remove",
+        params,
+        body,
+        rel_name.clone(),
+        true,
+        format!("{}删除", rel.comment.clone()),
+    ));
+
+    // remove_ids
+    if let Some(pkcol) = pkcols.first() {
+        let pktype = parse_data_type_as_rust_type(
+            &pkcol.data_type.clone().unwrap_or_default().to_lowercase(),
+        );
+        let mut body = vec![];
+        body.push(format!("let rb = get_rbatis();"));
+        body.push(format!("let cond = {} {{", rel_name.clone()));
+        let has_su = process_detail_common_fields_v2(ctx, "cond", &mut body, &table_info, 0);
+        body.push(format!("..Default::default()"));
+        body.push(format!("}};"));
+        push_transaction_lines(
+            &mut body,
+            &format!("{}::remove_rel_ids(&mut tx, ids, &cond)", rel_name.clone()),
+            "()",
+        );
+        funclist.push(new_service_func(
+            "remove_ids",
+            vec![su_param(has_su), ("ids".to_string(), format!("&[{}]", pktype))],
+            body,
+            "()".to_string(),
+            false,
+            format!("{}批量删除", rel.comment.clone()),
+        ));
+    }
+
+    // save
+    let mut body = vec![];
+    body.push(format!("let rb = get_rbatis();"));
+    body.push(format!("let mut val = val.clone();"));
+    body.push(format!("val.refine(su);"));
+    push_tenancy_check(
+        ctx,
+        &mut body,
+        &tbl_name,
+        "val",
+        "return Err(Error::from(\"非法处理\"));",
+    );
+    push_transaction_lines(&mut body, "val.save(&mut tx)", "val");
+    funclist.push(new_service_func(
+        "save",
+        vec![su_param(true), ("val".to_string(), format!("&{}", rel_name.clone()))],
+        body,
+        rel_name.clone(),
+        false,
+        format!("{}保存", rel.comment.clone()),
+    ));
+
+    let st = RustStruct {
+        is_pub: true,
+        has_paging: tbc.page_query,
+        struct_name: get_service_name(&rel_name),
+        annotations: vec!["#[derive(Debug, Clone, Default)]".to_string()],
+        fields: vec![],
+        funclist: funclist,
+        usings: vec![],
+    };
+
+    Some(RustFileImpl {
+        file_name: format!("{}.rs", snake_case(rel_name.as_str())),
+        mod_name: "service".to_string(),
+        caretlist: vec![],
+        usinglist: vec![
+            "crate::utils::ServiceUser".to_string(),
+            "chimes_utils::get_rbatis".to_string(),
+            "rbatis::error::Error".to_string(),
+            format!("crate::entity::{}", rel_name.clone()),
+        ],
+        structlist: vec![st],
+        funclist: vec![],
+        implist: vec![],
+        artifact: Some(RustArtifact::Service(rel_name.clone())),
+    })
+}
+
+/**
+ * 生成调用关系Service的handler
+ */
+pub fn generate_service_handler_for_relation(ctx: &GenerateContext, rel: &RelationConfig) -> Vec<RustFunc> {
+    let tbl_name = rel.major_table.clone();
+    let pkcols = get_primary_key_columns(ctx, &tbl_name);
+    let rel_name = rel.struct_name.clone();
+    let st = parse_relation_as_struct(ctx, rel);
+    let service_name = get_service_name(&st.struct_name);
+    let hn = rel.api_handler_name.clone().unwrap_or_default();
+    let su = ("su".to_string(), "SystemUser<ChimesUserInfo>".to_string());
+
+    let mut pk_params = vec![];
+    let mut pk_text = String::new();
+    let mut macrotext = String::new();
+    for col in pkcols.clone() {
+        let dt = parse_data_type_as_rust_type(&col.data_type.unwrap_or_default());
+        let colname = col.column_name.unwrap_or_default().to_lowercase();
+        pk_params.push((colname.clone(), format!("web::Path<{}>", dt)));
+        pk_text.push_str(format!(", &{}", colname).as_str());
+        macrotext.push_str(format!("/{{{}}}", colname).as_str());
+    }
+
+    let mut funclist = vec![];
+    if rel.generate_select {
+        let mut params = pk_params.clone();
+        let call = if ctx.codegen_conf.multi_tenancy {
+            params.insert(0, su.clone());
+            format!("{}::load(&ServiceUser::from(&su.user){})", service_name.clone(), pk_text.clone())
+        } else {
+            format!("{}::load({})", service_name.clone(), pk_text.trim_start_matches(", "))
+        };
+        let mut body = vec![];
+        push_service_call_lines(&mut body, &call, &format!("Option<{}>", rel_name.clone()), vec![]);
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            ("rel_load", &format!("load{}", macrotext.clone()), "GET"),
+            params,
+            body,
+            format!("{}加载", rel.comment.clone()),
+        ));
+    }
+
+    if rel.generate_delete {
+        let mut params = pk_params.clone();
+        params.insert(0, su.clone());
+        let mut body = vec![];
+        push_service_option_lines(
+            &mut body,
+            &format!("{}::remove(&ServiceUser::from(&su.user){})", service_name.clone(), pk_text.clone()),
+            &rel_name,
+        );
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            ("rel_remove", &format!("remove{}", macrotext.clone()), "POST"),
+            params,
+            body,
+            format!("{}删除", rel.comment.clone()),
+        ));
+
+        if let Some(pkcol) = pkcols.first() {
+            let pktype = parse_data_type_as_rust_type(
+                &pkcol.data_type.clone().unwrap_or_default().to_lowercase(),
+            );
+            let mut body = vec![];
+            push_service_success_lines(
+                &mut body,
+                &format!("{}::remove_ids(&ServiceUser::from(&su.user), req.as_slice())", service_name.clone()),
+            );
+            funclist.push(new_service_handler(
+                ctx,
+                &hn,
+                ("rel_remove_multi", "multi/remove", "POST"),
+                vec![su.clone(), ("req".to_string(), format!("web::Json<Vec<{}>>", pktype))],
+                body,
+                format!("{}删除", rel.comment.clone()),
+            ));
+        }
+    }
+
+    if rel.generate_save {
+        let mut body = vec![];
+        body.push(format!("let val = req.to_owned();"));
+        push_service_success_lines(&mut body, &format!("{}::save(&ServiceUser::from(&su.user), &val)", service_name.clone()));
+        if get_version_field(ctx, &rel.major_table).is_some() {
+            // 主表版本冲突时返回5409
+            let errline = update_error_line("String", 5010, false);
+            for ln in body.iter_mut().filter(|l| **l == errline) {
+                *ln = update_error_line("String", 5010, true);
+            }
+        }
+        funclist.push(new_service_handler(
+            ctx,
+            &hn,
+            ("rel_save", "rel/save", "POST"),
+            vec![su.clone(), ("req".to_string(), format!("web::Json<{}>", rel_name.clone()))],
+            body,
+            format!("{}保存", rel.comment.clone()),
+        ));
+    }
+    funclist
+}
@@ -0,0 +1,642 @@
+use regex::Regex;
+
+use crate::codegen::{
+    find_wrapper_call, get_version_field, is_common_search_func, parse_skip_columns,
+    parse_wrapper_conditions, GenerateContext, RustFileImpl, RustFunc, RustStruct,
+    WrapperCondition, WrapperKind,
+};
+
+/**
+ * rbatis 3.x的类型与sqlx（chrono、rust_decimal、serde_json）中的类型的对应
+ * DateTimeNative::now()需要在类型之前替换
+ */
+const SQLX_TYPES: [(&str, &str); 12] = [
+    (
+        "rbatis::DateTimeNative::now()",
+        "chrono::Local::now().naive_local()",
+    ),
+    ("rbatis::DateTimeNative", "chrono::NaiveDateTime"),
+    ("rbatis::DateTimeUtc", "chrono::DateTime<chrono::Utc>"),
+    ("rbatis::DateNative", "chrono::NaiveDate"),
+    ("rbatis::DateUtc", "chrono::NaiveDate"),
+    ("rbatis::TimeNative", "chrono::NaiveTime"),
+    ("rbatis::TimeUtc", "chrono::NaiveTime"),
+    ("rbatis::TimestampZ", "chrono::DateTime<chrono::Utc>"),
+    ("rbatis::Timestamp", "chrono::DateTime<chrono::Utc>"),
+    ("rbatis::Decimal", "rust_decimal::Decimal"),
+    ("rbatis::Bytes", "Vec<u8>"),
+    ("rbatis::Json", "serde_json::Value"),
+];
+
+/**
+ * 将代码中rbatis 3.x的类型替换为sqlx所支持的类型
+ */
+pub fn to_sqlx_type(text: &str) -> String {
+    let mut ret = text.to_string();
+    for (from, to) in SQLX_TYPES.iter() {
+        ret = ret.replace(from, to);
+    }
+    ret
+}
+
+/**
+ * sqlx的生成
+ * 代码先按rbatis 3.1的方式生成，再转换为sqlx的形式：
+ * 1、#[crud_table]替换为#[derive(sqlx::FromRow)]
+ * 2、只有固定条件的查询（如from_id、query_all）使用sqlx::query_as，带有r#if条件的查询、更新及删除使用QueryBuilder拼接
+ * 3、分页先按相同的条件查询总数，再使用LIMIT查询当前页，Page由utils提供
+ * 4、&Rbatis替换为&MySqlPool，&mut RBatisTxExecutor<'_>替换为&mut Transaction<'_, MySql>，acquire_begin替换为begin
+ * 5、rb.fetch/rb.fetch_page的原生SQL使用sqlx::query_as，参数统一按字符串绑定
+ * 6、CommonSearch（chimes-utils）依赖于rbatis 3.x的wrapper，相关的方法及handler不再生成
+ */
+pub fn convert_file_to_sqlx(ctx: &GenerateContext, fl: &mut RustFileImpl) {
+    for st in fl.structlist.iter_mut() {
+        convert_struct_to_sqlx(ctx, st);
+    }
+
+    fl.funclist.retain(|func| {
+        let unsupported = is_common_search_func(func);
+        if unsupported {
+            log::warn!(
+                "{}::{} uses CommonSearch which is not supported by sqlx, it was not generated.",
+                fl.mod_name,
+                func.func_name
+            );
+        }
+        !unsupported
+    });
+    for func in fl.funclist.iter_mut() {
+        convert_func_lines_to_sqlx(func);
+    }
+    for imp in fl.implist.iter_mut() {
+        for func in imp.funclist.iter_mut() {
+            convert_func_lines_to_sqlx(func);
+        }
+    }
+
+    let mut usings = vec![];
+    for us in fl.usinglist.iter() {
+        if let Some(t) = to_sqlx_using(us) {
+            usings.push(t);
+        }
+    }
+    let text = fl.to_source();
+    for (code, us) in [
+        ("QueryBuilder::<MySql>", "sqlx::QueryBuilder"),
+        ("MySql>", "sqlx::MySql"),
+        ("&MySqlPool", "sqlx::MySqlPool"),
+        ("Transaction<'_, MySql>", "sqlx::Transaction"),
+        ("get_pool()", "crate::utils::get_pool"),
+        ("to_sql_arg(", "crate::utils::to_sql_arg"),
+    ] {
+        if text.contains(code) {
+            usings.push(us.to_string());
+        }
+    }
+    if text.contains("Page<") || text.contains("Page::new_total(") {
+        usings.push("crate::utils::Page".to_string());
+    }
+    usings.sort();
+    usings.dedup();
+    fl.usinglist = usings;
+}
+
+fn to_sqlx_using(us: &String) -> Option<String> {
+    match us.as_str() {
+        "rbatis::crud_table"
+        | "rbatis::crud::{CRUD, CRUDMut, Skip}"
+        | "rbatis::crud::CRUD"
+        | "rbson::Bson"
+        | "rbatis::rbatis::Rbatis"
+        | "rbatis::executor::{RbatisRef, RBatisTxExecutor}"
+        | "rbatis::executor::RBatisTxExecutor"
+        | "rbatis::executor::ExecutorMut"
+        | "rbatis::Page"
+        | "rbatis::PageRequest"
+        | "chimes_utils::get_rbatis"
+        | "chimes_utils::CommonSearch" => None,
+        "rbatis::error::Error" => Some("sqlx::Error".to_string()),
+        _ => Some(to_sqlx_type(us)),
+    }
+}
+
+/**
+ * 转换结构体
+ * 带有#[crud_table]的实体、查询结果，以及通过sqlx::query_as查询自身的结构体（如Present）派生sqlx::FromRow，
+ * 并将其中使用wrapper的方法转换为sqlx的查询
+ */
+fn convert_struct_to_sqlx(ctx: &GenerateContext, st: &mut RustStruct) {
+    for fd in st.fields.iter_mut() {
+        fd.field_type = to_sqlx_type(&fd.field_type);
+    }
+    st.usings = st.usings.iter().filter_map(to_sqlx_using).collect();
+
+    let mut funclist = vec![];
+    for func in st.funclist.clone() {
+        if is_common_search_func(&func) {
+            log::warn!(
+                "{}::{} uses CommonSearch which is not supported by sqlx, it was not generated.",
+                st.struct_name,
+                func.func_name
+            );
+            continue;
+        }
+        match convert_struct_func_to_sqlx(ctx, st, &func) {
+            Ok(nfunc) => funclist.push(nfunc),
+            Err(line) => {
+                log::warn!(
+                    "{}::{} could not be converted to sqlx ({}), it was not generated.",
+                    st.struct_name,
+                    func.func_name,
+                    line
+                );
+            }
+        }
+    }
+    st.funclist = funclist;
+
+    let from_row = st
+        .annotations
+        .iter()
+        .any(|an| an.starts_with("#[crud_table("))
+        || st
+            .funclist
+            .iter()
+            .any(|f| f.bodylines.iter().any(|l| l.contains("sqlx::query_as(")));
+    if !from_row {
+        return;
+    }
+
+    // FromRow按字段名匹配列，列名不同时需要rename
+    // 非表中的列（如include_deleted）以及与其它字段对应同一列的辅助字段（如日期范围的*_end）跳过
+    let mapped = st
+        .fields
+        .iter()
+        .filter(|fd| !fd.column_name.is_empty() && fd.column_name == fd.field_name)
+        .map(|fd| fd.column_name.clone())
+        .collect::<Vec<String>>();
+    for fd in st.fields.iter_mut() {
+        if fd.column_name.is_empty()
+            || (fd.column_name != fd.field_name && mapped.contains(&fd.column_name))
+        {
+            fd.annotations.push("#[sqlx(skip)]".to_string());
+        } else if fd.column_name != fd.field_name {
+            fd.annotations
+                .push(format!("#[sqlx(rename = \"{}\")]", fd.column_name));
+        }
+    }
+
+    let mut annotations = vec![];
+    for an in st.annotations.iter() {
+        if an.starts_with("#[crud_table(") {
+            continue;
+        }
+        match an
+            .strip_prefix("#[derive(")
+            .and_then(|a| a.strip_suffix(")]"))
+        {
+            Some(derives) => annotations.push(format!("#[derive({}, sqlx::FromRow)]", derives)),
+            None => annotations.push(an.clone()),
+        }
+    }
+    st.annotations = annotations;
+}
+
+/**
+ * 转换结构体的方法，不能转换时返回无法识别的代码行
+ */
+fn convert_struct_func_to_sqlx(
+    ctx: &GenerateContext,
+    st: &RustStruct,
+    func: &RustFunc,
+) -> Result<RustFunc, String> {
+    let mut nfunc = func.clone();
+    convert_func_lines_to_sqlx(&mut nfunc);
+    let exec = executor_of(&nfunc);
+
+    if let Some(saveline) = func.bodylines.iter().find(|l| l.contains("rb.save(self,")) {
+        let table = ctx
+            .get_table_conf_by_struct_name(&st.struct_name)
+            .map(|t| t.name)
+            .ok_or_else(|| format!("no table for {}", st.struct_name))?;
+        nfunc.bodylines =
+            convert_save_lines(st, func, &table, &parse_skip_columns(saveline), &exec);
+        return Ok(nfunc);
+    }
+
+    let conds = match parse_wrapper_conditions(func) {
+        Some(c) => c?,
+        None => return Ok(nfunc),
+    };
+    let call = find_wrapper_call(func)
+        .ok_or_else(|| func.bodylines.last().cloned().unwrap_or_default())?;
+    let entity = if call.entity == "Self" || call.entity == st.struct_name {
+        "Self".to_string()
+    } else {
+        call.entity.clone()
+    };
+    let table = ctx
+        .get_table_conf_by_struct_name(if entity == "Self" {
+            &st.struct_name
+        } else {
+            &entity
+        })
+        .map(|t| t.name)
+        .ok_or_else(|| format!("no table for {}", entity))?;
+
+    let fixed = conds
+        .iter()
+        .all(|c| c.test.is_none() && c.op == "eq" && !c.args[0].trim().starts_with('\''));
+    if fixed && (call.kind == WrapperKind::SelectOne || call.kind == WrapperKind::SelectList) {
+        // 只有固定的等值条件，使用sqlx::query_as
+        let mut sql = format!("SELECT * FROM {}", table);
+        if !conds.is_empty() {
+            let wheres = conds
+                .iter()
+                .map(|c| format!("{} = ?", c.column))
+                .collect::<Vec<String>>();
+            sql.push_str(&format!(" WHERE {}", wheres.join(" AND ")));
+        }
+        let binds = conds
+            .iter()
+            .map(|c| format!(".bind({})", c.args[0]))
+            .collect::<Vec<String>>()
+            .join("");
+        let fetch = if call.kind == WrapperKind::SelectOne {
+            sql.push_str(" LIMIT 1");
+            "fetch_optional"
+        } else {
+            "fetch_all"
+        };
+        nfunc.bodylines = vec![format!(
+            "sqlx::query_as::<_, {}>(\"{}\"){}.{}({}).await",
+            entity, sql, binds, fetch, exec
+        )];
+        return Ok(nfunc);
+    }
+
+    let mut condlines = vec![];
+    for cond in conds.iter() {
+        condlines
+            .append(&mut translate_wrapper_condition(cond).ok_or_else(|| format!("{:?}", cond))?);
+    }
+
+    let mut body = vec![];
+    match call.kind.clone() {
+        WrapperKind::SelectOne | WrapperKind::SelectList => {
+            body.push(format!(
+                "let mut qb = QueryBuilder::<MySql>::new(\"SELECT * FROM {} WHERE 1 = 1\");",
+                table
+            ));
+            body.append(&mut condlines);
+            if call.kind == WrapperKind::SelectOne {
+                body.push("qb.push(\" LIMIT 1\");".to_string());
+                body.push(format!(
+                    "qb.build_query_as::<{}>().fetch_optional({}).await",
+                    entity, exec
+                ));
+            } else {
+                body.push(format!(
+                    "qb.build_query_as::<{}>().fetch_all({}).await",
+                    entity, exec
+                ));
+            }
+        }
+        WrapperKind::Page => {
+            let (curr, ps) = (call.page[0].clone(), call.page[1].clone());
+            body.push(format!(
+                "let mut count_qb = QueryBuilder::<MySql>::new(\"SELECT COUNT(1) FROM {} WHERE 1 = 1\");",
+                table
+            ));
+            body.push(format!(
+                "let mut list_qb = QueryBuilder::<MySql>::new(\"SELECT * FROM {} WHERE 1 = 1\");",
+                table
+            ));
+            // 总数与当前页使用相同的条件
+            body.push("for qb in [&mut count_qb, &mut list_qb] {".to_string());
+            body.append(&mut condlines);
+            body.push("}".to_string());
+            body.push(format!(
+                "let total: i64 = count_qb.build_query_scalar().fetch_one({}).await?;",
+                exec
+            ));
+            body.push(format!(
+                "let mut page = Page::new_total({}, {}, total as u64);",
+                curr, ps
+            ));
+            body.push(format!(
+                "list_qb.push(\" LIMIT \").push_bind((({}.max(1) - 1) * {}) as i64).push(\", \").push_bind({} as i64);",
+                curr, ps, ps
+            ));
+            body.push(format!(
+                "page.records = list_qb.build_query_as::<{}>().fetch_all({}).await?;",
+                entity, exec
+            ));
+            body.push("Ok(page)".to_string());
+        }
+        WrapperKind::Delete => {
+            body.push(format!(
+                "let mut qb = QueryBuilder::<MySql>::new(\"DELETE FROM {} WHERE 1 = 1\");",
+                table
+            ));
+            body.append(&mut condlines);
+            body.push(format!(
+                "qb.build().execute({}).await.map(|r| r.rows_affected())",
+                exec
+            ));
+        }
+        WrapperKind::UpdateSet(sets) => {
+            body.push(format!(
+                "let mut qb = QueryBuilder::<MySql>::new(\"UPDATE {} SET {} WHERE 1 = 1\");",
+                table, sets
+            ));
+            body.append(&mut condlines);
+            body.push(format!(
+                "qb.build().execute({}).await.map(|r| r.rows_affected())",
+                exec
+            ));
+        }
+        WrapperKind::Update(skips, selective) => {
+            let version = get_version_field(ctx, &table);
+            body.push(format!(
+                "let mut qb = QueryBuilder::<MySql>::new(\"UPDATE {} SET \");",
+                table
+            ));
+            body.push("let mut sep = qb.separated(\", \");".to_string());
+            for fd in st.fields.iter().filter(|f| !f.column_name.is_empty()) {
+                if skips.contains(&fd.column_name) || skips.contains(&fd.field_name) {
+                    continue;
+                }
+                if let Some(vf) = version.as_ref().filter(|v| v.column == fd.column_name) {
+                    // 乐观锁的版本在SQL中加1，条件中仍为原来的值
+                    body.push(format!("sep.push(\"{}\");", vf.increment_sql()));
+                    continue;
+                }
+                let set = format!(
+                    "sep.push(\"{} = \").push_bind_unseparated(self.{}.clone());",
+                    fd.column_name, fd.field_name
+                );
+                if selective && fd.is_option {
+                    body.push(format!("if self.{}.is_some() {{", fd.field_name));
+                    body.push(set);
+                    body.push("}".to_string());
+                } else {
+                    body.push(set);
+                }
+            }
+            body.push("sep.push_unseparated(\" WHERE 1 = 1\");".to_string());
+            body.append(&mut condlines);
+            body.push(format!(
+                "qb.build().execute({}).await.map(|r| r.rows_affected())",
+                exec
+            ));
+        }
+    }
+    nfunc.bodylines = body;
+    Ok(nfunc)
+}
+
+/**
+ * 执行SQL所使用的Executor，事务中为其中的连接
+ */
+fn executor_of(func: &RustFunc) -> String {
+    if func
+        .params
+        .iter()
+        .any(|p| p.0 == "rb" && p.1.starts_with("&mut Transaction<"))
+    {
+        "&mut **rb".to_string()
+    } else {
+        "rb".to_string()
+    }
+}
+
+/**
+ * save使用INSERT，跳过的列（如自增的主键）不插入，自增的主键从last_insert_id()中获取
+ */
+fn convert_save_lines(
+    st: &RustStruct,
+    func: &RustFunc,
+    table: &str,
+    skips: &Vec<String>,
+    exec: &str,
+) -> Vec<String> {
+    let fields = st
+        .fields
+        .iter()
+        .filter(|f| !f.column_name.is_empty() && !skips.contains(&f.column_name))
+        .collect::<Vec<_>>();
+    let columns = fields
+        .iter()
+        .map(|f| f.column_name.clone())
+        .collect::<Vec<String>>();
+
+    let mut body = vec![format!(
+        "let mut qb = QueryBuilder::<MySql>::new(\"INSERT INTO {} ({}) VALUES (\");",
+        table,
+        columns.join(", ")
+    )];
+    body.push("let mut sep = qb.separated(\", \");".to_string());
+    for fd in fields.iter() {
+        body.push(format!("sep.push_bind(self.{}.clone());", fd.field_name));
+    }
+    body.push("sep.push_unseparated(\")\");".to_string());
+    body.push(format!("let ds = qb.build().execute({}).await?;", exec));
+
+    let reg =
+        Regex::new(r"^self\.([\w#]+) = ds\.last_insert_id(\.unwrap_or_default\(\))?;$").unwrap();
+    for ln in func.bodylines.iter() {
+        if let Some(cap) = reg.captures(ln.trim()) {
+            let fdname = cap[1].to_string();
+            let fdtype = st
+                .fields
+                .iter()
+                .find(|f| f.field_name == fdname)
+                .map(|f| f.field_type.clone())
+                .unwrap_or("i64".to_string());
+            if cap.get(2).is_some() {
+                body.push(format!(
+                    "self.{} = ds.last_insert_id() as {};",
+                    fdname, fdtype
+                ));
+            } else {
+                body.push(format!(
+                    "self.{} = Some(ds.last_insert_id() as {});",
+                    fdname, fdtype
+                ));
+            }
+        }
+    }
+    body.push("Ok(ds.rows_affected())".to_string());
+    body
+}
+
+/**
+ * 将wrapper的一个条件转换为QueryBuilder的代码行
+ * .eq("col", x) => qb.push(" AND col = ").push_bind(x);
+ * .r#if(x.is_some(), |w| w.and().eq("col", x.unwrap())) => if x.is_some() { qb.push(" AND col = ").push_bind(x.unwrap()); }
+ */
+fn translate_wrapper_condition(cond: &WrapperCondition) -> Option<Vec<String>> {
+    let mut lines = translate_operation(cond)?;
+    if let Some(test) = cond.test.clone() {
+        lines.insert(0, format!("if {} {{", test));
+        lines.push("}".to_string());
+    }
+    Some(lines)
+}
+
+fn translate_operation(cond: &WrapperCondition) -> Option<Vec<String>> {
+    let col = cond.column.clone();
+    let args = &cond.args;
+    let compare = |op: &str| -> Option<Vec<String>> {
+        Some(vec![format!(
+            "qb.push(\" AND {} {} \"){};",
+            col,
+            op,
+            push_value(&args[0])
+        )])
+    };
+    match (cond.op.as_str(), args.len()) {
+        ("push_sql", 1) => {
+            // 全文检索：(match(cols) against(?))
+            let sql = col.trim();
+            let pos = sql.find('?')?;
+            Some(vec![format!(
+                "qb.push(\" AND {}\"){}.push(\"{}\");",
+                &sql[..pos],
+                push_value(&args[0]),
+                &sql[pos + 1..]
+            )])
+        }
+        ("eq", 1) => compare("="),
+        ("ne", 1) => compare("<>"),
+        ("gt", 1) => compare(">"),
+        ("ge", 1) => compare(">="),
+        ("lt", 1) => compare("<"),
+        ("le", 1) => compare("<="),
+        ("like", 1) => Some(vec![format!(
+            "qb.push(\" AND {} LIKE CONCAT('%', \"){}.push(\", '%')\");",
+            col,
+            push_value(&args[0])
+        )]),
+        ("is_null", 0) => Some(vec![format!("qb.push(\" AND {} IS NULL\");", col)]),
+        ("between", 2) => Some(vec![format!(
+            "qb.push(\" AND {} BETWEEN \"){}.push(\" AND \"){};",
+            col,
+            push_value(&args[0]),
+            push_value(&args[1])
+        )]),
+        ("in", 1) | ("not_in", 1) => {
+            let not = if cond.op == "not_in" { "NOT " } else { "" };
+            let list = args[0].trim();
+            let list = if Regex::new(r"^[\w.]+$").unwrap().is_match(list) {
+                list.to_string()
+            } else {
+                format!("({})", list)
+            };
+            Some(vec![
+                format!("qb.push(\" AND {} {}IN (\");", col, not),
+                "let mut sep = qb.separated(\", \");".to_string(),
+                format!("for it in {}.iter() {{", list),
+                "sep.push_bind(it.clone());".to_string(),
+                "}".to_string(),
+                "sep.push_unseparated(\")\");".to_string(),
+            ])
+        }
+        _ => None,
+    }
+}
+
+/**
+ * 参数的值，常量（如树形的根节点值）直接写入SQL，其余的使用push_bind
+ */
+fn push_value(expr: &str) -> String {
+    let e = expr.trim();
+    if Regex::new(r"^-?[\d.]+$|^'[^']*'$").unwrap().is_match(e) {
+        format!(".push(\"{}\")", e)
+    } else {
+        format!(".push_bind({})", e)
+    }
+}
+
+/**
+ * 不依赖于wrapper的代码：参数、返回值及代码行中的类型和API
+ */
+fn convert_func_lines_to_sqlx(func: &mut RustFunc) {
+    for pm in func.params.iter_mut() {
+        pm.1 = match pm.1.as_str() {
+            "&Rbatis" => "&MySqlPool".to_string(),
+            "&mut RBatisTxExecutor<'_>" => "&mut Transaction<'_, MySql>".to_string(),
+            _ => to_sqlx_type(&pm.1),
+        };
+    }
+    func.return_type = func.return_type.clone().map(|t| to_sqlx_type(&t));
+    let exec = executor_of(func);
+
+    let page =
+        Regex::new(r"^rb\.fetch_page\(&sql, rb_args, &PageRequest::new\((\w+), (\w+)\)\)\.await$")
+            .unwrap();
+    let fetch = Regex::new(r"^(.*)rb\.fetch\(([^,]+), rb_args\)\.await(.*)$").unwrap();
+    let arg = Regex::new(r"rbson::to_bson\((.*)\)\.unwrap_or_default\(\)").unwrap();
+    let mut body = vec![];
+    for ln in func.bodylines.iter() {
+        if let Some(cap) = page.captures(ln.trim()) {
+            // 原生SQL的分页：先查询总数，再使用LIMIT查询当前页
+            body.push("let count_sql = format!(\"SELECT COUNT(1) FROM ({}) t\", sql);".to_string());
+            body.push(
+                "let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);".to_string(),
+            );
+            body.push("for arg in rb_args.iter() {".to_string());
+            body.push("count_query = count_query.bind(arg.clone());".to_string());
+            body.push("}".to_string());
+            body.push(format!(
+                "let total = count_query.fetch_one({}).await?;",
+                exec
+            ));
+            body.push(format!(
+                "let mut page = Page::new_total({}, {}, total as u64);",
+                &cap[1], &cap[2]
+            ));
+            body.push(format!(
+                "let page_sql = format!(\"{{}} LIMIT {{}}, {{}}\", sql, ({}.max(1) - 1) * {}, {});",
+                &cap[1], &cap[2], &cap[2]
+            ));
+            body.push("let mut query = sqlx::query_as(&page_sql);".to_string());
+            body.push("for arg in rb_args {".to_string());
+            body.push("query = query.bind(arg);".to_string());
+            body.push("}".to_string());
+            body.push(format!("page.records = query.fetch_all({}).await?;", exec));
+            body.push("Ok(page)".to_string());
+            continue;
+        }
+        if let Some(cap) = fetch.captures(ln.trim()) {
+            // 原生SQL的查询，单行的结果使用fetch_optional
+            let method = if cap[1].is_empty() && func.return_is_option {
+                "fetch_optional"
+            } else {
+                "fetch_all"
+            };
+            body.push(format!("let mut query = sqlx::query_as({});", &cap[2]));
+            body.push("for arg in rb_args {".to_string());
+            body.push("query = query.bind(arg);".to_string());
+            body.push("}".to_string());
+            body.push(format!(
+                "{}query.{}({}).await{}",
+                &cap[1], method, exec, &cap[3]
+            ));
+            continue;
+        }
+        let ln = if ln.trim() == "let mut rb_args = vec![];" {
+            "let mut rb_args: Vec<Option<String>> = vec![];".to_string()
+        } else {
+            arg.replace_all(ln, "to_sql_arg($1)").to_string()
+        };
+        body.push(
+            to_sqlx_type(&ln)
+                .replace("rb.get_rbatis()", "get_pool()")
+                .replace("get_rbatis()", "get_pool()")
+                .replace("rb.acquire_begin()", "rb.begin()"),
+        );
+    }
+    func.bodylines = body;
+}
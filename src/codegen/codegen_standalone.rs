@@ -0,0 +1,76 @@
+use crate::codegen::RustFileImpl;
+
+/**
+ * standalone的生成
+ * 不依赖chimes-auth、chimes-rust及chimes-utils，引用的类型和方法改为生成到utils中的实现：
+ * 1、chimes_auth::ApiResult => crate::utils::ApiResult
+ * 2、chimes_rust::SystemUser<ChimesUserInfo> => crate::utils::SystemUser<UserInfo>，当前用户由utils中的CurrentUser提供
+ * 3、chimes_utils::{get_rbatis, CommonSearch, *_from_str} => crate::utils中的同名实现
+ * 4、axum时SystemUser本身即为提取器，不再需要通过Extension获取
+ * 附件（ChimesAttachmentInfo）依赖于chimes-rust中的表，standalone时不生成
+ */
+pub fn convert_file_to_standalone(fl: &mut RustFileImpl) {
+    let mut uses_extension = false;
+    for func in fl.funclist.iter_mut() {
+        for pm in func.params.iter_mut() {
+            if pm.1 == "Extension<SystemUser<ChimesUserInfo>>" {
+                *pm = (
+                    pm.0.trim_start_matches("Extension(")
+                        .trim_end_matches(')')
+                        .to_string(),
+                    "SystemUser<UserInfo>".to_string(),
+                );
+            } else {
+                pm.1 = to_standalone_type(&pm.1);
+            }
+            uses_extension = uses_extension || pm.1.starts_with("Extension<");
+        }
+        func.bodylines = func
+            .bodylines
+            .iter()
+            .map(|l| to_standalone_type(l))
+            .collect();
+    }
+    for st in fl.structlist.iter_mut() {
+        for func in st.funclist.iter_mut() {
+            for pm in func.params.iter_mut() {
+                pm.1 = to_standalone_type(&pm.1);
+            }
+        }
+        st.usings = st.usings.iter().filter_map(to_standalone_using).collect();
+    }
+
+    let mut usings = vec![];
+    for us in fl.usinglist.iter() {
+        if let Some(mut t) = to_standalone_using(us) {
+            if t.starts_with("axum::extract::{") && !uses_extension {
+                t = t.replace("Extension, ", "").replace(", Extension", "");
+                if t == "axum::extract::{Extension}" {
+                    continue;
+                }
+            }
+            usings.push(t);
+        }
+    }
+    usings.dedup();
+    fl.usinglist = usings;
+}
+
+fn to_standalone_type(text: &str) -> String {
+    text.replace("SystemUser<ChimesUserInfo>", "SystemUser<UserInfo>")
+}
+
+fn to_standalone_using(us: &String) -> Option<String> {
+    match us.as_str() {
+        "chimes_rust::{ChimesUserInfo, SystemUser}"
+        | "chimes_rust::{SystemUser, ChimesUserInfo}" => {
+            Some("crate::utils::{SystemUser, UserInfo}".to_string())
+        }
+        "chimes_rust::{ChimesAttachmentInfo, ChimesAttachmentRefInfo}" => None,
+        "chimes_auth::ApiResult" => Some("crate::utils::ApiResult".to_string()),
+        _ => match us.strip_prefix("chimes_utils::") {
+            Some(name) => Some(format!("crate::utils::{}", name)),
+            None => Some(us.clone()),
+        },
+    }
+}
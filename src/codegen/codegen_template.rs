@@ -0,0 +1,361 @@
+use crate::codegen::{
+    parse_composite_column_list, CodeWriter, GenerateContext, RelationTable, RustFileImpl,
+    RustFunc, RustOutput, RustStruct, RustStructFieldExtend, RustTraitImpl,
+};
+use crate::config::{
+    safe_struct_field_name, CodeGenConfig, QueryConfig, RelationConfig, TableConfig,
+};
+use crate::schema::TableInfo;
+use serde_derive::{Deserialize, Serialize};
+use tera::Context;
+
+use super::RustArtifact;
+
+/**
+ * 结构体及其按内置方式生成的代码
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RustStructSource {
+    pub info: RustStruct,
+    pub source: String,
+}
+
+/**
+ * 函数及其按内置方式生成的代码
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RustFuncSource {
+    pub info: RustFunc,
+    pub source: String,
+}
+
+/**
+ * impl块及其按内置方式生成的代码
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RustImplSource {
+    pub info: RustTraitImpl,
+    pub source: String,
+}
+
+/**
+ * Cargo.toml中[dependencies]的一项，spec为等号右边的内容，如"0.4"或{version = "1.10", features = [...]}
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CargoDependency {
+    pub name: String,
+    pub spec: String,
+}
+
+/**
+ * 提供给Rust模板（templates/rust目录下的模板）的上下文
+ * artifact: entity, handler, relation, relation_handler, query, query_handler, service, main, lib, cargo
+ * table_info/table_conf/fields/primary_key: 表对应的信息，关系时为主表
+ * relation_conf/relations: 关系的配置及其关联表
+ * query_conf: 查询的配置
+ * usings/structs/funcs/impls: 按内置方式解析出来的use、结构体、函数和impl块，source为其生成的代码
+ * mods/services: 仅用于main.rs和lib.rs，为所有的mod以及handler（axum时为Router的路由）
+ * dependencies: 仅用于Cargo.toml，为按配置调整后的所有依赖
+ * generated: 按内置方式生成的完整文件内容
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RustTemplateContext {
+    pub codegen: CodeGenConfig,
+    pub artifact: String,
+    pub file_name: String,
+    pub mod_name: String,
+    pub table_info: Option<TableInfo>,
+    pub table_conf: Option<TableConfig>,
+    pub relation_conf: Option<RelationConfig>,
+    pub query_conf: Option<QueryConfig>,
+    pub primary_key: Option<RustStructFieldExtend>,
+    pub fields: Vec<RustStructFieldExtend>,
+    pub relations: Vec<RelationTable>,
+    pub carets: Vec<String>,
+    pub usings: Vec<String>,
+    pub structs: Vec<RustStructSource>,
+    pub funcs: Vec<RustFuncSource>,
+    pub impls: Vec<RustImplSource>,
+    pub mods: Vec<String>,
+    pub services: Vec<String>,
+    pub dependencies: Vec<CargoDependency>,
+    pub generated: String,
+}
+
+fn write_as_source(writer: &dyn CodeWriter) -> String {
+    let mut ro = RustOutput::default();
+    writer.write(&mut ro);
+    ro.to_text()
+}
+
+fn parse_table_fields(ctx: &GenerateContext, tbconf: &TableConfig) -> Vec<RustStructFieldExtend> {
+    let cols = ctx.get_table_columns(&tbconf.name);
+    let mut columns = String::new();
+    let mut joinlist = String::new();
+    let mut usings = vec![];
+    parse_composite_column_list(
+        ctx,
+        tbconf,
+        &cols,
+        &mut columns,
+        &mut joinlist,
+        false,
+        false,
+        &mut usings,
+    )
+}
+
+fn find_primary_key(
+    ctx: &GenerateContext,
+    tbconf: &TableConfig,
+    fields: &Vec<RustStructFieldExtend>,
+) -> Option<RustStructFieldExtend> {
+    let mut pkcols = ctx.get_table_column_by_primary_key(&tbconf.name);
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(&tbconf.name));
+    }
+    let pkname = match pkcols.first() {
+        Some(col) => col.column_name.clone().unwrap_or_default(),
+        None => tbconf.primary_key.clone(),
+    };
+    fields
+        .iter()
+        .find(|f| f.field.column_name == pkname)
+        .cloned()
+}
+
+fn parse_relation_tables(ctx: &GenerateContext, rel: &RelationConfig) -> Vec<RelationTable> {
+    let mut relations = vec![];
+    for (cl, one_many) in rel
+        .one_to_one
+        .iter()
+        .map(|r| (r, false))
+        .chain(rel.one_to_many.iter().map(|r| (r, true)))
+    {
+        let rel_table_name = cl.table_name.clone().unwrap_or_default();
+        if let Some(reltbc) = ctx.get_table_conf(&rel_table_name) {
+            let fdname = safe_struct_field_name(&reltbc.api_handler_name);
+            relations.push(RelationTable {
+                table_info: ctx.get_table_info(&rel_table_name),
+                table_conf: Some(reltbc.clone()),
+                major_field_name: Some(if one_many {
+                    format!("{}s", fdname)
+                } else {
+                    fdname
+                }),
+                fields: parse_table_fields(ctx, &reltbc),
+                dialog_form: cl.use_dialog_form,
+                one_many,
+            });
+        }
+    }
+    relations
+}
+
+impl RustTemplateContext {
+    fn fill_table(&mut self, ctx: &GenerateContext, tbl_name: &String) {
+        if let Some(tbconf) = ctx.get_table_conf(tbl_name) {
+            self.table_info = ctx.get_table_info(tbl_name);
+            self.fields = parse_table_fields(ctx, &tbconf);
+            self.primary_key = find_primary_key(ctx, &tbconf, &self.fields);
+            self.table_conf = Some(tbconf);
+        }
+    }
+
+    /**
+     * 根据生成的文件构建模板上下文
+     */
+    pub fn from_file(ctx: &GenerateContext, file: &RustFileImpl) -> Self {
+        let mut rtc = Self {
+            codegen: ctx.codegen_conf.clone(),
+            file_name: file.file_name.clone(),
+            mod_name: file.mod_name.clone(),
+            carets: file.caretlist.clone(),
+            usings: file.get_usings(),
+            structs: file
+                .structlist
+                .iter()
+                .map(|st| RustStructSource {
+                    info: st.clone(),
+                    source: write_as_source(st),
+                })
+                .collect(),
+            funcs: file
+                .funclist
+                .iter()
+                .map(|func| RustFuncSource {
+                    info: func.clone(),
+                    source: write_as_source(func),
+                })
+                .collect(),
+            impls: file
+                .implist
+                .iter()
+                .map(|imp| RustImplSource {
+                    info: imp.clone(),
+                    source: write_as_source(imp),
+                })
+                .collect(),
+            generated: file.to_source(),
+            ..Default::default()
+        };
+
+        if let Some(artifact) = file.artifact.clone() {
+            rtc.artifact = artifact.kind().to_string();
+            let source = artifact.source();
+            match artifact {
+                RustArtifact::Entity(_) | RustArtifact::Handler(_) => {
+                    rtc.fill_table(ctx, &source);
+                }
+                RustArtifact::Relation(_) | RustArtifact::RelationHandler(_) => {
+                    let rel = ctx
+                        .codegen_conf
+                        .relations
+                        .iter()
+                        .find(|r| r.struct_name == source)
+                        .cloned();
+                    if let Some(relconf) = rel {
+                        rtc.fill_table(ctx, &relconf.major_table);
+                        rtc.relations = parse_relation_tables(ctx, &relconf);
+                        rtc.relation_conf = Some(relconf);
+                    }
+                }
+                RustArtifact::Service(_) => {
+                    // 关系的Service来源为关系的结构体名，否则为表名
+                    let rel = ctx
+                        .codegen_conf
+                        .relations
+                        .iter()
+                        .find(|r| r.struct_name == source)
+                        .cloned();
+                    match rel {
+                        Some(relconf) => {
+                            rtc.fill_table(ctx, &relconf.major_table);
+                            rtc.relations = parse_relation_tables(ctx, &relconf);
+                            rtc.relation_conf = Some(relconf);
+                        }
+                        None => rtc.fill_table(ctx, &source),
+                    }
+                }
+                RustArtifact::Query(_) | RustArtifact::QueryHandler(_) => {
+                    rtc.query_conf = ctx
+                        .codegen_conf
+                        .queries
+                        .iter()
+                        .find(|q| q.struct_name == source)
+                        .cloned();
+                }
+            }
+        }
+        rtc
+    }
+}
+
+/**
+ * 使用模板生成Rust文件的内容
+ * 没有指定类型的文件（如配置解释程序）按内置的方式生成
+ */
+pub fn render_rust_file(ctx: &GenerateContext, file: &RustFileImpl) -> Option<String> {
+    match file.artifact.clone() {
+        Some(artifact) => {
+            let rtc = RustTemplateContext::from_file(ctx, file);
+            match Context::from_serialize(&rtc) {
+                Ok(context) => ctx.render_template(artifact.template_name(), &file.file_name, &context),
+                Err(err) => {
+                    log::error!(
+                        "Could not build the template context for {}: {}",
+                        file.file_name,
+                        err
+                    );
+                    None
+                }
+            }
+        }
+        None => Some(file.to_source()),
+    }
+}
+
+/**
+ * 使用模板生成main.rs
+ */
+pub fn render_rust_main(
+    ctx: &GenerateContext,
+    mods: &Vec<String>,
+    services: &Vec<String>,
+    generated: &String,
+) -> Option<String> {
+    let rtc = RustTemplateContext {
+        codegen: ctx.codegen_conf.clone(),
+        artifact: "main".to_string(),
+        file_name: "main.rs".to_string(),
+        mods: mods.clone(),
+        services: services.clone(),
+        generated: generated.clone(),
+        ..Default::default()
+    };
+    let context = Context::from_serialize(&rtc).unwrap_or_default();
+    ctx.render_template("rust/main.rs", "main.rs", &context)
+}
+
+/**
+ * 使用模板生成lib.rs（generate-for-lib时）
+ */
+pub fn render_rust_lib(
+    ctx: &GenerateContext,
+    mods: &Vec<String>,
+    services: &Vec<String>,
+    generated: &String,
+) -> Option<String> {
+    let rtc = RustTemplateContext {
+        codegen: ctx.codegen_conf.clone(),
+        artifact: "lib".to_string(),
+        file_name: "lib.rs".to_string(),
+        mods: mods.clone(),
+        services: services.clone(),
+        generated: generated.clone(),
+        ..Default::default()
+    };
+    let context = Context::from_serialize(&rtc).unwrap_or_default();
+    ctx.render_template("rust/lib.rs", "lib.rs", &context)
+}
+
+/**
+ * 解析内置方式生成的Cargo.toml中[dependencies]的各项
+ */
+pub fn parse_cargo_dependencies(cargotext: &str) -> Vec<CargoDependency> {
+    let mut deps = vec![];
+    let mut in_deps = false;
+    for ln in cargotext.lines() {
+        let ln = ln.trim();
+        if ln.starts_with('[') {
+            in_deps = ln == "[dependencies]";
+            continue;
+        }
+        if !in_deps || ln.is_empty() || ln.starts_with('#') {
+            continue;
+        }
+        if let Some((name, spec)) = ln.split_once('=') {
+            deps.push(CargoDependency {
+                name: name.trim().to_string(),
+                spec: spec.trim().to_string(),
+            });
+        }
+    }
+    deps
+}
+
+/**
+ * 使用模板生成Cargo.toml
+ */
+pub fn render_cargo_toml(ctx: &GenerateContext, generated: &String) -> Option<String> {
+    let rtc = RustTemplateContext {
+        codegen: ctx.codegen_conf.clone(),
+        artifact: "cargo".to_string(),
+        file_name: "Cargo.toml".to_string(),
+        dependencies: parse_cargo_dependencies(generated),
+        generated: generated.clone(),
+        ..Default::default()
+    };
+    let context = Context::from_serialize(&rtc).unwrap_or_default();
+    ctx.render_template("rust/Cargo.toml", "Cargo.toml", &context)
+}
@@ -0,0 +1,684 @@
+use regex::Regex;
+
+use crate::codegen::{
+    get_logic_deletion, is_date_time_type, parse_column_as_field, GenerateContext, RustArtifact,
+    RustFileImpl, RustFunc,
+};
+use crate::config::{RelationConfig, TableConfig};
+
+/**
+ * handler的集成测试（generate-test: true）
+ * 为每个表及关系的handler文件在src/tests中生成{file}_test.rs，通过actix_web::test::init_service调用生成的handler：
+ * 1、表：保存 → 查询（找到刚保存的记录）→ 分页查询 → 获取 → 更新 → 删除
+ * 2、关系：保存主表及一对多的明细 → 加载 → 删除
+ * 请求体根据列的类型及长度生成，handler会自动填写的字段（create_time、company_id等）及可为空的外键（xxx_id）不填写
+ * 在ORM的转换之后生成，只测试最终存在的handler；axum时不生成
+ */
+const AUTO_FILLED_FIELDS: [&str; 18] = [
+    "company_id",
+    "company_code",
+    "create_by",
+    "modify_by",
+    "create_userid",
+    "create_user_id",
+    "modify_user_id",
+    "modify_userid",
+    "modify_username",
+    "modify_user_name",
+    "create_username",
+    "create_user_name",
+    "create_time",
+    "modify_time",
+    "update_time",
+    "create_date",
+    "update_date",
+    "modify_date",
+];
+
+#[derive(Debug, Clone, Default)]
+struct FixtureField {
+    column: String,
+    field: String,
+    field_type: String,
+    length: i64,
+    value: String, // json!中的表达式
+}
+
+#[derive(Debug, Clone, Default)]
+struct TableFixture {
+    fields: Vec<FixtureField>,
+    marker: Option<FixtureField>, // 用来找到刚保存的记录的字符串字段，值为marker变量
+    pk: Option<FixtureField>,     // 单一主键，自增时value为空
+    renames: Vec<(String, String)>, // 字段名与列名不同的字段
+}
+
+fn fixture_value(field_type: &str, length: i64, is_pk: bool) -> Option<String> {
+    let tp = field_type.trim_start_matches("rbatis::");
+    let value = match tp {
+        "bool" => "true".to_string(),
+        "i16" | "u16" if is_pk => "test_id(30000)".to_string(),
+        "i8" | "u8" if is_pk => "test_id(100)".to_string(),
+        "i32" | "u32" | "i64" | "u64" if is_pk => "test_id(1_000_000_000)".to_string(),
+        "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" => "1".to_string(),
+        "f32" | "f64" => "1.5".to_string(),
+        "Decimal" => "\"1\"".to_string(),
+        "DateTimeNative" | "DateTimeUtc" => "\"2024-01-01T10:00:00\"".to_string(),
+        "DateNative" | "DateUtc" => "\"2024-01-01\"".to_string(),
+        "TimeNative" | "TimeUtc" => "\"10:00:00\"".to_string(),
+        "Timestamp" | "TimestampZ" => "\"2024-01-01T10:00:00Z\"".to_string(),
+        "Bytes" => "[]".to_string(),
+        "Json" => "{}".to_string(),
+        "String" => format!("test_string(\"\", {})", length.max(0)),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/**
+ * 根据列的类型及长度生成请求体的各个字段，exclude中的列（如一对多明细的关联字段）可为空时不填写
+ */
+fn build_table_fixture(
+    ctx: &GenerateContext,
+    tbc: &TableConfig,
+    exclude: &Vec<String>,
+) -> TableFixture {
+    let mut fixture = TableFixture::default();
+    let mut pkcols = ctx.get_table_column_by_primary_key(&tbc.name);
+    if pkcols.is_empty() {
+        pkcols.append(&mut ctx.get_table_pkey_column(&tbc.name));
+    }
+    let pknames = pkcols
+        .iter()
+        .map(|c| c.column_name.clone().unwrap_or_default())
+        .collect::<Vec<String>>();
+    let tree_field = tbc
+        .tree_parent_field
+        .clone()
+        .unwrap_or_default()
+        .to_lowercase();
+    let logic_deletion = get_logic_deletion(ctx, &tbc.name);
+
+    let mut usings = vec![];
+    for col in ctx.get_table_columns(&tbc.name).iter() {
+        let fd = parse_column_as_field(ctx, tbc, col, false, &mut usings);
+        let column = fd.column_name.clone();
+        let field = fd.field_name.trim_start_matches("r#").to_string();
+        let nullable = col.is_nullable.clone().unwrap_or_default().to_lowercase() == "yes";
+        let is_pk = pknames.contains(&column);
+        let is_auto = col.extra.clone().unwrap_or_default().to_lowercase() == "auto_increment";
+        let mut ffd = FixtureField {
+            column: column.clone(),
+            field: field.clone(),
+            field_type: fd.field_type.clone(),
+            length: fd.length.clamp(0, 32),
+            value: String::new(),
+        };
+        if column != field {
+            fixture.renames.push((field.clone(), column.clone()));
+        }
+        if is_pk && is_auto {
+            if pknames.len() == 1 {
+                fixture.pk = Some(ffd);
+            }
+            continue;
+        }
+        if let Some(ld) = logic_deletion.as_ref().filter(|ld| ld.column == column) {
+            // 逻辑删除的列，保存为未删除，否则查询时找不到刚保存的记录
+            ffd.value = ld.undeleted.clone();
+            fixture.fields.push(ffd);
+            continue;
+        }
+        if nullable
+            && (AUTO_FILLED_FIELDS.contains(&field.as_str())
+                || exclude.contains(&column)
+                || (field.ends_with("_id") && !is_pk)
+                || matches!(
+                    fd.field_type.trim_start_matches("rbatis::"),
+                    "Timestamp" | "TimestampZ" | "Bytes" | "Json"
+                ))
+        {
+            continue;
+        }
+        let value = match fixture_value(&fd.field_type, fd.length, is_pk) {
+            Some(v) => v,
+            None => continue,
+        };
+        ffd.value = if field == tree_field && !is_pk && value == "1" {
+            // 树形的父节点使用0（根节点）
+            "0".to_string()
+        } else {
+            value
+        };
+        let can_mark = fd.field_type == "String"
+            && !is_pk
+            && field != tree_field
+            && !AUTO_FILLED_FIELDS.contains(&field.as_str())
+            && (fd.length == 0 || fd.length >= 8);
+        if can_mark && fixture.marker.is_none() {
+            ffd.value = "marker".to_string();
+            fixture.marker = Some(ffd.clone());
+        }
+        if is_pk && pknames.len() == 1 {
+            fixture.pk = Some(ffd.clone());
+        }
+        fixture.fields.push(ffd);
+    }
+    fixture
+}
+
+fn json_object_lines(var: &str, fields: &Vec<FixtureField>) -> Vec<String> {
+    let mut lines = vec![format!("let {} = json!({{", var)];
+    for fd in fields.iter() {
+        lines.push(format!("\"{}\": {},", fd.column, fd.value));
+    }
+    lines.push("});".to_string());
+    lines
+}
+
+/**
+ * 调用API并断言status为200，path为Rust的表达式
+ */
+fn call_lines(func: &RustFunc, path: &str, body: Option<&str>) -> Vec<String> {
+    let method = func
+        .api_method
+        .clone()
+        .unwrap_or("GET".to_string())
+        .to_uppercase();
+    let mut lines = vec![];
+    match body {
+        Some(b) => lines.push(format!(
+            "let req = test_request(Method::{}, {}).set_json(&{}).to_request();",
+            method, path, b
+        )),
+        None => lines.push(format!(
+            "let req = test_request(Method::{}, {}).to_request();",
+            method, path
+        )),
+    }
+    lines.push("let ret: Value = test::call_and_read_body_json(&app, req).await;".to_string());
+    lines
+}
+
+/**
+ * 路径参数替换为id的值，如/api/v1/user/get/{id}
+ */
+fn path_with_id(pattern: &str) -> String {
+    let reg = Regex::new(r"\{\w+\}").unwrap();
+    if reg.is_match(pattern) {
+        format!(
+            "&format!(\"{}\", path_value(&id))",
+            reg.replace_all(pattern, "{}")
+        )
+    } else {
+        format!("\"{}\"", pattern)
+    }
+}
+
+fn init_app_lines(ctx: &GenerateContext, services: &Vec<String>) -> Vec<String> {
+    let mut lines = vec![
+        "init_test_env();".to_string(),
+        "let app = test::init_service(".to_string(),
+        "    App::new()".to_string(),
+    ];
+    if ctx.is_standalone() {
+        lines.push(
+            "        .wrap_fn(|req, srv| { set_test_user(&req); srv.call(req) })".to_string(),
+        );
+    }
+    for svc in services.iter() {
+        lines.push(format!("        .service(crate::handler::{})", svc));
+    }
+    lines.push(")".to_string());
+    lines.push(".await;".to_string());
+    lines
+}
+
+/**
+ * 用于查询的请求体：CommonSearch时按marker查询；XxxQuery中的日期、时间为范围，不作为条件
+ */
+fn search_body_lines(tbc: &TableConfig, fixture: &TableFixture) -> Vec<String> {
+    if tbc.using_common_search {
+        return match fixture.marker.as_ref() {
+            Some(mk) => vec![format!(
+                "let query = json!({{\"conditions\": [{{\"field\": \"{}\", \"op\": \"eq\", \"value\": marker}}]}});",
+                mk.column
+            )],
+            None => vec!["let query = json!({\"conditions\": []});".to_string()],
+        };
+    }
+    let ranges = fixture
+        .fields
+        .iter()
+        .filter(|fd| is_date_time_type(&fd.field_type))
+        .map(|fd| format!("\"{}\"", fd.column))
+        .collect::<Vec<String>>();
+    if tbc.generate_param_struct && !ranges.is_empty() {
+        let mut lines = vec!["let mut query = fixture.clone();".to_string()];
+        for key in ranges.iter() {
+            lines.push(format!("query.as_object_mut().unwrap().remove({});", key));
+        }
+        return lines;
+    }
+    vec!["let query = fixture.clone();".to_string()]
+}
+
+/**
+ * 找到刚保存的记录，need_id时得到其主键id，后续的步骤都不需要时只断言能找到
+ * 有marker时按marker查找，非自增的主键按主键查找，否则取主键最大的记录
+ */
+fn locate_lines(
+    fixture: &TableFixture,
+    func_name: &str,
+    need_record: bool,
+    need_id: bool,
+) -> Vec<String> {
+    let pk = match fixture.pk.as_ref() {
+        Some(pk) => pk,
+        None => return vec![],
+    };
+    let find = match (fixture.marker.as_ref(), pk.value.is_empty()) {
+        (Some(mk), _) => format!(
+            "find_record(&ret[\"data\"], \"{}\", &json!(marker))",
+            mk.field
+        ),
+        (None, false) => format!(
+            "find_record(&ret[\"data\"], \"{}\", &fixture[\"{}\"])",
+            pk.field, pk.column
+        ),
+        (None, true) => format!("find_latest(&ret[\"data\"], \"{}\")", pk.field),
+    };
+    if !need_record && !need_id {
+        return vec![format!(
+            "assert!({}.is_some(), \"{} did not return the saved record\");",
+            find, func_name
+        )];
+    }
+    let mut lines = vec![
+        format!("let record = {}", find),
+        format!(
+            "    .expect(\"{} did not return the saved record\");",
+            func_name
+        ),
+    ];
+    if need_id {
+        lines.push(format!("let id = record[\"{}\"].clone();", pk.field));
+    }
+    lines
+}
+
+fn rename_lines(var: &str, fixture: &TableFixture) -> Vec<String> {
+    if fixture.renames.is_empty() {
+        return vec![];
+    }
+    let names = fixture
+        .renames
+        .iter()
+        .map(|(f, c)| format!("(\"{}\", \"{}\")", f, c))
+        .collect::<Vec<String>>();
+    vec![format!(
+        "rename_keys(&mut {}, &[{}]);",
+        var,
+        names.join(", ")
+    )]
+}
+
+fn test_func(name: &str, comment: String, bodylines: Vec<String>) -> RustFunc {
+    RustFunc {
+        is_struct_fn: false,
+        is_self_fn: false,
+        is_self_mut: false,
+        is_pub: false,
+        is_async: true,
+        func_name: name.to_string(),
+        return_is_option: false,
+        return_is_result: false,
+        return_type: None,
+        params: vec![],
+        bodylines,
+        macros: vec![format!("/// {}", comment), "#[actix_web::test]".to_string()],
+        comment: Some(comment),
+        api_method: None,
+        api_pattern: None,
+    }
+}
+
+fn find_func<'a>(funcs: &'a Vec<RustFunc>, name: &str) -> Option<&'a RustFunc> {
+    funcs
+        .iter()
+        .find(|f| f.func_name == name && f.api_pattern.is_some())
+}
+
+/**
+ * 表的CRUD测试
+ */
+fn generate_table_test(
+    ctx: &GenerateContext,
+    tbc: &TableConfig,
+    funcs: &Vec<RustFunc>,
+) -> Option<RustFunc> {
+    let api = tbc.api_handler_name.clone();
+    let save = find_func(funcs, &format!("{}_save", api))?;
+    let fixture = build_table_fixture(ctx, tbc, &vec![]);
+    let services = funcs
+        .iter()
+        .filter(|f| f.api_pattern.is_some())
+        .map(|f| f.func_name.clone())
+        .collect::<Vec<String>>();
+
+    let mut steps = vec!["保存"];
+    let mut body = init_app_lines(ctx, &services);
+    if let Some(mk) = fixture.marker.as_ref() {
+        body.push(format!("let marker = test_string(\"t\", {});", mk.length));
+    }
+    body.append(&mut json_object_lines("fixture", &fixture.fields));
+
+    body.append(&mut call_lines(
+        save,
+        &format!("\"{}\"", save.api_pattern.clone().unwrap_or_default()),
+        Some("fixture"),
+    ));
+    body.push(format!("assert_api_ok(&ret, \"{}\");", save.func_name));
+
+    let search = match find_func(funcs, &format!("{}_search", api)) {
+        Some(f) if fixture.pk.is_some() => f,
+        _ => {
+            return Some(test_func(
+                &format!("test_{}_crud", api),
+                format!("{}：保存", tbc.comment),
+                body,
+            ))
+        }
+    };
+    let get = find_func(funcs, &format!("{}_get", api));
+    let update = find_func(funcs, &format!("{}_update", api));
+    let delete = find_func(funcs, &format!("{}_delete", api));
+    steps.push("查询");
+    body.append(&mut search_body_lines(tbc, &fixture));
+    body.append(&mut call_lines(
+        search,
+        &format!("\"{}\"", search.api_pattern.clone().unwrap_or_default()),
+        Some("query"),
+    ));
+    body.push(format!("assert_api_ok(&ret, \"{}\");", search.func_name));
+    let modify = update.is_some() || delete.is_some();
+    body.append(&mut locate_lines(
+        &fixture,
+        &search.func_name,
+        modify && get.is_none(),
+        get.is_some(),
+    ));
+
+    if let Some(paged) = find_func(funcs, &format!("{}_paged", api)) {
+        let path = paged
+            .api_pattern
+            .clone()
+            .unwrap_or_default()
+            .replace("{current}", "1")
+            .replace("{size}", "10");
+        steps.push("分页查询");
+        body.append(&mut call_lines(
+            paged,
+            &format!("\"{}\"", path),
+            Some("query"),
+        ));
+        body.push(format!("assert_api_ok(&ret, \"{}\");", paged.func_name));
+        body.push(format!(
+            "assert!(ret[\"data\"][\"records\"].as_array().map(|r| !r.is_empty()).unwrap_or(false), \"{} returned no records\");",
+            paged.func_name
+        ));
+    }
+
+    // 更新、删除的请求体为获取到的记录，按列名反序列化
+    let mutable = if update.is_some() || !fixture.renames.is_empty() {
+        "mut "
+    } else {
+        ""
+    };
+    if modify && get.is_none() {
+        body.push(format!("let {}data = record;", mutable));
+    }
+    if let Some(get) = get {
+        steps.push("获取");
+        body.append(&mut call_lines(
+            get,
+            &path_with_id(&get.api_pattern.clone().unwrap_or_default()),
+            None,
+        ));
+        body.push(format!("assert_api_ok(&ret, \"{}\");", get.func_name));
+        if modify {
+            body.push(format!("let {}data = ret[\"data\"].clone();", mutable));
+        }
+    }
+    if modify {
+        body.append(&mut rename_lines("data", &fixture));
+    }
+
+    if let Some(update) = update {
+        steps.push("更新");
+        if let Some(mk) = fixture.marker.as_ref() {
+            body.push(format!("let marker = test_string(\"u\", {});", mk.length));
+            body.push(format!("data[\"{}\"] = json!(marker);", mk.column));
+        }
+        body.append(&mut call_lines(
+            update,
+            &format!("\"{}\"", update.api_pattern.clone().unwrap_or_default()),
+            Some("data"),
+        ));
+        body.push(format!("assert_api_ok(&ret, \"{}\");", update.func_name));
+        if let (Some(get), Some(mk)) = (get, fixture.marker.as_ref()) {
+            body.append(&mut call_lines(
+                get,
+                &path_with_id(&get.api_pattern.clone().unwrap_or_default()),
+                None,
+            ));
+            body.push(format!("assert_api_ok(&ret, \"{}\");", get.func_name));
+            body.push(format!(
+                "assert_eq!(ret[\"data\"][\"{}\"], json!(marker), \"{} did not change the record\");",
+                mk.field, update.func_name
+            ));
+        }
+    }
+
+    if let Some(delete) = delete {
+        steps.push("删除");
+        body.append(&mut call_lines(
+            delete,
+            &format!("\"{}\"", delete.api_pattern.clone().unwrap_or_default()),
+            Some("data"),
+        ));
+        body.push(format!("assert_api_ok(&ret, \"{}\");", delete.func_name));
+        if let Some(get) = get {
+            body.append(&mut call_lines(
+                get,
+                &path_with_id(&get.api_pattern.clone().unwrap_or_default()),
+                None,
+            ));
+            body.push(format!(
+                "assert_ne!(ret[\"status\"], 200, \"{} did not remove the record\");",
+                delete.func_name
+            ));
+        }
+    }
+
+    Some(test_func(
+        &format!("test_{}_crud", api),
+        format!("{}：{}", tbc.comment, steps.join("、")),
+        body,
+    ))
+}
+
+/**
+ * 关系的测试：保存主表及一对多的明细，再通过主表的查询找到主键，加载并删除
+ */
+fn generate_relation_test(
+    ctx: &GenerateContext,
+    rel: &RelationConfig,
+    funcs: &Vec<RustFunc>,
+    all_funcs: &Vec<RustFunc>,
+) -> Option<RustFunc> {
+    let api = rel.api_handler_name.clone().unwrap_or_default();
+    let save = find_func(funcs, &format!("{}_rel_save", api))?;
+    let major = ctx.get_table_conf(&rel.major_table)?;
+    let fixture = build_table_fixture(ctx, &major, &vec![]);
+    let major_search = find_func(all_funcs, &format!("{}_search", major.api_handler_name));
+
+    let mut services = funcs
+        .iter()
+        .filter(|f| f.api_pattern.is_some())
+        .map(|f| f.func_name.clone())
+        .collect::<Vec<String>>();
+    if let Some(f) = major_search {
+        services.push(f.func_name.clone());
+    }
+
+    let mut body = init_app_lines(ctx, &services);
+    if let Some(mk) = fixture.marker.as_ref() {
+        body.push(format!("let marker = test_string(\"t\", {});", mk.length));
+    }
+    body.append(&mut json_object_lines("fixture", &fixture.fields));
+    if rel.extend_major {
+        body.push("let mut data = fixture.clone();".to_string());
+    } else {
+        body.push("let mut data = json!({});".to_string());
+        body.push(format!(
+            "data[\"{}\"] = fixture.clone();",
+            major.api_handler_name
+        ));
+    }
+
+    let mut children = vec![];
+    for rl in rel.one_to_many.iter() {
+        let rltbc = match ctx.get_table_conf(&rl.table_name.clone().unwrap_or_default()) {
+            Some(t) => t,
+            None => continue,
+        };
+        let fdname = format!("{}s", rltbc.api_handler_name);
+        if rl.middle_table.is_none() && !rl.readonly {
+            // 明细的关联字段由保存时填写
+            let exclude = vec![rl.join_field.clone().unwrap_or_default()];
+            let child = build_table_fixture(ctx, &rltbc, &exclude);
+            let mut fields = child.fields.clone();
+            for fd in fields.iter_mut().filter(|f| f.value == "marker") {
+                fd.value = format!("test_string(\"\", {})", fd.length);
+            }
+            let var = format!("{}_item", rltbc.api_handler_name);
+            body.append(&mut json_object_lines(&var, &fields));
+            body.push(format!("data[\"{}\"] = json!([{}]);", fdname, var));
+            children.push(fdname.clone());
+        } else {
+            body.push(format!("data[\"{}\"] = json!([]);", fdname));
+        }
+        body.push(format!("data[\"{}_deleted\"] = json!([]);", fdname));
+    }
+
+    body.append(&mut call_lines(
+        save,
+        &format!("\"{}\"", save.api_pattern.clone().unwrap_or_default()),
+        Some("data"),
+    ));
+    body.push(format!("assert_api_ok(&ret, \"{}\");", save.func_name));
+
+    let load = find_func(funcs, &format!("{}_rel_load", api));
+    let remove = find_func(funcs, &format!("{}_rel_remove", api));
+    if let (Some(search), Some(load)) = (major_search, load) {
+        if fixture.pk.is_some() {
+            body.append(&mut search_body_lines(&major, &fixture));
+            body.append(&mut call_lines(
+                search,
+                &format!("\"{}\"", search.api_pattern.clone().unwrap_or_default()),
+                Some("query"),
+            ));
+            body.push(format!("assert_api_ok(&ret, \"{}\");", search.func_name));
+            body.append(&mut locate_lines(&fixture, &search.func_name, false, true));
+
+            let load_path = path_with_id(&load.api_pattern.clone().unwrap_or_default());
+            body.append(&mut call_lines(load, &load_path, None));
+            body.push(format!("assert_api_ok(&ret, \"{}\");", load.func_name));
+            for fdname in children.iter() {
+                body.push(format!(
+                    "assert_eq!(ret[\"data\"][\"{}\"].as_array().map(|v| v.len()), Some(1), \"{} did not save {}\");",
+                    fdname, save.func_name, fdname
+                ));
+            }
+
+            if let Some(remove) = remove {
+                body.append(&mut call_lines(
+                    remove,
+                    &path_with_id(&remove.api_pattern.clone().unwrap_or_default()),
+                    None,
+                ));
+                body.push(format!("assert_api_ok(&ret, \"{}\");", remove.func_name));
+                body.append(&mut call_lines(load, &load_path, None));
+                body.push(format!(
+                    "assert!(ret[\"data\"].is_null(), \"{} did not remove the relation\");",
+                    remove.func_name
+                ));
+            }
+        }
+    }
+
+    Some(test_func(
+        &format!("test_{}_relation", api),
+        format!("{}：保存、加载、删除", rel.comment),
+        body,
+    ))
+}
+
+/**
+ * 生成src/tests中的测试文件，files为转换之后的文件
+ */
+pub fn generate_handler_test_files(
+    ctx: &GenerateContext,
+    files: &Vec<RustFileImpl>,
+) -> Vec<RustFileImpl> {
+    let all_funcs = files
+        .iter()
+        .filter(|f| f.mod_name == "handler")
+        .flat_map(|f| f.funclist.iter().cloned())
+        .collect::<Vec<RustFunc>>();
+    let mut tests = vec![];
+    for fl in files.iter().filter(|f| f.mod_name == "handler") {
+        let func = match fl.artifact.clone() {
+            Some(RustArtifact::Handler(tbl_name)) => match ctx.get_table_conf(&tbl_name) {
+                Some(tbc) => generate_table_test(ctx, &tbc, &fl.funclist),
+                None => None,
+            },
+            Some(RustArtifact::RelationHandler(rel_name)) => {
+                match ctx
+                    .codegen_conf
+                    .relations
+                    .iter()
+                    .find(|r| r.struct_name == rel_name)
+                {
+                    Some(rel) => generate_relation_test(ctx, rel, &fl.funclist, &all_funcs),
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        let func = match func {
+            Some(f) => f,
+            None => continue,
+        };
+        let mut usinglist = vec![
+            "actix_web::http::Method".to_string(),
+            "actix_web::{test, App}".to_string(),
+            "serde_json::{json, Value}".to_string(),
+            "super::common::*".to_string(),
+        ];
+        if ctx.is_standalone() {
+            usinglist.push("actix_web::dev::Service".to_string());
+        }
+        tests.push(RustFileImpl {
+            file_name: fl.file_name.trim_end_matches(".rs").to_string() + "_test.rs",
+            mod_name: "tests".to_string(),
+            caretlist: vec![],
+            usinglist,
+            structlist: vec![],
+            funclist: vec![func],
+            implist: vec![],
+            artifact: None,
+        });
+    }
+    tests
+}
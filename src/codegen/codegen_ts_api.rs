@@ -0,0 +1,455 @@
+use change_case::{pascal_case, snake_case};
+use regex::Regex;
+use serde_derive::{Deserialize, Serialize};
+use tera::Context;
+
+use crate::codegen::{
+    GenerateContext, RustArtifact, RustFileImpl, RustFunc, RustStruct, RustStructField,
+    VueAndJSFileImpl,
+};
+use crate::config::{CodeGenConfig, QueryConfig, RelationConfig, TableConfig};
+
+/**
+ * TypeScript的API（api-language: ts）
+ * 代替api-js.js，在front/api/{schema}中生成：
+ * 1、types.ts：ApiResult<T>、Page<T>、CommonSearch，以及entity、query中每个结构体（实体、XxxQuery、XxxValue、XxxPresent、关系、自定义查询）对应的interface
+ * 2、{api}.ts：由api-ts.ts模板生成，每个handler对应一个带类型的函数，关系的handler放在其major-table的文件中，自定义查询单独一个文件
+ * 函数的参数、请求体及返回值的类型来自handler，在ORM及web框架的转换之前生成，转换时被去掉的handler（如CommonSearch）不生成
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TsParam {
+    pub name: String,
+    pub ts_type: String,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TsEndpoint {
+    pub name: String,
+    pub comment: String,
+    pub method: String,
+    pub url: String,          // TypeScript的表达式，有路径参数时为模板字符串
+    pub params: Vec<TsParam>, // 函数的参数，依次为请求体、路径参数及query参数
+    pub data: Option<String>, // 作为请求体的参数
+    pub query: Vec<String>,   // 作为query string的参数
+    pub return_type: String,  // ApiResult<T>中的T
+}
+
+/**
+ * 提供给api-ts.ts模板的上下文
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TsApiContext {
+    pub codegen: CodeGenConfig,
+    pub table_conf: Option<TableConfig>,
+    pub relation_conf: Option<RelationConfig>,
+    pub query_conf: Option<QueryConfig>,
+    pub type_imports: Vec<String>,
+    pub endpoints: Vec<TsEndpoint>,
+}
+
+const TS_BUILTIN_TYPES: &str = r#"/**
+ * 统一的返回结果
+ */
+export interface ApiResult<T> {
+  status: number
+  message: string
+  data?: T | null
+  timestamp?: number | null
+}
+
+/**
+ * 分页查询的结果
+ */
+export interface Page<T> {
+  records: T[]
+  total: number
+  page_no: number
+  page_size: number
+}
+
+/**
+ * 通用查询的一个条件，op为eq、ne、gt、ge、lt、le、like、in、is_null
+ */
+export interface SearchCondition {
+  field: string
+  op: string
+  value?: any
+}
+
+/**
+ * 通用查询，由前端组合查询条件及排序
+ */
+export interface CommonSearch {
+  conditions: SearchCondition[]
+  order_by?: string | null
+  asc?: boolean | null
+}
+"#;
+
+pub fn strip_generic<'a>(tp: &'a str, name: &str) -> Option<&'a str> {
+    tp.strip_prefix(format!("{}<", name).as_str())
+        .and_then(|t| t.strip_suffix('>'))
+        .map(|t| t.trim())
+}
+
+/**
+ * Rust类型对应的TypeScript类型，i64等均使用number，日期、时间及Decimal使用string
+ */
+fn ts_type(tp: &str, structs: &Vec<String>) -> String {
+    let tp = tp.trim().trim_start_matches('&').trim();
+    if let Some(inner) = strip_generic(tp, "Option") {
+        return format!("{} | null", ts_type(inner, structs));
+    }
+    if let Some(inner) = strip_generic(tp, "Vec") {
+        let item = ts_type(inner, structs);
+        if item.contains(' ') {
+            return format!("({})[]", item);
+        }
+        return format!("{}[]", item);
+    }
+    if let Some(inner) = strip_generic(tp, "Page").or(strip_generic(tp, "rbatis::Page")) {
+        return format!("Page<{}>", ts_type(inner, structs));
+    }
+    match tp.trim_start_matches("rbatis::") {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128" | "isize"
+        | "usize" | "f32" | "f64" => "number".to_string(),
+        "bool" => "boolean".to_string(),
+        "String" | "str" | "DateTimeNative" | "DateTimeUtc" | "Timestamp" | "TimestampZ"
+        | "DateNative" | "DateUtc" | "TimeNative" | "TimeUtc" | "Decimal" | "Bytes" => {
+            "string".to_string()
+        }
+        "CommonSearch" | "chimes_utils::CommonSearch" => "CommonSearch".to_string(),
+        other => {
+            let name = other.rsplit("::").next().unwrap_or(other);
+            if structs.contains(&name.to_string()) {
+                name.to_string()
+            } else {
+                "any".to_string()
+            }
+        }
+    }
+}
+
+fn ts_field(fd: &RustStructField, structs: &Vec<String>) -> Vec<String> {
+    let mut lines = vec![];
+    if let Some(cmt) = fd.comment.clone().filter(|c| !c.trim().is_empty()) {
+        lines.push(format!(
+            "  /** {} */",
+            cmt.replace("*/", "* /").replace('\n', " ")
+        ));
+    }
+    let name = fd.field_name.trim_start_matches("r#");
+    // skip_serializing_if的字段在JSON中可能不存在
+    if fd.is_option
+        || fd
+            .annotations
+            .iter()
+            .any(|an| an.contains("skip_serializing_if"))
+    {
+        lines.push(format!(
+            "  {}?: {} | null",
+            name,
+            ts_type(&fd.field_type, structs)
+        ));
+    } else {
+        lines.push(format!("  {}: {}", name, ts_type(&fd.field_type, structs)));
+    }
+    lines
+}
+
+fn ts_interface(st: &RustStruct, structs: &Vec<String>) -> String {
+    let mut lines = vec![format!("export interface {} {{", st.struct_name)];
+    for fd in st.fields.iter() {
+        if fd.field_name.is_empty() || fd.annotations.iter().any(|an| an.contains("serde(skip)")) {
+            continue;
+        }
+        lines.append(&mut ts_field(fd, structs));
+    }
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn collect_ts_structs(files: &[RustFileImpl]) -> Vec<RustStruct> {
+    files
+        .iter()
+        .filter(|f| f.mod_name == "entity" || f.mod_name == "query")
+        .flat_map(|f| f.structlist.iter().cloned())
+        .filter(|st| !st.fields.is_empty() && st.fields.iter().any(|fd| !fd.field_name.is_empty()))
+        .collect()
+}
+
+/**
+ * 生成types.ts
+ */
+pub fn generate_ts_types(files: &[RustFileImpl]) -> String {
+    let structs = collect_ts_structs(files);
+    let names = structs
+        .iter()
+        .map(|st| st.struct_name.clone())
+        .collect::<Vec<String>>();
+    let mut text = TS_BUILTIN_TYPES.to_string();
+    for st in structs.iter() {
+        text.push('\n');
+        text.push_str(&ts_interface(st, &names));
+    }
+    text
+}
+
+/**
+ * 函数名：去掉handler名称中的api-handler-name前缀，新增、修改、删除等沿用api-js.js中的名称
+ */
+fn ts_func_name(func: &RustFunc, prefix: &str) -> String {
+    let suffix = func
+        .func_name
+        .strip_prefix(format!("{}_", prefix).as_str())
+        .unwrap_or(func.func_name.as_str());
+    match suffix {
+        "save" => "add".to_string(),
+        "update" => "edit".to_string(),
+        "delete" => "del".to_string(),
+        "delete_ids" => "delIds".to_string(),
+        _ => {
+            let name = pascal_case(suffix);
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(c) => c.to_lowercase().collect::<String>() + chars.as_str(),
+                None => name,
+            }
+        }
+    }
+}
+
+fn ts_endpoint(func: &RustFunc, prefix: &str, structs: &Vec<String>) -> Option<TsEndpoint> {
+    let (method, pattern) = match (func.api_method.clone(), func.api_pattern.clone()) {
+        (Some(m), Some(p)) => (m.to_lowercase(), p),
+        _ => return None,
+    };
+    let mut params = vec![];
+    let mut data = None;
+    for (_, ptype) in func.params.iter() {
+        if let Some(body) = strip_generic(ptype, "web::Json") {
+            params.push(TsParam {
+                name: "data".to_string(),
+                ts_type: ts_type(body, structs),
+                optional: false,
+            });
+            data = Some("data".to_string());
+        }
+    }
+
+    // web::Path<(A, B)>按顺序对应路径中的各个{name}
+    let mut path_types = vec![];
+    for (_, ptype) in func.params.iter() {
+        if let Some(inner) = strip_generic(ptype, "web::Path") {
+            match inner.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+                Some(tuple) => path_types.extend(tuple.split(',').map(|t| t.trim().to_string())),
+                None => path_types.push(inner.to_string()),
+            }
+        }
+    }
+    let reg = Regex::new(r"\{(\w+)\}").unwrap();
+    for (i, cap) in reg.captures_iter(&pattern).enumerate() {
+        params.push(TsParam {
+            name: cap[1].to_string(),
+            ts_type: ts_type(
+                &path_types.get(i).cloned().unwrap_or("String".to_string()),
+                structs,
+            ),
+            optional: false,
+        });
+    }
+    let url = if reg.is_match(&pattern) {
+        format!("`{}`", reg.replace_all(&pattern, "$${$1}"))
+    } else {
+        format!("'{}'", pattern)
+    };
+
+    let mut query = vec![];
+    let qreg = Regex::new(r#"get_hash_value\(&dic, "(\w+)"\)"#).unwrap();
+    for ln in func.bodylines.iter() {
+        for cap in qreg.captures_iter(ln) {
+            if !query.contains(&cap[1].to_string()) {
+                query.push(cap[1].to_string());
+                params.push(TsParam {
+                    name: cap[1].to_string(),
+                    ts_type: "string | number".to_string(),
+                    optional: true,
+                });
+            }
+        }
+    }
+
+    let rreg = Regex::new(r"web::Json<ApiResult<(.+)>> = web::Json\(ApiResult::ok\(").unwrap();
+    let return_type = func
+        .bodylines
+        .iter()
+        .find_map(|ln| rreg.captures(ln).map(|cap| ts_type(&cap[1], structs)))
+        .unwrap_or("any".to_string());
+
+    Some(TsEndpoint {
+        name: ts_func_name(func, prefix),
+        comment: func.comment.clone().unwrap_or_default(),
+        method,
+        url,
+        params,
+        data,
+        query,
+        return_type,
+    })
+}
+
+/**
+ * 函数中用到的types.ts中的类型
+ */
+fn ts_type_imports(endpoints: &[TsEndpoint], structs: &[String]) -> Vec<String> {
+    let mut imports = vec!["ApiResult".to_string()];
+    let reg = Regex::new(r"[A-Za-z_]\w*").unwrap();
+    for ep in endpoints.iter() {
+        let mut types = ep
+            .params
+            .iter()
+            .map(|p| p.ts_type.clone())
+            .collect::<Vec<String>>();
+        types.push(ep.return_type.clone());
+        for tp in types.iter() {
+            for m in reg.find_iter(tp) {
+                let name = m.as_str().to_string();
+                let known = name == "Page" || name == "CommonSearch" || structs.contains(&name);
+                if known && !imports.contains(&name) {
+                    imports.push(name);
+                }
+            }
+        }
+    }
+    imports
+}
+
+/**
+ * 按handler文件生成{api}.ts，关系的handler合并到其major-table的文件中
+ * surviving为ORM及web框架转换之后仍然存在的handler
+ */
+pub fn generate_ts_api_files(
+    ctx: &GenerateContext,
+    files: &[RustFileImpl],
+    surviving: &[String],
+) -> Vec<VueAndJSFileImpl> {
+    let structs = collect_ts_structs(files)
+        .iter()
+        .map(|st| st.struct_name.clone())
+        .collect::<Vec<String>>();
+    let mut apis: Vec<(String, TsApiContext)> = vec![];
+
+    for fl in files.iter().filter(|f| f.mod_name == "handler") {
+        let (api_name, prefix, mut tsctx) = match fl.artifact.clone() {
+            Some(RustArtifact::Handler(tbl_name)) => {
+                let tbc = match ctx.get_table_conf(&tbl_name) {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let tsctx = TsApiContext {
+                    codegen: ctx.codegen_conf.clone(),
+                    table_conf: Some(tbc.clone()),
+                    ..Default::default()
+                };
+                (
+                    tbc.api_handler_name.clone(),
+                    tbc.api_handler_name.clone(),
+                    tsctx,
+                )
+            }
+            Some(RustArtifact::RelationHandler(rel_name)) => {
+                let rel = match ctx
+                    .codegen_conf
+                    .relations
+                    .iter()
+                    .find(|r| r.struct_name == rel_name)
+                {
+                    Some(r) => r.clone(),
+                    None => continue,
+                };
+                let rel_api = rel.api_handler_name.clone().unwrap_or_default();
+                let api_name = match ctx.get_table_conf(&rel.major_table) {
+                    Some(tbc) if tbc.generate_handler => tbc.api_handler_name.clone(),
+                    _ => rel_api.clone(),
+                };
+                let tsctx = TsApiContext {
+                    codegen: ctx.codegen_conf.clone(),
+                    relation_conf: Some(rel.clone()),
+                    ..Default::default()
+                };
+                (api_name, rel_api, tsctx)
+            }
+            Some(RustArtifact::QueryHandler(qry_name)) => {
+                let qry = match ctx
+                    .codegen_conf
+                    .queries
+                    .iter()
+                    .find(|q| q.struct_name == qry_name)
+                {
+                    Some(q) => q.clone(),
+                    None => continue,
+                };
+                let tsctx = TsApiContext {
+                    codegen: ctx.codegen_conf.clone(),
+                    query_conf: Some(qry.clone()),
+                    ..Default::default()
+                };
+                (
+                    qry.api_handler_name.clone(),
+                    snake_case(&qry.struct_name),
+                    tsctx,
+                )
+            }
+            _ => continue,
+        };
+
+        tsctx.endpoints = fl
+            .funclist
+            .iter()
+            .filter(|f| surviving.contains(&f.func_name))
+            .filter_map(|f| ts_endpoint(f, &prefix, &structs))
+            .collect();
+
+        match apis.iter_mut().find(|(name, _)| *name == api_name) {
+            Some((_, exist)) => {
+                exist.endpoints.append(&mut tsctx.endpoints);
+                if tsctx.table_conf.is_some() {
+                    exist.table_conf = tsctx.table_conf;
+                }
+                if tsctx.relation_conf.is_some() {
+                    exist.relation_conf = tsctx.relation_conf;
+                }
+            }
+            None => apis.push((api_name, tsctx)),
+        }
+    }
+
+    let mut outputs = vec![VueAndJSFileImpl {
+        file_name: "types.ts".to_string(),
+        mod_name: "".to_string(),
+        js_vue: false,
+        usinglist: vec![],
+        funclist: vec![generate_ts_types(files)],
+    }];
+    for (api_name, mut tsctx) in apis.into_iter() {
+        if tsctx.endpoints.is_empty() {
+            continue;
+        }
+        tsctx.type_imports = ts_type_imports(&tsctx.endpoints, &structs);
+        let context = match Context::from_serialize(&tsctx) {
+            Ok(c) => c,
+            Err(_) => Context::new(),
+        };
+        if let Some(text) = ctx.render_template("api-ts.ts", &api_name, &context) {
+            outputs.push(VueAndJSFileImpl {
+                file_name: api_name.clone() + ".ts",
+                mod_name: "".to_string(),
+                js_vue: false,
+                usinglist: vec![],
+                funclist: vec![text],
+            });
+        }
+    }
+    outputs
+}
@@ -0,0 +1,178 @@
+use change_case::pascal_case;
+
+use crate::codegen::{
+    parse_data_type_as_rust_type, GenerateContext, RustArtifact, RustFileImpl, RustFunc,
+};
+use crate::config::safe_struct_field_name;
+
+/**
+ * 乐观锁
+ * 配置了version-field的表，更新时加入version = 当前值的条件，并将该列加1，
+ * 没有更新到数据时（已被其它人修改）返回版本冲突的错误
+ */
+#[derive(Debug, Clone)]
+pub struct VersionField {
+    pub column: String,
+    pub field_name: String,
+    pub is_option: bool,
+}
+
+/**
+ * 表的版本列，没有配置version-field、表中没有该列或者该列不是整数时返回None
+ */
+pub fn get_version_field(ctx: &GenerateContext, tbl_name: &String) -> Option<VersionField> {
+    let tbc = ctx.get_table_conf(tbl_name)?;
+    let col = ctx.find_table_column(tbl_name, &tbc.version_field?)?;
+    // 只支持整数的列
+    let field_type =
+        parse_data_type_as_rust_type(&col.data_type.clone().unwrap_or_default().to_lowercase());
+    if !field_type.starts_with('i') && !field_type.starts_with('u') {
+        return None;
+    }
+    let column = col.column_name.clone().unwrap_or_default();
+    Some(VersionField {
+        field_name: safe_struct_field_name(&column.to_lowercase()),
+        column,
+        is_option: tbc.all_field_option
+            || col.is_nullable.clone().unwrap_or_default().to_lowercase() == "yes",
+    })
+}
+
+impl VersionField {
+    /**
+     * 更新时的条件，按rbatis 3.x的wrapper生成
+     * 可为空的列，版本为None时匹配该列为NULL的数据（如加入版本列之前已有的数据）
+     */
+    pub fn update_condition(&self) -> Vec<String> {
+        if self.is_option {
+            vec![
+                format!(
+                    "    .r#if(self.{}.is_some(), |w| w.and().eq(\"{}\", self.{}.unwrap()))",
+                    self.field_name, self.column, self.field_name
+                ),
+                format!(
+                    "    .r#if(self.{}.is_none(), |w| w.and().is_null(\"{}\"))",
+                    self.field_name, self.column
+                ),
+            ]
+        } else {
+            vec![format!("    .eq(\"{}\", self.{})", self.column, self.field_name)]
+        }
+    }
+
+    /**
+     * rbatis 4.x、sqlx在SQL中将版本加1，为NULL时从0开始
+     */
+    pub fn increment_sql(&self) -> String {
+        format!("{} = COALESCE({}, 0) + 1", self.column, self.column)
+    }
+
+    /**
+     * rbatis 3.x按实体的值更新，在wrapper生成之后将版本加1
+     * 转换为rbatis 4.x、sqlx时该行被丢弃，由SQL中的version = version + 1代替
+     */
+    pub fn increment_line(&self) -> String {
+        format!(
+            "self.{} = {};",
+            self.field_name,
+            self.next_value(&format!("self.{}", self.field_name))
+        )
+    }
+
+    fn next_value(&self, current: &str) -> String {
+        if self.is_option {
+            format!("Some({}.unwrap_or_default() + 1)", current)
+        } else {
+            format!("{} + 1", current)
+        }
+    }
+}
+
+/**
+ * 执行更新出错时的返回，有版本列时版本冲突返回5409
+ */
+pub fn update_error_line(ret_type: &str, code: i32, versioned: bool) -> String {
+    if versioned {
+        format!(
+            "let ret: web::Json<ApiResult<{}>> = web::Json(if crate::utils::is_version_conflict(&err) {{ ApiResult::error(5409, &\"数据已被修改，请刷新后重试\".to_string()) }} else {{ ApiResult::error({}, &err.to_string()) }});",
+            ret_type, code
+        )
+    } else {
+        format!(
+            "let ret: web::Json<ApiResult<{}>> = web::Json(ApiResult::error({}, &err.to_string()));",
+            ret_type, code
+        )
+    }
+}
+
+/**
+ * 乐观锁的检查
+ * 在ORM转换之后进行，为配置了version-field的实体：
+ * 1、save时版本为空则从0开始
+ * 2、update/update_selective没有更新到数据时返回版本冲突的错误，成功时实体的版本加1，失败时恢复原来的版本
+ */
+pub fn apply_optimistic_lock(ctx: &GenerateContext, files: &mut Vec<RustFileImpl>) {
+    for fl in files.iter_mut() {
+        let table = match fl.artifact.clone() {
+            Some(RustArtifact::Entity(t)) => t,
+            _ => continue,
+        };
+        let vf = match get_version_field(ctx, &table) {
+            Some(t) => t,
+            None => continue,
+        };
+        let struct_name = match ctx.get_struct_name(&table) {
+            Some(t) => t,
+            None => pascal_case(&table),
+        };
+        // 版本冲突的错误由生成的utils提供，handler中通过is_version_conflict判断
+        let conflict = "Err(crate::utils::version_conflict_error())".to_string();
+        if let Some(st) = fl
+            .structlist
+            .iter_mut()
+            .find(|s| s.struct_name == struct_name)
+        {
+            for func in st.funclist.iter_mut() {
+                match func.func_name.as_str() {
+                    "save" if vf.is_option => {
+                        func.bodylines.insert(
+                            0,
+                            format!(
+                                "if self.{}.is_none() {{ self.{} = Some(0); }}",
+                                vf.field_name, vf.field_name
+                            ),
+                        );
+                    }
+                    "update" | "update_selective" => wrap_version_check(func, &vf, &conflict),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/**
+ * 原来的方法体放在块中，根据更新的行数设置版本或者返回版本冲突
+ */
+fn wrap_version_check(func: &mut RustFunc, vf: &VersionField, conflict: &str) {
+    let fd = vf.field_name.clone();
+    let mut body = vec![format!("let ver = self.{};", fd)];
+    body.push("let ret: Result<u64, Error> = {".to_string());
+    body.append(&mut func.bodylines.clone());
+    body.push("};".to_string());
+    body.push("match ret {".to_string());
+    body.push("Ok(0) => {".to_string());
+    body.push(format!("self.{} = ver;", fd));
+    body.push(conflict.to_string());
+    body.push("}".to_string());
+    body.push("Ok(n) => {".to_string());
+    body.push(format!("self.{} = {};", fd, vf.next_value("ver")));
+    body.push("Ok(n)".to_string());
+    body.push("}".to_string());
+    body.push("Err(err) => {".to_string());
+    body.push(format!("self.{} = ver;", fd));
+    body.push("Err(err)".to_string());
+    body.push("}".to_string());
+    body.push("}".to_string());
+    func.bodylines = body;
+}
@@ -1,19 +1,16 @@
 use crate::codegen::GenerateContext;
-use crate::config::TEMPLATES;
 use crate::schema::TableInfo;
 use std::collections::HashSet;
 use tera::Context;
 
-use super::{parse_composite_column_list, tera_pascal, RelationForm, RustStruct};
+use super::{parse_composite_column_list, RelationForm, RustStruct};
 
 pub fn generate_vue_view_for_table(
     ctx: &mut GenerateContext,
     tbl: &TableInfo,
     comps: &Option<RustStruct>,
-) -> Vec<String> {
-    let mut tera = TEMPLATES.clone();
+) -> Option<String> {
     let tbl_name = tbl.table_name.clone().unwrap_or_default();
-    tera.register_function("pascal", tera_pascal);
 
     let tbl = ctx.get_table_info(&tbl_name);
     let tbc = ctx.get_table_conf(&tbl_name);
@@ -76,15 +73,19 @@ pub fn generate_vue_view_for_table(
         context.insert("composition_struct", &comps.clone().unwrap());
     }
 
-    match tera.render("index.vue", &context) {
-        Ok(text) => vec![text],
-        Err(err) => {
-            log::info!(
-                "Error for parse the index.vue for {} template: {}",
-                tbl_name.clone(),
-                err
-            );
-            vec![]
-        }
-    }
+    context.insert("typescript_api", &ctx.is_typescript_api());
+    context.insert("relation_tables", &relform.get_relation_tables());
+
+    let tmpl = ctx.get_frontend_template("index.vue");
+    ctx.render_template(&tmpl, &tbl_name, &context)
+}
+
+/**
+ * vue3-element-plus的composables或react-antd的hooks，各页面共用（字典、列表、分页及树形的加载）
+ */
+pub fn generate_frontend_composables(ctx: &GenerateContext) -> Option<String> {
+    let mut context = Context::new();
+    context.insert("codegen", &ctx.codegen_conf);
+    let tmpl = ctx.get_frontend_template("useCrud.ts");
+    ctx.render_template(&tmpl, "useCrud.ts", &context)
 }
@@ -0,0 +1,221 @@
+use regex::Regex;
+
+use crate::codegen::RustFunc;
+
+/**
+ * rbatis 3.x的wrapper的解析
+ * 实体及参数的方法先按rbatis 3.x的wrapper生成，再由rbatis 4.x、sqlx等输出目标解析后转换为各自的形式
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum WrapperKind {
+    SelectOne,
+    SelectList,
+    Page,
+    Delete,
+    Update(Vec<String>, bool), // 跳过的列，是否跳过空值（update_selective）
+    UpdateSet(String),         // 更新为固定的值，如逻辑删除的deleted = 1
+}
+
+pub struct WrapperCall {
+    pub kind: WrapperKind,
+    pub entity: String,
+    pub page: Vec<String>, // 分页的参数，如curr, ps
+}
+
+/**
+ * wrapper的一个条件
+ * .r#if(self.name.is_some(), |w| w.and().eq("name", self.name.clone().unwrap()))
+ * => test: self.name.is_some(), op: eq, column: name, args: [self.name.clone().unwrap()]
+ */
+#[derive(Debug, Clone)]
+pub struct WrapperCondition {
+    pub test: Option<String>, // r#if的条件，为Rust的表达式
+    pub op: String,           // eq、ne、like、in、between、push_sql等
+    pub column: String,       // 列名，push_sql时为SQL片段
+    pub args: Vec<String>,    // 列之后的参数，为Rust的表达式
+}
+
+/**
+ * 查找方法中最后执行的wrapper调用，如rb.fetch_list_by_wrapper::<Self>(wp)
+ */
+pub fn find_wrapper_call(func: &RustFunc) -> Option<WrapperCall> {
+    let fetch = Regex::new(r"fetch_by_wrapper::<Option<(\w+)>>").unwrap();
+    let list = Regex::new(r"fetch_list_by_wrapper::<(\w+)>").unwrap();
+    let page =
+        Regex::new(r"fetch_page_by_wrapper::<(\w+)>\(wp, &PageRequest::new\((\w+), (\w+)\)\)")
+            .unwrap();
+    let remove = Regex::new(r"remove_by_wrapper::<(\w+)>").unwrap();
+    let update = Regex::new(r"update_by_wrapper\(self, wp, &\[(.*)\]\)").unwrap();
+    let updateset = Regex::new(r#"format!\("UPDATE \w+ SET (.+) WHERE \{\}", wp\.sql\)"#).unwrap();
+
+    for ln in func.bodylines.iter().rev() {
+        if let Some(cap) = fetch.captures(ln) {
+            return Some(WrapperCall {
+                kind: WrapperKind::SelectOne,
+                entity: cap[1].to_string(),
+                page: vec![],
+            });
+        }
+        if let Some(cap) = list.captures(ln) {
+            return Some(WrapperCall {
+                kind: WrapperKind::SelectList,
+                entity: cap[1].to_string(),
+                page: vec![],
+            });
+        }
+        if let Some(cap) = page.captures(ln) {
+            return Some(WrapperCall {
+                kind: WrapperKind::Page,
+                entity: cap[1].to_string(),
+                page: vec![cap[2].to_string(), cap[3].to_string()],
+            });
+        }
+        if let Some(cap) = remove.captures(ln) {
+            return Some(WrapperCall {
+                kind: WrapperKind::Delete,
+                entity: cap[1].to_string(),
+                page: vec![],
+            });
+        }
+        if let Some(cap) = update.captures(ln) {
+            let selective = cap[1].contains("Skip::Value(Bson::Null)");
+            return Some(WrapperCall {
+                kind: WrapperKind::Update(parse_skip_columns(&cap[1]), selective),
+                entity: "Self".to_string(),
+                page: vec![],
+            });
+        }
+        if let Some(cap) = updateset.captures(ln) {
+            return Some(WrapperCall {
+                kind: WrapperKind::UpdateSet(cap[1].to_string()),
+                entity: "Self".to_string(),
+                page: vec![],
+            });
+        }
+    }
+    None
+}
+
+/**
+ * Skip::Column("x")中跳过的列
+ */
+pub fn parse_skip_columns(text: &str) -> Vec<String> {
+    let skipcol = Regex::new("Skip::Column\\(\"(\\w+)\"\\)").unwrap();
+    skipcol
+        .captures_iter(text)
+        .map(|c| c[1].to_string())
+        .collect::<Vec<String>>()
+}
+
+/**
+ * 方法中wrapper的条件，即new_wrapper()之后直到以;结束的各行
+ * 没有使用wrapper时返回None，有无法识别的条件时返回Err(该行)
+ */
+pub fn parse_wrapper_conditions(func: &RustFunc) -> Option<Result<Vec<WrapperCondition>, String>> {
+    let wpos = func
+        .bodylines
+        .iter()
+        .position(|l| l.contains("new_wrapper()"))?;
+
+    let mut conds = vec![];
+    if func.bodylines[wpos].trim().ends_with(";") {
+        return Some(Ok(conds));
+    }
+    for ln in func.bodylines.iter().skip(wpos + 1) {
+        let cond = ln.trim().trim_end_matches(';');
+        if cond != ".and()" {
+            match parse_wrapper_condition(cond) {
+                Some(c) => conds.push(c),
+                None => return Some(Err(cond.to_string())),
+            }
+        }
+        if ln.trim().ends_with(";") {
+            break;
+        }
+    }
+    Some(Ok(conds))
+}
+
+fn parse_wrapper_condition(line: &str) -> Option<WrapperCondition> {
+    let line = line.trim();
+    let (test, op) = if line.starts_with(".r#if(") && line.ends_with(")") {
+        let inner = &line[".r#if(".len()..line.len() - 1];
+        let pos = inner.find(", |w| w.and().")?;
+        (
+            Some(inner[..pos].trim().to_string()),
+            inner[pos + ", |w| w.and().".len()..].trim().to_string(),
+        )
+    } else if line.starts_with(".and().") {
+        (None, line[".and().".len()..].trim().to_string())
+    } else if line.starts_with(".") {
+        (None, line[1..].trim().to_string())
+    } else {
+        return None;
+    };
+
+    if let Some(rest) = op.strip_prefix("push_sql(\"") {
+        // 全文检索：push_sql(" (match(cols) against(?)) ").push_arg(x)
+        let pos = rest.find("\").push_arg(")?;
+        return Some(WrapperCondition {
+            test,
+            op: "push_sql".to_string(),
+            column: rest[..pos].to_string(),
+            args: vec![rest[pos + "\").push_arg(".len()..rest.len() - 1].to_string()],
+        });
+    }
+
+    let pos = op.find('(')?;
+    if !op.ends_with(")") {
+        return None;
+    }
+    let mut args = split_args(&op[pos + 1..op.len() - 1]);
+    if args.is_empty() {
+        return None;
+    }
+    let column = args.remove(0).trim_matches('"').to_string();
+    Some(WrapperCondition {
+        test,
+        op: op[..pos].trim_start_matches("r#").to_string(),
+        column,
+        args,
+    })
+}
+
+/**
+ * 按顶层的逗号拆分参数
+ */
+pub fn split_args(text: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut in_str = false;
+    let mut current = String::new();
+    for ch in text.chars() {
+        match ch {
+            '"' => in_str = !in_str,
+            '(' | '[' | '<' if !in_str => depth += 1,
+            ')' | ']' | '>' if !in_str => depth -= 1,
+            ',' if !in_str && depth == 0 => {
+                args.push(current.trim().to_string());
+                current = String::new();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+    }
+    if !current.trim().is_empty() {
+        args.push(current.trim().to_string());
+    }
+    args
+}
+
+/**
+ * 使用CommonSearch（chimes-utils）的方法，其依赖于rbatis 3.x的wrapper
+ */
+pub fn is_common_search_func(func: &RustFunc) -> bool {
+    func.params.iter().any(|p| p.1.contains("CommonSearch"))
+        || func
+            .bodylines
+            .iter()
+            .any(|l| l.contains("into_wrapper(") || l.contains("::common_query_"))
+}
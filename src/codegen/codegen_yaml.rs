@@ -28,6 +28,8 @@ pub fn parse_yaml_as_file(conf_path: &str, filename: &String) -> RustFileImpl {
         usinglist: usinglist,
         structlist: sts,
         funclist: vec![],
+        implist: vec![],
+        artifact: None,
     }
 }
 
@@ -25,6 +25,62 @@ pub use codegen_js_api::*;
 mod codegen_vue_view;
 pub use codegen_vue_view::*;
 
+mod codegen_template;
+pub use codegen_template::*;
+
+mod codegen_service;
+pub use codegen_service::*;
+
+mod codegen_axum;
+pub use codegen_axum::*;
+
+mod codegen_wrapper;
+pub use codegen_wrapper::*;
+
+mod codegen_logic_deletion;
+pub use codegen_logic_deletion::*;
+mod codegen_version;
+pub use codegen_version::*;
+
+mod codegen_rbatis4;
+pub use codegen_rbatis4::*;
+
+mod codegen_sqlx;
+pub use codegen_sqlx::*;
+
+mod codegen_standalone;
+pub use codegen_standalone::*;
+
+mod codegen_grpc;
+pub use codegen_grpc::*;
+
+mod codegen_graphql;
+pub use codegen_graphql::*;
+
+mod codegen_openapi;
+pub use codegen_openapi::*;
+
+mod codegen_ts_api;
+pub use codegen_ts_api::*;
+
+mod codegen_client;
+pub use codegen_client::*;
+
+mod codegen_test;
+pub use codegen_test::*;
+
+mod codegen_seed;
+pub use codegen_seed::*;
+
+mod codegen_ddl;
+pub use codegen_ddl::*;
+
+mod codegen_cache;
+pub use codegen_cache::*;
+
+mod codegen_incremental;
+pub use codegen_incremental::*;
+
 pub fn is_date_time_type(dt: &String) -> bool {
     match dt.as_str() {
         "rbatis::DateTimeNative" => true,
@@ -1,4 +1,5 @@
 use change_case::snake_case;
+use crate::output::{match_file_pattern, WritePolicy};
 use rbatis::rbatis::Rbatis;
 use redis::{FromRedisValue, RedisError, RedisResult, Value};
 use serde_derive::{Deserialize, Serialize};
@@ -9,7 +10,6 @@ use std::mem::MaybeUninit;
 use std::str::FromStr;
 use std::sync::{Mutex, Once};
 use substring::Substring;
-use tera::Tera;
 use yaml_rust::Yaml;
 
 #[derive(Debug, Clone, Default)]
@@ -79,22 +79,6 @@ lazy_static! {
     };
 }
 
-lazy_static! {
-    pub static ref TEMPLATES: Tera = {
-        let mut tera = match Tera::new("templates/**/*") {
-            Ok(t) => t,
-            Err(e) => {
-                println!("Parsing error(s): {}", e);
-                ::std::process::exit(1);
-            }
-        };
-        tera.autoescape_on(vec!["html", ".sql"]);
-        tera.build_inheritance_chains().unwrap();
-        // tera.register_filter("do_nothing", do_nothing_filter);
-        tera
-    };
-}
-
 pub fn safe_struct_field_name(oldname: &String) -> String {
     let m_oldname = oldname.clone();
     let reg = regex::Regex::from_str("\\.").unwrap();
@@ -345,13 +329,20 @@ pub struct TableConfig {
     pub with_attachment: bool,
     pub page_query: bool,
     pub logic_deletion: bool,
-    pub cache_by_fields: Option<String>, // TODO: give a field and generate related cache method get/set/del
+    pub logic_deletion_field: String,  // 逻辑删除的列，默认为deleted
+    pub logic_deleted_value: String,   // 已删除的值，默认为1
+    pub logic_undeleted_value: String, // 未删除的值，默认为0
+    pub cache_by_fields: Option<String>, // 按这些字段（逗号分隔）的值生成Redis缓存的get/set/del，需要allow-redis-cache
+    pub cache_ttl: u64,                  // 缓存的过期时间（秒），默认为3600
+    pub version_field: Option<String>,   // 乐观锁的版本列，更新时检查并递增该列的值
     pub full_text_columns: Option<String>,
     pub datetime_between_columns: Option<String>,
     pub in_columns: Option<String>,
     pub in_spliter: Option<String>,
     pub generate_handler: bool,
     pub simple_funclist: Vec<SimpleFuncation>, //定义简单的查询方法，根据指定的字段来进行简单的查询
+    pub columns: Vec<ColumnConfig>, // model-first时声明的列，有声明时不再从数据库中读取该表的结构
+    pub indexes: Vec<IndexConfig>, // model-first时声明的索引，用于生成DDL
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -628,6 +619,246 @@ impl RelationConfig {
     }
 }
 
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FilePolicyConfig {
+    pub pattern: String,
+    pub policy: WritePolicy,
+}
+
+impl FilePolicyConfig {
+    pub fn load_from_yaml_node(node: &Yaml) -> Vec<Self> {
+        let mut policies = vec![];
+        if let Some(nodes) = node.as_vec() {
+            for nd in nodes {
+                let pattern = match nd["pattern"].as_str() {
+                    Some(s) => s.to_string(),
+                    None => continue,
+                };
+                match WritePolicy::parse(nd["policy"].as_str().unwrap_or_default()) {
+                    Some(policy) => policies.push(Self { pattern, policy }),
+                    None => {
+                        log::warn!(
+                            "Unknown file policy {} for {}, the policy should be overwrite, skip-if-exists or merge.",
+                            nd["policy"].as_str().unwrap_or_default(),
+                            pattern
+                        );
+                    }
+                }
+            }
+        }
+        policies
+    }
+}
+
+/**
+ * model-first时在表中声明的列
+ * type按MySQL的类型书写，如bigint、varchar(64)、decimal(10,2)，长度也可以通过length、precision、scale指定
+ * comment中同样可以使用字典、关联表、必填等说明
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ColumnConfig {
+    pub name: String,
+    pub data_type: String, // 不带长度的类型，小写
+    pub column_type: String, // 完整的类型，如varchar(64)
+    pub length: Option<i64>,
+    pub precision: Option<i64>,
+    pub scale: Option<i64>,
+    pub nullable: bool, // 默认可为空，主键不可为空
+    pub default_value: Option<String>,
+    pub comment: String,
+    pub primary_key: bool,
+    pub auto_increment: bool,
+}
+
+impl ColumnConfig {
+    pub fn load_from_yaml_node(node: &Yaml) -> Vec<Self> {
+        let mut columns = vec![];
+        if let Some(nodes) = node.as_vec() {
+            for nd in nodes {
+                let name = match nd["name"].as_str() {
+                    Some(s) => s.to_string(),
+                    None => continue,
+                };
+                let text = nd["type"].as_str().unwrap_or("varchar").trim().to_lowercase();
+                let (data_type, args) = match text.find('(') {
+                    Some(pos) => (
+                        text[..pos].trim().to_string(),
+                        text[pos + 1..]
+                            .split(')')
+                            .next()
+                            .unwrap_or_default()
+                            .split(',')
+                            .filter_map(|a| a.trim().parse::<i64>().ok())
+                            .collect::<Vec<i64>>(),
+                    ),
+                    None => (
+                        text.split_whitespace().next().unwrap_or_default().to_string(),
+                        vec![],
+                    ),
+                };
+                let is_decimal = data_type == "decimal" || data_type == "numeric";
+                let length = nd["length"].as_i64().or(if is_decimal {
+                    None
+                } else if data_type == "varchar" && args.is_empty() {
+                    Some(255)
+                } else {
+                    args.first().copied()
+                });
+                let precision = nd["precision"].as_i64().or(if is_decimal {
+                    args.first().copied()
+                } else {
+                    None
+                });
+                let scale = nd["scale"].as_i64().or(if is_decimal {
+                    args.get(1).copied()
+                } else {
+                    None
+                });
+                let column_type = if text.contains('(') {
+                    text.clone()
+                } else if let Some(l) = length {
+                    format!("{}({})", data_type, l)
+                } else if let Some(p) = precision {
+                    format!("{}({},{})", data_type, p, scale.unwrap_or_default())
+                } else {
+                    text.clone()
+                };
+                let primary_key = nd["primary-key"].as_bool().unwrap_or_default();
+                columns.push(Self {
+                    name,
+                    data_type,
+                    column_type,
+                    length,
+                    precision,
+                    scale,
+                    nullable: nd["nullable"].as_bool().unwrap_or(!primary_key),
+                    default_value: match &nd["default"] {
+                        Yaml::String(s) => Some(s.clone()),
+                        Yaml::Integer(i) => Some(i.to_string()),
+                        Yaml::Real(r) => Some(r.clone()),
+                        Yaml::Boolean(b) => Some(if *b { "1" } else { "0" }.to_string()),
+                        _ => None,
+                    },
+                    comment: nd["comment"].as_str().unwrap_or_default().to_string(),
+                    primary_key,
+                    auto_increment: nd["auto-increment"].as_bool().unwrap_or_default(),
+                });
+            }
+        }
+        columns
+    }
+}
+
+/**
+ * model-first时在表中声明的索引，columns为逗号分隔的列名
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IndexConfig {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+impl IndexConfig {
+    pub fn load_from_yaml_node(node: &Yaml, table: &str) -> Vec<Self> {
+        let mut indexes = vec![];
+        if let Some(nodes) = node.as_vec() {
+            for nd in nodes {
+                let columns = match &nd["columns"] {
+                    Yaml::String(s) => s.split(',').map(|c| c.trim().to_string()).collect(),
+                    Yaml::Array(vs) => vs
+                        .iter()
+                        .filter_map(|v| v.as_str().map(|c| c.trim().to_string()))
+                        .collect(),
+                    _ => vec![],
+                }
+                .into_iter()
+                .filter(|c: &String| !c.is_empty())
+                .collect::<Vec<String>>();
+                if columns.is_empty() {
+                    continue;
+                }
+                let unique = nd["unique"].as_bool().unwrap_or_default();
+                indexes.push(Self {
+                    name: match nd["name"].as_str() {
+                        Some(s) => s.to_string(),
+                        None => format!(
+                            "{}_{}_{}",
+                            if unique { "uk" } else { "idx" },
+                            table.to_lowercase(),
+                            columns.join("_")
+                        ),
+                    },
+                    columns,
+                    unique,
+                });
+            }
+        }
+        indexes
+    }
+}
+
+/**
+ * seed子命令的配置，为每个表生成演示或压测用的数据
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SeedConfig {
+    pub rows: i64, // 每个表生成的行数，默认10
+    pub format: String, // 输出的形式：sql（默认，seed/seed.sql）或rust（src/seed中使用生成的实体保存的模块）
+    pub random_seed: u64, // 随机数的种子，相同的种子及表结构生成相同的数据，默认1
+    pub id_start: i64, // 整数主键的起始值，默认1
+    pub dicts: Vec<SeedDictConfig>, // 字典的取值，注释中为字典{name}的列从中取值
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SeedDictConfig {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl SeedConfig {
+    pub fn load_from_yaml_node(node: &Yaml) -> Self {
+        let mut dicts = vec![];
+        if let Some(nodes) = node["dicts"].as_vec() {
+            for nd in nodes {
+                let name = match nd["name"].as_str() {
+                    Some(s) => s.to_string(),
+                    None => continue,
+                };
+                let values = nd["values"]
+                    .as_vec()
+                    .map(|vs| {
+                        vs.iter()
+                            .filter_map(|v| match v {
+                                Yaml::String(s) => Some(s.clone()),
+                                Yaml::Integer(i) => Some(i.to_string()),
+                                Yaml::Real(r) => Some(r.clone()),
+                                Yaml::Boolean(b) => Some(b.to_string()),
+                                _ => None,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                dicts.push(SeedDictConfig { name, values });
+            }
+        }
+        Self {
+            rows: node["rows"].as_i64().unwrap_or(10),
+            format: node["format"].as_str().unwrap_or("sql").to_lowercase(),
+            random_seed: node["random-seed"].as_i64().unwrap_or(1) as u64,
+            id_start: node["id-start"].as_i64().unwrap_or(1),
+            dicts,
+        }
+    }
+
+    pub fn get_dict_values(&self, name: &str) -> Option<Vec<String>> {
+        self.dicts
+            .iter()
+            .find(|d| d.name == name && !d.values.is_empty())
+            .map(|d| d.values.clone())
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct CodeGenConfig {
     pub app_authors: String,
@@ -636,12 +867,31 @@ pub struct CodeGenConfig {
     pub app_version: String,
     pub output_path: String,
     pub entity_in_one_file: bool,
+    pub handler_in_one_file: bool,
+    pub query_in_one_file: bool,
     pub generate_for_lib: bool,
+    pub generate_service: bool,
+    pub web_framework: String, // 生成的handler所使用的web框架：actix（默认）或axum
+    pub rbatis_version: i64, // 生成的代码所使用的rbatis版本：3（默认）或4
+    pub orm: String, // 生成的代码所使用的数据库访问方式：rbatis（默认）或sqlx
+    pub frontend: String, // 生成的前端页面风格：element-ui（默认，Vue 2）、vue3-element-plus（Vue 3 + Element Plus）或react-antd（React + Ant Design）
+    pub api_language: String, // 生成的前端API所使用的语言：js（默认，api-js.js）或ts（TypeScript，types.ts及api-ts.ts）
+    pub standalone: bool, // if true, 不依赖chimes-auth、chimes-rust及chimes-utils，所需的ApiResult、SystemUser等生成到utils中
+    pub generate_grpc: bool, // if true, 为每个表及关系生成.proto及tonic的服务实现，放在grpc目录中
+    pub generate_graphql: bool, // if true, 为每个表及查询生成async-graphql的类型及resolver，放在graphql目录中
+    pub generate_openapi: bool, // if true, 根据生成的handler生成openapi.json及openapi.yaml
+    pub openapi_swagger_ui: bool, // if true, 同时为生成的代码加上utoipa的注解，并在main.rs/lib.rs中挂载Swagger UI
+    pub generate_client: bool, // if true, 在{app-name}-client目录中生成基于reqwest的Rust客户端crate
+    pub generate_test: bool, // if true, 在src/tests中为每个handler生成基于actix_web::test的集成测试
     pub always_override: bool,
     pub allow_number_widecard: bool,
     pub allow_bool_widecard: bool,
     pub allow_redis_cache: bool,
     pub config_template_generate: Option<String>,
+    pub template_dir: Option<String>, // 自定义模板所在的目录，其中的同名模板将覆盖内置的默认模板
+    pub file_policies: Vec<FilePolicyConfig>, // 按路径指定生成文件的写入策略
+    pub seed: SeedConfig, // seed子命令的配置
+    pub ddl_dialects: Vec<String>, // model-first时生成DDL的数据库：mysql、postgres、sqlite，默认全部生成
     pub always_generate_handler: bool,
     pub always_generate_entity: bool,
     pub multi_tenancy: bool, // if true, 所有的Handler方法加入su: SystemUser<ChimesUserInfo>，并自动检查条件，JSON体中的company_id，modify_by, modify_user_id, modify_user_name的值
@@ -655,6 +905,38 @@ pub struct CodeGenConfig {
 }
 
 impl CodeGenConfig {
+    /**
+     * 获取文件的写入策略，第一个匹配的file-policies生效，没有匹配时使用默认的策略
+     */
+    pub fn get_file_policy(&self, path: &str, default: WritePolicy) -> WritePolicy {
+        for fp in self.file_policies.iter() {
+            if match_file_pattern(&fp.pattern, path) {
+                return fp.policy;
+            }
+        }
+        default
+    }
+
+    /**
+     * 在配置中声明了列（model-first）的表
+     */
+    pub fn get_declared_tables(&self) -> Vec<TableConfig> {
+        self.tables
+            .iter()
+            .filter(|t| !t.columns.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * 所有的表都在配置中声明了列，并且没有自定义查询时，不需要连接数据库
+     */
+    pub fn is_model_only(&self) -> bool {
+        !self.tables.is_empty()
+            && self.tables.iter().all(|t| !t.columns.is_empty())
+            && self.queries.is_empty()
+    }
+
     pub fn load_from_yaml(node: &Yaml) -> Self {
         let mut tables = Vec::new();
 
@@ -683,7 +965,6 @@ impl CodeGenConfig {
                             }
                         },
                         struct_name: tbn["struct-name"].as_str().unwrap_or_default().to_string(),
-                        primary_key: tbn["primary-key"].as_str().unwrap_or_default().to_string(),
                         api_handler_name: match tbn["api-handler-name"].as_str() {
                             Some(tstr) => tstr.to_string(),
                             None => match tbn["struct-name"].as_str() {
@@ -717,6 +998,14 @@ impl CodeGenConfig {
                             Some(tstr) => Some(tstr.to_string()),
                             None => None,
                         },
+                        cache_ttl: match tbn["cache-ttl"].as_i64() {
+                            Some(t) if t > 0 => t as u64,
+                            _ => 3600,
+                        },
+                        version_field: match tbn["version-field"].as_str() {
+                            Some(tstr) => Some(tstr.to_string()),
+                            None => None,
+                        },
                         tree_parent_field: match tbn["tree-parent-field"].as_str() {
                             Some(tstr) => Some(tstr.to_string()),
                             None => None,
@@ -757,11 +1046,41 @@ impl CodeGenConfig {
                         page_query: tbn["page-query"].as_bool().unwrap_or_default(),
                         with_attachment: tbn["with-attachment"].as_bool().unwrap_or_default(),
                         logic_deletion: tbn["logic-deletion"].as_bool().unwrap_or_default(),
+                        logic_deletion_field: tbn["logic-deletion-field"]
+                            .as_str()
+                            .unwrap_or("deleted")
+                            .to_string(),
+                        logic_deleted_value: match &tbn["logic-deleted-value"] {
+                            Yaml::String(s) => s.clone(),
+                            Yaml::Integer(i) => i.to_string(),
+                            Yaml::Boolean(b) => if *b { "1" } else { "0" }.to_string(),
+                            _ => "1".to_string(),
+                        },
+                        logic_undeleted_value: match &tbn["logic-undeleted-value"] {
+                            Yaml::String(s) => s.clone(),
+                            Yaml::Integer(i) => i.to_string(),
+                            Yaml::Boolean(b) => if *b { "1" } else { "0" }.to_string(),
+                            _ => "0".to_string(),
+                        },
                         using_common_search: tbn["common-search"].as_bool().unwrap_or_default(),
                         generate_handler: match tbn["generate-handler"].as_bool() {
                             Some(ff) => ff,
                             None => gh,
                         },
+                        primary_key: match tbn["primary-key"].as_str() {
+                            Some(tstr) => tstr.to_string(),
+                            None => ColumnConfig::load_from_yaml_node(&tbn["columns"])
+                                .iter()
+                                .filter(|c| c.primary_key)
+                                .map(|c| c.name.clone())
+                                .collect::<Vec<String>>()
+                                .join(","),
+                        },
+                        columns: ColumnConfig::load_from_yaml_node(&tbn["columns"]),
+                        indexes: IndexConfig::load_from_yaml_node(
+                            &tbn["indexes"],
+                            tbn["name"].as_str().unwrap_or_default(),
+                        ),
                         simple_funclist: match tbn["simple-funclist"].as_vec() {
                             Some(listnode) => {
                                 let mut funclist = vec![];
@@ -830,6 +1149,21 @@ impl CodeGenConfig {
             } else {
                 None
             },
+            file_policies: FilePolicyConfig::load_from_yaml_node(&node["file-policies"]),
+            seed: SeedConfig::load_from_yaml_node(&node["seed"]),
+            ddl_dialects: match &node["ddl-dialects"] {
+                Yaml::String(s) => s.split(',').map(|d| d.trim().to_lowercase()).collect(),
+                Yaml::Array(vs) => vs
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|d| d.trim().to_lowercase()))
+                    .collect(),
+                _ => vec!["mysql".to_string(), "postgres".to_string(), "sqlite".to_string()],
+            },
+            template_dir: if let Some(s) = node["template-dir"].as_str() {
+                Some(s.to_owned())
+            } else {
+                None
+            },
             output_path: if let Some(s) = node["output-path"].as_str() {
                 s.to_owned()
             } else {
@@ -856,11 +1190,85 @@ impl CodeGenConfig {
             } else {
                 false
             },
+            handler_in_one_file: if let Some(s) = node["handler-in-one-file"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            query_in_one_file: if let Some(s) = node["query-in-one-file"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
             generate_for_lib: if let Some(s) = node["generate-for-lib"].as_bool() {
                 s.to_owned()
             } else {
                 false
             },
+            generate_service: if let Some(s) = node["generate-service"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            web_framework: if let Some(s) = node["web-framework"].as_str() {
+                s.to_lowercase()
+            } else {
+                "actix".to_string()
+            },
+            rbatis_version: match node["rbatis-version"].as_i64() {
+                Some(t) => t,
+                None => 3i64,
+            },
+            orm: if let Some(s) = node["orm"].as_str() {
+                s.to_lowercase()
+            } else {
+                "rbatis".to_string()
+            },
+            frontend: if let Some(s) = node["frontend"].as_str() {
+                s.to_lowercase()
+            } else {
+                "element-ui".to_string()
+            },
+            api_language: if let Some(s) = node["api-language"].as_str() {
+                s.to_lowercase()
+            } else {
+                "js".to_string()
+            },
+            standalone: if let Some(s) = node["standalone"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            generate_grpc: if let Some(s) = node["generate-grpc"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            generate_graphql: if let Some(s) = node["generate-graphql"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            generate_openapi: if let Some(s) = node["generate-openapi"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            openapi_swagger_ui: if let Some(s) = node["openapi-swagger-ui"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            generate_client: if let Some(s) = node["generate-client"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
+            generate_test: if let Some(s) = node["generate-test"].as_bool() {
+                s.to_owned()
+            } else {
+                false
+            },
             allow_redis_cache: if let Some(s) = node["allow-redis-cache"].as_bool() {
                 s.to_owned()
             } else {
@@ -0,0 +1,959 @@
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use rbatis::rbatis::Rbatis;
+
+use crate::codegen::{
+    generate_seed_files, CodeGenerator, GenerateContext, GeneratedFile, RustPermission,
+};
+use crate::config::{AppConfig, CodeGenConfig, RedisConfig};
+use crate::output::{FileSystemSink, OutputSink};
+use crate::permission::save_permissions;
+use crate::schema::{ModelSchemaSource, MysqlSchemaSource, SchemaSource};
+
+/**
+ * 代码生成的结果
+ * files为生成的文件，permissions为生成的handler所对应的权限
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GenerateResult {
+    pub files: Vec<GeneratedFile>,
+    pub permissions: Vec<RustPermission>,
+}
+
+impl GenerateResult {
+    /**
+     * 将Permission写入到数据库
+     */
+    pub async fn write_permission(&self, rb: &Rbatis) {
+        save_permissions(rb, &self.permissions).await;
+    }
+}
+
+/**
+ * 代码生成器
+ * 不依赖全局的配置与数据库连接，可用于build.rs或其它工具中：
+ * Generator::from_config(&cfg).schema_source(src).output(dir).run().await
+ * 没有指定schema_source时，使用codegen配置中的database_url连接MySQL读取表结构
+ * 在配置中声明了列（model-first）的表使用声明的结构，所有的表都已声明时不连接数据库
+ * output写入到目录，sink可以写入到其它的输出目标（如内存、归档文件或标准输出）
+ * 没有指定output或sink时，只返回生成的结果，不写入文件
+ */
+pub struct Generator {
+    codegen_conf: CodeGenConfig,
+    redis_conf: RedisConfig,
+    schema_source: Option<Arc<dyn SchemaSource>>,
+    output: Option<String>,
+    sink: Option<Box<dyn OutputSink + Send>>,
+}
+
+impl Generator {
+    pub fn from_config(conf: &CodeGenConfig) -> Self {
+        Self {
+            codegen_conf: conf.clone(),
+            redis_conf: RedisConfig::default(),
+            schema_source: None,
+            output: None,
+            sink: None,
+        }
+    }
+
+    pub fn from_app_config(conf: &AppConfig) -> Self {
+        let mut cgconf = conf.codegen_conf.clone();
+        cgconf.database_url = conf.mysql_conf.url.clone();
+        Self::from_config(&cgconf).redis(&conf.redis_conf)
+    }
+
+    pub fn redis(mut self, conf: &RedisConfig) -> Self {
+        self.redis_conf = conf.clone();
+        self
+    }
+
+    pub fn schema_source(mut self, source: Arc<dyn SchemaSource>) -> Self {
+        self.schema_source = Some(source);
+        self
+    }
+
+    pub fn output(mut self, dir: &str) -> Self {
+        self.output = Some(dir.to_string());
+        self.sink = Some(Box::new(FileSystemSink::new(dir)));
+        self
+    }
+
+    pub fn sink(mut self, sink: Box<dyn OutputSink + Send>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /**
+     * 演示数据（seed子命令）的行数，小于等于0时为10
+     */
+    pub fn seed_rows(mut self, rows: i64) -> Self {
+        self.codegen_conf.seed.rows = rows;
+        self
+    }
+
+    /**
+     * 演示数据的输出格式，sql或rust
+     */
+    pub fn seed_format(mut self, format: &str) -> Self {
+        self.codegen_conf.seed.format = format.to_string();
+        self
+    }
+
+    /**
+     * 读取表结构，没有指定schema_source时连接MySQL
+     */
+    pub(crate) async fn load(&self) -> std::io::Result<CodeGenerator> {
+        let mut cgconf = self.codegen_conf.clone();
+        if let Some(dir) = self.output.clone() {
+            cgconf.output_path = dir;
+        }
+
+        // 所有的表都在配置中声明了列（model-first）时不需要连接数据库
+        let inner: Option<Arc<dyn SchemaSource>> = match self.schema_source.clone() {
+            Some(s) => Some(s),
+            None if cgconf.is_model_only() => None,
+            None => match MysqlSchemaSource::connect(&cgconf.database_url).await {
+                Ok(s) => Some(Arc::new(s)),
+                Err(err) => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        format!("Could not connect to {}: {}", cgconf.database_url, err),
+                    ));
+                }
+            },
+        };
+        let source: Arc<dyn SchemaSource> = match inner {
+            Some(s) if cgconf.get_declared_tables().is_empty() => s,
+            inner => Arc::new(ModelSchemaSource::new(&cgconf, inner)),
+        };
+
+        let ctx = GenerateContext::create(&cgconf, &self.redis_conf)?;
+        let mut cg = CodeGenerator::new(&ctx);
+
+        cg.load_tables(source.as_ref()).await;
+        Ok(cg)
+    }
+
+    pub async fn run(mut self) -> std::io::Result<GenerateResult> {
+        let mut cg = self.load().await?;
+        cg.generate();
+
+        let files = if let Some(sink) = self.sink.as_mut() {
+            cg.write_out(sink.as_mut())?
+        } else {
+            cg.collect_outputs()
+        };
+
+        Ok(GenerateResult {
+            files,
+            permissions: cg.ctx.permissions.clone(),
+        })
+    }
+
+    /**
+     * 只生成演示数据（seed/seed.sql或src/seed），不生成代码，也没有Permission
+     */
+    pub async fn run_seed(mut self) -> std::io::Result<GenerateResult> {
+        let cg = self.load().await?;
+
+        let files = if let Some(sink) = self.sink.as_mut() {
+            cg.write_seed(sink.as_mut())?
+        } else {
+            generate_seed_files(&cg.ctx)
+        };
+
+        Ok(GenerateResult {
+            files,
+            permissions: vec![],
+        })
+    }
+
+    /**
+     * 在同步的环境（如build.rs）中执行代码生成
+     */
+    pub fn run_blocking(self) -> std::io::Result<GenerateResult> {
+        tokio::runtime::Runtime::new()?.block_on(self.run())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use yaml_rust::YamlLoader;
+
+    use super::Generator;
+    use crate::config::CodeGenConfig;
+    use crate::output::{MemorySink, OutputSink};
+    use crate::schema::{ColumnInfo, MemorySchemaSource, TableInfo};
+
+    fn column(name: &str, data_type: &str, key: &str, nullable: bool, pos: i64) -> ColumnInfo {
+        ColumnInfo {
+            table_schema: Some("demo".to_string()),
+            table_name: Some("sys_user".to_string()),
+            column_name: Some(name.to_string()),
+            column_type: Some(data_type.to_string()),
+            column_comment: Some(name.to_string()),
+            column_key: Some(key.to_string()),
+            data_type: Some(data_type.to_string()),
+            extra: Some(if key == "PRI" { "auto_increment" } else { "" }.to_string()),
+            ordinal_position: Some(pos),
+            character_maximum_length: if data_type == "varchar" { Some(64) } else { None },
+            is_nullable: Some(if nullable { "YES" } else { "NO" }.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * 没有配置struct-name的sys_user表
+     */
+    fn memory_schema() -> MemorySchemaSource {
+        let tbl = TableInfo {
+            table_schema: Some("demo".to_string()),
+            table_type: Some("BASE TABLE".to_string()),
+            table_name: Some("sys_user".to_string()),
+            table_comment: Some("用户".to_string()),
+            ..Default::default()
+        };
+        let cols = vec![
+            column("user_id", "bigint", "PRI", false, 1),
+            column("username", "varchar", "UNI", false, 2),
+            column("nick_name", "varchar", "", true, 3),
+            column("version", "int", "", true, 5),
+            column("deleted", "int", "", false, 4),
+        ];
+        MemorySchemaSource::new().add_table(&tbl, &cols)
+    }
+
+    /**
+     * sys_user通过注释中的关联表引用sys_dept，sys_dept在配置中声明（model-first）
+     */
+    fn present_schema() -> MemorySchemaSource {
+        let mut schema = memory_schema();
+        let mut cols = schema.columns["sys_user"].clone();
+        let mut dept = column("dept_id", "bigint", "", true, 6);
+        dept.column_comment = Some("部门 关联表sys_dept".to_string());
+        cols.push(dept);
+        cols.push(column("create_time", "datetime", "", true, 7));
+        schema.columns.insert("sys_user".to_string(), cols);
+        schema
+    }
+
+    const SYS_DEPT_TABLE: &str = "    - name: sys_dept
+      primary-key: dept_id
+      comment: 部门
+      columns:
+        - name: dept_id
+          type: bigint
+          primary-key: true
+          comment: 部门ID
+        - name: dept_name
+          type: varchar(64)
+          comment: 部门名称";
+
+    /**
+     * codegen配置，extra为追加到codegen下的配置项
+     */
+    fn codegen_conf(extra: &str, table_extra: &str) -> CodeGenConfig {
+        let text = format!(
+            "codegen:
+  app-name: demo
+  app-version: 0.1.0
+  app-edition: \"2021\"
+  output-path: /tmp/demo
+  schema-name: demo
+  api-handler-prefix: /api/v1
+  always-generate-handler: true
+  always-generate-entity: true
+  always-override: true
+{}
+  tables:
+    - name: sys_user
+      primary-key: user_id
+      comment: 用户
+      page-query: true
+{}
+",
+            extra, table_extra
+        );
+        let docs = YamlLoader::load_from_str(&text).unwrap();
+        CodeGenConfig::load_from_yaml(&docs[0]["codegen"])
+    }
+
+    async fn generate(conf: &CodeGenConfig) -> MemorySink {
+        generate_with(conf, memory_schema()).await
+    }
+
+    async fn generate_with(conf: &CodeGenConfig, schema: MemorySchemaSource) -> MemorySink {
+        let result = Generator::from_config(conf)
+            .schema_source(Arc::new(schema))
+            .run()
+            .await
+            .unwrap();
+        let mut sink = MemorySink::new();
+        for gf in result.files.iter() {
+            sink.write_generated(gf).unwrap();
+        }
+        sink
+    }
+
+    #[tokio::test]
+    async fn test_generate_entity_without_struct_name() {
+        let sink = generate(&codegen_conf("", "")).await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("pub struct SysUser"));
+        assert!(entity.contains("pub async fn from_id"));
+        assert!(entity.contains("pub async fn save"));
+        assert!(sink.exists("src/handler/sys_user.rs"));
+        assert!(sink.exists("Cargo.toml"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_rbatis4_without_struct_name() {
+        let sink = generate(&codegen_conf("  rbatis-version: 4", "")).await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("crud!(SysUser{}, \"sys_user\");"));
+        assert!(entity.contains("impl_select!(SysUser{select_by_pk"));
+        assert!(entity.contains("pub async fn from_id"));
+        assert!(entity.contains("pub async fn update"));
+        assert!(entity.contains("pub async fn query_paged"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_sqlx_without_struct_name() {
+        let sink = generate(&codegen_conf("  orm: sqlx", "")).await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        for func in [
+            "pub async fn from_id",
+            "pub async fn save",
+            "pub async fn update",
+            "pub async fn remove",
+            "pub async fn query_paged",
+            "pub async fn query_all",
+        ] {
+            assert!(entity.contains(func), "{} was not generated", func);
+        }
+        assert!(entity.contains("\"SELECT * FROM sys_user WHERE user_id = ? LIMIT 1\""));
+        assert!(!entity.contains("rbatis"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_redis_cache_without_struct_name() {
+        let sink = generate(&codegen_conf(
+            "  allow-redis-cache: true",
+            "      cache-by-fields: username",
+        ))
+        .await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("pub async fn cache_get_by_username"));
+        assert!(entity.contains("pub async fn load_by_username_cached"));
+        assert!(entity.contains("redis_cache_del_tracked(&format!(\"sys_user:cached:{}\", self.user_id"));
+        assert!(entity.contains("Self::cache_del_ids(ids).await;"));
+        assert_eq!(entity.matches("self.cache_del().await;").count(), 3);
+        assert_eq!(entity.matches("Self::cache_clear().await;").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_logic_deletion_without_struct_name() {
+        let sink = generate(&codegen_conf(
+            "  generate-test: true",
+            "      logic-deletion: true",
+        ))
+        .await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        // from_id、load_ids、query_paged、common_query_paged、query_list、common_query_list、query_all
+        assert_eq!(entity.matches(".eq(\"deleted\", 0)").count(), 7);
+        assert!(entity.contains("UPDATE sys_user SET deleted = 1 WHERE"));
+        let test = sink.read("src/tests/sys_user_test.rs").unwrap();
+        assert!(test.contains("\"deleted\": 0,"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_optimistic_lock_without_struct_name() {
+        for (extra, cond) in [
+            ("", ".r#if(self.version.is_none(), |w| w.and().is_null(\"version\"))"),
+            ("  orm: sqlx", "qb.push(\" AND version IS NULL\");"),
+            ("  rbatis-version: 4", "<if test=\"this.version == null\">` and version is null`</if>"),
+        ] {
+            let sink = generate(&codegen_conf(extra, "      version-field: version")).await;
+            let entity = sink.read("src/entity/sys_user.rs").unwrap();
+            assert!(entity.contains(cond), "{}: {}", extra, cond);
+            assert!(entity.contains("Err(crate::utils::version_conflict_error())"), "{}", extra);
+            let handler = sink.read("src/handler/sys_user.rs").unwrap();
+            assert!(handler.contains("if crate::utils::is_version_conflict(&err) {"), "{}", extra);
+            let utils = sink.read("src/utils/mod.rs").unwrap();
+            assert!(utils.contains("pub fn is_version_conflict("), "{}", extra);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broken_template_dir_fails() {
+        let dir = std::env::temp_dir().join(format!("rbatisgen-broken-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rust")).unwrap();
+        std::fs::write(dir.join("rust/entity.rs"), "{% if %}").unwrap();
+        let conf = codegen_conf(&format!("  template-dir: {}", dir.display()), "");
+        let ret = Generator::from_config(&conf)
+            .schema_source(Arc::new(memory_schema()))
+            .run()
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(ret.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_template_dir_structured_context() {
+        let dir = std::env::temp_dir().join(format!("rbatisgen-structured-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rust")).unwrap();
+        std::fs::write(
+            dir.join("rust/main.rs"),
+            "{% for m in mods %}mod {{ m }};\n{% endfor %}{% for s in services %}// {{ s }}\n{% endfor %}",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("rust/Cargo.toml"),
+            "[dependencies]\n{% for d in dependencies %}{{ d.name }} = {{ d.spec }}\n{% endfor %}",
+        )
+        .unwrap();
+        let sink = generate(&codegen_conf(&format!("  template-dir: {}", dir.display()), "")).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        let main = sink.read("src/main.rs").unwrap();
+        assert!(main.contains("mod entity;\nmod handler;\n"));
+        assert!(main.contains("// crate::handler::user_save\n"));
+        let cargo = sink.read("Cargo.toml").unwrap();
+        assert!(cargo.contains("actix-web = \"4.0.1\"\n"));
+        assert!(cargo.contains("rbatis = {version = \"3.1.11\", features = [\"debug_mode\"]}\n"));
+    }
+
+    #[tokio::test]
+    async fn test_merge_policy_round_trip() {
+        let conf = codegen_conf(
+            "  file-policies:\n    - pattern: src/entity/*\n      policy: merge\n    - pattern: src/handler/*\n      policy: merge",
+            "",
+        );
+        let mut sink = generate(&conf).await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("// rbatisgen:keep-begin(usings)\n"));
+        assert!(entity.contains("    // rbatisgen:keep-begin(SysUser)\n    // rbatisgen:keep-end\n}\n"));
+        assert!(entity.ends_with("// rbatisgen:keep-begin(custom)\n// rbatisgen:keep-end\n"));
+        assert!(sink
+            .read("src/handler/sys_user.rs")
+            .unwrap()
+            .contains("// rbatisgen:keep-begin(custom)\n"));
+        // main.rs没有配置merge，不加入保留块
+        assert!(!sink.read("src/main.rs").unwrap().contains("rbatisgen:keep-begin"));
+
+        let edited = entity
+            .replace(
+                "    // rbatisgen:keep-begin(SysUser)\n",
+                "    // rbatisgen:keep-begin(SysUser)\n    pub fn is_admin(&self) -> bool {\n        self.user_id == Some(1)\n    }\n",
+            )
+            .replace(
+                "// rbatisgen:keep-begin(custom)\n",
+                "// rbatisgen:keep-begin(custom)\npub const ADMIN_ID: i64 = 1;\n",
+            );
+        sink.put("src/entity/sys_user.rs", &edited).unwrap();
+
+        let result = Generator::from_config(&conf)
+            .schema_source(Arc::new(memory_schema()))
+            .run()
+            .await
+            .unwrap();
+        for gf in result.files.iter() {
+            sink.write_generated(gf).unwrap();
+        }
+        assert_eq!(sink.read("src/entity/sys_user.rs").unwrap(), edited);
+    }
+
+    #[tokio::test]
+    async fn test_generate_for_lib() {
+        let sink = generate(&codegen_conf("  generate-for-lib: true", "")).await;
+        assert!(!sink.exists("src/main.rs"));
+        let lib = sink.read("src/lib.rs").unwrap();
+        assert!(lib.contains("pub mod entity;\npub mod handler;\n"));
+        assert!(lib.contains("pub fn configure(cfg: &mut web::ServiceConfig) {"));
+        assert!(lib.contains("    cfg.service(crate::handler::user_save);"));
+        let cargo = sink.read("Cargo.toml").unwrap();
+        assert!(cargo.contains("[lib]"));
+        assert!(cargo.find("[lib]").unwrap() < cargo.find("[dependencies]").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_generate_in_one_file() {
+        let dir = std::env::temp_dir().join(format!("rbatisgen-one-file-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("rust")).unwrap();
+        std::fs::write(
+            dir.join("rust/entity.rs"),
+            "// {{ artifact }} of {{ structs | length }} structs\n{% include \"rust/file.rs\" %}",
+        )
+        .unwrap();
+        let conf = codegen_conf(
+            &format!(
+                "  entity-in-one-file: true\n  handler-in-one-file: true\n  template-dir: {}",
+                dir.display()
+            ),
+            "",
+        );
+        let sink = generate(&conf).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!sink.exists("src/entity/sys_user.rs"));
+        let entity = sink.read("src/entity/mod.rs").unwrap();
+        assert!(entity.starts_with("// entity of "));
+        assert!(entity.contains("pub struct SysUser {"));
+        assert!(entity.contains("pub async fn from_id"));
+        assert!(!entity.contains("mod sys_user;"));
+        let handler = sink.read("src/handler/mod.rs").unwrap();
+        assert!(handler.contains("pub async fn user_save("));
+        assert!(!handler.contains("use crate::handler::"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_service_with_tenancy() {
+        let tbl = TableInfo {
+            table_schema: Some("demo".to_string()),
+            table_name: Some("sys_user".to_string()),
+            table_comment: Some("用户".to_string()),
+            ..Default::default()
+        };
+        let cols = vec![
+            column("user_id", "bigint", "PRI", false, 1),
+            column("username", "varchar", "", false, 2),
+            column("parent_id", "bigint", "", true, 3),
+            column("company_id", "bigint", "", true, 4),
+        ];
+        let schema = MemorySchemaSource::new().add_table(&tbl, &cols);
+        let conf = codegen_conf(
+            "  generate-service: true\n  multi-tenancy: true",
+            "      tree-parent-field: parent_id",
+        );
+        let sink = generate_with(&conf, schema).await;
+        let service = sink.read("src/service/sys_user.rs").unwrap();
+        assert!(service.contains("use crate::utils::ServiceUser;"));
+        assert!(!service.contains("SystemUser"));
+        assert!(service.contains("pub async fn create(su: &ServiceUser,val: &SysUser)"));
+        assert!(service.contains("val.company_id = su.company_id.clone();"));
+        assert!(service.contains("pub async fn tree(su: &ServiceUser,pid: &Option<i64>)"));
+        assert!(service.contains("Ok(rows.into_iter().filter(|tv| tv.company_id == su.company_id).collect())"));
+        let handler = sink.read("src/handler/sys_user.rs").unwrap();
+        assert!(handler.contains("SysUserService::create(&ServiceUser::from(&su.user), &val)"));
+        assert!(handler.contains("use crate::utils::ServiceUser;"));
+        assert!(!handler.contains("use chimes_utils::get_rbatis;"));
+        let utils = sink.read("src/utils/mod.rs").unwrap();
+        assert!(utils.contains("impl From<&chimes_rust::ChimesUserInfo> for ServiceUser {"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_axum() {
+        let sink = generate(&codegen_conf("  web-framework: axum", "")).await;
+        let handler = sink.read("src/handler/sys_user.rs").unwrap();
+        assert!(handler.contains("pub async fn user_save(State(state): State<AppState>,Extension(_su): Extension<SystemUser<ChimesUserInfo>>,Json(req): Json<SysUser>) -> Response {"));
+        assert!(handler.contains("use crate::utils::AppState;"));
+        assert!(!handler.contains("actix_web"));
+        let main = sink.read("src/main.rs").unwrap();
+        assert!(main.contains(".route(\"/api/v1/user/get/:id\", get(crate::handler::user_get))"));
+        assert!(main.contains(".route(\"/api/v1/user/paged/:current/:size\", post(crate::handler::user_paged))"));
+        let cargo = sink.read("Cargo.toml").unwrap();
+        assert!(cargo.contains("axum = "));
+        assert!(!cargo.contains("actix-web"));
+        let utils = sink.read("src/utils/mod.rs").unwrap();
+        assert!(utils.contains("pub struct AppState {"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_sqlx_present() {
+        let table_extra = format!("      logic-deletion: true\n{}", SYS_DEPT_TABLE);
+        let sink = generate_with(&codegen_conf("  orm: sqlx", &table_extra), present_schema()).await;
+        let present = sink.read("src/entity/sys_user_present.rs").unwrap();
+        assert!(present.contains("#[derive(Debug, Clone, Default, Deserialize, Serialize, sqlx::FromRow)]\npub struct SysUserPresent {"));
+        assert!(present.contains("    #[sqlx(skip)]\n    pub create_time_end: Option<chrono::NaiveDateTime>,"));
+        assert!(present.contains("    pub create_time: Option<chrono::NaiveDateTime>,\n"));
+        assert!(present.contains("param:  &SysUserPresent"));
+        assert!(present.contains("sqlx::query_as(&sql)"));
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("    #[sqlx(skip)]\n    pub include_deleted: bool,"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_standalone() {
+        let sink = generate(&codegen_conf("  standalone: true", "")).await;
+        for path in ["Cargo.toml", "src/main.rs", "src/entity/sys_user.rs", "src/handler/sys_user.rs"] {
+            let text = sink.read(path).unwrap();
+            assert!(!text.contains("chimes"), "{}", path);
+        }
+        let handler = sink.read("src/handler/sys_user.rs").unwrap();
+        assert!(handler.contains("use crate::utils::{SystemUser, UserInfo};"));
+        assert!(handler.contains("pub async fn user_save(_su: SystemUser<UserInfo>,req: web::Json<SysUser>)"));
+        let utils = sink.read("src/utils/mod.rs").unwrap();
+        assert!(utils.contains("pub fn get_rbatis() -> &'static Rbatis {"));
+        assert!(utils.contains("pub struct ApiResult<T> {"));
+        assert!(utils.contains("pub struct UserInfo {"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_grpc() {
+        let sink = generate(&codegen_conf("  generate-grpc: true", "")).await;
+        let proto = sink.read("proto/sys_user.proto").unwrap();
+        assert!(proto.contains("package demo;"));
+        assert!(proto.contains("message SysUser {\n  optional int64 user_id = 1;"));
+        assert!(proto.contains("message SysUserKey {\n  int64 user_id = 1;"));
+        assert!(proto.contains("  rpc Page(SysUserPageRequest) returns (SysUserPage);"));
+        let service = sink.read("src/grpc/sys_user.rs").unwrap();
+        assert!(service.contains("impl pb::sys_user_service_server::SysUserService for SysUserGrpcService {"));
+        assert!(service.contains("impl From<pb::SysUser> for SysUser {"));
+        let server = sink.read("src/grpc/grpc_server.rs").unwrap();
+        assert!(server.contains(".add_service(SysUserServiceServer::new(SysUserGrpcService::default()))"));
+        assert!(sink.read("build.rs").unwrap().contains("tonic_build::configure()"));
+        assert!(sink.read("Cargo.toml").unwrap().contains("tonic-build = "));
+    }
+
+    #[tokio::test]
+    async fn test_generate_graphql() {
+        let sink = generate(&codegen_conf("  generate-graphql: true", "")).await;
+        let schema = sink.read("src/graphql/schema.rs").unwrap();
+        assert!(schema.contains("pub struct QueryRoot(SysUserGraphqlQuery);"));
+        assert!(schema.contains("pub struct MutationRoot(SysUserGraphqlMutation);"));
+        assert!(schema.contains("#[post(\"/api/v1/graphql\")]"));
+        let object = sink.read("src/graphql/sys_user.rs").unwrap();
+        assert!(object.contains("#[graphql(name = \"SysUser\")]\npub struct SysUserObject {"));
+        assert!(object.contains("async fn user_page(&self,query: Option<SysUserInput>,current: u64,size: u64) -> Result<SysUserPage> {"));
+        assert!(object.contains("async fn delete_user(&self,user_id: i64) -> Result<SysUserObject> {"));
+        let main = sink.read("src/main.rs").unwrap();
+        assert!(main.contains(".service(crate::graphql::graphql_handler)"));
+        assert!(sink.read("Cargo.toml").unwrap().contains("async-graphql-actix-web = "));
+    }
+
+    #[tokio::test]
+    async fn test_generate_openapi_utoipa_path() {
+        let sink = generate(&codegen_conf("  generate-openapi: true\n  openapi-swagger-ui: true", "")).await;
+        let doc: serde_json::Value = serde_json::from_str(&sink.read("openapi.json").unwrap()).unwrap();
+        assert_eq!(doc["openapi"], "3.0.3");
+        assert!(doc["paths"]["/api/v1/user/create"]["post"].is_object());
+        assert!(doc["components"]["schemas"]["SysUser"].is_object());
+        assert!(sink.read("openapi.yaml").is_some());
+        let handler = sink.read("src/handler/sys_user.rs").unwrap();
+        assert!(handler.contains("#[utoipa::path(post, path = \"/api/v1/user/create\", tag = \"user\", request_body = SysUser, responses((status = 200, description = \"ApiResult<SysUser>\")))]\npub async fn user_save("));
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        assert!(entity.contains("utoipa::ToSchema)]"));
+        let apidoc = sink.read("src/openapi/api_doc.rs").unwrap();
+        assert!(apidoc.contains("        crate::handler::user_get,\n"));
+        assert!(apidoc.contains("        crate::entity::SysUser,\n"));
+        assert!(sink.read("src/main.rs").unwrap().contains(".service(crate::openapi::swagger_ui())"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_typescript_api() {
+        let sink = generate(&codegen_conf("  api-language: ts", "")).await;
+        assert!(sink.read("front/api/demo/user.js").is_none());
+        let types = sink.read("front/api/demo/types.ts").unwrap();
+        assert!(types.contains("export interface ApiResult<T> {"));
+        assert!(types.contains("export interface SysUser {\n  /** user_id */\n  user_id?: number | null\n"));
+        let api = sink.read("front/api/demo/user.ts").unwrap();
+        assert!(api.contains("import type { ApiResult, SysUser, Page } from './types'"));
+        assert!(api.contains("export function paged(data: SysUser, current: number, size: number): Promise<ApiResult<Page<SysUser>>> {"));
+        assert!(api.contains("    url: `/api/v1/user/get/${id}`,"));
+        assert!(api.contains("export default { add, edit, del, delIds, search, paged, get }"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_vue3_element_plus() {
+        let sink = generate(&codegen_conf("  frontend: vue3-element-plus", "")).await;
+        let view = sink.read("front/views/demo/user/index.vue").unwrap();
+        assert!(view.contains("<script setup lang=\"ts\">"));
+        assert!(view.contains("import { ElMessage, ElMessageBox } from 'element-plus'"));
+        assert!(view.contains("<el-dialog v-model=\"dialogVisible\""));
+        assert!(view.contains("import { useDict, usePage } from '../composables/useCrud'"));
+        assert!(!view.contains(":visible.sync"));
+        let composables = sink.read("front/views/demo/composables/useCrud.ts").unwrap();
+        assert!(composables.contains("export function usePage<T = any>("));
+        assert!(composables.contains("export function useDict("));
+    }
+
+    #[tokio::test]
+    async fn test_generate_react_antd() {
+        let sink = generate(&codegen_conf("  frontend: react-antd", "")).await;
+        assert!(sink.read("front/views/demo/user/index.vue").is_none());
+        let view = sink.read("front/views/demo/user/index.tsx").unwrap();
+        assert!(view.contains("import { Button, Form, Input, InputNumber, Modal, Space, Table, message } from 'antd'"));
+        assert!(view.contains("import { fromFormValues, toFormValues, usePage } from '../hooks/useCrud'"));
+        assert!(view.contains("        rowKey=\"user_id\""));
+        assert!(view.contains("export default Page"));
+        let hooks = sink.read("front/views/demo/hooks/useCrud.ts").unwrap();
+        assert!(hooks.contains("export function usePage<T = any>("));
+        assert!(hooks.contains("export function toFormValues("));
+    }
+
+    #[tokio::test]
+    async fn test_generate_client() {
+        let sink = generate(&codegen_conf("  generate-client: true", "")).await;
+        let cargo = sink.read("demo-client/Cargo.toml").unwrap();
+        assert!(cargo.contains("name = \"demo-client\""));
+        assert!(cargo.contains("reqwest = "));
+        let models = sink.read("demo-client/src/models.rs").unwrap();
+        assert!(models.contains("pub struct SysUser {"));
+        assert!(!models.contains("rbatis"));
+        let api = sink.read("demo-client/src/api.rs").unwrap();
+        assert!(api.contains("pub async fn user_paged(&self,data: &SysUser,current: u64,size: u64) -> Result<Page<SysUser>, Error> {"));
+        assert!(api.contains("pub async fn user_delete_ids(&self,data: &[i64]) -> Result<u64, Error> {"));
+        assert!(api.contains("pub async fn user_get(&self,id: i64) -> Result<SysUser, Error> {"));
+        for path in ["demo-client/src/lib.rs", "demo-client/src/base.rs", "demo-client/src/error.rs"] {
+            assert!(sink.read(path).is_some(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_handler_tests() {
+        let sink = generate(&codegen_conf("  generate-test: true", "")).await;
+        let main = sink.read("src/main.rs").unwrap();
+        assert!(main.contains("#[cfg(test)]\nmod tests;"));
+        let tests = sink.read("src/tests/mod.rs").unwrap();
+        assert!(tests.contains("mod common;\nmod sys_user_test;"));
+        let test = sink.read("src/tests/sys_user_test.rs").unwrap();
+        assert!(test.contains("#[actix_web::test]\nasync fn test_user_crud() {"));
+        assert!(test.contains("            .service(crate::handler::user_save)\n"));
+        assert!(test.contains("test_request(Method::POST, \"/api/v1/user/create\")"));
+        assert!(test.contains("let id = record[\"user_id\"].clone();"));
+        assert!(sink.read("src/tests/common.rs").unwrap().contains("use actix_web::test::TestRequest;"));
+
+        // axum的handler暂不支持生成测试
+        let sink = generate(&codegen_conf("  generate-test: true\n  web-framework: axum", "")).await;
+        assert!(sink.read("src/tests/sys_user_test.rs").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_seed() {
+        let conf = codegen_conf("", SYS_DEPT_TABLE);
+        let seed = |fmt: &'static str| {
+            let conf = conf.clone();
+            async move {
+                Generator::from_config(&conf)
+                    .schema_source(Arc::new(present_schema()))
+                    .seed_rows(3)
+                    .seed_format(fmt)
+                    .run_seed()
+                    .await
+                    .unwrap()
+                    .files
+            }
+        };
+        let files = seed("sql").await;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "seed/seed.sql");
+        let sql = files[0].content.clone();
+        // 被引用的sys_dept在前，sys_user.dept_id取sys_dept中已生成的值
+        let dept = sql.find("INSERT INTO `sys_dept` (`dept_id`, `dept_name`) VALUES").unwrap();
+        let user = sql.find("INSERT INTO `sys_user` (`user_id`, `username`, `nick_name`, `version`, `deleted`, `dept_id`, `create_time`) VALUES").unwrap();
+        assert!(dept < user);
+        for line in sql[user..].lines().skip(1).filter(|l| l.starts_with('(')) {
+            let dept_id = line.split(", ").nth(5).unwrap();
+            assert!(["1", "2", "3"].contains(&dept_id), "{}", line);
+        }
+        // 相同的random-seed生成相同的数据
+        assert_eq!(seed("sql").await[0].content, sql);
+
+        let files = seed("rust").await;
+        let module = files.iter().find(|f| f.path == "src/seed/seed_data.rs").unwrap();
+        assert!(module.content.contains("    seed_sys_dept(&mut tx, &mut ids).await?;\n    seed_sys_user(&mut tx, &mut ids).await?;\n"));
+        assert!(module.content.contains("use crate::entity::{SysDept, SysUser};"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_ddl_column_types() {
+        let table = "    - name: sys_order
+      primary-key: order_id
+      comment: 订单
+      columns:
+        - name: order_id
+          type: bigint
+          primary-key: true
+          auto-increment: true
+          comment: 订单ID
+        - name: order_no
+          type: varchar(32)
+          nullable: false
+          comment: 订单号
+        - name: amount
+          type: decimal(10,2)
+          comment: 金额
+        - name: paid
+          type: tinyint
+          comment: 已支付
+        - name: create_time
+          type: datetime
+          default: CURRENT_TIMESTAMP
+      indexes:
+        - columns: order_no
+          unique: true";
+        let sink = generate(&codegen_conf("", table)).await;
+        let mysql = sink.read("ddl/mysql.sql").unwrap();
+        for line in [
+            "  `order_id` bigint NOT NULL AUTO_INCREMENT COMMENT '订单ID',",
+            "  `order_no` varchar(32) NOT NULL COMMENT '订单号',",
+            "  `amount` decimal(10,2) COMMENT '金额',",
+            "  `create_time` datetime DEFAULT CURRENT_TIMESTAMP,",
+            "  UNIQUE KEY `uk_sys_order_order_no` (`order_no`)",
+        ] {
+            assert!(mysql.contains(line), "{}", line);
+        }
+        let postgres = sink.read("ddl/postgres.sql").unwrap();
+        for line in [
+            "  \"order_id\" BIGINT GENERATED BY DEFAULT AS IDENTITY NOT NULL,",
+            "  \"order_no\" VARCHAR(32) NOT NULL,",
+            "  \"amount\" NUMERIC(10,2),",
+            "  \"paid\" BOOLEAN,",
+            "  \"create_time\" TIMESTAMP DEFAULT CURRENT_TIMESTAMP",
+            "COMMENT ON COLUMN \"sys_order\".\"amount\" IS '金额';",
+        ] {
+            assert!(postgres.contains(line), "{}", line);
+        }
+        let sqlite = sink.read("ddl/sqlite.sql").unwrap();
+        for line in [
+            "  \"order_id\" INTEGER PRIMARY KEY AUTOINCREMENT, -- 订单ID",
+            "  \"order_no\" TEXT NOT NULL, -- 订单号",
+            "  \"create_time\" TEXT DEFAULT CURRENT_TIMESTAMP",
+        ] {
+            assert!(sqlite.contains(line), "{}", line);
+        }
+        // 声明的列与从数据库中读取的列生成相同的类型
+        let entity = sink.read("src/entity/sys_order.rs").unwrap();
+        assert!(entity.contains("    pub amount: Option<rbatis::Decimal>,\n    pub paid: Option<bool>,\n"));
+        // sys_user未声明列，不生成建表语句
+        assert!(!mysql.contains("sys_user"));
+    }
+
+
+    #[tokio::test]
+    async fn test_generate_redis_cache_invalidation() {
+        let conf = codegen_conf(
+            "  allow-redis-cache: true\n  multi-tenancy: true",
+            "      logic-deletion: true\n      cache-by-fields: username",
+        );
+        let mut schema = memory_schema();
+        let mut cols = schema.columns["sys_user"].clone();
+        cols.push(column("company_id", "bigint", "", true, 6));
+        schema.columns.insert("sys_user".to_string(), cols);
+        let sink = generate_with(&conf, schema).await;
+        let entity = sink.read("src/entity/sys_user.rs").unwrap();
+        // save、update、remove、restore、purge
+        assert_eq!(entity.matches("self.cache_del().await;").count(), 5);
+        assert!(entity.contains("pub async fn cache_get_by_username(company_id: &i64,val: &String)"));
+        assert!(entity.contains("&format!(\"sys_user:{}:username:{}\", company_id, val)"));
+        assert!(entity.contains(".eq(\"company_id\", company_id)"));
+        assert!(entity.contains("Self::load_by_username(rb, company_id, username).await?"));
+        // 事务提交成功后再次失效
+        let handler = sink.read("src/handler/sys_user.rs").unwrap();
+        assert_eq!(handler.matches("val.cache_del().await;").count(), 5);
+        assert!(handler.contains("SysUser::cache_del_ids(ids).await;"));
+        let purge = &handler[handler.find("val.purge(&mut tx)").unwrap()..];
+        let commit = purge.find("tx.commit().await").unwrap();
+        assert!(purge.find("val.cache_del().await;").unwrap() > commit);
+    }
+
+
+    #[tokio::test]
+    async fn test_generate_logic_deletion_guards() {
+        let table_extra = format!("      logic-deletion: true\n{}", SYS_DEPT_TABLE);
+        for (extra, error) in [
+            ("", "return Err(Error::E(\"remove_batch requires at least one condition\".to_string()));"),
+            ("  rbatis-version: 4", "return Err(Error::E(\"remove_batch requires at least one condition\".to_string()));"),
+            ("  orm: sqlx", "return Err(Error::Protocol(\"remove_batch requires at least one condition\".to_string()));"),
+        ] {
+            let sink = generate_with(&codegen_conf(extra, &table_extra), present_schema()).await;
+            let entity = sink.read("src/entity/sys_user.rs").unwrap();
+            let batch = entity[entity.find("pub async fn remove_batch(").unwrap()..]
+                .lines()
+                .take(3)
+                .map(|l| l.trim())
+                .collect::<Vec<&str>>();
+            assert!(batch[1].starts_with("if self.user_id.is_none() && self.username.is_none()"), "{}", extra);
+            // 日期范围的结束字段不单独作为条件
+            assert!(batch[1].contains("self.create_time.is_none()") && !batch[1].contains("create_time_end"), "{}", extra);
+            assert_eq!(batch[2], error, "{}", extra);
+            let present = sink.read("src/entity/sys_user_present.rs").unwrap();
+            assert!(present.contains("    pub include_deleted: bool,"), "{}", extra);
+            assert!(present.contains("where 1 = 1 \"#.to_string();"), "{}", extra);
+            assert_eq!(present.matches("if !param.include_deleted {").count(), 2, "{}", extra);
+        }
+
+        let result = Generator::from_config(&codegen_conf("", "      logic-deletion: true"))
+            .schema_source(Arc::new(memory_schema()))
+            .run()
+            .await
+            .unwrap();
+        let perm = result.permissions.iter().find(|p| p.alias == "USER").unwrap();
+        for child in perm.children.iter() {
+            let restricted = child.alias == "USER_PURGE" || child.alias == "USER_RESTORE";
+            assert_eq!(child.api_bypass.is_none(), restricted, "{}", child.alias);
+        }
+        assert!(perm.children.iter().any(|c| c.alias == "USER_PURGE"));
+    }
+
+
+    #[tokio::test]
+    async fn test_generate_relation_save() {
+        let extra = "  generate-grpc: true
+  relations:
+    - struct-name: SysUserDetail
+      comment: 用户详情
+      major-table: sys_user
+      extend-major: true
+      generate-handler: true
+      generate-select: true
+      generate-save: true
+      one-to-many:
+        - table-name: sys_user_addr
+          join-field: user_id
+          major-field: user_id
+        - table-name: sys_role
+          join-field: role_id
+          major-field: user_id
+          middle-table: sys_user_role";
+        let table_extra = "      version-field: version
+    - name: sys_user_addr
+      primary-key: addr_id
+      comment: 地址
+      columns:
+        - name: addr_id
+          type: bigint
+          primary-key: true
+        - name: user_id
+          type: bigint
+        - name: address
+          type: varchar(64)
+    - name: sys_role
+      primary-key: role_id
+      comment: 角色
+      columns:
+        - name: role_id
+          type: bigint
+          primary-key: true
+    - name: sys_user_role
+      primary-key: role_id
+      comment: 用户角色
+      columns:
+        - name: user_id
+          type: bigint
+        - name: role_id
+          type: bigint";
+        let sink = generate(&codegen_conf(extra, table_extra)).await;
+        let entity = sink.read("src/entity/sys_user_detail.rs").unwrap();
+        let save = &entity[entity.find("pub async fn save(&mut self,").unwrap()..];
+        let save = &save[..save.find("\n    }\n").unwrap()];
+        // 主表的版本已过期时update_selective返回错误，不再保存子表的行
+        assert!(save.contains("ret = match self_user.update_selective(rb).await {"));
+        for rows in ["self.user_addrs.clone()", "self.roles.clone()"] {
+            let pos = save.find(&format!("for row in {} {{", rows)).unwrap();
+            let guard = save[..pos].rfind("if ret.is_none() {").unwrap();
+            assert!(!save[guard..pos].contains("\n        }\n"), "{}", rows);
+            let body = &save[pos..];
+            let end = body.find("\n            }\n").unwrap();
+            assert!(body[..end].contains("if ret.is_some() {\n                    break;\n                }"), "{}", rows);
+        }
+        assert!(save.contains("let mut svrow_user_role = "));
+        // 全部成功后才写回版本
+        assert!(save.ends_with("None => {\n                self.version = self_user.version;\n                Ok(true)\n            }\n        }"));
+        let handler = sink.read("src/handler/sys_user_detail.rs").unwrap();
+        assert!(handler.contains("let mut val = req.to_owned();"));
+        let grpc = sink.read("src/grpc/sys_user_detail.rs").unwrap();
+        assert!(grpc.contains("let mut val = SysUserDetail::from(request.into_inner());"));
+    }
+}
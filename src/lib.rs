@@ -0,0 +1,17 @@
+//! copyright © 了知信息科技 2021 - present
+//! 代码生成器的库入口，可在build.rs或其它工具中使用Generator来生成代码
+#[macro_use]
+extern crate lazy_static;
+extern crate rbatis;
+
+pub mod codegen;
+pub mod config;
+pub mod generator;
+pub mod output;
+pub mod permission;
+pub mod schema;
+pub mod tmpl;
+pub mod utils;
+pub mod watch;
+
+pub use generator::*;
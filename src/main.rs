@@ -1,21 +1,17 @@
 //! copyright © 了知信息科技 2021 - present
 //! 应用
 //! created by longzou 20220614
-#[macro_use]
-extern crate lazy_static;
-extern crate rbatis;
-
-mod codegen;
-mod config;
-mod permission;
-mod schema;
-mod tmpl;
-mod utils;
-
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::codegen::{CodeGenerator, GenerateContext};
-use crate::config::AppConfig;
+use rbatisgen::config::AppConfig;
+use rbatisgen::output::{ArchiveFormat, ArchiveSink, StdoutSink};
+use rbatisgen::schema::{CachedSchemaSource, MemorySchemaSource, MysqlSchemaSource, SchemaSource};
+use rbatisgen::watch::Watcher;
+use rbatisgen::Generator;
+
+const USAGE: &str = "Usage: rbatisgen [seed] [conf/rbatis.yml] [--zip <file> | --tar <file> | --stdout] [--watch] [--snapshot <file>]
+       seed: [--rows <n>] [--format sql|rust]";
 
 //#[actix_web::main]
 #[tokio::main(flavor = "multi_thread", worker_threads = 10)]
@@ -32,7 +28,56 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
-    let conf = std::env::args().nth(1);
+    // 参数：[seed] [配置文件] [--zip <file> | --tar <file> | --stdout] [--watch] [--snapshot <file>]
+    // seed子命令只生成演示数据：[--rows <n>] [--format sql|rust]
+    let mut conf = None;
+    let mut seed = false;
+    let mut seed_rows = None;
+    let mut seed_format = None;
+    let mut archive = None;
+    let mut to_stdout = false;
+    let mut watch = false;
+    let mut snapshot_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--zip" | "--tar" => {
+                let format = args
+                    .next()
+                    .filter(|path| !path.starts_with("--"))
+                    .and_then(|path| ArchiveFormat::from_flag(&arg, &path).map(|f| (path, f)));
+                match format {
+                    Some(af) => archive = Some(af),
+                    None => {
+                        log::error!("{} requires the path of the archive file.", arg);
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("{} requires the path of the archive file.", arg),
+                        ));
+                    }
+                }
+            }
+            "--stdout" => to_stdout = true,
+            "--watch" => watch = true,
+            "--snapshot" => snapshot_path = args.next(),
+            "seed" if conf.is_none() && !seed => seed = true,
+            "--rows" => seed_rows = args.next().and_then(|n| n.parse::<i64>().ok()),
+            "--format" => seed_format = args.next(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                log::error!("Unrecognized argument {}.", arg);
+                eprintln!("{}", USAGE);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unrecognized argument {}.", arg),
+                ));
+            }
+            _ => conf = Some(arg),
+        }
+    }
     let conf_path = if conf.is_none() {
         std::env::current_dir()
             .unwrap()
@@ -60,35 +105,102 @@ async fn main() -> std::io::Result<()> {
     log::info!("Parsing rust-generator config file: {}", conf_path);
 
     // // 加载配置信息
-    let mut webc = AppConfig::get().lock().unwrap();
-    webc.load_yaml(&conf_path.clone());
-    log::info!("MySQL: {}", webc.mysql_conf.url);
-    let conf = webc.clone();
-    drop(webc);
+    let mut conf = AppConfig::default();
+    conf.load_yaml(&conf_path.clone());
+    log::info!("MySQL: {}", conf.mysql_conf.url);
 
-    // let mut rcnn = AppConfig::redis();
+    // 快照文件已存在时直接从快照中读取表结构，不再连接数据库；不存在时从数据库中读取并保存为快照
+    let mut snapshot = None;
+    if let Some(path) = snapshot_path.clone() {
+        if std::path::Path::new(&path).exists() {
+            log::info!("Loading the schema snapshot {}", path);
+            snapshot = Some(MemorySchemaSource::load_snapshot(&path)?);
+        }
+    }
 
-    // match redis::cmd("GET").arg("h").query::<String>(&mut rcnn) {
-    //     Ok(r) => {
-    //         log::info!("Get the redis value: {}", r);
-    //     }
-    //     Err(err) => {
-    //         log::info!("The error is {}", err.to_string());
-    //     }
-    // };
+    // 所有的表都在配置中声明了列（model-first）时不需要连接数据库
+    let model_only = conf.codegen_conf.is_model_only();
+    let mysql = if snapshot.is_none() && !model_only {
+        match MysqlSchemaSource::connect(&conf.mysql_conf.url).await {
+            Ok(s) => Some(Arc::new(s)),
+            Err(err) => {
+                log::error!("Could not connect to {}: {}", conf.mysql_conf.url, err);
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, err.to_string()));
+            }
+        }
+    } else {
+        None
+    };
 
-    let mut cgconf = conf.codegen_conf.clone();
-    cgconf.database_url = conf.mysql_conf.url.clone();
+    if let (Some(path), Some(my)) = (snapshot_path.clone(), mysql.clone()) {
+        let mut cgconf = conf.codegen_conf.clone();
+        cgconf.database_url = conf.mysql_conf.url.clone();
+        let captured = MemorySchemaSource::capture(my.as_ref(), &cgconf).await;
+        captured.save_snapshot(&path)?;
+        log::info!("The schema snapshot was saved to {}", path);
+        snapshot = Some(captured);
+    }
 
-    let ctx = GenerateContext::create(&cgconf.clone(), &conf.redis_conf);
+    let source: Arc<dyn SchemaSource> = match (snapshot.clone(), mysql.clone()) {
+        (Some(ss), _) => Arc::new(ss),
+        (None, Some(my)) => my,
+        (None, None) => Arc::new(MemorySchemaSource::new()),
+    };
 
-    let mut cg = CodeGenerator::new(&ctx);
+    if seed {
+        if watch {
+            log::warn!("--watch is ignored by the seed subcommand.");
+        }
+        let mut generator = Generator::from_app_config(&conf).schema_source(source);
+        if let Some(rows) = seed_rows {
+            generator = generator.seed_rows(rows);
+        }
+        if let Some(format) = seed_format {
+            generator = generator.seed_format(&format);
+        }
+        let generator = if let Some((path, format)) = archive {
+            generator.sink(Box::new(ArchiveSink::new(&path, format)))
+        } else if to_stdout {
+            generator.sink(Box::new(StdoutSink::new()))
+        } else {
+            generator.output(&conf.codegen_conf.output_path)
+        };
+        let result = generator.run_seed().await?;
+        log::info!("{} seed file(s) were generated.", result.files.len());
+        std::thread::sleep(Duration::from_secs(2));
+        return Ok(());
+    }
 
-    cg.load_tables().await;
-    cg.generate();
-    cg.write_out()?;
+    if watch {
+        if archive.is_some() || to_stdout {
+            log::warn!("--zip, --tar and --stdout are ignored in the watch mode.");
+        }
+        let mut watcher = Watcher::new(&conf_path, &conf, Arc::new(CachedSchemaSource::new(source)));
+        if let (Some(path), Some(ss)) = (snapshot_path, snapshot) {
+            watcher = watcher.snapshot(&path, &ss);
+        }
+        // Permission只在第一次生成时写入
+        let result = watcher.start().await?;
+        if let Some(my) = mysql {
+            result.write_permission(my.rbatis()).await;
+        }
+        return watcher.run().await;
+    }
+
+    let generator = Generator::from_app_config(&conf).schema_source(source);
+    let generator = if let Some((path, format)) = archive {
+        generator.sink(Box::new(ArchiveSink::new(&path, format)))
+    } else if to_stdout {
+        generator.sink(Box::new(StdoutSink::new()))
+    } else {
+        generator.output(&conf.codegen_conf.output_path)
+    };
+    let result = generator.run().await?;
 
-    cg.write_permission().await;
+    match mysql {
+        Some(my) => result.write_permission(my.rbatis()).await,
+        None => log::info!("The permissions were not written as the schema was not loaded from the database."),
+    }
 
     std::thread::sleep(Duration::from_secs(2));
     Ok(())
@@ -0,0 +1,560 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::codegen::GeneratedFile;
+
+/**
+ * 文件的写入策略
+ * Overwrite: 总是覆盖
+ * SkipIfExists: 文件已经存在时跳过
+ * Merge: 保留已有文件中 rbatisgen:keep-begin(名称) 与 rbatisgen:keep-end 之间的内容
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WritePolicy {
+    Overwrite,
+    SkipIfExists,
+    Merge,
+}
+
+impl Default for WritePolicy {
+    fn default() -> Self {
+        WritePolicy::Overwrite
+    }
+}
+
+impl WritePolicy {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().replace('_', "-").as_str() {
+            "overwrite" => Some(WritePolicy::Overwrite),
+            "skip" | "skip-if-exists" => Some(WritePolicy::SkipIfExists),
+            "merge" => Some(WritePolicy::Merge),
+            _ => None,
+        }
+    }
+}
+
+const KEEP_BEGIN: &str = "rbatisgen:keep-begin";
+const KEEP_END: &str = "rbatisgen:keep-end";
+
+fn keep_block_name(line: &str) -> Option<String> {
+    match line.find(KEEP_BEGIN) {
+        Some(pos) => {
+            let rest = &line[pos + KEEP_BEGIN.len()..];
+            let name = rest
+                .trim_start_matches('(')
+                .split(|c| c == ')' || c == ' ')
+                .next()
+                .unwrap_or_default();
+            Some(name.to_string())
+        }
+        None => None,
+    }
+}
+
+fn parse_keep_blocks(text: &str) -> Vec<(String, Vec<String>)> {
+    let mut blocks = vec![];
+    let mut current: Option<(String, Vec<String>)> = None;
+    for line in text.lines() {
+        if let Some(name) = keep_block_name(line) {
+            current = Some((name, vec![]));
+        } else if line.contains(KEEP_END) {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            }
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line.to_string());
+        }
+    }
+    blocks
+}
+
+/**
+ * 合并已有的文件与新生成的内容
+ * 已有文件中的保留块替换新内容中同名的块，新内容中不存在的保留块追加到文件末尾
+ */
+pub fn merge_content(existing: &str, generated: &str) -> String {
+    let kept = parse_keep_blocks(existing);
+    if kept.is_empty() {
+        return generated.to_string();
+    }
+    let mut used = vec![];
+    let mut output = String::new();
+    let mut skipping = false;
+    for line in generated.lines() {
+        if let Some(name) = keep_block_name(line) {
+            output.push_str(line);
+            output.push('\n');
+            if let Some((_, lines)) = kept.iter().find(|(n, _)| *n == name) {
+                for ln in lines {
+                    output.push_str(ln);
+                    output.push('\n');
+                }
+                used.push(name);
+                skipping = true;
+            }
+        } else if line.contains(KEEP_END) {
+            skipping = false;
+            output.push_str(line);
+            output.push('\n');
+        } else if !skipping {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    for (name, lines) in kept.iter() {
+        if !used.contains(name) {
+            // 原来的位置已经不存在（如结构体改名），注释掉后追加到文件末尾，以免生成的文件无法编译
+            output.push_str(format!("// {}({})\n", KEEP_BEGIN, name).as_str());
+            for ln in lines {
+                if !ln.trim_start().starts_with("//") {
+                    output.push_str("// ");
+                }
+                output.push_str(ln);
+                output.push('\n');
+            }
+            output.push_str(format!("// {}\n", KEEP_END).as_str());
+        }
+    }
+    output
+}
+
+fn keep_region(output: &mut String, indent: &str, name: &str) {
+    output.push_str(format!("{}// {}({})\n", indent, KEEP_BEGIN, name).as_str());
+    output.push_str(format!("{}// {}\n", indent, KEEP_END).as_str());
+}
+
+/**
+ * 根据impl块的声明得到保留块的名称，如 impl SysUser { 为SysUser，impl From<A> for B { 为From-A-for-B
+ */
+fn impl_region_name(line: &str) -> String {
+    let decl = line
+        .trim_start_matches("impl")
+        .trim_end()
+        .trim_end_matches('{')
+        .trim();
+    let mut name = String::new();
+    for ch in decl.chars() {
+        if ch.is_alphanumeric() || ch == '_' {
+            name.push(ch);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.trim_end_matches('-').to_string()
+}
+
+/**
+ * 为使用Merge策略的Rust文件加入保留块，使已有文件中自行编写的代码在重新生成后仍保留在原来的位置
+ * usings: use语句之后；impl块的名称: 每个impl块的末尾；custom: 文件的末尾
+ * 内容中已经有保留块（如自定义的模板中已经加入）时不作处理
+ */
+pub fn insert_keep_regions(generated: &str) -> String {
+    if generated.contains(KEEP_BEGIN) {
+        return generated.to_string();
+    }
+    let lines = generated.lines().collect::<Vec<&str>>();
+    let last_use = lines.iter().rposition(|ln| ln.starts_with("use "));
+    let mut output = String::new();
+    let mut impl_name: Option<String> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if impl_name.is_some() && *line == "}" {
+            keep_region(&mut output, "    ", &impl_name.take().unwrap_or_default());
+        }
+        output.push_str(line);
+        output.push('\n');
+        if (line.starts_with("impl ") || line.starts_with("impl<")) && line.trim_end().ends_with('{') {
+            impl_name = Some(impl_region_name(line));
+        }
+        if Some(i) == last_use {
+            output.push('\n');
+            keep_region(&mut output, "", "usings");
+        }
+    }
+    output.push('\n');
+    keep_region(&mut output, "", "custom");
+    output
+}
+
+/**
+ * 生成文件的输出目标
+ * 所有生成的文件都通过它写出，路径为相对于输出根目录的路径
+ */
+pub trait OutputSink {
+    fn exists(&self, path: &str) -> bool;
+
+    fn read(&self, path: &str) -> Option<String>;
+
+    fn put(&mut self, path: &str, content: &str) -> std::io::Result<()>;
+
+    /**
+     * 所有文件写出后调用，如归档文件在此时写入磁盘
+     */
+    fn finish(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /**
+     * 按写入策略写出文件，返回该文件是否被写出
+     */
+    fn write_file(&mut self, path: &str, content: &str, policy: WritePolicy) -> std::io::Result<bool> {
+        match policy {
+            WritePolicy::Overwrite => {
+                self.put(path, content)?;
+                Ok(true)
+            }
+            WritePolicy::SkipIfExists => {
+                if self.exists(path) {
+                    log::info!("Skipped the existed file {}.", path);
+                    Ok(false)
+                } else {
+                    self.put(path, content)?;
+                    Ok(true)
+                }
+            }
+            WritePolicy::Merge => match self.read(path) {
+                Some(existing) => {
+                    let merged = merge_content(&existing, content);
+                    self.put(path, &merged)?;
+                    Ok(true)
+                }
+                None => {
+                    self.put(path, content)?;
+                    Ok(true)
+                }
+            },
+        }
+    }
+
+    fn write_generated(&mut self, gf: &GeneratedFile) -> std::io::Result<bool> {
+        self.write_file(&gf.path, &gf.content, gf.policy)
+    }
+}
+
+/**
+ * 写入到文件系统的指定目录中
+ */
+pub struct FileSystemSink {
+    root: PathBuf,
+}
+
+impl FileSystemSink {
+    pub fn new(root: &str) -> Self {
+        Self {
+            root: PathBuf::from(root),
+        }
+    }
+}
+
+impl OutputSink for FileSystemSink {
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).exists()
+    }
+
+    fn read(&self, path: &str) -> Option<String> {
+        let mut text = String::new();
+        match File::open(self.root.join(path)) {
+            Ok(mut file) => match file.read_to_string(&mut text) {
+                Ok(_) => Some(text),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        }
+    }
+
+    fn put(&mut self, path: &str, content: &str) -> std::io::Result<()> {
+        let filename = self.root.join(path);
+        if let Some(parent) = filename.parent() {
+            if !parent.exists() {
+                // should create the path
+                create_dir_all(parent)?;
+            }
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .append(false)
+            .create(true)
+            .truncate(true)
+            .open(filename)?;
+        file.write_all(content.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/**
+ * 写入到内存中，用于测试或预览
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    pub files: BTreeMap<String, String>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn read(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn put(&mut self, path: &str, content: &str) -> std::io::Result<()> {
+        self.files.insert(path.to_string(), content.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /**
+     * 根据命令行参数来确定归档的格式
+     * --zip为zip，--tar为tar，文件扩展名为.tar.gz或.tgz时为压缩的tar
+     */
+    pub fn from_flag(flag: &str, path: &str) -> Option<Self> {
+        let lower = path.to_lowercase();
+        match flag {
+            "--zip" => Some(ArchiveFormat::Zip),
+            "--tar" if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") => Some(ArchiveFormat::TarGz),
+            "--tar" => Some(ArchiveFormat::Tar),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * 写入到zip或tar归档文件中
+ * 文件先收集在内存中，finish时写出归档文件
+ */
+pub struct ArchiveSink {
+    path: PathBuf,
+    format: ArchiveFormat,
+    files: MemorySink,
+}
+
+impl ArchiveSink {
+    pub fn new(path: &str, format: ArchiveFormat) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            format,
+            files: MemorySink::new(),
+        }
+    }
+
+    /**
+     * 根据文件的扩展名(.zip, .tar, .tar.gz, .tgz)来确定归档的格式
+     */
+    pub fn from_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        let format = if lower.ends_with(".zip") {
+            ArchiveFormat::Zip
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            ArchiveFormat::TarGz
+        } else {
+            ArchiveFormat::Tar
+        };
+        Self::new(path, format)
+    }
+
+    fn write_zip(&self, file: File) -> std::io::Result<()> {
+        let mut zip = zip::ZipWriter::new(file);
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, content) in self.files.files.iter() {
+            zip.start_file(name.as_str(), options)
+                .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+            zip.write_all(content.as_bytes())?;
+        }
+        zip.finish()
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+        Ok(())
+    }
+
+    fn write_tar<W: Write>(&self, writer: W) -> std::io::Result<W> {
+        let mut tar = tar::Builder::new(writer);
+        for (name, content) in self.files.files.iter() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, content.as_bytes())?;
+        }
+        tar.into_inner()
+    }
+}
+
+impl OutputSink for ArchiveSink {
+    fn exists(&self, path: &str) -> bool {
+        self.files.exists(path)
+    }
+
+    fn read(&self, path: &str) -> Option<String> {
+        self.files.read(path)
+    }
+
+    fn put(&mut self, path: &str, content: &str) -> std::io::Result<()> {
+        self.files.put(path, content)
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                create_dir_all(parent)?;
+            }
+        }
+        let file = File::create(&self.path)?;
+        match self.format {
+            ArchiveFormat::Zip => self.write_zip(file)?,
+            ArchiveFormat::Tar => {
+                self.write_tar(file)?.flush()?;
+            }
+            ArchiveFormat::TarGz => {
+                let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+                self.write_tar(encoder)?.finish()?;
+            }
+        }
+        log::info!(
+            "{} files were written into {}",
+            self.files.files.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+/**
+ * 输出到标准输出，每个文件之前输出其路径
+ */
+#[derive(Debug, Clone, Default)]
+pub struct StdoutSink {
+    written: Vec<String>,
+}
+
+impl StdoutSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OutputSink for StdoutSink {
+    fn exists(&self, path: &str) -> bool {
+        self.written.contains(&path.to_string())
+    }
+
+    fn read(&self, _path: &str) -> Option<String> {
+        None
+    }
+
+    fn put(&mut self, path: &str, content: &str) -> std::io::Result<()> {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        writeln!(out, "==> {} <==", path)?;
+        out.write_all(content.as_bytes())?;
+        if !content.ends_with('\n') {
+            writeln!(out)?;
+        }
+        self.written.push(path.to_string());
+        Ok(())
+    }
+}
+
+/**
+ * 按路径匹配文件，pattern支持*通配符，如 *.vue, src/entity/mod.rs, src/handler*
+ */
+pub fn match_file_pattern(pattern: &str, path: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<&str>>();
+    if parts.len() == 1 {
+        return pattern == path;
+    }
+    let mut rest = path;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            if !rest.starts_with(part) {
+                return false;
+            }
+            rest = &rest[part.len()..];
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_content_keeps_blocks() {
+        let existing = "fn a() {}\n// rbatisgen:keep-begin(custom)\nfn mine() {}\n// rbatisgen:keep-end\n// rbatisgen:keep-begin(removed)\nfn old() {}\n// rbatisgen:keep-end\n";
+        let generated = "fn b() {}\n// rbatisgen:keep-begin(custom)\n// rbatisgen:keep-end\n";
+        let merged = merge_content(existing, generated);
+        assert_eq!(
+            merged,
+            "fn b() {}\n// rbatisgen:keep-begin(custom)\nfn mine() {}\n// rbatisgen:keep-end\n// rbatisgen:keep-begin(removed)\n// fn old() {}\n// rbatisgen:keep-end\n"
+        );
+        assert_eq!(merge_content("fn a() {}\n", generated), generated);
+    }
+
+    #[test]
+    fn test_insert_keep_regions() {
+        let generated = "use a::b;\n\npub struct A {\n}\n\nimpl A {\n    fn x() {\n    }\n}\n\nimpl From<B> for A {\n    fn from(b: B) -> Self {\n    }\n}\n";
+        let text = insert_keep_regions(generated);
+        assert!(text.starts_with("use a::b;\n\n// rbatisgen:keep-begin(usings)\n// rbatisgen:keep-end\n"));
+        assert!(text.contains("    }\n    // rbatisgen:keep-begin(A)\n    // rbatisgen:keep-end\n}\n"));
+        assert!(text.contains("    // rbatisgen:keep-begin(From-B-for-A)\n"));
+        assert!(text.ends_with("}\n\n// rbatisgen:keep-begin(custom)\n// rbatisgen:keep-end\n"));
+        assert_eq!(insert_keep_regions(&text), text);
+    }
+
+    #[test]
+    fn test_write_policy() {
+        assert_eq!(WritePolicy::parse("skip_if_exists"), Some(WritePolicy::SkipIfExists));
+        assert_eq!(WritePolicy::parse("Merge"), Some(WritePolicy::Merge));
+        assert_eq!(WritePolicy::parse("append"), None);
+
+        let mut sink = MemorySink::new();
+        assert!(sink.write_file("a.rs", "first", WritePolicy::SkipIfExists).unwrap());
+        assert!(!sink.write_file("a.rs", "second", WritePolicy::SkipIfExists).unwrap());
+        assert_eq!(sink.read("a.rs").unwrap(), "first");
+        assert!(sink.write_file("a.rs", "third", WritePolicy::Overwrite).unwrap());
+        assert_eq!(sink.read("a.rs").unwrap(), "third");
+
+        sink.put("b.rs", "// rbatisgen:keep-begin(x)\nkept\n// rbatisgen:keep-end\n").unwrap();
+        sink.write_file("b.rs", "new\n// rbatisgen:keep-begin(x)\n// rbatisgen:keep-end\n", WritePolicy::Merge)
+            .unwrap();
+        assert_eq!(
+            sink.read("b.rs").unwrap(),
+            "new\n// rbatisgen:keep-begin(x)\nkept\n// rbatisgen:keep-end\n"
+        );
+    }
+
+    #[test]
+    fn test_archive_format_from_flag() {
+        assert_eq!(ArchiveFormat::from_flag("--zip", "out"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_flag("--zip", "out.tar"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_flag("--tar", "out.zip"), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::from_flag("--tar", "out.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_flag("--stdout", "out"), None);
+    }
+}
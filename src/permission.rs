@@ -11,6 +11,8 @@ use serde_derive::{Deserialize, Serialize};
  */
 use std::fmt::Debug;
 
+use crate::codegen::RustPermission;
+
 #[crud_table(table_name:"chimes_permission"|table_columns:"id,alias,create_time,name,pid,api_pattern,service_id,api_method,api_bypass")]
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ChimesPermissionInfo {
@@ -342,3 +344,75 @@ impl ChimesPermissionInfoValue {
         tree
     }
 }
+
+/**
+ * 将Permission写入到数据库
+ */
+pub async fn save_permissions(rb: &Rbatis, permissions: &Vec<RustPermission>) {
+    for ele in permissions.clone() {
+        let mut perm = ChimesPermissionInfo {
+            id: None,
+            alias: Some(ele.alias.clone()),
+            create_time: None,
+            name: Some(ele.name.clone()),
+            pid: Some(0i64),
+            api_pattern: ele.api_pattern,
+            service_id: Some(ele.service_id.clone()),
+            api_method: ele.api_method,
+            api_bypass: ele.api_bypass,
+        };
+        // log::info!("Permission: {} {}", ele.name.clone(), ele.alias.clone());
+
+        let mut query = ChimesPermissionInfo::default();
+        query.alias = Some(ele.alias.clone());
+        query.service_id = Some(ele.service_id.clone());
+        let stid = match query.query_list(rb).await {
+            Ok(rs) => {
+                if rs.len() > 0 {
+                    let mut mp = rs[0].clone();
+                    mp.name = perm.name.clone();
+                    // mp.api_bypass = perm.api_bypass.clone();
+                    mp.api_method = perm.api_method.clone();
+                    mp.api_pattern = perm.api_pattern.clone();
+                    match mp.update(rb).await {
+                        Ok(_r) => rs[0].id.unwrap_or_default(),
+                        Err(_) => rs[0].id.unwrap_or_default(),
+                    }
+                } else {
+                    match perm.save(rb).await {
+                        Ok(_r) => perm.id.unwrap(),
+                        Err(err) => {
+                            log::info!("Error: {}", err.to_string());
+                            0i64
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                log::info!("Error: {}", err.to_string());
+                0i64
+            }
+        };
+        if stid != 0i64 {
+            for chl in ele.children.clone() {
+                let mut chperm = ChimesPermissionInfo {
+                    id: None,
+                    alias: Some(chl.alias.clone()),
+                    create_time: None,
+                    name: Some(chl.name.clone()),
+                    pid: Some(stid),
+                    api_pattern: chl.api_pattern,
+                    service_id: Some(chl.service_id.clone()),
+                    api_method: chl.api_method,
+                    api_bypass: chl.api_bypass,
+                };
+                match chperm.save_or_update(rb).await {
+                    Ok(_) => {}
+                    Err(err) => {
+                        log::info!("Error: {}", err.to_string());
+                    }
+                }
+            }
+        }
+    }
+}
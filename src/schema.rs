@@ -1,8 +1,14 @@
+use async_trait::async_trait;
 use rbatis::error::Error;
 use rbatis::rbatis::Rbatis;
 use rbatis::DateTimeNative;
 use serde_derive::{Deserialize, Serialize};
+use sqlx::{Column, Row, TypeInfo};
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{CodeGenConfig, ColumnConfig, TableConfig};
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct TableInfo {
@@ -72,3 +78,401 @@ impl ColumnInfo {
         rb_args).await ;
     }
 }
+
+/**
+ * 自定义查询结果中的列
+ * data_type为数据库返回的类型名称（小写），如varchar, bigint
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct QueryColumn {
+    pub name: String,
+    pub data_type: String,
+}
+
+/**
+ * 表结构的来源
+ * 代码生成时通过它来获取表、列的信息以及自定义查询的结果列
+ */
+#[async_trait]
+pub trait SchemaSource: Send + Sync {
+    async fn load_table(&self, ts: &str, tn: &str) -> Result<Option<TableInfo>, Error>;
+
+    async fn load_columns(&self, ts: &str, tn: &str) -> Result<Vec<ColumnInfo>, Error>;
+
+    async fn query_columns(&self, sql: &str, params: &Vec<String>) -> Result<Vec<QueryColumn>, Error>;
+}
+
+/**
+ * 从MySQL的INFORMATION_SCHEMA中读取表结构
+ */
+pub struct MysqlSchemaSource {
+    rb: Rbatis,
+    url: String,
+}
+
+impl MysqlSchemaSource {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let rb = Rbatis::new();
+        log::info!("Make the database connection {}", url);
+        rb.link(url).await?;
+        Ok(Self {
+            rb,
+            url: url.to_string(),
+        })
+    }
+
+    pub fn rbatis(&self) -> &Rbatis {
+        &self.rb
+    }
+}
+
+#[async_trait]
+impl SchemaSource for MysqlSchemaSource {
+    async fn load_table(&self, ts: &str, tn: &str) -> Result<Option<TableInfo>, Error> {
+        TableInfo::load_table(&self.rb, ts, tn).await
+    }
+
+    async fn load_columns(&self, ts: &str, tn: &str) -> Result<Vec<ColumnInfo>, Error> {
+        ColumnInfo::load_columns(&self.rb, ts, tn).await
+    }
+
+    async fn query_columns(&self, sql: &str, params: &Vec<String>) -> Result<Vec<QueryColumn>, Error> {
+        let pool = sqlx::MySqlPool::connect_lazy(self.url.replace("127.0.0.1", "localhost").as_str())?;
+        let mut cn = pool.acquire().await?;
+        let mut qry = sqlx::query(sql);
+        for fs in params.clone() {
+            qry = qry.bind(fs);
+        }
+        let rs = qry.fetch_one(&mut cn).await?;
+        Ok(rs
+            .columns()
+            .iter()
+            .map(|col| QueryColumn {
+                name: col.name().to_string(),
+                data_type: col.type_info().name().to_string().to_lowercase(),
+            })
+            .collect())
+    }
+}
+
+/**
+ * 内存中的表结构，用于测试或不连接数据库的场景
+ * 也可以保存为JSON格式的快照文件，之后从快照文件中加载而不需要连接数据库
+ */
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MemorySchemaSource {
+    pub tables: Vec<TableInfo>,
+    pub columns: HashMap<String, Vec<ColumnInfo>>,
+    pub queries: HashMap<String, Vec<QueryColumn>>,
+}
+
+impl MemorySchemaSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_table(mut self, tbl: &TableInfo, cols: &Vec<ColumnInfo>) -> Self {
+        let tn = tbl.table_name.clone().unwrap_or_default();
+        self.tables.push(tbl.clone());
+        self.columns.insert(tn, cols.clone());
+        self
+    }
+
+    pub fn add_query(mut self, sql: &str, cols: &Vec<QueryColumn>) -> Self {
+        self.queries.insert(sql.to_string(), cols.clone());
+        self
+    }
+
+    /**
+     * 配置中声明了列（model-first）的表
+     */
+    pub fn from_model(conf: &CodeGenConfig) -> Self {
+        let mut model = Self::new();
+        for tbc in conf.get_declared_tables() {
+            let tb = TableInfo {
+                table_schema: Some(conf.schema_name.clone()),
+                table_type: Some("BASE TABLE".to_string()),
+                table_name: Some(tbc.name.clone()),
+                table_comment: Some(tbc.comment.clone()),
+                ..Default::default()
+            };
+            let cols = tbc
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(pos, cc)| ColumnInfo::from_column_config(&conf.schema_name, &tbc, pos, cc))
+                .collect::<Vec<ColumnInfo>>();
+            model = model.add_table(&tb, &cols);
+        }
+        model
+    }
+
+    /**
+     * 从其它的来源中读取配置中的表及自定义查询的结构，生成快照
+     */
+    pub async fn capture(source: &dyn SchemaSource, conf: &CodeGenConfig) -> Self {
+        let mut snapshot = Self::new();
+        let ts = conf.schema_name.clone();
+        for tbc in conf.tables.clone() {
+            match source.load_table(&ts, &tbc.name).await {
+                Ok(Some(tb)) => match source.load_columns(&ts, &tbc.name).await {
+                    Ok(cols) => {
+                        snapshot = snapshot.add_table(&tb, &cols);
+                    }
+                    Err(err) => {
+                        log::info!("Load the columns for table {} with an error {}", &tbc.name, err);
+                    }
+                },
+                Ok(None) => {
+                    log::info!("Could not found the table {}", &tbc.name);
+                }
+                Err(err) => {
+                    log::info!("Load the table {} with an error {}", &tbc.name, err);
+                }
+            }
+        }
+        for qry in conf.queries.clone() {
+            let fds = qry
+                .params
+                .iter()
+                .map(|p| p.default_value.clone().unwrap_or_default())
+                .collect::<Vec<String>>();
+            match source.query_columns(&qry.base_sql, &fds).await {
+                Ok(cols) => {
+                    snapshot = snapshot.add_query(&qry.base_sql, &cols);
+                }
+                Err(err) => {
+                    log::info!("Execute the query with an error {}", err);
+                }
+            }
+        }
+        snapshot
+    }
+
+    pub fn load_snapshot(path: &str) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save_snapshot(&self, path: &str) -> std::io::Result<()> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        std::fs::write(path, text)
+    }
+
+    /**
+     * 表及其列的快照文本，用于比较两个快照中同一个表是否发生了变化
+     */
+    pub fn table_fingerprint(&self, tn: &str) -> String {
+        let tbl = self
+            .tables
+            .iter()
+            .find(|t| t.table_name.clone().unwrap_or_default() == tn);
+        serde_json::to_string(&(tbl, self.columns.get(tn))).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SchemaSource for MemorySchemaSource {
+    async fn load_table(&self, ts: &str, tn: &str) -> Result<Option<TableInfo>, Error> {
+        Ok(self
+            .tables
+            .iter()
+            .find(|t| {
+                t.table_name.clone().unwrap_or_default() == tn
+                    && (t.table_schema.is_none() || t.table_schema.clone().unwrap_or_default() == ts)
+            })
+            .cloned())
+    }
+
+    async fn load_columns(&self, _ts: &str, tn: &str) -> Result<Vec<ColumnInfo>, Error> {
+        Ok(self.columns.get(tn).cloned().unwrap_or_default())
+    }
+
+    async fn query_columns(&self, sql: &str, _params: &Vec<String>) -> Result<Vec<QueryColumn>, Error> {
+        match self.queries.get(sql) {
+            Some(cols) => Ok(cols.clone()),
+            None => Err(Error::from(format!("The query {} was not defined.", sql))),
+        }
+    }
+}
+
+impl ColumnInfo {
+    /**
+     * model-first时由配置中声明的列生成与INFORMATION_SCHEMA.COLUMNS相同的信息
+     * column_key：主键为PRI，单列的唯一索引为UNI，其它索引的第一列为MUL
+     */
+    pub fn from_column_config(ts: &str, tbc: &TableConfig, pos: usize, cc: &ColumnConfig) -> Self {
+        let pkeys = tbc
+            .primary_key
+            .split(',')
+            .map(|k| k.trim().to_lowercase())
+            .collect::<Vec<String>>();
+        let name = cc.name.to_lowercase();
+        let column_key = if pkeys.contains(&name) {
+            "PRI"
+        } else if tbc
+            .indexes
+            .iter()
+            .any(|i| i.unique && i.columns.len() == 1 && i.columns[0].to_lowercase() == name)
+        {
+            "UNI"
+        } else if tbc
+            .indexes
+            .iter()
+            .any(|i| i.columns[0].to_lowercase() == name)
+        {
+            "MUL"
+        } else {
+            ""
+        };
+        let length = match cc.data_type.as_str() {
+            "tinytext" => Some(255),
+            "text" => Some(65535),
+            "mediumtext" => Some(16777215),
+            "longtext" => Some(4294967295),
+            _ => cc.length,
+        };
+        Self {
+            table_schema: Some(ts.to_string()),
+            table_name: Some(tbc.name.clone()),
+            column_name: Some(cc.name.clone()),
+            column_type: Some(cc.column_type.clone()),
+            column_comment: Some(cc.comment.clone()),
+            column_key: Some(column_key.to_string()),
+            column_default: cc.default_value.clone(),
+            data_type: Some(cc.data_type.clone()),
+            extra: Some(if cc.auto_increment { "auto_increment" } else { "" }.to_string()),
+            ordinal_position: Some(pos as i64 + 1),
+            character_maximum_length: length,
+            is_nullable: Some(if cc.nullable && column_key != "PRI" { "YES" } else { "NO" }.to_string()),
+            numeric_precision: cc.precision,
+            numeric_scale: cc.scale,
+        }
+    }
+}
+
+/**
+ * model-first的表结构
+ * 在配置中声明了列的表使用声明的结构，其它的表及自定义查询从内部的来源（如MySQL）读取
+ */
+pub struct ModelSchemaSource {
+    model: MemorySchemaSource,
+    inner: Option<Arc<dyn SchemaSource>>,
+}
+
+impl ModelSchemaSource {
+    pub fn new(conf: &CodeGenConfig, inner: Option<Arc<dyn SchemaSource>>) -> Self {
+        Self {
+            model: MemorySchemaSource::from_model(conf),
+            inner,
+        }
+    }
+}
+
+#[async_trait]
+impl SchemaSource for ModelSchemaSource {
+    async fn load_table(&self, ts: &str, tn: &str) -> Result<Option<TableInfo>, Error> {
+        match (self.model.load_table(ts, tn).await?, self.inner.as_ref()) {
+            (Some(tb), _) => Ok(Some(tb)),
+            (None, Some(inner)) => inner.load_table(ts, tn).await,
+            (None, None) => Ok(None),
+        }
+    }
+
+    async fn load_columns(&self, ts: &str, tn: &str) -> Result<Vec<ColumnInfo>, Error> {
+        match (self.model.columns.contains_key(tn), self.inner.as_ref()) {
+            (false, Some(inner)) => inner.load_columns(ts, tn).await,
+            _ => self.model.load_columns(ts, tn).await,
+        }
+    }
+
+    async fn query_columns(&self, sql: &str, params: &Vec<String>) -> Result<Vec<QueryColumn>, Error> {
+        match self.inner.as_ref() {
+            Some(inner) => inner.query_columns(sql, params).await,
+            None => Err(Error::from(format!(
+                "The query {} requires a database connection in the model-first mode.",
+                sql
+            ))),
+        }
+    }
+}
+
+/**
+ * 缓存表结构的来源
+ * 同一个表只从内部的来源读取一次，watch模式下通过invalidate使发生了变化的表重新读取
+ */
+pub struct CachedSchemaSource {
+    inner: Mutex<Arc<dyn SchemaSource>>,
+    tables: Mutex<HashMap<String, Option<TableInfo>>>,
+    columns: Mutex<HashMap<String, Vec<ColumnInfo>>>,
+    queries: Mutex<HashMap<String, Vec<QueryColumn>>>,
+}
+
+impl CachedSchemaSource {
+    pub fn new(inner: Arc<dyn SchemaSource>) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+            tables: Mutex::new(HashMap::new()),
+            columns: Mutex::new(HashMap::new()),
+            queries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * 替换内部的来源（如重新加载了快照文件），已缓存的内容保留，需要另外调用invalidate
+     */
+    pub fn replace_inner(&self, inner: Arc<dyn SchemaSource>) {
+        *self.inner.lock().unwrap() = inner;
+    }
+
+    pub fn invalidate(&self, tn: &str) {
+        self.tables.lock().unwrap().remove(tn);
+        self.columns.lock().unwrap().remove(tn);
+    }
+
+    pub fn invalidate_queries(&self) {
+        self.queries.lock().unwrap().clear();
+    }
+
+    pub fn invalidate_all(&self) {
+        self.tables.lock().unwrap().clear();
+        self.columns.lock().unwrap().clear();
+        self.invalidate_queries();
+    }
+
+    fn inner(&self) -> Arc<dyn SchemaSource> {
+        self.inner.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl SchemaSource for CachedSchemaSource {
+    async fn load_table(&self, ts: &str, tn: &str) -> Result<Option<TableInfo>, Error> {
+        if let Some(tb) = self.tables.lock().unwrap().get(tn) {
+            return Ok(tb.clone());
+        }
+        let tb = self.inner().load_table(ts, tn).await?;
+        self.tables.lock().unwrap().insert(tn.to_string(), tb.clone());
+        Ok(tb)
+    }
+
+    async fn load_columns(&self, ts: &str, tn: &str) -> Result<Vec<ColumnInfo>, Error> {
+        if let Some(cols) = self.columns.lock().unwrap().get(tn) {
+            return Ok(cols.clone());
+        }
+        let cols = self.inner().load_columns(ts, tn).await?;
+        self.columns.lock().unwrap().insert(tn.to_string(), cols.clone());
+        Ok(cols)
+    }
+
+    async fn query_columns(&self, sql: &str, params: &Vec<String>) -> Result<Vec<QueryColumn>, Error> {
+        let key = format!("{}{:?}", sql, params);
+        if let Some(cols) = self.queries.lock().unwrap().get(&key) {
+            return Ok(cols.clone());
+        }
+        let cols = self.inner().query_columns(sql, params).await?;
+        self.queries.lock().unwrap().insert(key, cols.clone());
+        Ok(cols)
+    }
+}
@@ -0,0 +1,132 @@
+const AXUM_MAIN_TMPL: &str = r#"
+#[macro_use]
+extern crate lazy_static;
+
+#[allow(unused_imports)]
+use axum::routing::{get, post};
+use axum::response::Html;
+use axum::Router;
+
+${generated_mod_list}
+
+mod utils;
+
+use crate::utils::{AppConfig, AppState, WebServerConfig};
+
+async fn index_handler() -> Html<&'static str> {
+    Html("App is running.")
+}
+
+
+/// 应用启动入口
+#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
+async fn main() -> std::io::Result<()> {
+    // 加载配置文件
+    let conf_path = std::env::current_dir().unwrap().as_os_str().to_str().unwrap().to_owned() + "/conf/app.yml";
+    log::info!("Current Path: {}", conf_path);
+
+    match fast_log::init(fast_log::config::Config::new().console()) {
+        Ok (_) => {}
+        Err(err) => {
+            log::info!("An error occurred on the Logger initializing. {}", err);
+        }
+    };
+
+    // // 加载配置信息
+    AppConfig::get().lock().unwrap().load_yaml(&conf_path.clone());
+    let conf = AppConfig::get().lock().unwrap().clone();
+
+    // 提前创建rbatis的连接池
+    // 实践证明，如果不提前建立连接池，在后面请求时再进行建立的话，很有可能会死锁
+    utils::get_rbatis();
+
+    // 启动web服务
+    start_web_server(&conf.webserver_conf).await
+}
+
+/// web服务启动
+async fn start_web_server(webconf: &WebServerConfig) -> std::io::Result<()> {
+    // 设置服务器运行ip和端口信息
+    let ip = format!("{}:{}", "0.0.0.0", webconf.port.clone());
+    log::info!("App is listening on {}.", ip.clone());
+    // handler中的SystemUser通过Extension获取，需要在此加入认证的中间件（.layer(...)）将其放入到请求的extensions中
+    let app = Router::new()
+        .route("/", get(index_handler))
+${generated_route_list}        .with_state(AppState::new());
+    let listener = tokio::net::TcpListener::bind(ip).await?;
+    axum::serve(listener, app).await
+}
+
+"#;
+
+const AXUM_LIB_TMPL: &str = r#"
+#[macro_use]
+extern crate lazy_static;
+
+#[allow(unused_imports)]
+use axum::routing::{get, post};
+use axum::Router;
+
+${generated_mod_list}
+
+pub mod utils;
+
+pub use crate::utils::AppState;
+
+/// 所有生成的路由，宿主应用可以通过Router::merge或nest将其挂载到自己的路由中，并使用with_state(AppState::new())
+pub fn router() -> Router<AppState> {
+    Router::new()
+${generated_route_list}}
+
+"#;
+
+/**
+ * axum的State，追加到生成的utils中
+ */
+pub const AXUM_STATE_TMPL: &str = r#"
+/// axum的State，handler通过State<AppState>获取数据库连接
+#[derive(Clone)]
+pub struct AppState {
+    pub rb: &'static Rbatis,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self {
+            rb: chimes_utils::get_rbatis(),
+        }
+    }
+}
+"#;
+
+pub fn format_axum_main_template(modlist: Vec<String>, routelist: Vec<String>) -> String {
+    let mut mod_text = String::new();
+    let mut route_text = String::new();
+    for xl in modlist {
+        mod_text.push_str(format!("mod {};\n", xl).as_str());
+    }
+
+    for xl in routelist {
+        route_text.push_str(format!("        {}\n", xl).as_str());
+    }
+
+    AXUM_MAIN_TMPL
+        .replace("${generated_mod_list}", mod_text.as_str())
+        .replace("${generated_route_list}", route_text.as_str())
+}
+
+pub fn format_axum_lib_template(modlist: Vec<String>, routelist: Vec<String>) -> String {
+    let mut mod_text = String::new();
+    let mut route_text = String::new();
+    for xl in modlist {
+        mod_text.push_str(format!("pub mod {};\n", xl).as_str());
+    }
+
+    for xl in routelist {
+        route_text.push_str(format!("        {}\n", xl).as_str());
+    }
+
+    AXUM_LIB_TMPL
+        .replace("${generated_mod_list}", mod_text.as_str())
+        .replace("${generated_route_list}", route_text.as_str())
+}
@@ -0,0 +1,127 @@
+/**
+ * Redis缓存的连接及读写，追加到生成的utils中
+ * 连接使用conf/app.yml中的redis，未启用或者连接失败时缓存不生效，直接访问数据库
+ */
+pub const REDIS_CACHE_UTILS_TMPL: &str = r#"
+lazy_static!{
+    static ref REDIS_CACHE_CONN: tokio::sync::OnceCell<Option<redis::aio::MultiplexedConnection>> = tokio::sync::OnceCell::new();
+}
+
+/// 获取Redis的连接，只在第一次调用时连接
+/// app.yml中redis未启用（enabled: false）或者连接失败时返回None
+#[allow(dead_code)]
+pub async fn get_redis_connection() -> Option<redis::aio::MultiplexedConnection> {
+    REDIS_CACHE_CONN
+        .get_or_init(|| async {
+            let conf = AppConfig::get().lock().unwrap().redis_conf.clone();
+            if !conf.has_redis {
+                return None;
+            }
+            let url = format!(
+                "redis://{}:{}@{}:{}/{}",
+                conf.username.clone().unwrap_or_default(),
+                conf.password.clone().unwrap_or_default(),
+                conf.host,
+                conf.port,
+                conf.db
+            );
+            let client = match redis::Client::open(url) {
+                Ok(c) => c,
+                Err(err) => {
+                    log::warn!("Redis was not configured correctly: {}", err);
+                    return None;
+                }
+            };
+            match client.get_multiplexed_tokio_connection().await {
+                Ok(conn) => {
+                    log::info!("Redis was connected.");
+                    Some(conn)
+                }
+                Err(err) => {
+                    log::warn!("Redis could not be connected, the cache is disabled: {}", err);
+                    None
+                }
+            }
+        })
+        .await
+        .clone()
+}
+
+/// 读取缓存，缓存中保存的是JSON，不存在或者无法解析时返回None
+#[allow(dead_code)]
+pub async fn redis_cache_get<T: serde::de::DeserializeOwned>(key: &str) -> Option<T> {
+    let mut conn = get_redis_connection().await?;
+    match redis::cmd("GET").arg(key).query_async::<_, Option<String>>(&mut conn).await {
+        Ok(Some(text)) => serde_json::from_str::<T>(&text).ok(),
+        Ok(None) => None,
+        Err(err) => {
+            log::warn!("Redis GET {} failed: {}", key, err);
+            None
+        }
+    }
+}
+
+/// 写入缓存，ttl为过期的秒数
+#[allow(dead_code)]
+pub async fn redis_cache_set<T: serde::Serialize>(key: &str, val: &T, ttl: u64) {
+    let mut conn = match get_redis_connection().await {
+        Some(c) => c,
+        None => return,
+    };
+    let text = match serde_json::to_string(val) {
+        Ok(t) => t,
+        Err(_) => return,
+    };
+    if let Err(err) = redis::cmd("SETEX").arg(key).arg(ttl).arg(text).query_async::<_, ()>(&mut conn).await {
+        log::warn!("Redis SETEX {} failed: {}", key, err);
+    }
+}
+
+/// 删除缓存
+#[allow(dead_code)]
+pub async fn redis_cache_del(key: &str) {
+    let mut conn = match get_redis_connection().await {
+        Some(c) => c,
+        None => return,
+    };
+    if let Err(err) = redis::cmd("DEL").arg(key).query_async::<_, ()>(&mut conn).await {
+        log::warn!("Redis DEL {} failed: {}", key, err);
+    }
+}
+
+/// 将写入的缓存key记录在集合index中，用于之后按主键或整个表删除缓存
+#[allow(dead_code)]
+pub async fn redis_cache_track(index: &str, key: &str, ttl: u64) {
+    let mut conn = match get_redis_connection().await {
+        Some(c) => c,
+        None => return,
+    };
+    if let Err(err) = redis::pipe()
+        .cmd("SADD").arg(index).arg(key).ignore()
+        .cmd("EXPIRE").arg(index).arg(ttl).ignore()
+        .query_async::<_, ()>(&mut conn)
+        .await
+    {
+        log::warn!("Redis SADD {} failed: {}", index, err);
+    }
+}
+
+/// 删除集合index中记录的所有缓存key及集合本身
+#[allow(dead_code)]
+pub async fn redis_cache_del_tracked(index: &str) {
+    let mut conn = match get_redis_connection().await {
+        Some(c) => c,
+        None => return,
+    };
+    let keys = match redis::cmd("SMEMBERS").arg(index).query_async::<_, Vec<String>>(&mut conn).await {
+        Ok(keys) => keys,
+        Err(err) => {
+            log::warn!("Redis SMEMBERS {} failed: {}", index, err);
+            return;
+        }
+    };
+    if let Err(err) = redis::cmd("DEL").arg(keys).arg(index).query_async::<_, ()>(&mut conn).await {
+        log::warn!("Redis DEL {} failed: {}", index, err);
+    }
+}
+"#;
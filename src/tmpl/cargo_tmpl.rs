@@ -1,3 +1,5 @@
+use change_case::snake_case;
+
 use crate::config::CodeGenConfig;
 
 const CARGO_TMPL: &str = r#"
@@ -48,6 +50,12 @@ captcha = "0.0.9"
 jsonwebtoken = "8.1.1"
 "#;
 
+const LIB_SECTION_TMPL: &str = r#"
+[lib]
+name = "${lib_name}"
+path = "src/lib.rs"
+"#;
+
 pub fn replace_cargo_toml(ctx: &CodeGenConfig) -> String {
     CARGO_TMPL
         .replace("${authors}", ctx.app_authors.as_str())
@@ -55,3 +63,159 @@ pub fn replace_cargo_toml(ctx: &CodeGenConfig) -> String {
         .replace("${app_name}", ctx.app_name.as_str())
         .replace("${app_version}", ctx.app_version.as_str())
 }
+
+/**
+ * 生成库形式的Cargo.toml，在[dependencies]之前加入[lib]的定义
+ */
+pub fn replace_lib_cargo_toml(ctx: &CodeGenConfig) -> String {
+    let libsec = LIB_SECTION_TMPL.replace("${lib_name}", snake_case(ctx.app_name.as_str()).as_str());
+    replace_cargo_toml(ctx).replacen("\n[dependencies]", format!("{}\n[dependencies]", libsec).as_str(), 1)
+}
+
+/**
+ * axum的web框架：去掉actix相关的依赖，加入axum
+ */
+pub fn replace_axum_dependencies(cargotext: &str) -> String {
+    let mut lines = cargotext
+        .lines()
+        .filter(|ln| !ln.starts_with("actix-") && !ln.starts_with("awc "))
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    if let Some(pos) = lines.iter().position(|ln| ln == "[dependencies]") {
+        lines.insert(pos + 1, "axum = \"0.7\"".to_string());
+    }
+    lines.join("\n") + "\n"
+}
+
+/**
+ * rbatis 4.x：rbatis升级为4.x，rbson由rbs代替，并加入MySQL的驱动rbdc-mysql
+ */
+pub fn replace_rbatis4_dependencies(cargotext: &str) -> String {
+    let mut lines = vec![];
+    for ln in cargotext.lines() {
+        if ln.starts_with("rbson ") {
+            lines.push("rbs = \"4.5\"".to_string());
+        } else if ln.starts_with("rbatis ") {
+            lines.push("rbatis = {version = \"4.5\", features = [\"debug_mode\"]}".to_string());
+            lines.push("rbdc-mysql = \"4.5\"".to_string());
+        } else {
+            lines.push(ln.to_string());
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/**
+ * sqlx：去掉rbatis及rbson，加入sqlx（MySQL）及其所使用的rust_decimal，chrono需要serde的支持
+ */
+pub fn replace_sqlx_dependencies(cargotext: &str) -> String {
+    let mut lines = vec![];
+    for ln in cargotext.lines() {
+        if ln.starts_with("rbson ") {
+            continue;
+        } else if ln.starts_with("rbatis ") {
+            lines.push("sqlx = {version = \"0.7\", features = [\"mysql\", \"runtime-tokio-rustls\", \"chrono\", \"rust_decimal\", \"json\"]}".to_string());
+            lines.push("rust_decimal = \"1\"".to_string());
+        } else if ln.starts_with("chrono ") {
+            lines.push("chrono = {version = \"0.4.19\", features = [\"serde\"]}".to_string());
+        } else {
+            lines.push(ln.to_string());
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/**
+ * standalone：去掉chimes-auth、chimes-utils及chimes-rust，相应的实现生成到utils中
+ */
+pub fn replace_standalone_dependencies(cargotext: &str) -> String {
+    let lines = cargotext
+        .lines()
+        .filter(|ln| !ln.starts_with("chimes-"))
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    lines.join("\n") + "\n"
+}
+
+/**
+ * gRPC：加入tonic及prost，build.rs使用tonic-build编译proto
+ */
+pub fn replace_grpc_dependencies(cargotext: &str) -> String {
+    let mut lines = cargotext
+        .lines()
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    if let Some(pos) = lines.iter().position(|ln| ln == "[dependencies]") {
+        lines.insert(pos + 1, "tonic = \"0.11\"".to_string());
+        lines.insert(pos + 1, "prost-types = \"0.12\"".to_string());
+        lines.insert(pos + 1, "prost = \"0.12\"".to_string());
+    }
+    lines.push(String::new());
+    lines.push("[build-dependencies]".to_string());
+    lines.push("tonic-build = \"0.11\"".to_string());
+    lines.join("\n") + "\n"
+}
+
+/**
+ * Redis缓存：加入redis的异步连接
+ */
+pub fn replace_redis_dependencies(cargotext: &str) -> String {
+    let mut lines = cargotext
+        .lines()
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    if let Some(pos) = lines.iter().position(|ln| ln == "[dependencies]") {
+        lines.insert(
+            pos + 1,
+            "redis = { version = \"0.23\", features = [\"tokio-comp\"] }".to_string(),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+/**
+ * GraphQL：加入async-graphql及对应web框架的集成
+ */
+pub fn replace_graphql_dependencies(cargotext: &str, axum: bool) -> String {
+    let mut lines = cargotext
+        .lines()
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    if let Some(pos) = lines.iter().position(|ln| ln == "[dependencies]") {
+        if axum {
+            lines.insert(pos + 1, "async-graphql-axum = \"7.0\"".to_string());
+        } else {
+            lines.insert(pos + 1, "async-graphql-actix-web = \"7.0\"".to_string());
+        }
+        lines.insert(
+            pos + 1,
+            "async-graphql = { version = \"7.0\", features = [\"dataloader\"] }".to_string(),
+        );
+    }
+    lines.join("\n") + "\n"
+}
+
+/**
+ * OpenAPI：加入utoipa及对应web框架的Swagger UI
+ */
+pub fn replace_openapi_dependencies(cargotext: &str, axum: bool) -> String {
+    let mut lines = cargotext
+        .lines()
+        .map(|ln| ln.to_string())
+        .collect::<Vec<String>>();
+    if let Some(pos) = lines.iter().position(|ln| ln == "[dependencies]") {
+        if axum {
+            lines.insert(
+                pos + 1,
+                "utoipa-swagger-ui = { version = \"7\", features = [\"axum\"] }".to_string(),
+            );
+        } else {
+            lines.insert(
+                pos + 1,
+                "utoipa-swagger-ui = { version = \"7\", features = [\"actix-web\"] }".to_string(),
+            );
+        }
+        lines.insert(pos + 1, "utoipa = \"4\"".to_string());
+    }
+    lines.join("\n") + "\n"
+}
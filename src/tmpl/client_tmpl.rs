@@ -0,0 +1,193 @@
+use crate::config::CodeGenConfig;
+
+const CLIENT_CARGO_TMPL: &str = r#"
+[package]
+authors = ["${authors}"]
+edition = "${edition}"
+name = "${app_name}-client"
+version = "${app_version}"
+
+# See more keys and their definitions at https://doc.rust-lang.org/cargo/reference/manifest.html
+
+[dependencies]
+reqwest = {version = "0.11", default-features = false, features = ["json", "rustls-tls"]}
+serde = "1.0.136"
+serde_derive = "1.0.136"
+serde_json = "1.0.79"
+rust_decimal = "1"
+"#;
+
+const CLIENT_LIB_TMPL: &str = r#"//! ${app_name}的API客户端，由代码生成器根据handler生成
+//! 每个handler对应ApiClient的一个同名的async方法，返回ApiResult<T>中的T
+mod api;
+mod base;
+mod error;
+mod models;
+
+pub use base::*;
+pub use error::*;
+pub use models::*;
+"#;
+
+/**
+ * 客户端的错误及服务端统一返回的ApiResult、Page
+ */
+pub const CLIENT_ERROR_TMPL: &str = r#"use serde_derive::{Deserialize, Serialize};
+
+/// 服务端统一的返回结果
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiResult<T> {
+    pub status: i32,
+    pub message: String,
+    pub data: Option<T>,
+    pub timestamp: Option<u64>,
+}
+
+/// 分页查询的结果
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Page<T> {
+    pub records: Vec<T>,
+    pub total: u64,
+    pub page_no: u64,
+    pub page_size: u64,
+}
+
+/// 调用API时的错误
+#[derive(Debug)]
+pub enum Error {
+    /// 请求未能完成，如连接失败、超时
+    Http(reqwest::Error),
+    /// 服务端返回的status不为200，或HTTP状态码表示失败
+    Api { status: i32, message: String },
+    /// 返回的内容无法解析为期望的类型
+    Decode(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "http error: {}", err),
+            Error::Api { status, message } => write!(f, "api error {}: {}", status, message),
+            Error::Decode(msg) => write!(f, "decode error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+"#;
+
+/**
+ * ApiClient：保存服务端地址及token，token以Bearer的形式放在请求头Authorization中
+ * 生成的各个API方法均通过send发送请求，并将ApiResult<T>解包为T
+ */
+pub const CLIENT_BASE_TMPL: &str = r#"use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{ApiResult, Error};
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> Self {
+        Self::with_client(base_url, reqwest::Client::new())
+    }
+
+    /// 使用自定义的reqwest::Client，如需要设置超时、代理等
+    pub fn with_client(base_url: &str, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+            http,
+        }
+    }
+
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// 设置或清除token，如登录、退出之后
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.token = token;
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub(crate) async fn send<B, T>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+        query: &[(&str, Option<&str>)],
+    ) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let mut req = self.http.request(method, format!("{}{}", self.base_url, path));
+        let query = query
+            .iter()
+            .filter_map(|(k, v)| v.map(|v| (*k, v)))
+            .collect::<Vec<(&str, &str)>>();
+        if !query.is_empty() {
+            req = req.query(&query);
+        }
+        if let Some(token) = self.token.as_ref() {
+            req = req.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        let resp = req.send().await?;
+        let code = resp.status();
+        let text = resp.text().await?;
+        let ret: ApiResult<serde_json::Value> = match serde_json::from_str(&text) {
+            Ok(r) => r,
+            Err(err) => {
+                if code.is_success() {
+                    return Err(Error::Decode(err.to_string()));
+                }
+                return Err(Error::Api {
+                    status: code.as_u16() as i32,
+                    message: text,
+                });
+            }
+        };
+        if ret.status != 200 {
+            return Err(Error::Api {
+                status: ret.status,
+                message: ret.message,
+            });
+        }
+        // data为null时，Option<T>及()可以正常解析，其余的类型返回Decode
+        serde_json::from_value(ret.data.unwrap_or(serde_json::Value::Null))
+            .map_err(|err| Error::Decode(err.to_string()))
+    }
+}
+"#;
+
+pub fn format_client_cargo_toml(ctx: &CodeGenConfig) -> String {
+    CLIENT_CARGO_TMPL
+        .replace("${authors}", ctx.app_authors.as_str())
+        .replace("${edition}", ctx.app_edition.as_str())
+        .replace("${app_name}", ctx.app_name.as_str())
+        .replace("${app_version}", ctx.app_version.as_str())
+}
+
+pub fn format_client_lib(ctx: &CodeGenConfig) -> String {
+    CLIENT_LIB_TMPL.replace("${app_name}", ctx.app_name.as_str())
+}
@@ -8,10 +8,12 @@ pub fn format_redis_conf_tmpl(
     username: &Option<String>,
     password: &Option<String>,
     db: i64,
+    enabled: bool,
 ) -> String {
     if username.is_some() {
         format!(
-            "redis:\n    host: {}\n    port: {}\n    username: {}\n    password: {}\n    db: {}\n",
+            "redis:\n    enabled: {}\n    host: {}\n    port: {}\n    username: {}\n    password: {}\n    db: {}\n",
+            enabled,
             host,
             port,
             username.clone().unwrap_or_default(),
@@ -20,7 +22,8 @@ pub fn format_redis_conf_tmpl(
         )
     } else {
         format!(
-            "redis:\n    host: {}\n    port: {}\n    password: {}\n    db: {}\n",
+            "redis:\n    enabled: {}\n    host: {}\n    port: {}\n    password: {}\n    db: {}\n",
+            enabled,
             host,
             port,
             password.clone().unwrap_or_default(),
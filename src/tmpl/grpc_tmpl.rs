@@ -0,0 +1,49 @@
+const GRPC_BUILD_TMPL: &str = r#"
+/// 使用tonic-build编译proto目录中的.proto，生成的代码由grpc::pb引入
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::configure()
+        .build_client(false)
+        .compile(
+            &[
+${generated_proto_list}            ],
+            &["proto"],
+        )?;
+    Ok(())
+}
+"#;
+
+/**
+ * gRPC的Timestamp与日期时间的转换，追加到生成的utils中
+ */
+pub const GRPC_UTILS_TMPL: &str = r#"
+/// 日期时间转换为protobuf的Timestamp，日期时间按UTC处理
+/// 支持yyyy-MM-dd HH:mm:ss及yyyy-MM-ddTHH:mm:ss的格式
+#[allow(dead_code)]
+pub fn to_timestamp<T: ToString>(val: &T) -> Option<prost_types::Timestamp> {
+    let text = val.to_string().replace('T', " ");
+    let dt = chrono::NaiveDateTime::parse_from_str(text.get(0..19)?, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some(prost_types::Timestamp {
+        seconds: dt.and_utc().timestamp(),
+        nanos: 0,
+    })
+}
+
+/// protobuf的Timestamp转换为日期时间，日期时间按UTC处理
+#[allow(dead_code)]
+pub fn from_timestamp<T: std::str::FromStr>(ts: &prost_types::Timestamp) -> Option<T> {
+    let dt = chrono::DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32)?.naive_utc();
+    let text = dt.format("%Y-%m-%dT%H:%M:%S").to_string();
+    T::from_str(&text)
+        .ok()
+        .or_else(|| T::from_str(&format!("{}Z", text)).ok())
+}
+"#;
+
+pub fn format_grpc_build_template(protolist: Vec<String>) -> String {
+    let mut proto_text = String::new();
+    for xl in protolist {
+        proto_text.push_str(format!("                \"proto/{}\",\n", xl).as_str());
+    }
+
+    GRPC_BUILD_TMPL.replace("${generated_proto_list}", proto_text.as_str())
+}
@@ -0,0 +1,35 @@
+const LIB_TMPL: &str = r#"
+#[macro_use]
+extern crate actix_web;
+
+#[macro_use]
+extern crate lazy_static;
+
+use actix_web::web;
+
+${generated_mod_list}
+
+pub mod utils;
+
+/// 注册所有生成的服务，宿主应用可以通过App::configure(configure)将其挂载到自己的路由中
+pub fn configure(cfg: &mut web::ServiceConfig) {
+${generated_service_list}}
+
+"#;
+
+pub fn format_lib_template(modlist: Vec<String>, servicelist: Vec<String>) -> String {
+    let mut mod_text = String::new();
+    let mut svc_text = String::new();
+    for xl in modlist {
+        mod_text.push_str(format!("pub mod {};\n", xl).as_str());
+    }
+
+    for xl in servicelist {
+        svc_text.push_str(format!("    cfg.service({});\n", xl).as_str());
+    }
+
+    let cp = LIB_TMPL;
+
+    cp.replace("${generated_mod_list}", mod_text.as_str())
+        .replace("${generated_service_list}", svc_text.as_str())
+}
@@ -101,3 +101,12 @@ pub fn format_main_template(modlist: Vec<String>, servicelist: Vec<String>) -> S
     cp.replace("${generated_mod_list}", mod_text.as_str())
         .replace("${generated_service_list}", svc_text.as_str())
 }
+
+/**
+ * sqlx：启动时创建sqlx的连接池，代替rbatis
+ */
+pub fn replace_sqlx_main(maintext: &str) -> String {
+    maintext
+        .replace("// 提前创建rbatis的连接池", "// 提前创建sqlx的连接池")
+        .replace("utils::get_rbatis();", "utils::get_pool();")
+}
@@ -7,5 +7,32 @@ pub use conf_tmpl::*;
 mod main_tmpl;
 pub use main_tmpl::*;
 
+mod lib_tmpl;
+pub use lib_tmpl::*;
+
+mod axum_tmpl;
+pub use axum_tmpl::*;
+
+mod grpc_tmpl;
+pub use grpc_tmpl::*;
+
+mod cache_tmpl;
+pub use cache_tmpl::*;
+
+mod version_tmpl;
+pub use version_tmpl::*;
+
+mod service_tmpl;
+pub use service_tmpl::*;
+
 mod utils_tmpl;
 pub use utils_tmpl::*;
+
+mod tera_tmpl;
+pub use tera_tmpl::*;
+
+mod client_tmpl;
+pub use client_tmpl::*;
+
+mod test_tmpl;
+pub use test_tmpl::*;
@@ -0,0 +1,40 @@
+/**
+ * generate-service时加入到utils中的ServiceUser
+ * Service只接收当前用户及租户的值，可以在job或其它的传输层中直接构造后调用
+ */
+const SERVICE_USER_UTILS_TMPL: &str = r#"
+/// Service所需要的当前用户及租户的信息，与web框架的提取器无关
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct ServiceUser {
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub nick_name: Option<String>,
+    pub company_id: Option<i64>,
+    pub company_code: Option<String>,
+}
+
+impl From<&#USER_INFO#> for ServiceUser {
+    fn from(user: &#USER_INFO#) -> Self {
+        Self {
+            user_id: user.user_id,
+            username: user.username.clone(),
+            nick_name: user.nick_name.clone(),
+            company_id: user.company_id,
+            company_code: user.company_code.clone(),
+        }
+    }
+}
+"#;
+
+/**
+ * standalone时当前用户为utils中的UserInfo，否则为chimes_rust的ChimesUserInfo
+ */
+pub fn replace_service_user_utils(standalone: bool) -> String {
+    let user_info = if standalone {
+        "UserInfo"
+    } else {
+        "chimes_rust::ChimesUserInfo"
+    };
+    SERVICE_USER_UTILS_TMPL.replace("#USER_INFO#", user_info)
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::Path;
+use tera::Tera;
+
+use crate::codegen::tera_pascal;
+
+pub const API_JS_TMPL: &str = include_str!("../../templates/api-js.js");
+pub const API_TS_TMPL: &str = include_str!("../../templates/api-ts.ts");
+pub const INDEX_VUE_TMPL: &str = include_str!("../../templates/index.vue");
+pub const FORM_VUE_TMPL: &str = include_str!("../../templates/form.vue");
+pub const VUE3_INDEX_VUE_TMPL: &str = include_str!("../../templates/vue3/index.vue");
+pub const VUE3_FORM_VUE_TMPL: &str = include_str!("../../templates/vue3/form.vue");
+pub const VUE3_USE_CRUD_TMPL: &str = include_str!("../../templates/vue3/useCrud.ts");
+pub const REACT_INDEX_TSX_TMPL: &str = include_str!("../../templates/react/index.tsx");
+pub const REACT_FORM_TSX_TMPL: &str = include_str!("../../templates/react/form.tsx");
+pub const REACT_USE_CRUD_TMPL: &str = include_str!("../../templates/react/useCrud.ts");
+pub const RUST_FILE_TMPL: &str = include_str!("../../templates/rust/file.rs");
+pub const RUST_ENTITY_TMPL: &str = include_str!("../../templates/rust/entity.rs");
+pub const RUST_HANDLER_TMPL: &str = include_str!("../../templates/rust/handler.rs");
+pub const RUST_RELATION_TMPL: &str = include_str!("../../templates/rust/relation.rs");
+pub const RUST_RELATION_HANDLER_TMPL: &str = include_str!("../../templates/rust/relation_handler.rs");
+pub const RUST_QUERY_TMPL: &str = include_str!("../../templates/rust/query.rs");
+pub const RUST_QUERY_HANDLER_TMPL: &str = include_str!("../../templates/rust/query_handler.rs");
+pub const RUST_SERVICE_TMPL: &str = include_str!("../../templates/rust/service.rs");
+pub const RUST_MAIN_TMPL: &str = include_str!("../../templates/rust/main.rs");
+pub const RUST_LIB_TMPL: &str = include_str!("../../templates/rust/lib.rs");
+pub const RUST_CARGO_TMPL: &str = include_str!("../../templates/rust/Cargo.toml");
+
+/**
+ * 内置的模板，编译进可执行文件中
+ * 模板名称与template-dir中的文件相对路径一致，同名的文件将覆盖内置的模板
+ * rust/目录下的模板默认使用内置方式生成的代码，可通过覆盖rust/file.rs或单个模板来调整生成的Rust代码
+ */
+pub const DEFAULT_TERA_TMPLS: [(&str, &str); 21] = [
+    ("api-js.js", API_JS_TMPL),
+    ("api-ts.ts", API_TS_TMPL),
+    ("index.vue", INDEX_VUE_TMPL),
+    ("form.vue", FORM_VUE_TMPL),
+    ("vue3/index.vue", VUE3_INDEX_VUE_TMPL),
+    ("vue3/form.vue", VUE3_FORM_VUE_TMPL),
+    ("vue3/useCrud.ts", VUE3_USE_CRUD_TMPL),
+    ("react/index.tsx", REACT_INDEX_TSX_TMPL),
+    ("react/form.tsx", REACT_FORM_TSX_TMPL),
+    ("react/useCrud.ts", REACT_USE_CRUD_TMPL),
+    ("rust/file.rs", RUST_FILE_TMPL),
+    ("rust/entity.rs", RUST_ENTITY_TMPL),
+    ("rust/handler.rs", RUST_HANDLER_TMPL),
+    ("rust/relation.rs", RUST_RELATION_TMPL),
+    ("rust/relation_handler.rs", RUST_RELATION_HANDLER_TMPL),
+    ("rust/query.rs", RUST_QUERY_TMPL),
+    ("rust/query_handler.rs", RUST_QUERY_HANDLER_TMPL),
+    ("rust/service.rs", RUST_SERVICE_TMPL),
+    ("rust/main.rs", RUST_MAIN_TMPL),
+    ("rust/lib.rs", RUST_LIB_TMPL),
+    ("rust/Cargo.toml", RUST_CARGO_TMPL),
+];
+
+fn collect_template_files(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_template_files(root, &path, files)?;
+        } else {
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .join("/");
+            files.push((path.to_string_lossy().to_string(), name));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * 加载Tera模板
+ * 先加载内置的默认模板，如果指定了template_dir，则使用该目录下的同名模板覆盖
+ */
+pub fn load_tera_templates(template_dir: &Option<String>) -> Result<Tera, tera::Error> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(DEFAULT_TERA_TMPLS.to_vec())?;
+
+    if let Some(dir) = template_dir {
+        let root = Path::new(dir);
+        if !root.is_dir() {
+            return Err(tera::Error::msg(format!(
+                "The template-dir {} is not a directory.",
+                dir
+            )));
+        }
+        let mut files = vec![];
+        if let Err(err) = collect_template_files(root, root, &mut files) {
+            return Err(tera::Error::chain(
+                format!("Could not read the template-dir {}", dir),
+                err,
+            ));
+        }
+        for (path, name) in files.clone() {
+            log::info!("Template {} is overridden by {}", name, path);
+        }
+        tera.add_template_files(
+            files
+                .into_iter()
+                .map(|(path, name)| (path, Some(name)))
+                .collect::<Vec<(String, Option<String>)>>(),
+        )?;
+    }
+
+    tera.autoescape_on(vec!["html", ".sql"]);
+    tera.register_function("pascal", tera_pascal);
+    Ok(tera)
+}
@@ -0,0 +1,155 @@
+const TEST_COMMON_TMPL: &str = r#"//! 生成的handler测试所共用的函数
+//! 测试会在数据库中新增、修改及删除数据，请使用可以随意清理的数据库：
+//! 通过环境变量TEST_DATABASE_URL指定，未指定时使用conf/app.yml中的数据库
+#![allow(dead_code)]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::http::Method;
+use actix_web::test::TestRequest;
+use serde_json::Value;
+
+use crate::utils::AppConfig;
+
+/// 加载配置并初始化数据库的连接，只执行一次
+pub fn init_test_env() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let conf_path = std::env::current_dir().unwrap().as_os_str().to_str().unwrap().to_owned() + "/conf/app.yml";
+        {
+            let mut conf = AppConfig::get().lock().unwrap();
+            conf.load_yaml(&conf_path);
+            if let Ok(url) = std::env::var("TEST_DATABASE_URL") {
+                conf.db_conf.url = url;
+            }
+        }
+        crate::utils::get_rbatis();
+    });
+}
+
+fn next_seq() -> u64 {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default();
+    micros + SEQ.fetch_add(1, Ordering::SeqCst)
+}
+
+/// 不重复的字符串，超过max_len时保留后面的部分，max_len为0时不限制
+pub fn test_string(prefix: &str, max_len: usize) -> String {
+    let text = format!("{}{:x}", prefix, next_seq());
+    if max_len > 0 && text.len() > max_len {
+        text[text.len() - max_len..].to_string()
+    } else {
+        text
+    }
+}
+
+/// 不重复的数值，用于非自增的主键
+pub fn test_id(max: i64) -> i64 {
+    (next_seq() % max as u64) as i64 + 1
+}
+
+/// 测试的请求，设置了环境变量TEST_AUTH_TOKEN时带上Authorization
+pub fn test_request(method: Method, uri: &str) -> TestRequest {
+    let req = TestRequest::default().method(method).uri(uri);
+    match std::env::var("TEST_AUTH_TOKEN") {
+        Ok(token) => req.insert_header(("Authorization", format!("Bearer {}", token))),
+        Err(_) => req,
+    }
+}
+
+/// 断言ApiResult的status为200
+pub fn assert_api_ok(ret: &Value, action: &str) {
+    assert_eq!(ret["status"], 200, "{} failed: {}", action, ret["message"]);
+}
+
+/// 在查询结果中查找字段的值为value的记录
+pub fn find_record(list: &Value, field: &str, value: &Value) -> Option<Value> {
+    list.as_array()?.iter().find(|r| &r[field] == value).cloned()
+}
+
+/// 查询结果中主键最大的记录，没有能识别刚保存的记录的字段时使用
+pub fn find_latest(list: &Value, pk: &str) -> Option<Value> {
+    list.as_array()?
+        .iter()
+        .max_by_key(|r| r[pk].as_i64().unwrap_or_default())
+        .cloned()
+}
+
+/// 作为路径参数的值，字符串去掉引号
+pub fn path_value(val: &Value) -> String {
+    match val {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// 查询结果按字段名序列化，作为请求体时需要改为列名
+pub fn rename_keys(data: &mut Value, names: &[(&str, &str)]) {
+    if let Some(obj) = data.as_object_mut() {
+        for (field, column) in names.iter() {
+            if let Some(val) = obj.remove(*field) {
+                obj.insert(column.to_string(), val);
+            }
+        }
+    }
+}
+"#;
+
+/**
+ * standalone时SystemUser由utils提供，测试中使用固定的用户
+ */
+const TEST_USER_TMPL: &str = r#"
+/// 测试中固定的当前用户，多租户的company_id、company_code以及create_by等字段均来自该用户
+pub fn test_user() -> crate::utils::SystemUser<crate::utils::UserInfo> {
+    crate::utils::SystemUser {
+        user: crate::utils::UserInfo {
+            user_id: Some(1),
+            username: Some("test".to_string()),
+            nick_name: Some("test".to_string()),
+            company_id: Some(1),
+            company_code: Some("test".to_string()),
+        },
+    }
+}
+
+/// 将固定的用户放入请求的扩展中，SystemUser的提取器优先使用它
+pub fn set_test_user(req: &actix_web::dev::ServiceRequest) {
+    actix_web::HttpMessage::extensions_mut(req).insert(test_user());
+}
+"#;
+
+/**
+ * 生成的测试共用的tests/common.rs
+ */
+pub fn format_test_common_template(is_standalone: bool, is_sqlx: bool) -> String {
+    let text = if is_standalone {
+        TEST_COMMON_TMPL.to_string() + TEST_USER_TMPL
+    } else {
+        TEST_COMMON_TMPL.to_string()
+    };
+    if is_sqlx {
+        text.replace("crate::utils::get_rbatis();", "crate::utils::get_pool();")
+    } else {
+        text
+    }
+}
+
+/**
+ * 在main.rs或lib.rs的mod utils之后加入#[cfg(test)] mod tests
+ */
+pub fn add_test_mod(text: &str) -> String {
+    for utils in ["\npub mod utils;\n", "\nmod utils;\n"] {
+        if text.contains(utils) {
+            return text.replacen(
+                utils,
+                format!("{}\n#[cfg(test)]\nmod tests;\n", utils).as_str(),
+                1,
+            );
+        }
+    }
+    text.to_string()
+}
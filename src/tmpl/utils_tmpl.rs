@@ -448,3 +448,260 @@ where
 
 
 "#;
+
+/**
+ * rbatis 4.x的utils：RBatis通过init指定驱动来连接数据库，Decimal等类型由rbdc提供
+ */
+pub fn replace_rbatis4_utils(utilstext: &str) -> String {
+    crate::codegen::to_rbatis4_type(
+        &utilstext
+            .replace("rbatis::Decimal::from_str(", "<rbatis::Decimal as std::str::FromStr>::from_str(")
+            .replace("use rbatis::rbatis::{Rbatis};", "use rbatis::RBatis;")
+            .replace("match rb.link(&url).await {", "match rb.init(rbdc_mysql::driver::MysqlDriver {}, &url) {")
+            .replace("chimes_utils::get_rbatis()", "get_rbatis()")
+            .replace("Rbatis", "RBatis"),
+    )
+}
+
+const SQLX_POOL_TMPL: &str = r#"#[allow(dead_code)]
+pub fn get_pool() -> &'static MySqlPool {
+    // 使用MaybeUninit延迟初始化
+    static mut STATIC_POOL: MaybeUninit<MySqlPool> = MaybeUninit::uninit();
+    // Once带锁保证只进行一次初始化
+    static ONCE: Once = Once::new();
+
+    ONCE.call_once(|| unsafe {
+        let conf = AppConfig::get().lock().unwrap().to_owned();
+        let url = conf.db_conf.url.clone();
+
+        // connect_lazy在第一次执行SQL时才建立连接，不需要在此等待
+        let pool = MySqlPoolOptions::new()
+            .connect_lazy(&url)
+            .expect("The database url is invalid.");
+        log::info!("The sqlx pool was initialized successfully.");
+        STATIC_POOL.as_mut_ptr().write(pool);
+    });
+    unsafe { &*STATIC_POOL.as_ptr() }
+}
+
+/// 分页查询的结果，字段与rbatis的Page相同
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Page<T> {
+    pub records: Vec<T>,
+    pub total: u64,
+    pub page_no: u64,
+    pub page_size: u64,
+}
+
+impl<T> Page<T> {
+    #[allow(dead_code)]
+    pub fn new_total(page_no: u64, page_size: u64, total: u64) -> Self {
+        Self {
+            records: vec![],
+            total,
+            page_no,
+            page_size,
+        }
+    }
+}
+
+/// 原生SQL的参数统一按字符串绑定，由MySQL进行隐式的类型转换
+#[allow(dead_code)]
+pub fn to_sql_arg<T: Serialize>(val: T) -> Option<String> {
+    match serde_json::to_value(val) {
+        Ok(serde_json::Value::Null) | Err(_) => None,
+        Ok(serde_json::Value::String(s)) => Some(s),
+        Ok(serde_json::Value::Bool(b)) => Some(if b { "1" } else { "0" }.to_string()),
+        Ok(v) => Some(v.to_string()),
+    }
+}
+"#;
+
+/**
+ * sqlx的utils：使用MySqlPool代替Rbatis，并提供Page及原生SQL的参数转换
+ */
+pub fn replace_sqlx_utils(utilstext: &str) -> String {
+    let text = utilstext
+        .replace("rbatis::Decimal::from_str(", "<rbatis::Decimal as std::str::FromStr>::from_str(")
+        .replace("use rbatis::rbatis::{Rbatis};", "use sqlx::mysql::{MySqlPool, MySqlPoolOptions};")
+        .replace("pub rb: &'static Rbatis,", "pub rb: &'static MySqlPool,")
+        .replace("chimes_utils::get_rbatis()", "get_pool()");
+    let start = text.find("lazy_static!{\n    pub static ref RB: Rbatis");
+    let end_mark = "unsafe { &*STATIC_RB.as_ptr() }\n}\n";
+    let text = match (start, text.find(end_mark)) {
+        (Some(s), Some(e)) => {
+            text[..s].to_string() + SQLX_POOL_TMPL + &text[e + end_mark.len()..]
+        }
+        _ => text,
+    };
+    crate::codegen::to_sqlx_type(&text)
+}
+
+const STANDALONE_TMPL: &str = r#"
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiResult<T> {
+    pub status: i32,
+    pub message: String,
+    pub data: Option<T>,
+    pub timestamp: Option<u64>,
+}
+
+impl<T> ApiResult<T> {
+    #[allow(dead_code)]
+    pub fn ok(dt: T) -> Self {
+        ApiResult {
+            status: 200,
+            message: "OK".to_string(),
+            data: Option::Some(dt),
+            timestamp: Some(get_local_timestamp()),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn error(code: i32, msg: &String) -> Self {
+        ApiResult {
+            status: code,
+            message: msg.to_owned(),
+            data: None,
+            timestamp: Some(get_local_timestamp()),
+        }
+    }
+}
+
+/// 当前登录的用户，由应用按自己的认证方式实现，handler中的SystemUser<T>通过它从请求的Authorization中获取用户
+pub trait CurrentUser: Sized {
+    /// 根据token（已去掉Bearer前缀）获取当前用户，无法识别时返回None，请求将返回401
+    fn from_token(token: &str) -> Option<Self>;
+}
+
+/// handler中的当前用户
+#[derive(Debug, Clone, Default)]
+pub struct SystemUser<T> {
+    pub user: T,
+}
+
+/// 默认的用户信息，字段与handler中所使用的字段对应
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserInfo {
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub nick_name: Option<String>,
+    pub company_id: Option<i64>,
+    pub company_code: Option<String>,
+}
+
+impl CurrentUser for UserInfo {
+    /// 默认使用UserClaims解析JWT，sub为用户名，其它字段需要应用按需加载
+    fn from_token(token: &str) -> Option<Self> {
+        UserClaims::decode(token).map(|claims| UserInfo {
+            username: Some(claims.sub),
+            ..Default::default()
+        })
+    }
+}
+
+fn get_bearer_token(auth: Option<&str>) -> Option<String> {
+    auth.map(|s| s.trim_start_matches("Bearer ").trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+"#;
+
+const STANDALONE_ACTIX_TMPL: &str = r#"
+impl<T: CurrentUser + Clone + 'static> actix_web::FromRequest for SystemUser<T> {
+    type Error = actix_web::Error;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        // 中间件（如测试中的固定用户）已放入请求扩展的用户优先
+        if let Some(su) = actix_web::HttpMessage::extensions(req).get::<SystemUser<T>>() {
+            return std::future::ready(Ok(su.clone()));
+        }
+        let auth = req.headers().get("Authorization").and_then(|h| h.to_str().ok());
+        std::future::ready(match get_bearer_token(auth).and_then(|t| T::from_token(&t)) {
+            Some(user) => Ok(SystemUser { user }),
+            None => Err(actix_web::error::ErrorUnauthorized("Unauthorized")),
+        })
+    }
+}
+"#;
+
+const STANDALONE_AXUM_TMPL: &str = r#"
+#[axum::async_trait]
+impl<S: Send + Sync, T: CurrentUser + Send> axum::extract::FromRequestParts<S> for SystemUser<T> {
+    type Rejection = axum::http::StatusCode;
+
+    async fn from_request_parts(parts: &mut axum::http::request::Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = parts.headers.get("Authorization").and_then(|h| h.to_str().ok());
+        match get_bearer_token(auth).and_then(|t| T::from_token(&t)) {
+            Some(user) => Ok(SystemUser { user }),
+            None => Err(axum::http::StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+"#;
+
+const STANDALONE_COMMON_SEARCH_TMPL: &str = r#"
+/// 通用查询的一个条件，op为eq、ne、gt、ge、lt、le、like、in、is_null
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SearchCondition {
+    pub field: String,
+    pub op: String,
+    pub value: Option<serde_json::Value>,
+}
+
+/// 通用查询，由前端组合查询条件及排序
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CommonSearch {
+    pub conditions: Vec<SearchCondition>,
+    pub order_by: Option<String>,
+    pub asc: Option<bool>,
+}
+
+impl CommonSearch {
+    /// 列名直接拼接到SQL中，只允许字母、数字及下划线
+    fn is_safe_column(col: &str) -> bool {
+        !col.is_empty() && col.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    #[allow(dead_code)]
+    pub fn into_wrapper(&self, wp: rbatis::wrapper::Wrapper) -> rbatis::wrapper::Wrapper {
+        let mut wp = wp;
+        for cond in self.conditions.iter().filter(|c| Self::is_safe_column(&c.field)) {
+            let field = cond.field.as_str();
+            let value = cond.value.clone().unwrap_or(serde_json::Value::Null);
+            wp = match (cond.op.as_str(), value) {
+                ("eq", v) => wp.and().eq(field, v),
+                ("ne", v) => wp.and().ne(field, v),
+                ("gt", v) => wp.and().gt(field, v),
+                ("ge", v) => wp.and().ge(field, v),
+                ("lt", v) => wp.and().lt(field, v),
+                ("le", v) => wp.and().le(field, v),
+                ("like", v) => wp.and().like(field, v),
+                ("in", serde_json::Value::Array(items)) => wp.and().r#in(field, &items),
+                ("is_null", _) => wp.and().is_null(field),
+                _ => wp,
+            };
+        }
+        match self.order_by.clone() {
+            Some(col) if Self::is_safe_column(&col) => wp.order_by(self.asc.unwrap_or(true), &[col.as_str()]),
+            _ => wp,
+        }
+    }
+}
+"#;
+
+/**
+ * standalone的utils：加入ApiResult、SystemUser及其提取器、CurrentUser，rbatis 3.x时加入CommonSearch
+ */
+pub fn replace_standalone_utils(utilstext: &str, is_axum: bool, with_common_search: bool) -> String {
+    let mut text = utilstext.replace("chimes_utils::get_rbatis()", "get_rbatis()") + STANDALONE_TMPL;
+    if is_axum {
+        text.push_str(STANDALONE_AXUM_TMPL);
+    } else {
+        text.push_str(STANDALONE_ACTIX_TMPL);
+    }
+    if with_common_search {
+        text.push_str(STANDALONE_COMMON_SEARCH_TMPL);
+    }
+    text
+}
@@ -0,0 +1,36 @@
+/**
+ * 乐观锁的版本冲突，追加到生成的utils中
+ * #ERROR#为所使用的ORM的错误类型，#VARIANT#为用来表示版本冲突的错误
+ */
+pub const VERSION_UTILS_TMPL: &str = r#"
+/// 乐观锁版本冲突的错误信息，update没有更新到数据（已被其它人修改）时返回
+pub const VERSION_CONFLICT: &str = "rbatisgen:version-conflict";
+
+/// 乐观锁版本冲突的错误
+#[allow(dead_code)]
+pub fn version_conflict_error() -> #ERROR# {
+    #VARIANT#(VERSION_CONFLICT.to_string())
+}
+
+/// 是否为version_conflict_error()返回的错误，比较错误的类型及完整的错误信息
+#[allow(dead_code)]
+pub fn is_version_conflict(err: &#ERROR#) -> bool {
+    matches!(err, #VARIANT#(msg) if msg == VERSION_CONFLICT)
+}
+"#;
+
+/**
+ * rbatis 3.x为Error::E，rbatis 4.x为rbatis::Error::E，sqlx为Error::Protocol
+ */
+pub fn replace_version_utils(rbatis_v4: bool, sqlx: bool) -> String {
+    let (error, variant) = if sqlx {
+        ("sqlx::Error", "sqlx::Error::Protocol")
+    } else if rbatis_v4 {
+        ("rbatis::Error", "rbatis::Error::E")
+    } else {
+        ("rbatis::error::Error", "rbatis::error::Error::E")
+    };
+    VERSION_UTILS_TMPL
+        .replace("#ERROR#", error)
+        .replace("#VARIANT#", variant)
+}
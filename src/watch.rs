@@ -0,0 +1,648 @@
+//! copyright © 了知信息科技 2021 - present
+//! watch模式：监视配置文件、模板目录以及表结构的快照文件，发生变化时重新生成代码
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::codegen::{
+    expand_affected_tables, is_incremental_supported, restrict_config, splice_generated, CodeGenerator,
+};
+use crate::config::{AppConfig, CodeGenConfig, TableConfig};
+use crate::output::{FileSystemSink, OutputSink};
+use crate::schema::{CachedSchemaSource, MemorySchemaSource};
+use crate::{GenerateResult, Generator};
+
+/**
+ * 一次重新生成的变化情况
+ * reasons为触发的原因（config/templates/snapshot），tables为受影响的表，all_tables表示所有的表都受到影响
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSummary {
+    pub reasons: Vec<String>,
+    pub tables: Vec<String>,
+    pub all_tables: bool,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub stale: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl ChangeSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.stale.is_empty()
+    }
+}
+
+impl fmt::Display for ChangeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] tables: {}; {} added, {} changed, {} stale, {} unchanged",
+            self.reasons.join(","),
+            if self.all_tables {
+                "all".to_string()
+            } else if self.tables.is_empty() {
+                "none".to_string()
+            } else {
+                self.tables.join(", ")
+            },
+            self.added.len(),
+            self.changed.len(),
+            self.stale.len(),
+            self.unchanged
+        )?;
+        for p in self.added.iter() {
+            write!(f, "\n  + {}", p)?;
+        }
+        for p in self.changed.iter() {
+            write!(f, "\n  ~ {}", p)?;
+        }
+        for p in self.stale.iter() {
+            write!(f, "\n  - {} (no longer generated, kept)", p)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * 监视文件的变化并重新生成代码
+ * 表结构通过CachedSchemaSource读取，只有配置或快照发生了变化的表才会重新读取；
+ * 只为发生了变化的表（及引用了它们的表和relation）重新生成代码，与上一次生成的结果合并后，
+ * 汇总的文件（如mod.rs、main.rs）按所有的表输出，只写入内容发生了变化的文件
+ */
+pub struct Watcher {
+    config_path: String,
+    snapshot_path: Option<String>,
+    interval: Duration,
+    conf: AppConfig,
+    source: Arc<CachedSchemaSource>,
+    snapshot: Option<MemorySchemaSource>,
+    files: HashMap<String, String>,
+    mtimes: HashMap<String, SystemTime>,
+    prev: Option<CodeGenerator>,
+}
+
+impl Watcher {
+    pub fn new(config_path: &str, conf: &AppConfig, source: Arc<CachedSchemaSource>) -> Self {
+        Self {
+            config_path: config_path.to_string(),
+            snapshot_path: None,
+            interval: Duration::from_secs(1),
+            conf: conf.clone(),
+            source,
+            snapshot: None,
+            files: HashMap::new(),
+            mtimes: HashMap::new(),
+            prev: None,
+        }
+    }
+
+    /**
+     * 同时监视表结构的快照文件，source的内部来源应为从该文件加载的MemorySchemaSource
+     */
+    pub fn snapshot(mut self, path: &str, snapshot: &MemorySchemaSource) -> Self {
+        self.snapshot_path = Some(path.to_string());
+        self.snapshot = Some(snapshot.clone());
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /**
+     * 执行第一次生成，然后开始监视文件的变化
+     * 第一次生成的结果会返回，以便调用者写入Permission
+     */
+    pub async fn start(&mut self) -> std::io::Result<GenerateResult> {
+        self.mtimes = self.scan_mtimes();
+        let (summary, result) = self.regenerate(vec!["initial".to_string()], vec![], true).await?;
+        log::info!(
+            "Generated {} files, {} written to {}.",
+            result.files.len(),
+            summary.added.len(),
+            self.conf.codegen_conf.output_path
+        );
+        Ok(result)
+    }
+
+    pub async fn run(mut self) -> std::io::Result<()> {
+        log::info!(
+            "Watching {} for changes, press Ctrl+C to stop.",
+            self.watched_paths().join(", ")
+        );
+        loop {
+            tokio::time::sleep(self.interval).await;
+            if let Some(summary) = self.poll().await? {
+                log::info!("{}", summary);
+            }
+        }
+    }
+
+    /**
+     * 检查一次文件的变化，没有变化时返回None
+     */
+    pub async fn poll(&mut self) -> std::io::Result<Option<ChangeSummary>> {
+        let mtimes = self.scan_mtimes();
+        if mtimes == self.mtimes {
+            return Ok(None);
+        }
+        let changed_paths = mtimes
+            .iter()
+            .filter(|(p, t)| self.mtimes.get(*p) != Some(*t))
+            .map(|(p, _)| p.clone())
+            .chain(self.mtimes.keys().filter(|p| !mtimes.contains_key(*p)).cloned())
+            .collect::<Vec<String>>();
+        self.mtimes = mtimes;
+
+        let mut reasons = vec![];
+        let mut tables = vec![];
+        let mut all_tables = false;
+
+        if changed_paths.contains(&self.config_path) {
+            match self.reload_config() {
+                Some((tbls, all)) => {
+                    tables.extend(tbls);
+                    all_tables = all_tables || all;
+                    reasons.push("config".to_string());
+                }
+                None => return Ok(None),
+            }
+        }
+
+        if let Some(path) = self.snapshot_path.clone() {
+            if changed_paths.contains(&path) {
+                match self.reload_snapshot(&path) {
+                    Some((tbls, all)) => {
+                        tables.extend(tbls);
+                        all_tables = all_tables || all;
+                        reasons.push("snapshot".to_string());
+                    }
+                    None => return Ok(None),
+                }
+            }
+        }
+
+        if changed_paths
+            .iter()
+            .any(|p| p != &self.config_path && Some(p.clone()) != self.snapshot_path)
+        {
+            all_tables = true;
+            reasons.push("templates".to_string());
+        }
+
+        if reasons.is_empty() {
+            return Ok(None);
+        }
+        tables.sort();
+        tables.dedup();
+        let (summary, _) = self.regenerate(reasons, tables, all_tables).await?;
+        Ok(Some(summary))
+    }
+
+    fn watched_paths(&self) -> Vec<String> {
+        let mut paths = vec![self.config_path.clone()];
+        if let Some(dir) = self.conf.codegen_conf.template_dir.clone() {
+            paths.push(dir);
+        }
+        if let Some(path) = self.snapshot_path.clone() {
+            paths.push(path);
+        }
+        paths
+    }
+
+    fn scan_mtimes(&self) -> HashMap<String, SystemTime> {
+        let mut mtimes = HashMap::new();
+        for path in self.watched_paths() {
+            collect_mtimes(Path::new(&path), &mut mtimes);
+        }
+        mtimes
+    }
+
+    /**
+     * 重新加载配置文件，返回配置发生了变化的表，以及是否影响到所有的表
+     * 配置文件无法解析（如正在编辑中）时返回None，等待下一次变化
+     */
+    fn reload_config(&mut self) -> Option<(Vec<String>, bool)> {
+        let text = std::fs::read_to_string(&self.config_path).unwrap_or_default();
+        match yaml_rust::YamlLoader::load_from_str(&text) {
+            Ok(docs) if !docs.is_empty() => {}
+            Ok(_) => {
+                log::warn!("The config file {} is empty, skipped.", self.config_path);
+                return None;
+            }
+            Err(err) => {
+                log::warn!("The config file {} could not be parsed, skipped. {}", self.config_path, err);
+                return None;
+            }
+        }
+        let mut conf = AppConfig::default();
+        conf.load_yaml(&self.config_path);
+
+        if conf.mysql_conf.url != self.conf.mysql_conf.url {
+            log::warn!("The database url was changed, restart the generator to connect to the new database.");
+        }
+
+        let old = &self.conf.codegen_conf;
+        let new = &conf.codegen_conf;
+        if new.schema_name != old.schema_name {
+            self.source.invalidate_all();
+        }
+        if serde_json::to_string(&new.queries).ok() != serde_json::to_string(&old.queries).ok() {
+            self.source.invalidate_queries();
+        }
+        let diff = diff_table_configs(old, new);
+
+        self.conf = conf;
+        Some(diff)
+    }
+
+    /**
+     * 重新加载快照文件，返回结构发生了变化的表，以及是否影响到所有的表（查询的结构发生了变化）
+     */
+    fn reload_snapshot(&mut self, path: &str) -> Option<(Vec<String>, bool)> {
+        let snapshot = match MemorySchemaSource::load_snapshot(path) {
+            Ok(s) => s,
+            Err(err) => {
+                log::warn!("The snapshot file {} could not be loaded, skipped. {}", path, err);
+                return None;
+            }
+        };
+        let prev = self.snapshot.clone().unwrap_or_default();
+        let mut names = snapshot.columns.keys().cloned().collect::<Vec<String>>();
+        names.extend(prev.columns.keys().cloned());
+        names.sort();
+        names.dedup();
+        let tables = names
+            .into_iter()
+            .filter(|tn| prev.table_fingerprint(tn) != snapshot.table_fingerprint(tn))
+            .collect::<Vec<String>>();
+        for tn in tables.iter() {
+            self.source.invalidate(tn);
+        }
+        let queries = serde_json::to_string(&prev.queries).ok() != serde_json::to_string(&snapshot.queries).ok();
+        if queries {
+            self.source.invalidate_queries();
+        }
+        self.source.replace_inner(Arc::new(snapshot.clone()));
+        self.snapshot = Some(snapshot);
+        Some((tables, queries))
+    }
+
+    /**
+     * 在内存中重新生成受影响的表，与上一次的结果比较后只写入新增或发生了变化的文件
+     * all_tables或者生成的文件汇总了所有的表（如openapi.json、gRPC）时，重新生成所有的表
+     */
+    async fn regenerate(
+        &mut self,
+        reasons: Vec<String>,
+        tables: Vec<String>,
+        all_tables: bool,
+    ) -> std::io::Result<(ChangeSummary, GenerateResult)> {
+        for tn in tables.iter() {
+            self.source.invalidate(tn);
+        }
+        let generator = Generator::from_app_config(&self.conf).schema_source(self.source.clone());
+        // 读取所有的表结构（未变化的表从缓存中读取），用于输出汇总的文件
+        let base = generator.load().await?;
+
+        let mut summary = ChangeSummary {
+            reasons,
+            tables,
+            all_tables,
+            ..Default::default()
+        };
+        let cg = match self.prev.take() {
+            Some(prev) if !all_tables && is_incremental_supported(&base.ctx) => {
+                let affected = expand_affected_tables(&base.ctx, &summary.tables);
+                let partconf = restrict_config(&base.ctx, &affected);
+                let mut part = Generator::from_config(&partconf)
+                    .redis(&self.conf.redis_conf)
+                    .schema_source(self.source.clone())
+                    .load()
+                    .await?;
+                part.generate();
+                summary.tables = affected.clone();
+                splice_generated(base, &prev, &part, &affected)
+            }
+            _ => {
+                summary.all_tables = true;
+                let mut cg = base;
+                cg.generate();
+                cg
+            }
+        };
+        let result = GenerateResult {
+            files: cg.collect_outputs(),
+            permissions: cg.ctx.permissions.clone(),
+        };
+        self.prev = Some(cg);
+
+        let mut sink = FileSystemSink::new(&self.conf.codegen_conf.output_path);
+        let mut files = HashMap::new();
+        for gf in result.files.iter() {
+            match self.files.get(&gf.path) {
+                Some(prev) if prev == &gf.content => {
+                    summary.unchanged += 1;
+                }
+                prev => {
+                    if sink.write_generated(gf)? {
+                        if prev.is_none() {
+                            summary.added.push(gf.path.clone());
+                        } else {
+                            summary.changed.push(gf.path.clone());
+                        }
+                    } else {
+                        summary.unchanged += 1;
+                    }
+                }
+            }
+            files.insert(gf.path.clone(), gf.content.clone());
+        }
+        sink.finish()?;
+        summary.stale = self
+            .files
+            .keys()
+            .filter(|p| !files.contains_key(*p))
+            .cloned()
+            .collect();
+        summary.stale.sort();
+        self.files = files;
+        Ok((summary, result))
+    }
+}
+
+fn table_fingerprint(tbc: &TableConfig) -> String {
+    serde_json::to_string(tbc).unwrap_or_default()
+}
+
+/**
+ * 比较两次的配置，返回配置发生了变化（含新增、删除）的表，以及是否影响到所有的表
+ * 表以外的配置（如relations、queries或全局的选项）发生变化时，所有的表都可能受影响
+ */
+fn diff_table_configs(old: &CodeGenConfig, new: &CodeGenConfig) -> (Vec<String>, bool) {
+    let mut tables = vec![];
+    for tbc in new.tables.iter() {
+        let prev = old.tables.iter().find(|t| t.name == tbc.name);
+        if prev.map(table_fingerprint) != Some(table_fingerprint(tbc)) {
+            tables.push(tbc.name.clone());
+        }
+    }
+    for tbc in old.tables.iter() {
+        if !new.tables.iter().any(|t| t.name == tbc.name) {
+            tables.push(tbc.name.clone());
+        }
+    }
+
+    let mut rest_old = old.clone();
+    let mut rest_new = new.clone();
+    rest_old.tables.clear();
+    rest_new.tables.clear();
+    let all = serde_json::to_string(&rest_old).ok() != serde_json::to_string(&rest_new).ok();
+    (tables, all)
+}
+
+fn collect_mtimes(path: &Path, mtimes: &mut HashMap<String, SystemTime>) {
+    if path.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_mtimes(&entry.path(), mtimes);
+            }
+        }
+    } else if let Ok(meta) = std::fs::metadata(path) {
+        if let Ok(mt) = meta.modified() {
+            mtimes.insert(path.to_string_lossy().to_string(), mt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    use yaml_rust::YamlLoader;
+
+    use super::{diff_table_configs, Watcher};
+    use crate::config::{AppConfig, CodeGenConfig};
+    use crate::schema::{CachedSchemaSource, ColumnInfo, MemorySchemaSource, TableInfo};
+    use crate::Generator;
+
+    const CONFIG: &str = "codegen:
+  app-name: demo
+  app-version: 0.1.0
+  app-edition: \"2021\"
+  output-path: #OUTPUT#
+  schema-name: demo
+  api-handler-prefix: /api/v1
+  always-generate-handler: true
+  always-generate-entity: true
+  always-override: true
+  tables:
+    - name: sys_role
+      primary-key: role_id
+      comment: 角色
+    - name: sys_user
+      primary-key: user_id
+      comment: #USER_COMMENT#
+      page-query: true
+  relations:
+    - struct-name: RoleUsers
+      major-table: sys_role
+      comment: 角色用户
+      one-to-many:
+        - table-name: sys_user
+          join-field: role_id
+          major-field: role_id
+";
+
+    fn column(tn: &str, name: &str, data_type: &str, key: &str, comment: &str, pos: i64) -> ColumnInfo {
+        ColumnInfo {
+            table_schema: Some("demo".to_string()),
+            table_name: Some(tn.to_string()),
+            column_name: Some(name.to_string()),
+            column_type: Some(data_type.to_string()),
+            column_comment: Some(comment.to_string()),
+            column_key: Some(key.to_string()),
+            data_type: Some(data_type.to_string()),
+            ordinal_position: Some(pos),
+            is_nullable: Some(if key == "PRI" { "NO" } else { "YES" }.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn table(tn: &str, comment: &str) -> TableInfo {
+        TableInfo {
+            table_schema: Some("demo".to_string()),
+            table_type: Some("BASE TABLE".to_string()),
+            table_name: Some(tn.to_string()),
+            table_comment: Some(comment.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * sys_user通过注释中的关联表引用sys_role
+     */
+    fn snapshot(role_name_comment: &str) -> MemorySchemaSource {
+        MemorySchemaSource::new()
+            .add_table(
+                &table("sys_role", "角色"),
+                &vec![
+                    column("sys_role", "role_id", "bigint", "PRI", "角色ID", 1),
+                    column("sys_role", "role_name", "varchar", "", role_name_comment, 2),
+                ],
+            )
+            .add_table(
+                &table("sys_user", "用户"),
+                &vec![
+                    column("sys_user", "user_id", "bigint", "PRI", "用户ID", 1),
+                    column("sys_user", "username", "varchar", "", "用户名", 2),
+                    column("sys_user", "role_id", "bigint", "", "角色 关联表sys_role", 3),
+                ],
+            )
+            .add_table(
+                &table("sys_log", "日志"),
+                &vec![column("sys_log", "log_id", "bigint", "PRI", "日志ID", 1)],
+            )
+    }
+
+    fn write_touched(path: &str, text: &str, tick: u64) {
+        std::fs::write(path, text).unwrap();
+        let mtime = SystemTime::now() + Duration::from_secs(tick);
+        File::options().write(true).open(path).unwrap().set_modified(mtime).unwrap();
+    }
+
+    fn config_text(output: &str, user_comment: &str, extra_table: bool) -> String {
+        let mut text = CONFIG.replace("#OUTPUT#", output).replace("#USER_COMMENT#", user_comment);
+        if extra_table {
+            text = text.replace(
+                "  relations:",
+                "    - name: sys_log\n      primary-key: log_id\n      comment: 日志\n  relations:",
+            );
+        }
+        text
+    }
+
+    /**
+     * 部分重新生成的结果应与全部重新生成的结果相同
+     */
+    async fn assert_same_as_full(watcher: &Watcher, snap: &MemorySchemaSource) {
+        let result = Generator::from_app_config(&watcher.conf)
+            .schema_source(Arc::new(snap.clone()))
+            .run()
+            .await
+            .unwrap();
+        let full = result
+            .files
+            .into_iter()
+            .map(|gf| (gf.path, gf.content))
+            .collect::<HashMap<String, String>>();
+        let mut paths = full.keys().cloned().collect::<Vec<String>>();
+        paths.sort();
+        let mut watched = watcher.files.keys().cloned().collect::<Vec<String>>();
+        watched.sort();
+        assert_eq!(watched, paths);
+        for p in paths.iter() {
+            assert_eq!(watcher.files[p], full[p], "{}", p);
+        }
+    }
+
+    fn codegen(text: &str) -> CodeGenConfig {
+        let docs = YamlLoader::load_from_str(text).unwrap();
+        CodeGenConfig::load_from_yaml(&docs[0]["codegen"])
+    }
+
+    #[test]
+    fn test_diff_table_configs() {
+        let old = codegen(&config_text("/tmp/demo", "用户", false));
+        let (tables, all) = diff_table_configs(&old, &old);
+        assert!(tables.is_empty());
+        assert!(!all);
+
+        let new = codegen(&config_text("/tmp/demo", "系统用户", true));
+        let (tables, all) = diff_table_configs(&old, &new);
+        assert_eq!(tables, vec!["sys_user".to_string(), "sys_log".to_string()]);
+        assert!(!all);
+
+        // 去掉的表同样受影响
+        let (tables, _) = diff_table_configs(&new, &old);
+        assert_eq!(tables, vec!["sys_user".to_string(), "sys_log".to_string()]);
+
+        let global = codegen(&config_text("/tmp/demo", "用户", false).replace("always-override: true", "always-override: false"));
+        let (tables, all) = diff_table_configs(&old, &global);
+        assert!(tables.is_empty());
+        assert!(all);
+    }
+
+    #[tokio::test]
+    async fn test_watch_regenerates_changed_tables() {
+        let dir = std::env::temp_dir().join(format!("rbatisgen-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("out").to_string_lossy().to_string();
+        let config_path = dir.join("rbatis.yml").to_string_lossy().to_string();
+        let snapshot_path = dir.join("schema.json").to_string_lossy().to_string();
+
+        write_touched(&config_path, &config_text(&output, "用户", false), 0);
+        let snap = snapshot("角色名称");
+        snap.save_snapshot(&snapshot_path).unwrap();
+        let mut conf = AppConfig::default();
+        conf.load_yaml(&config_path);
+
+        let source = Arc::new(CachedSchemaSource::new(Arc::new(snap.clone())));
+        let mut watcher = Watcher::new(&config_path, &conf, source).snapshot(&snapshot_path, &snap);
+        watcher.start().await.unwrap();
+        assert!(watcher.poll().await.unwrap().is_none());
+
+        // 快照中sys_role的注释变化：引用了它的sys_user及relation同样重新生成
+        let snap = snapshot("角色的名称");
+        snap.save_snapshot(&snapshot_path).unwrap();
+        File::options()
+            .write(true)
+            .open(&snapshot_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        let summary = watcher.poll().await.unwrap().unwrap();
+        assert_eq!(summary.reasons, vec!["snapshot".to_string()]);
+        assert_eq!(summary.tables, vec!["sys_role".to_string(), "sys_user".to_string()]);
+        assert!(!summary.all_tables);
+        assert!(summary.changed.iter().any(|p| p.ends_with("role/index.vue")), "{}", summary);
+        assert!(summary.stale.is_empty());
+        assert_same_as_full(&watcher, &snap).await;
+
+        // 配置中新增的表及变化的表
+        write_touched(&config_path, &config_text(&output, "系统用户", true), 20);
+        let summary = watcher.poll().await.unwrap().unwrap();
+        assert_eq!(summary.reasons, vec!["config".to_string()]);
+        assert_eq!(summary.tables, vec!["sys_log".to_string(), "sys_user".to_string()]);
+        assert!(!summary.all_tables);
+        assert!(summary.added.iter().any(|p| p.ends_with("entity/sys_log.rs")), "{}", summary);
+        assert_same_as_full(&watcher, &snap).await;
+
+        // 去掉的表不再生成
+        write_touched(&config_path, &config_text(&output, "系统用户", false), 30);
+        let summary = watcher.poll().await.unwrap().unwrap();
+        assert_eq!(summary.tables, vec!["sys_log".to_string()]);
+        assert!(summary.stale.iter().any(|p| p.ends_with("entity/sys_log.rs")), "{}", summary);
+        assert_same_as_full(&watcher, &snap).await;
+
+        // 全局的配置变化时重新生成所有的表
+        write_touched(
+            &config_path,
+            &config_text(&output, "系统用户", false).replace("always-override: true", "always-override: false"),
+            40,
+        );
+        let summary = watcher.poll().await.unwrap().unwrap();
+        assert_eq!(summary.reasons, vec!["config".to_string()]);
+        assert!(summary.all_tables);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,164 @@
+import request from '@/utils/request'
+{%- set api_base = codegen.api_handler_prefix ~ "/" ~ table_conf.api_handler_name %}
+
+/**
+ * {{ table_conf.comment }} - 新增
+ */
+export function add(data) {
+  return request({
+    url: '{{ api_base }}/create',
+    method: 'post',
+    data
+  })
+}
+
+/**
+ * {{ table_conf.comment }} - 删除
+ */
+export function del(data) {
+  return request({
+    url: '{{ api_base }}/delete',
+    method: 'post',
+    data
+  })
+}
+
+/**
+ * {{ table_conf.comment }} - 批量删除
+ */
+export function delIds(ids) {
+  return request({
+    url: '{{ api_base }}/delete_ids',
+    method: 'post',
+    data: ids
+  })
+}
+{%- if table_conf.logic_deletion %}
+
+/**
+ * {{ table_conf.comment }} - 恢复
+ */
+export function restore(data) {
+  return request({
+    url: '{{ api_base }}/restore',
+    method: 'post',
+    data
+  })
+}
+
+/**
+ * {{ table_conf.comment }} - 彻底删除
+ */
+export function purge(data) {
+  return request({
+    url: '{{ api_base }}/purge',
+    method: 'post',
+    data
+  })
+}
+{%- endif %}
+
+/**
+ * {{ table_conf.comment }} - 修改
+ */
+export function edit(data) {
+  return request({
+    url: '{{ api_base }}/update',
+    method: 'post',
+    data
+  })
+}
+
+/**
+ * {{ table_conf.comment }} - 按主键获取
+ */
+export function get(id) {
+  return request({
+    url: '{{ api_base }}/get/' + id,
+    method: 'get'
+  })
+}
+
+/**
+ * {{ table_conf.comment }} - 列表查询
+ */
+export function search(data) {
+  return request({
+    url: '{{ api_base }}/search',
+    method: 'post',
+    data
+  })
+}
+{% if table_conf.page_query %}
+/**
+ * {{ table_conf.comment }} - 分页查询
+ */
+export function paged(data, current, size) {
+  return request({
+    url: '{{ api_base }}/paged/' + current + '/' + size,
+    method: 'post',
+    data
+  })
+}
+{% endif %}
+{%- if table_conf.tree_parent_field %}
+/**
+ * {{ table_conf.comment }} - 树形查询
+ */
+export function tree(pid) {
+  return request({
+    url: '{{ api_base }}/tree',
+    method: 'get',
+    params: { pid }
+  })
+}
+{% endif %}
+{%- if relation_conf and relation_conf.api_handler_name %}
+{%- set rel_base = codegen.api_handler_prefix ~ "/" ~ relation_conf.api_handler_name %}
+{%- if relation_conf.generate_select %}
+/**
+ * {{ relation_conf.comment }} - 加载关系数据
+ */
+export function relLoad(id) {
+  return request({
+    url: '{{ rel_base }}/load/' + id,
+    method: 'get'
+  })
+}
+{% endif %}
+{%- if relation_conf.generate_save %}
+/**
+ * {{ relation_conf.comment }} - 保存关系数据
+ */
+export function relSave(data) {
+  return request({
+    url: '{{ rel_base }}/rel/save',
+    method: 'post',
+    data
+  })
+}
+{% endif %}
+{%- if relation_conf.generate_delete %}
+/**
+ * {{ relation_conf.comment }} - 删除关系数据
+ */
+export function relRemove(id) {
+  return request({
+    url: '{{ rel_base }}/remove/' + id,
+    method: 'post'
+  })
+}
+
+/**
+ * {{ relation_conf.comment }} - 批量删除关系数据
+ */
+export function relRemoveMulti(data) {
+  return request({
+    url: '{{ rel_base }}/multi/remove',
+    method: 'post',
+    data
+  })
+}
+{% endif %}
+{%- endif %}
+export default { add, edit, del, delIds, {% if table_conf.logic_deletion %}restore, purge, {% endif %}get, search{% if table_conf.page_query %}, paged{% endif %}{% if table_conf.tree_parent_field %}, tree{% endif %}{% if relation_conf and relation_conf.api_handler_name %}{% if relation_conf.generate_select %}, relLoad{% endif %}{% if relation_conf.generate_save %}, relSave{% endif %}{% if relation_conf.generate_delete %}, relRemove, relRemoveMulti{% endif %}{% endif %} }
@@ -0,0 +1,16 @@
+import request from '@/utils/request'
+import type { {{ type_imports | join(sep=", ") }} } from './types'
+{% for ep in endpoints %}
+/**
+ * {{ ep.comment }}
+ */
+export function {{ ep.name }}({% for pm in ep.params %}{{ pm.name }}{% if pm.optional %}?{% endif %}: {{ pm.ts_type }}{% if not loop.last %}, {% endif %}{% endfor %}): Promise<ApiResult<{{ ep.return_type }}>> {
+  return request<ApiResult<{{ ep.return_type }}>, ApiResult<{{ ep.return_type }}>>({
+    url: {{ ep.url }},
+    method: '{{ ep.method }}'{% if ep.data %},
+    data: {{ ep.data }}{% endif %}{% if ep.query %},
+    params: { {{ ep.query | join(sep=", ") }} }{% endif %}
+  })
+}
+{% endfor %}
+export default { {% for ep in endpoints %}{{ ep.name }}{% if not loop.last %}, {% endif %}{% endfor %} }
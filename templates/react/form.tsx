@@ -0,0 +1,301 @@
+{%- macro widget(fd, relation_map, inline=false, on_change="") -%}
+{%- if fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler -%}
+<Select showSearch optionFilterProp="label" placeholder="请选择{{ fd.caption }}" options={options{{ relation_map[fd.relation].struct_name }}.options}{{ on_change | safe }} />
+{%- elif fd.dict and fd.dict != "area" -%}
+<Select placeholder="请选择{{ fd.caption }}" options={dictOptions('{{ fd.dict }}')}{{ on_change | safe }} />
+{%- elif fd.dict == "area" -%}
+<AreaPicker{{ on_change | safe }} />
+{%- elif fd.field.field_type == "bool" -%}
+<Switch{{ on_change | safe }} />
+{%- elif not inline and fd.field.field_type is containing("DateTime") -%}
+<DatePicker showTime />
+{%- elif not inline and (fd.field.field_type is containing("i64") or fd.field.field_type is containing("i32") or fd.field.field_type is containing("Decimal")) -%}
+<InputNumber style={{ "{{" }} width: '100%' {{ "}}" }} />
+{%- else -%}
+<Input{{ on_change | safe }} />
+{%- endif -%}
+{%- endmacro widget -%}
+{%- macro rules(fd) -%}
+{%- if fd.required or fd.validate and fd.regex_check %} rules={[{% if fd.required %}{ required: true, message: '{{ fd.caption }}不能为空' }{% endif %}{% if fd.validate and fd.regex_check %}{% if fd.required %}, {% endif %}{ pattern: /{{ fd.validate }}/, message: '{{ fd.caption }}格式不正确' }{% endif %}]}{% endif -%}
+{%- endmacro rules -%}
+{%- set api = "api" ~ table_conf.struct_name -%}
+{%- set api_path = "@/api/" ~ codegen.schema_name ~ "/" -%}
+{%- if not codegen.schema_name %}{% set api_path = "@/api/" %}{% endif -%}
+{%- if relation_conf.extend_major %}{% set major = "" %}{% else %}{% set major = table_conf.api_handler_name %}{% endif -%}
+{#- 按照实际用到的字段决定需要import的组件，避免noUnusedLocals的错误 #}
+{%- set_global use_date = false %}{% set_global use_number = false %}{% set_global use_switch = false %}{% set_global use_input = false %}
+{%- set_global use_select = false %}{% set_global use_dict_label = false %}{% set_global use_dict_options = false %}{% set_global use_area = false %}
+{%- set_global has_many = false %}{% set_global has_dialog = false %}
+{%- set inline_group = false %}{% set dialog_group = false %}
+{%- for fd in fields %}{% if not fd.hidden %}
+{%- if fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}{% set_global use_select = true %}
+{%- elif fd.dict and fd.dict != "area" %}{% set_global use_select = true %}{% set_global use_dict_options = true %}{% if dialog_group %}{% set_global use_dict_label = true %}{% endif %}
+{%- elif fd.dict == "area" %}{% set_global use_area = true %}
+{%- elif fd.field.field_type == "bool" %}{% set_global use_switch = true %}
+{%- elif not inline_group and fd.field.field_type is containing("DateTime") %}{% set_global use_date = true %}
+{%- elif not inline_group and (fd.field.field_type is containing("i64") or fd.field.field_type is containing("i32") or fd.field.field_type is containing("Decimal")) %}{% set_global use_number = true %}
+{%- else %}{% set_global use_input = true %}
+{%- endif %}
+{%- endif %}{% endfor %}
+{%- for rel in relations %}
+{%- if rel.one_many %}{% set_global has_many = true %}{% if rel.dialog_form %}{% set_global has_dialog = true %}{% endif %}{% endif %}
+{%- set inline_group = rel.one_many and not rel.dialog_form %}{% set dialog_group = rel.one_many and rel.dialog_form %}
+{%- for fd in rel.fields %}{% if not fd.hidden %}
+{%- if fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}{% set_global use_select = true %}
+{%- elif fd.dict and fd.dict != "area" %}{% set_global use_select = true %}{% set_global use_dict_options = true %}{% if dialog_group %}{% set_global use_dict_label = true %}{% endif %}
+{%- elif fd.dict == "area" %}{% set_global use_area = true %}
+{%- elif fd.field.field_type == "bool" %}{% set_global use_switch = true %}
+{%- elif not inline_group and fd.field.field_type is containing("DateTime") %}{% set_global use_date = true %}
+{%- elif not inline_group and (fd.field.field_type is containing("i64") or fd.field.field_type is containing("i32") or fd.field.field_type is containing("Decimal")) %}{% set_global use_number = true %}
+{%- else %}{% set_global use_input = true %}
+{%- endif %}
+{%- endif %}{% endfor %}
+{%- endfor %}
+{%- set_global use_options = false %}{% for rc in relation_tables %}{% if rc.generate_handler %}{% set_global use_options = true %}{% endif %}{% endfor -%}
+import React, { forwardRef, useImperativeHandle, useState } from 'react'
+import { {% if has_many %}Button, {% endif %}{% if use_date %}DatePicker, {% endif %}Divider, Form{% if use_input %}, Input{% endif %}{% if use_number %}, InputNumber{% endif %}, Modal{% if use_select %}, Select{% endif %}{% if has_many %}, Space{% endif %}{% if use_switch %}, Switch{% endif %}{% if has_many %}, Table{% endif %}, message } from 'antd'
+{%- if has_many %}
+import type { ColumnsType } from 'antd/es/table'
+import { DeleteOutlined{% if has_dialog %}, EditOutlined{% endif %}, PlusOutlined } from '@ant-design/icons'
+{%- endif %}
+import {{ api }} from '{{ api_path }}{{ table_conf.api_handler_name }}'
+{%- if typescript_api %}
+import type { {{ relation_conf.struct_name }} } from '{{ api_path }}types'
+{%- endif %}
+{%- for rc in relation_tables %}{% if rc.generate_handler and rc.struct_name != table_conf.struct_name %}
+import api{{ rc.struct_name }} from '{{ api_path }}{{ rc.api_handler_name }}'
+{%- endif %}{% endfor %}
+import { fromFormValues, toFormValues{% if use_dict_label or use_dict_options %}, useDict{% endif %}{% if use_options %}, useOptions{% endif %} } from '../hooks/useCrud'
+{%- if use_area %}
+import AreaPicker from '@/components/AreaPicker'
+{%- endif %}
+
+export interface RelFormRef {
+  open: (id?: number | null) => void
+}
+
+interface RelFormProps {
+  onSaved?: (data: unknown) => void
+}
+
+type Row = Record<string, any>
+
+// 表单中需要转换为dayjs的日期、时间字段
+{%- set_global date_fields = [] %}{% for fd in fields %}{% if fd.field.field_type is containing("DateTime") %}{% set_global date_fields = date_fields | concat(with=fd.field.field_name) %}{% endif %}{% endfor %}
+const majorDateFields: string[] = [{% for f in date_fields %}'{{ f }}'{% if not loop.last %}, {% endif %}{% endfor %}]
+{%- for rel in relations %}{% if not rel.one_many or rel.dialog_form %}
+{%- set_global date_fields = [] %}{% for fd in rel.fields %}{% if fd.field.field_type is containing("DateTime") %}{% set_global date_fields = date_fields | concat(with=fd.field.field_name) %}{% endif %}{% endfor %}
+const {{ rel.major_field_name }}DateFields: string[] = [{% for f in date_fields %}'{{ f }}'{% if not loop.last %}, {% endif %}{% endfor %}]
+{%- endif %}{% endfor %}
+
+// 接口数据与表单数据的转换，一对多的关联表不在表单中
+const toValues = (data: Row): Row => ({
+{%- if major %}
+  {{ major }}: toFormValues(data.{{ major }} || {}, majorDateFields),
+{%- else %}
+  ...toFormValues(data, majorDateFields),
+{%- endif %}
+{%- for rel in relations %}{% if not rel.one_many %}
+  {{ rel.major_field_name }}: toFormValues(data.{{ rel.major_field_name }} || {}, {{ rel.major_field_name }}DateFields),
+{%- endif %}{% endfor %}
+})
+
+const fromValues = (values: Row): Row => ({
+{%- if major %}
+  {{ major }}: fromFormValues(values.{{ major }} || {}, majorDateFields),
+{%- else %}
+  ...fromFormValues(values, majorDateFields),
+{%- endif %}
+{%- for rel in relations %}{% if not rel.one_many %}
+  {{ rel.major_field_name }}: fromFormValues(values.{{ rel.major_field_name }} || {}, {{ rel.major_field_name }}DateFields),
+{%- endif %}{% endfor %}
+})
+
+/**
+ * {{ relation_conf.comment }}
+ */
+const {{ relation_conf.struct_name }}Form = forwardRef<RelFormRef, RelFormProps>((props, ref) => {
+  const [form] = Form.useForm()
+{%- if use_dict_label or use_dict_options %}
+  const { {% if use_dict_options %}dictOptions{% endif %}{% if use_dict_label and use_dict_options %}, {% endif %}{% if use_dict_label %}dictLabel{% endif %} } = useDict({% for dt in dict_list %}'{{ dt }}'{% if not loop.last %}, {% endif %}{% endfor %})
+{%- endif %}
+{%- for rc in relation_tables %}{% if rc.generate_handler %}
+  const options{{ rc.struct_name }} = useOptions(api{{ rc.struct_name }}.search, '{{ rc.primary_key }}')
+{%- endif %}{% endfor %}
+  const [visible, setVisible] = useState(false)
+  const [editing, setEditing] = useState(false)
+  const [saving, setSaving] = useState(false)
+  const [loaded, setLoaded] = useState<Row>({})
+{%- for rel in relations %}{% if rel.one_many %}
+  const [{{ rel.major_field_name }}, set{{ pascal(str=rel.major_field_name) }}] = useState<Row[]>([])
+  const [{{ rel.major_field_name }}Deleted, set{{ pascal(str=rel.major_field_name) }}Deleted] = useState<Row[]>([])
+{%- if rel.dialog_form %}
+  const [{{ rel.major_field_name }}Form] = Form.useForm()
+  const [{{ rel.major_field_name }}Editing, set{{ pascal(str=rel.major_field_name) }}Editing] = useState({ visible: false, index: -1 })
+{%- endif %}
+{%- endif %}{% endfor %}
+
+  const reset = (data: Row) => {
+    setLoaded(data)
+    form.resetFields()
+    form.setFieldsValue(toValues(data))
+{%- for rel in relations %}{% if rel.one_many %}
+    set{{ pascal(str=rel.major_field_name) }}(data.{{ rel.major_field_name }} || [])
+    set{{ pascal(str=rel.major_field_name) }}Deleted([])
+{%- endif %}{% endfor %}
+  }
+
+  useImperativeHandle(ref, () => ({
+    open: (id?: number | null) => {
+      reset({})
+      setEditing(id !== null && id !== undefined)
+      if (id !== null && id !== undefined) {
+        {{ api }}.relLoad(id).then((res: any) => reset(res.data || {}))
+      }
+      setVisible(true)
+    }
+  }))
+{%- for rel in relations %}{% if rel.one_many %}
+{%- set name = rel.major_field_name %}
+{%- set rel_name = pascal(str=rel.major_field_name) %}
+
+  // {{ rel.table_conf.comment }}
+  const remove{{ rel_name }} = (index: number) => {
+    const removed = {{ name }}[index]
+    set{{ rel_name }}({{ name }}.filter((_, i) => i !== index))
+    if (removed && removed.{{ rel.table_conf.primary_key }}) {
+      set{{ rel_name }}Deleted([...{{ name }}Deleted, removed])
+    }
+  }
+{%- if rel.dialog_form %}
+
+  // {{ rel.table_conf.comment }}在对话框中编辑，index为-1时为新增
+  const edit{{ rel_name }} = (index: number) => {
+    {{ name }}Form.resetFields()
+    if (index >= 0) {
+      {{ name }}Form.setFieldsValue(toFormValues({{ name }}[index], {{ name }}DateFields))
+    }
+    set{{ rel_name }}Editing({ visible: true, index })
+  }
+
+  const confirm{{ rel_name }} = () => {
+    {{ name }}Form.validateFields().then(values => {
+      const index = {{ name }}Editing.index
+      const row = { ...(index >= 0 ? {{ name }}[index] : {}), ...fromFormValues(values, {{ name }}DateFields) }
+      set{{ rel_name }}(index >= 0 ? {{ name }}.map((it, i) => (i === index ? row : it)) : [...{{ name }}, row])
+      set{{ rel_name }}Editing({ visible: false, index: -1 })
+    }).catch(() => {})
+  }
+{%- else %}
+
+  const update{{ rel_name }} = (index: number, field: string, value: unknown) => {
+    set{{ rel_name }}({{ name }}.map((it, i) => (i === index ? { ...it, [field]: value } : it)))
+  }
+{%- endif %}
+
+  const {{ name }}Columns: ColumnsType<Row> = [
+{%- for fd in rel.fields %}{% if not fd.hidden %}
+{%- if rel.dialog_form %}
+{%- if fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => options{{ relation_map[fd.relation].struct_name }}.optionLabel(value) as React.ReactNode },
+{%- elif fd.dict and fd.dict != "area" %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => dictLabel('{{ fd.dict }}', value) as React.ReactNode },
+{%- elif fd.field.field_type == "bool" %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => (value ? '是' : '否') },
+{%- else %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}' },
+{%- endif %}
+{%- else %}
+{%- if fd.field.field_type == "bool" %}{% set on_change = " checked={row." ~ fd.field.field_name ~ "} onChange={(value: boolean) => update" ~ rel_name ~ "(index, '" ~ fd.field.field_name ~ "', value)}" %}
+{%- elif fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler or fd.dict %}{% set on_change = " value={row." ~ fd.field.field_name ~ "} onChange={(value: unknown) => update" ~ rel_name ~ "(index, '" ~ fd.field.field_name ~ "', value)}" %}
+{%- else %}{% set on_change = " value={row." ~ fd.field.field_name ~ "} onChange={(e: React.ChangeEvent<HTMLInputElement>) => update" ~ rel_name ~ "(index, '" ~ fd.field.field_name ~ "', e.target.value)}" %}
+{%- endif %}
+    {
+      title: '{{ fd.caption }}',
+      dataIndex: '{{ fd.field.field_name }}',
+      render: (_: unknown, row: Row, index: number) => {{ self::widget(fd=fd, relation_map=relation_map, inline=true, on_change=on_change) }}
+    },
+{%- endif %}
+{%- endif %}{% endfor %}
+    {
+      title: '操作',
+      key: 'action',
+      width: {% if rel.dialog_form %}100{% else %}60{% endif %},
+      align: 'center',
+      render: (_: unknown, _row: Row, index: number) => (
+        <Space>
+{%- if rel.dialog_form %}
+          <Button type="primary" size="small" icon={<EditOutlined />} onClick={() => edit{{ rel_name }}(index)} />
+{%- endif %}
+          <Button danger size="small" icon={<DeleteOutlined />} onClick={() => remove{{ rel_name }}(index)} />
+        </Space>
+      )
+    }
+  ]
+{%- endif %}{% endfor %}
+
+  const submit = () => {
+    form.validateFields().then(values => {
+      setSaving(true)
+      const data = {
+        ...loaded,
+        ...fromValues(values),
+{%- for rel in relations %}{% if rel.one_many %}
+        {{ rel.major_field_name }},
+        {{ rel.major_field_name }}_deleted: {{ rel.major_field_name }}Deleted,
+{%- endif %}{% endfor %}
+      }
+      {{ api }}.relSave(data{% if typescript_api %} as {{ relation_conf.struct_name }}{% endif %}).then((res: any) => {
+        if (res.status === 200) {
+          message.success('保存成功')
+          setVisible(false)
+          props.onSaved?.(res.data)
+        } else {
+          message.error(res.message)
+        }
+      }).finally(() => setSaving(false))
+    }).catch(() => {})
+  }
+
+  return (
+    <Modal open={visible} title={editing ? '编辑{{ relation_conf.comment }}' : '新增{{ relation_conf.comment }}'} width={900} maskClosable={false} confirmLoading={saving} onOk={submit} onCancel={() => setVisible(false)} forceRender>
+      <Form form={form} size="small" labelCol={{ "{{" }} span: 4 {{ "}}" }} wrapperCol={{ "{{" }} span: 18 {{ "}}" }}>
+        <Divider orientation="left">{{ table_conf.comment }}</Divider>
+{%- for fd in fields %}{% if not fd.hidden %}
+        <Form.Item label="{{ fd.caption }}" name={{ "{" }}{% if major %}['{{ major }}', '{{ fd.field.field_name }}']{% else %}'{{ fd.field.field_name }}'{% endif %}{{ "}" }}{% if fd.field.field_type == "bool" %} valuePropName="checked"{% endif %}{{ self::rules(fd=fd) }}>
+          {{ self::widget(fd=fd, relation_map=relation_map) }}
+        </Form.Item>
+{%- endif %}{% endfor %}
+{%- for rel in relations %}
+        <Divider orientation="left">{{ rel.table_conf.comment }}</Divider>
+{%- if rel.one_many %}
+        <Button type="primary" size="small" icon={<PlusOutlined />} style={{ "{{" }} marginBottom: 8 {{ "}}" }} onClick={() => {% if rel.dialog_form %}edit{{ pascal(str=rel.major_field_name) }}(-1){% else %}set{{ pascal(str=rel.major_field_name) }}([...{{ rel.major_field_name }}, {}]){% endif %}}>添加</Button>
+        <Table<Row> rowKey={(_, index) => String(index)} size="small" bordered pagination={false} dataSource={{ "{" }}{{ rel.major_field_name }}{{ "}" }} columns={{ "{" }}{{ rel.major_field_name }}Columns{{ "}" }} />
+{%- else %}
+{%- for fd in rel.fields %}{% if not fd.hidden %}
+        <Form.Item label="{{ fd.caption }}" name={['{{ rel.major_field_name }}', '{{ fd.field.field_name }}']}{% if fd.field.field_type == "bool" %} valuePropName="checked"{% endif %}{{ self::rules(fd=fd) }}>
+          {{ self::widget(fd=fd, relation_map=relation_map) }}
+        </Form.Item>
+{%- endif %}{% endfor %}
+{%- endif %}
+{%- endfor %}
+      </Form>
+{%- for rel in relations %}{% if rel.one_many and rel.dialog_form %}
+{%- set rel_name = pascal(str=rel.major_field_name) %}
+
+      {/* {{ rel.table_conf.comment }} 明细 */}
+      <Modal open={{ "{" }}{{ rel.major_field_name }}Editing.visible{{ "}" }} title="{{ rel.table_conf.comment }}" width={600} maskClosable={false} onOk={confirm{{ rel_name }}} onCancel={() => set{{ rel_name }}Editing({ visible: false, index: -1 })} forceRender>
+        <Form form={{ "{" }}{{ rel.major_field_name }}Form{{ "}" }} size="small" labelCol={{ "{{" }} span: 6 {{ "}}" }} wrapperCol={{ "{{" }} span: 16 {{ "}}" }}>
+{%- for fd in rel.fields %}{% if not fd.hidden %}
+          <Form.Item label="{{ fd.caption }}" name="{{ fd.field.field_name }}"{% if fd.field.field_type == "bool" %} valuePropName="checked"{% endif %}{{ self::rules(fd=fd) }}>
+            {{ self::widget(fd=fd, relation_map=relation_map) }}
+          </Form.Item>
+{%- endif %}{% endfor %}
+        </Form>
+      </Modal>
+{%- endif %}{% endfor %}
+    </Modal>
+  )
+})
+
+export default {{ relation_conf.struct_name }}Form
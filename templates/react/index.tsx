@@ -0,0 +1,295 @@
+{%- set api = "api" ~ table_conf.struct_name -%}
+{%- set api_path = "@/api/" ~ codegen.schema_name ~ "/" -%}
+{%- if not codegen.schema_name %}{% set api_path = "@/api/" %}{% endif -%}
+{%- if typescript_api %}{% set row_type = table_conf.struct_name %}{% else %}{% set row_type = "Record<string, any>" %}{% endif -%}
+{%- set with_rel_form = relation_conf and relation_conf.generate_form -%}
+{#- 按照实际用到的字段决定需要import的组件，避免noUnusedLocals的错误 #}
+{%- set_global use_date = false %}{% set_global use_number = false %}{% set_global use_switch = false %}{% set_global use_input = false %}
+{%- set_global use_select = false %}{% set_global use_dict_label = false %}{% set_global use_dict_options = false %}
+{%- for fd in fields %}
+{%- set in_form = not fd.hidden and not with_rel_form %}
+{%- set is_rel = fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}
+{%- set is_date = fd.field.field_type is containing("DateTime") %}
+{%- if fd.display_table and fd.dict and fd.dict != "area" %}{% set_global use_dict_label = true %}{% endif %}
+{%- if fd.condition or in_form %}
+{%- if fd.dict == "area" %}
+{%- elif fd.dict %}{% set_global use_select = true %}{% set_global use_dict_options = true %}
+{%- elif is_rel %}{% set_global use_select = true %}
+{%- elif is_date %}{% set_global use_date = true %}
+{%- elif in_form and fd.field.field_type == "bool" %}{% set_global use_switch = true %}
+{%- elif in_form and (fd.field.field_type is containing("i64") or fd.field.field_type is containing("i32") or fd.field.field_type is containing("Decimal")) %}{% set_global use_number = true %}
+{%- else %}{% set_global use_input = true %}
+{%- endif %}
+{%- endif %}
+{%- endfor %}
+{%- set_global use_options = false %}{% for rc in relation_tables %}{% if rc.generate_handler %}{% set_global use_options = true %}{% endif %}{% endfor -%}
+import React, { useEffect, useState } from 'react'
+import { Button{% if table_conf.logic_deletion %}, Checkbox{% endif %}{% if use_date %}, DatePicker{% endif %}, Form{% if use_input %}, Input{% endif %}{% if use_number %}, InputNumber{% endif %}, Modal{% if use_select %}, Select{% endif %}, Space{% if use_switch %}, Switch{% endif %}, Table, message } from 'antd'
+import type { ColumnsType } from 'antd/es/table'
+import { {% if table_conf.logic_deletion %}CloseOutlined, {% endif %}DeleteOutlined, EditOutlined, PlusOutlined, ReloadOutlined, SearchOutlined{% if table_conf.logic_deletion %}, UndoOutlined{% endif %} } from '@ant-design/icons'
+import {{ api }} from '{{ api_path }}{{ table_conf.api_handler_name }}'
+{%- if typescript_api %}
+import type { {{ table_conf.struct_name }} } from '{{ api_path }}types'
+{%- endif %}
+{%- for rc in relation_tables %}{% if rc.generate_handler and rc.struct_name != table_conf.struct_name %}
+import api{{ rc.struct_name }} from '{{ api_path }}{{ rc.api_handler_name }}'
+{%- endif %}{% endfor %}
+import { fromFormValues{% if not with_rel_form %}, toFormValues{% endif %}{% if use_dict_label or use_dict_options %}, useDict{% endif %}{% if use_options %}, useOptions{% endif %}, {% if table_conf.tree_parent_field %}useTree{% elif table_conf.page_query %}usePage{% else %}useList{% endif %} } from '../hooks/useCrud'
+{%- if has_area %}
+import AreaPicker from '@/components/AreaPicker'
+{%- endif %}
+{%- if with_rel_form %}
+import RelForm from '../{{ relation_conf.api_handler_name }}/form'
+import type { RelFormRef } from '../{{ relation_conf.api_handler_name }}/form'
+{%- endif %}
+
+// 需要在表单中转换为dayjs的日期、时间字段
+{%- set_global date_fields = [] %}{% for fd in fields %}{% if fd.field.field_type is containing("DateTime") %}{% set_global date_fields = date_fields | concat(with=fd.field.field_name) %}{% endif %}{% endfor %}
+const dateFields: string[] = [{% for f in date_fields %}'{{ f }}'{% if not loop.last %}, {% endif %}{% endfor %}]
+
+/**
+ * {{ table_conf.comment }}
+ */
+const {{ table_conf.struct_name }}Page: React.FC = () => {
+  const [searchForm] = Form.useForm()
+{%- if use_dict_label or use_dict_options %}
+  const { {% if use_dict_options %}dictOptions{% endif %}{% if use_dict_label and use_dict_options %}, {% endif %}{% if use_dict_label %}dictLabel{% endif %} } = useDict({% for dt in dict_list %}'{{ dt }}'{% if not loop.last %}, {% endif %}{% endfor %})
+{%- endif %}
+{%- for rc in relation_tables %}{% if rc.generate_handler %}
+  const options{{ rc.struct_name }} = useOptions(api{{ rc.struct_name }}.search, '{{ rc.primary_key }}')
+{%- endif %}{% endfor %}
+{%- if table_conf.tree_parent_field %}
+  const { loading, data, refresh, onExpand, pagination } = useTree<{{ row_type }}>({{ api }}.tree, {% if table_conf.tree_root_value %}'{{ table_conf.tree_root_value }}'{% else %}undefined{% endif %}, '{{ table_conf.primary_key }}')
+{%- elif table_conf.page_query %}
+  const { loading, data, refresh, pagination } = usePage<{{ row_type }}>({{ api }}.paged)
+{%- else %}
+  const { loading, data, refresh, pagination } = useList<{{ row_type }}>({{ api }}.search)
+{%- endif %}
+  const [selectedKeys, setSelectedKeys] = useState<React.Key[]>([])
+{%- if table_conf.logic_deletion %}
+  const includeDeleted = Form.useWatch('include_deleted', searchForm)
+{%- endif %}
+{%- if with_rel_form %}
+  const relForm = React.useRef<RelFormRef>(null)
+{%- else %}
+  const [form] = Form.useForm()
+  const [dialogVisible, setDialogVisible] = useState(false)
+  const [editing, setEditing] = useState<{{ row_type }} | null>(null)
+  const [saving, setSaving] = useState(false)
+{%- endif %}
+
+  useEffect(() => {
+    refresh()
+  }, [refresh])
+
+  const toQuery = () => {
+    refresh(fromFormValues(searchForm.getFieldsValue(), dateFields){% if table_conf.page_query and not table_conf.tree_parent_field %}, 1{% endif %})
+  }
+
+  const resetQuery = () => {
+    searchForm.resetFields()
+    toQuery()
+  }
+{% if with_rel_form %}
+  const toAdd = () => {
+    relForm.current?.open()
+  }
+
+  const toEdit = (row: {{ row_type }}) => {
+    relForm.current?.open(row.{{ table_conf.primary_key }})
+  }
+{%- else %}
+  const toAdd = () => {
+    setEditing(null)
+    form.resetFields()
+    setDialogVisible(true)
+  }
+
+  const toEdit = (row: {{ row_type }}) => {
+    setEditing(row)
+    form.resetFields()
+    form.setFieldsValue(toFormValues(row, dateFields))
+    setDialogVisible(true)
+  }
+
+  const submitForm = () => {
+    form.validateFields().then(values => {
+      setSaving(true)
+      const row = { ...editing, ...fromFormValues(values, dateFields) } as {{ row_type }}
+      const action = editing ? {{ api }}.edit : {{ api }}.add
+      action(row).then((res: any) => {
+        if (res.status === 200) {
+          message.success('保存成功')
+          setDialogVisible(false)
+          refresh()
+        } else {
+          message.error(res.message)
+{%- if table_conf.version_field %}
+          if (res.status === 5409) {
+            // 已被其他人修改，重新加载后再编辑
+            setDialogVisible(false)
+            refresh()
+          }
+{%- endif %}
+        }
+      }).finally(() => setSaving(false))
+    }).catch(() => {})
+  }
+{%- endif %}
+
+  const toDelete = (row: {{ row_type }}) => {
+    Modal.confirm({
+      title: '提示',
+      content: '确定删除该{{ table_conf.comment }}吗？',
+      onOk: () => {{ api }}.del(row).then(() => {
+        message.success('删除成功')
+        refresh()
+      })
+    })
+  }
+
+{%- if table_conf.logic_deletion %}
+  const toRestore = (row: {{ row_type }}) => {
+    {{ api }}.restore(row).then(() => {
+      message.success('恢复成功')
+      refresh()
+    })
+  }
+
+  const toPurge = (row: {{ row_type }}) => {
+    Modal.confirm({
+      title: '提示',
+      content: '确定彻底删除该{{ table_conf.comment }}吗？删除后不可恢复',
+      onOk: () => {{ api }}.purge(row).then(() => {
+        message.success('删除成功')
+        refresh()
+      })
+    })
+  }
+
+{% endif %}
+  const toDeleteIds = () => {
+    const ids = selectedKeys as any[]
+    Modal.confirm({
+      title: '提示',
+      content: '确定删除选中的' + ids.length + '条{{ table_conf.comment }}吗？',
+      onOk: () => {{ api }}.delIds(ids).then(() => {
+        message.success('删除成功')
+        setSelectedKeys([])
+        refresh()
+      })
+    })
+  }
+
+  const columns: ColumnsType<{{ row_type }}> = [
+{%- for fd in fields %}{% if fd.display_table %}
+{%- if fd.dict and fd.dict != "area" %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => dictLabel('{{ fd.dict }}', value) as React.ReactNode },
+{%- elif fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => options{{ relation_map[fd.relation].struct_name }}.optionLabel(value) as React.ReactNode },
+{%- elif fd.field.field_type == "bool" %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}', render: (value: unknown) => (value ? '是' : '否') },
+{%- else %}
+    { title: '{{ fd.caption }}', dataIndex: '{{ fd.field.field_name }}' },
+{%- endif %}
+{%- endif %}{% endfor %}
+    {
+      title: '操作',
+      key: 'action',
+      width: {% if table_conf.logic_deletion %}200{% else %}120{% endif %},
+      align: 'center',
+      fixed: 'right',
+      render: (_: unknown, row: {{ row_type }}) => (
+        <Space>
+          <Button type="primary" size="small" icon={<EditOutlined />} onClick={() => toEdit(row)} />
+          <Button danger size="small" icon={<DeleteOutlined />} onClick={() => toDelete(row)} />
+{%- if table_conf.logic_deletion %}
+          {includeDeleted && <Button size="small" title="恢复" icon={<UndoOutlined />} onClick={() => toRestore(row)} />}
+          {includeDeleted && <Button danger size="small" title="彻底删除" icon={<CloseOutlined />} onClick={() => toPurge(row)} />}
+{%- endif %}
+        </Space>
+      )
+    }
+  ]
+
+  return (
+    <div className="app-container">
+      {/* {{ table_conf.comment }} 查询条件 */}
+      <Form form={searchForm} layout="inline" size="small" style={{ "{{" }} marginBottom: 8 {{ "}}" }}>
+{%- for fd in fields %}{% if fd.condition %}
+        <Form.Item label="{{ fd.caption }}" name="{{ fd.field.field_name }}">
+{%- if fd.dict == "area" %}
+          <AreaPicker style={{ "{{" }} width: 200 {{ "}}" }} />
+{%- elif fd.dict %}
+          <Select allowClear placeholder="{{ fd.caption }}" options={dictOptions('{{ fd.dict }}')} style={{ "{{" }} width: 160 {{ "}}" }} />
+{%- elif fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}
+          <Select allowClear showSearch optionFilterProp="label" placeholder="{{ fd.caption }}" options={options{{ relation_map[fd.relation].struct_name }}.options} style={{ "{{" }} width: 160 {{ "}}" }} />
+{%- elif fd.field.field_type is containing("DateTime") %}
+          <DatePicker.RangePicker showTime />
+{%- else %}
+          <Input allowClear placeholder="{{ fd.caption }}" style={{ "{{" }} width: 160 {{ "}}" }} onPressEnter={toQuery} />
+{%- endif %}
+        </Form.Item>
+{%- endif %}{% endfor %}
+{%- if table_conf.logic_deletion %}
+        <Form.Item name="include_deleted" valuePropName="checked">
+          <Checkbox onChange={() => setTimeout(toQuery)}>包含已删除</Checkbox>
+        </Form.Item>
+{%- endif %}
+        <Form.Item>
+          <Space>
+            <Button type="primary" icon={<SearchOutlined />} onClick={toQuery}>搜索</Button>
+            <Button icon={<ReloadOutlined />} onClick={resetQuery}>重置</Button>
+            <Button type="primary" icon={<PlusOutlined />} onClick={toAdd}>新增</Button>
+            <Button danger icon={<DeleteOutlined />} disabled={selectedKeys.length === 0} onClick={toDeleteIds}>删除</Button>
+          </Space>
+        </Form.Item>
+      </Form>
+
+      {/* {{ table_conf.comment }} 列表 */}
+      <Table<{{ row_type }}>
+        rowKey="{{ table_conf.primary_key }}"
+        size="small"
+        loading={loading}
+        dataSource={data}
+        columns={columns}
+        pagination={pagination}
+        rowSelection={{ "{{" }} selectedRowKeys: selectedKeys, onChange: setSelectedKeys {{ "}}" }}
+{%- if table_conf.tree_parent_field %}
+        expandable={{ "{{" }} onExpand {{ "}}" }}
+{%- endif %}
+        scroll={{ "{{" }} x: 'max-content' {{ "}}" }}
+      />
+{% if with_rel_form %}
+      <RelForm ref={relForm} onSaved={() => refresh()} />
+{%- else %}
+      {/* {{ table_conf.comment }} 编辑表单 */}
+      <Modal open={dialogVisible} title={editing ? '编辑{{ table_conf.comment }}' : '新增{{ table_conf.comment }}'} width={600} maskClosable={false} confirmLoading={saving} onOk={submitForm} onCancel={() => setDialogVisible(false)} forceRender>
+        <Form form={form} size="small" labelCol={{ "{{" }} span: 6 {{ "}}" }} wrapperCol={{ "{{" }} span: 16 {{ "}}" }}>
+{%- for fd in fields %}{% if not fd.hidden %}
+          <Form.Item label="{{ fd.caption }}" name="{{ fd.field.field_name }}"{% if fd.field.field_type == "bool" %} valuePropName="checked"{% endif %}{% if fd.required or fd.validate and fd.regex_check %} rules={[{% if fd.required %}{ required: true, message: '{{ fd.caption }}不能为空' }{% endif %}{% if fd.validate and fd.regex_check %}{% if fd.required %}, {% endif %}{ pattern: /{{ fd.validate }}/, message: '{{ fd.caption }}格式不正确' }{% endif %}]}{% endif %}>
+{%- if fd.dict == "area" %}
+            <AreaPicker />
+{%- elif fd.dict %}
+            <Select placeholder="请选择{{ fd.caption }}" options={dictOptions('{{ fd.dict }}')} />
+{%- elif fd.relation and relation_map[fd.relation] and relation_map[fd.relation].generate_handler %}
+            <Select showSearch optionFilterProp="label" placeholder="请选择{{ fd.caption }}" options={options{{ relation_map[fd.relation].struct_name }}.options} />
+{%- elif fd.field.field_type is containing("DateTime") %}
+            <DatePicker showTime />
+{%- elif fd.field.field_type == "bool" %}
+            <Switch />
+{%- elif fd.field.field_type is containing("i64") or fd.field.field_type is containing("i32") or fd.field.field_type is containing("Decimal") %}
+            <InputNumber style={{ "{{" }} width: '100%' {{ "}}" }} />
+{%- else %}
+            <Input />
+{%- endif %}
+          </Form.Item>
+{%- endif %}{% endfor %}
+        </Form>
+      </Modal>
+{%- endif %}
+    </div>
+  )
+}
+
+export default {{ table_conf.struct_name }}Page
@@ -0,0 +1,165 @@
+import { useCallback, useEffect, useRef, useState } from 'react'
+import dayjs from 'dayjs'
+import { get as getDictDetail } from '@/api/system/dictDetail'
+
+export const DATE_TIME_FORMAT = 'YYYY-MM-DD HH:mm:ss'
+
+/**
+ * 字典项，与element-ui版本中dicts加载的数据一致
+ */
+export interface DictItem {
+  id?: number | string
+  label: string
+  value: string
+}
+
+export interface OptionItem {
+  label: string
+  value: any
+}
+
+/**
+ * 加载字典
+ */
+export function useDict(...names: string[]) {
+  const [dict, setDict] = useState<Record<string, DictItem[]>>({})
+  useEffect(() => {
+    names.forEach(name => {
+      getDictDetail(name).then((res: any) => {
+        setDict(prev => ({ ...prev, [name]: res.content || res.data || [] }))
+      })
+    })
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [names.join(',')])
+  const dictOptions = useCallback((name: string): OptionItem[] => (dict[name] || []).map(it => ({ label: it.label, value: it.value })), [dict])
+  const dictLabel = useCallback((name: string, value: unknown) => {
+    const item = (dict[name] || []).find(it => it.value === value + '')
+    return item ? item.label : value
+  }, [dict])
+  return { dict, dictOptions, dictLabel }
+}
+
+/**
+ * 关联表的选项，通过关联表的search加载，显示name、title、label等字段
+ */
+export function useOptions(loader: (query: any) => Promise<any>, key = 'id') {
+  const [options, setOptions] = useState<OptionItem[]>([])
+  useEffect(() => {
+    loader({}).then(res => {
+      setOptions((res.data || []).map((row: any) => ({
+        label: String(row.name ?? row.title ?? row.label ?? row[key]),
+        value: row[key]
+      })))
+    })
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [key])
+  const optionLabel = useCallback((value: unknown) => {
+    const item = options.find(it => it.value === value)
+    return item ? item.label : value
+  }, [options])
+  return { options, optionLabel }
+}
+
+/**
+ * 表单中的日期、时间与接口中的字符串互相转换
+ */
+export function toFormValues(row: Record<string, any>, dateFields: string[]) {
+  const values = { ...row }
+  dateFields.forEach(fd => {
+    values[fd] = values[fd] ? dayjs(values[fd]) : undefined
+  })
+  return values
+}
+
+export function fromFormValues(values: Record<string, any>, dateFields: string[]) {
+  const row = { ...values }
+  dateFields.forEach(fd => {
+    if (Array.isArray(row[fd])) {
+      row[fd] = row[fd].map((it: any) => it ? dayjs(it).format(DATE_TIME_FORMAT) : it)
+    } else if (row[fd]) {
+      row[fd] = dayjs(row[fd]).format(DATE_TIME_FORMAT)
+    }
+  })
+  return row
+}
+
+/**
+ * 列表查询（search）
+ */
+export function useList<T = any>(loader: (query: any) => Promise<any>) {
+  const [loading, setLoading] = useState(false)
+  const [data, setData] = useState<T[]>([])
+  const queryRef = useRef<Record<string, any>>({})
+  const refresh = useCallback((query?: Record<string, any>) => {
+    if (query) {
+      queryRef.current = query
+    }
+    setLoading(true)
+    return loader(queryRef.current).then(res => {
+      setData(res.data || [])
+    }).finally(() => setLoading(false))
+  }, [loader])
+  return { loading, data, refresh, pagination: false as const }
+}
+
+/**
+ * 分页查询（paged）
+ */
+export function usePage<T = any>(loader: (query: any, current: number, size: number) => Promise<any>, size = 10) {
+  const [loading, setLoading] = useState(false)
+  const [data, setData] = useState<T[]>([])
+  const [page, setPage] = useState({ current: 1, size, total: 0 })
+  const queryRef = useRef<Record<string, any>>({})
+  const pageRef = useRef(page)
+  pageRef.current = page
+  const refresh = useCallback((query?: Record<string, any>, current?: number, pageSize?: number) => {
+    if (query) {
+      queryRef.current = query
+    }
+    const cur = current ?? pageRef.current.current
+    const sz = pageSize ?? pageRef.current.size
+    setLoading(true)
+    return loader(queryRef.current, cur, sz).then(res => {
+      setData(res.data ? res.data.records || [] : [])
+      setPage({ current: cur, size: sz, total: res.data ? res.data.total : 0 })
+    }).finally(() => setLoading(false))
+  }, [loader])
+  const pagination = {
+    current: page.current,
+    pageSize: page.size,
+    total: page.total,
+    showSizeChanger: true,
+    showTotal: (total: number) => `共 ${total} 条`,
+    onChange: (current: number, pageSize: number) => { refresh(undefined, current, pageSize) }
+  }
+  return { loading, data, refresh, pagination }
+}
+
+/**
+ * 树形查询（tree），子节点在展开时按pid懒加载
+ */
+export function useTree<T = any>(loader: (pid?: string | number) => Promise<any>, rootValue?: string | number, key = 'id') {
+  const [loading, setLoading] = useState(false)
+  const [data, setData] = useState<T[]>([])
+  const withChildren = (rows: any[]) => rows.map(row => (row.has_children && !row.children ? { ...row, children: [] } : row))
+  const refresh = useCallback((_query?: Record<string, any>) => {
+    setLoading(true)
+    return loader(rootValue).then(res => {
+      setData(withChildren(res.data || []))
+    }).finally(() => setLoading(false))
+  }, [loader, rootValue])
+  const replaceChildren = (rows: any[], id: unknown, children: any[]): any[] => rows.map(row => {
+    if (row[key] === id) {
+      return { ...row, children }
+    }
+    return row.children ? { ...row, children: replaceChildren(row.children, id, children) } : row
+  })
+  const onExpand = (expanded: boolean, row: any) => {
+    if (expanded && row.children && row.children.length === 0) {
+      loader(row[key]).then(res => {
+        setData(prev => replaceChildren(prev, row[key], withChildren(res.data || [])) as T[])
+      })
+    }
+  }
+  return { loading, data, refresh, onExpand, pagination: false as const }
+}
@@ -0,0 +1,18 @@
+/**
+ * Generate the file for {{ file_name }}, 
+ */
+{% for crt in carets -%}
+extern caret {{ crt }};
+{% endfor %}
+{% for usingline in usings -%}
+use {{ usingline }};
+{% endfor %}
+{% for st in structs -%}
+{{ st.source }}
+{%- endfor %}
+{%- for func in funcs -%}
+{{ func.source }}
+{%- endfor -%}
+{%- for imp in impls -%}
+{{ imp.source }}
+{%- endfor -%}
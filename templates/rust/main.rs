@@ -0,0 +1,6 @@
+{#
+  main.rs的默认模板直接输出内置方式生成的内容，覆盖时可以使用：
+  mods      所有的mod名称
+  services  所有handler的路径，axum时为Router的路由（如.route(...)）
+  codegen   codegen的配置
+#}{{ generated }}
\ No newline at end of file
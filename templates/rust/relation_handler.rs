@@ -0,0 +1 @@
+{% include "rust/file.rs" %}
\ No newline at end of file
@@ -0,0 +1,100 @@
+import { reactive, ref } from 'vue'
+import { get as getDictDetail } from '@/api/system/dictDetail'
+
+/**
+ * 字典项，与element-ui版本中dicts加载的数据一致
+ */
+export interface DictItem {
+  id?: number | string
+  label: string
+  value: string
+}
+
+/**
+ * 加载字典，对应element-ui版本中的dicts
+ */
+export function useDict(...names: string[]) {
+  const dict = reactive<Record<string, DictItem[]>>({})
+  names.forEach(name => {
+    dict[name] = []
+    getDictDetail(name).then((res: any) => {
+      dict[name] = res.content || res.data || []
+    })
+  })
+  const dictLabel = (name: string, value: unknown) => {
+    const item = (dict[name] || []).find(it => it.value === value + '')
+    return item ? item.label : value
+  }
+  return { dict, dictLabel }
+}
+
+/**
+ * 列表查询（search）
+ */
+export function useList<T = any>(loader: (query: any) => Promise<any>) {
+  const loading = ref(false)
+  const data = ref<T[]>([])
+  const query = ref<Record<string, any>>({})
+  const refresh = () => {
+    loading.value = true
+    return loader(query.value).then(res => {
+      data.value = res.data || []
+    }).finally(() => { loading.value = false })
+  }
+  const toQuery = () => refresh()
+  const resetQuery = () => {
+    query.value = {}
+    return toQuery()
+  }
+  return { loading, data, query, refresh, toQuery, resetQuery }
+}
+
+/**
+ * 分页查询（paged）
+ */
+export function usePage<T = any>(loader: (query: any, current: number, size: number) => Promise<any>, size = 10) {
+  const loading = ref(false)
+  const data = ref<T[]>([])
+  const query = ref<Record<string, any>>({})
+  const page = reactive({ current: 1, size, total: 0 })
+  const refresh = () => {
+    loading.value = true
+    return loader(query.value, page.current, page.size).then(res => {
+      data.value = res.data ? res.data.records || [] : []
+      page.total = res.data ? res.data.total : 0
+    }).finally(() => { loading.value = false })
+  }
+  const toQuery = () => {
+    page.current = 1
+    return refresh()
+  }
+  const resetQuery = () => {
+    query.value = {}
+    return toQuery()
+  }
+  return { loading, data, query, page, refresh, toQuery, resetQuery }
+}
+
+/**
+ * 树形查询（tree），子节点在展开时按pid懒加载
+ */
+export function useTree<T = any>(loader: (pid?: string | number) => Promise<any>, rootValue?: string | number, key = 'id') {
+  const loading = ref(false)
+  const data = ref<T[]>([])
+  const query = ref<Record<string, any>>({})
+  const refresh = () => {
+    loading.value = true
+    return loader(rootValue).then(res => {
+      data.value = res.data || []
+    }).finally(() => { loading.value = false })
+  }
+  const loadChildren = (row: any, _node: unknown, resolve: (children: T[]) => void) => {
+    loader(row[key]).then(res => resolve(res.data || []))
+  }
+  const toQuery = () => refresh()
+  const resetQuery = () => {
+    query.value = {}
+    return toQuery()
+  }
+  return { loading, data, query, refresh, loadChildren, toQuery, resetQuery }
+}